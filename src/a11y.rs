@@ -0,0 +1,59 @@
+use web_sys::window;
+
+use crate::{document::Document, Mode};
+
+/// What the screen reader announces after a mode change.
+pub fn mode(mode: Mode) -> &'static str {
+    match mode {
+        Mode::Insert => "Insert mode",
+        Mode::Normal => "Normal mode",
+        Mode::Command => "Command mode",
+        Mode::Visual => "Visual mode",
+    }
+}
+
+/// The character under the cursor as the screen reader announces it, with whitespace
+/// spelled out.
+pub fn character(document: &Document) -> String {
+    let (_, paragraph) = match document
+        .paragraphs()
+        .into_iter()
+        .find(|(_, paragraph)| paragraph.cursor.is_some())
+    {
+        Some(paragraph) => paragraph,
+        None => return String::new(),
+    };
+    let c = paragraph
+        .cursor
+        .and_then(|cursor| paragraph.text.get(cursor));
+    match c.map(String::as_str) {
+        None => "end of paragraph",
+        Some(" ") => "space",
+        Some("\n") => "line break",
+        Some("\t") => "tab",
+        Some(c) => c,
+    }
+    .to_owned()
+}
+
+/// Turns animations and smooth scrolling off or on, over what the system asks for.
+///
+/// The classes go on the root element, which is what scrolls smoothly.
+pub fn reduce_motion(reduced: bool) {
+    if let Some(root) = window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.document_element())
+    {
+        let classes = root.class_list();
+        let _ = classes.toggle_with_force("reduce-motion", reduced);
+        let _ = classes.toggle_with_force("allow-motion", !reduced);
+    }
+}
+
+/// Whether the system asks for less animation, the default of `:set reducedmotion`.
+pub fn prefers_reduced_motion() -> bool {
+    window()
+        .and_then(|window| window.match_media("(prefers-reduced-motion: reduce)").ok())
+        .flatten()
+        .map_or(false, |query| query.matches())
+}
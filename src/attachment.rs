@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+
+use js_sys::{Array, ArrayBuffer, Uint8Array};
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{Blob, DataTransfer, File, FileReader, Url};
+use yew::{classes, html, Callback, Html};
+
+use crate::{
+    links::{self, Target},
+    offline,
+};
+
+/// Urls of pasted or dropped files start with this, `![diagram](attachment:diagram.png)`.
+pub const SCHEME: &str = "attachment:";
+/// The largest attachment in KiB unless `:set attachmentlimit=` is set.
+pub const DEFAULT_LIMIT: u32 = 2048;
+
+/// An attachment shown in a note, fetched from IndexedDB when it is first shown.
+#[derive(Clone, Debug, PartialEq, Hash)]
+pub enum Attachment {
+    Loading,
+    /// An object url of its blob
+    Loaded(String),
+    Missing,
+}
+
+/// The files of a paste or drop.
+pub fn files(transfer: &DataTransfer) -> Vec<File> {
+    transfer
+        .files()
+        .map(|files| (0..files.length()).filter_map(|i| files.get(i)).collect())
+        .unwrap_or_default()
+}
+
+fn size(bytes: f64) -> String {
+    if bytes >= 1024. * 1024. {
+        format!("{:.1} MiB", bytes / (1024. * 1024.))
+    } else {
+        format!("{:.0} KiB", bytes / 1024.)
+    }
+}
+
+/// Stores `file` unless it is larger than `limit` KiB, `done` gets the name to link it by.
+pub fn attach(file: File, limit: u32, done: Callback<Result<String, String>>) {
+    // Spaces would end the link destination
+    let name = file.name().split_whitespace().collect::<Vec<_>>().join("-");
+    let limit = f64::from(limit) * 1024.;
+    if file.size() > limit {
+        return done.emit(Err(format!(
+            "{} is {}, over the attachment limit of {}",
+            name,
+            size(file.size()),
+            size(limit)
+        )));
+    }
+    let blob: Blob = file.into();
+    offline::attach(
+        &name.clone(),
+        blob,
+        Callback::from(move |stored: Option<String>| {
+            done.emit(stored.ok_or_else(|| format!("{} could not be stored", name)))
+        }),
+    );
+}
+
+/// Stores `content` as the attachment `name`, `done` gets the name it was stored as, which
+/// differs if `name` is taken, or `None` if that failed.
+pub fn store(name: &str, content: &[u8], done: Callback<Option<String>>) {
+    let parts = Array::of1(&Uint8Array::from(content));
+    match Blob::new_with_u8_array_sequence(&parts) {
+        Ok(blob) => offline::attach(name, blob, done),
+        Err(_) => done.emit(None),
+    }
+}
+
+/// Stores `content` as the attachment `name` unless the one of that name has the same
+/// content, `done` gets the name it is kept as like for [`store`].
+pub fn keep(name: &str, content: Vec<u8>, done: Callback<Option<String>>) {
+    let name = name.to_owned();
+    offline::attachment(
+        &name.clone(),
+        Callback::from(move |existing: Option<Blob>| {
+            let (name, content, done) = (name.clone(), content.clone(), done.clone());
+            match existing {
+                Some(existing) => bytes(&existing, move |existing| {
+                    if existing.as_deref() == Some(content.as_slice()) {
+                        done.emit(Some(name));
+                    } else {
+                        store(&name, &content, done);
+                    }
+                }),
+                None => store(&name, &content, done),
+            }
+        }),
+    );
+}
+
+/// `text` with its links to the attachment `old` pointing to `new` instead.
+pub fn rename(text: &str, old: &str, new: &str) -> String {
+    let link = format!("{}{}", SCHEME, old);
+    let mut renamed = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(&link) {
+        let end = start + link.len();
+        // Not a link to an attachment whose name only starts with `old`
+        let whole = rest[end..].chars().next().map_or(true, |c| {
+            c.is_whitespace() || matches!(c, ')' | '>' | '"' | '\'')
+        });
+        renamed.push_str(&rest[..start]);
+        if whole {
+            renamed.push_str(SCHEME);
+            renamed.push_str(new);
+        } else {
+            renamed.push_str(&link);
+        }
+        rest = &rest[end..];
+    }
+    renamed.push_str(rest);
+    renamed
+}
+
+/// `name` or, if it is `taken`, the first free one with a number before its extension.
+///
+/// The number is not in parentheses like for notes, a space would end the link to it.
+pub fn free_name(name: &str, taken: &[String]) -> String {
+    if !taken.iter().any(|taken| taken == name) {
+        return name.to_owned();
+    }
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{}", extension)),
+        _ => (name, String::new()),
+    };
+    (2..)
+        .map(|i| format!("{}-{}{}", stem, i, extension))
+        .find(|name| !taken.contains(name))
+        .unwrap()
+}
+
+/// Fetches the attachment `name`, `done` gets an object url of it or `None` if it is missing.
+pub fn load(name: &str, done: Callback<Option<String>>) {
+    offline::attachment(
+        name,
+        Callback::from(move |blob: Option<Blob>| {
+            done.emit(blob.and_then(|blob| Url::create_object_url_with_blob(&blob).ok()))
+        }),
+    );
+}
+
+/// Reads the content of `blob` for `done`, `None` if that failed.
+pub fn bytes(blob: &Blob, done: impl FnOnce(Option<Vec<u8>>) + 'static) {
+    let reader = FileReader::new().unwrap();
+    let read = reader.clone();
+    // Called whether reading worked or not
+    reader.set_onloadend(Some(
+        Closure::once_into_js(move || {
+            let buffer = read
+                .result()
+                .ok()
+                .and_then(|result| result.dyn_into::<ArrayBuffer>().ok());
+            done(buffer.map(|buffer| Uint8Array::new(&buffer).to_vec()))
+        })
+        .unchecked_ref(),
+    ));
+    reader.read_as_array_buffer(blob).unwrap();
+}
+
+/// The names of the attachments linked in `text`.
+pub fn linked(text: &str) -> Vec<String> {
+    if !text.contains(SCHEME) {
+        return vec![];
+    }
+    let inline = links::links(text)
+        .into_iter()
+        .filter_map(|link| match link.target {
+            Target::Inline(destination) => Some(destination),
+            Target::Reference(_) => None,
+        });
+    let definitions = text
+        .lines()
+        .filter_map(links::definition)
+        .map(|definition| definition.destination);
+    inline
+        .chain(definitions)
+        .filter_map(|destination| {
+            links::href(&destination)
+                .strip_prefix(SCHEME)
+                .map(String::from)
+        })
+        .collect()
+}
+
+/// The images linked in `text`, loaded once they are scrolled to.
+///
+/// `links` are the reference definitions of the note by normalized id. Attachments that
+/// are still being fetched show a placeholder.
+pub fn images(
+    text: &str,
+    links: &HashMap<String, String>,
+    attachments: &HashMap<String, Attachment>,
+) -> Html {
+    let images = links::links(text)
+        .into_iter()
+        .filter(|link| link.image)
+        .filter_map(|link| {
+            let url = match &link.target {
+                Target::Inline(destination) => links::href(destination),
+                Target::Reference(id) => links.get(&links::normalize(id))?.clone(),
+            };
+            let url = match url.strip_prefix(SCHEME) {
+                Some(name) => match attachments.get(name) {
+                    Some(Attachment::Loaded(url)) => url.clone(),
+                    Some(Attachment::Missing) => {
+                        return Some(html! {
+                            <span class={classes!("block", "text-sm", "text-red-500")}>
+                                {format!("Missing attachment {}", name)}
+                            </span>
+                        })
+                    }
+                    _ => {
+                        return Some(html! {
+                            <span class={classes!("block", "w-48", "h-32", "my-1", "rounded", "bg-gray-200", "animate-pulse")} title={link.text.clone()} />
+                        })
+                    }
+                },
+                None => url,
+            };
+            Some(html! {
+                <img src={url} alt={link.text.clone()} loading="lazy" class={classes!("block", "max-w-full", "max-h-96", "my-1")} />
+            })
+        });
+    html! {{for images}}
+}
@@ -0,0 +1,71 @@
+use crate::replacements::Replacements;
+
+/// Setting of the corrections of `:correct`, applied while `:set autocorrect` is on.
+pub const CORRECTIONS: &str = "corrections";
+
+/// Common letter swaps, the corrections until the table is first changed.
+pub const DEFAULT_CORRECTIONS: &[(&str, &str)] = &[
+    ("adn", "and"),
+    ("becuase", "because"),
+    ("definately", "definitely"),
+    ("hte", "the"),
+    ("occured", "occurred"),
+    ("recieve", "receive"),
+    ("seperate", "separate"),
+    ("taht", "that"),
+    ("teh", "the"),
+    ("thier", "their"),
+    ("waht", "what"),
+    ("wiht", "with"),
+];
+
+fn ends_sentence(text: &str) -> bool {
+    text.trim_end().ends_with(&['.', '!', '?'][..])
+}
+
+/// Whether a word after `before`, the text of the block before it, starts a sentence.
+///
+/// Words right after list, quote and heading markers do.
+pub fn starts_sentence(before: &str) -> bool {
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let line = &before[line_start..];
+    let content = line.trim_start_matches(|c: char| "-*+>#".contains(c) || c.is_whitespace());
+    if !content.is_empty() {
+        return ends_sentence(content);
+    }
+    let previous = before[..line_start].trim_end();
+    content.len() < line.len() || previous.is_empty() || ends_sentence(previous)
+}
+
+/// `word` with its first letter in uppercase.
+pub fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// The correction of `word`, which is typed after `before`, capitalized if it starts a
+/// sentence.
+pub fn correct(corrections: &Replacements, word: &str, before: &str) -> Option<String> {
+    let corrected = corrections.get(&word.to_lowercase()).map(|correction| {
+        if word.starts_with(char::is_uppercase) {
+            capitalize(correction)
+        } else {
+            correction.to_owned()
+        }
+    });
+    let candidate = corrected.as_deref().unwrap_or(word);
+    let capitalized = (starts_sentence(before)
+        && candidate.starts_with(char::is_lowercase)
+        && candidate.chars().all(char::is_alphabetic))
+    .then(|| capitalize(candidate));
+    capitalized.or(corrected)
+}
+
+/// Whether a space typed after `before_cursor` would double one between words.
+pub fn doubled_space(before_cursor: &str) -> bool {
+    let line = before_cursor.rsplit('\n').next().unwrap_or_default();
+    line.ends_with(' ') && !line.trim().is_empty()
+}
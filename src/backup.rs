@@ -0,0 +1,151 @@
+use js_sys::{Reflect, Uint8Array};
+use wasm_bindgen::JsValue;
+use web_sys::window;
+use yew::Callback;
+
+use crate::{
+    date, export, offline,
+    promise::{call, object},
+};
+
+/// The name the backup folder is kept by in IndexedDB.
+const FOLDER: &str = "backup";
+const EXPIRED: &str = "The permission for the backup folder expired, renew it with :backup";
+/// The longest interval, browsers run timers of more than `i32::MAX` milliseconds right away.
+pub const MAX_MINUTES: u32 = i32::MAX as u32 / 60_000;
+
+/// When the vault is written to the folder chosen with `:backup folder`, set with
+/// `:set backup=`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Schedule {
+    Off,
+    /// After every `:w`
+    OnSave,
+    /// Every that many minutes
+    Every(u32),
+}
+
+impl Schedule {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "off" => Some(Self::Off),
+            "save" => Some(Self::OnSave),
+            minutes => minutes
+                .parse()
+                .ok()
+                .filter(|&minutes| minutes > 0 && minutes <= MAX_MINUTES)
+                .map(Self::Every),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::Off => "off".to_owned(),
+            Self::OnSave => "save".to_owned(),
+            Self::Every(minutes) => minutes.to_string(),
+        }
+    }
+}
+
+/// An object with the `mode: "readwrite"` the folder is opened with.
+fn readwrite() -> JsValue {
+    object(&[("mode", "readwrite".into())]).into()
+}
+
+/// Lets the user pick the backup folder, kept across sessions, `done` receives its name or
+/// why none was picked.
+pub fn choose(done: Callback<Result<String, String>>) {
+    let window = window().unwrap();
+    if !Reflect::has(&window, &"showDirectoryPicker".into()).unwrap_or_default() {
+        return done.emit(Err("The browser cannot write to folders".to_owned()));
+    }
+    call(
+        &window,
+        "showDirectoryPicker",
+        &[readwrite()],
+        move |folder| {
+            done.emit(match folder {
+                Ok(folder) => {
+                    let name = Reflect::get(&folder, &"name".into())
+                        .ok()
+                        .and_then(|name| name.as_string())
+                        .unwrap_or_default();
+                    offline::keep_handle(FOLDER, folder);
+                    Ok(name)
+                }
+                Err(_) => Err("No backup folder picked".to_owned()),
+            })
+        },
+    );
+}
+
+/// Writes the archive of `notes` and the attachments to `notething-<date>.zip` in the
+/// backup folder, replacing the one of the same day, `done` receives the file name or what
+/// went wrong.
+///
+/// Browsers forget the permission for the folder after a while. It can only be asked for
+/// again with `ask`, which needs a key just pressed, so scheduled backups fail until then.
+pub fn run(notes: Vec<(String, String)>, ask: bool, done: Callback<Result<String, String>>) {
+    let file = format!("notething-{}.zip", date::now("%F"));
+    offline::handle(
+        FOLDER,
+        Callback::from(move |folder: Option<JsValue>| {
+            let folder = match folder {
+                Some(folder) => folder,
+                None => {
+                    return done.emit(Err(
+                        "No backup folder, choose one with :backup folder".to_owned()
+                    ))
+                }
+            };
+            let permission = if ask {
+                "requestPermission"
+            } else {
+                "queryPermission"
+            };
+            let (notes, file, done) = (notes.clone(), file.clone(), done.clone());
+            call(&folder.clone(), permission, &[readwrite()], move |state| {
+                if state.ok().and_then(|state| state.as_string()).as_deref() != Some("granted") {
+                    return done.emit(Err(EXPIRED.to_owned()));
+                }
+                let written = Callback::from(move |(archive, _): (Vec<u8>, usize)| {
+                    write(&folder, file.clone(), archive, done.clone())
+                });
+                export::archive(notes, written);
+            });
+        }),
+    );
+}
+
+/// Replaces `file` in `folder` with `content`.
+fn write(folder: &JsValue, file: String, content: Vec<u8>, done: Callback<Result<String, String>>) {
+    let failed = || Err("The backup could not be written".to_owned());
+    let options = object(&[("create", true.into())]);
+    call(
+        folder,
+        "getFileHandle",
+        &[file.as_str().into(), options.into()],
+        move |handle| {
+            let handle = match handle {
+                Ok(handle) => handle,
+                Err(_) => return done.emit(failed()),
+            };
+            call(&handle, "createWritable", &[], move |stream| {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => return done.emit(failed()),
+                };
+                let content: JsValue = Uint8Array::from(content.as_slice()).into();
+                call(&stream.clone(), "write", &[content], move |written| {
+                    if written.is_err() {
+                        return done.emit(failed());
+                    }
+                    // The file only changes once the stream is closed
+                    call(&stream, "close", &[], move |closed| {
+                        done.emit(closed.map(|_| file).or_else(|_| failed()))
+                    });
+                });
+            });
+        },
+    );
+}
@@ -0,0 +1,43 @@
+/// A block that `:codeblock`, `:quote` and `:heading` turn text into.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Block {
+    /// A fenced code block with the language after the fence
+    Code(String),
+    Quote,
+    Heading(usize),
+}
+
+impl Block {
+    /// The markdown of `text` as the block.
+    pub fn wrap(&self, text: &str) -> String {
+        let text = text.trim_matches('\n');
+        match self {
+            Block::Code(lang) => {
+                // The fence has to be longer than any run of backticks inside
+                let longest = text
+                    .split(|c| c != '`')
+                    .map(str::len)
+                    .max()
+                    .unwrap_or_default();
+                let fence = "`".repeat(longest.max(2) + 1);
+                format!("{}{}\n{}\n{}", fence, lang, text, fence)
+            }
+            Block::Quote => text
+                .lines()
+                .map(|line| {
+                    if line.trim().is_empty() {
+                        ">".to_owned()
+                    } else {
+                        format!("> {}", line)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Block::Heading(level) => format!(
+                "{} {}",
+                "#".repeat(*level),
+                text.split_whitespace().collect::<Vec<_>>().join(" ")
+            ),
+        }
+    }
+}
@@ -0,0 +1,293 @@
+use std::ops::Range;
+
+use yew::{classes, html, Html};
+
+use crate::{
+    document::{Command, Commandee, Render},
+    tasks::{self, Task},
+    ApplicationState,
+};
+
+/// A task shown on the board with the lines it spans, its own and those of its subtasks.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Card {
+    pub task: Task,
+    pub lines: Range<usize>,
+}
+
+/// The tasks of a heading and everything under it up to the next heading of its level.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Column {
+    pub title: String,
+    /// Line of the heading
+    pub line: usize,
+    pub cards: Vec<Card>,
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// The headings of the level closest to the top with tasks under them and more than one
+/// heading, with their tasks.
+fn columns(md: &str) -> Vec<Column> {
+    let lines: Vec<_> = md.split('\n').collect();
+    let mut code = false;
+    let mut headings = vec![];
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            code = !code;
+        }
+        let level = line.chars().take_while(|&c| c == '#').count();
+        if !code
+            && (1..=6).contains(&level)
+            && matches!(line[level..].chars().next(), None | Some(' '))
+        {
+            let title = line[level..].trim().trim_end_matches('#').trim_end();
+            headings.push((i, level, title.to_owned()));
+        }
+    }
+    let tasks = tasks::parse("", md);
+    let end = |h: usize| {
+        let (_, level, _) = headings[h];
+        headings[h + 1..]
+            .iter()
+            .find(|(_, other, _)| *other <= level)
+            .map_or(lines.len(), |(line, _, _)| *line)
+    };
+    let has_tasks = |h: usize| {
+        let (line, _, _) = headings[h];
+        tasks.iter().any(|task| (line..end(h)).contains(&task.line))
+    };
+    let mut levels: Vec<_> = (0..headings.len())
+        .filter(|&h| has_tasks(h))
+        .map(|h| headings[h].1)
+        .collect();
+    levels.sort_unstable();
+    levels.dedup();
+    // A title heading over the whole note is not a column
+    let level = match levels
+        .iter()
+        .find(|&&level| {
+            headings
+                .iter()
+                .filter(|(_, other, _)| *other == level)
+                .count()
+                > 1
+        })
+        .or_else(|| levels.first())
+    {
+        Some(&level) => level,
+        None => return vec![],
+    };
+    (0..headings.len())
+        .filter(|&h| headings[h].1 == level)
+        .map(|h| {
+            let (line, _, title) = &headings[h];
+            let section = *line..end(h);
+            let mut cards: Vec<Card> = vec![];
+            for task in tasks.iter().filter(|task| section.contains(&task.line)) {
+                // Subtasks move with their task
+                if cards
+                    .last()
+                    .map_or(false, |card| card.lines.contains(&task.line))
+                {
+                    continue;
+                }
+                let depth = indent(&task.source);
+                let len = lines[task.line + 1..section.end]
+                    .iter()
+                    .take_while(|line| !line.trim().is_empty() && indent(line) > depth)
+                    .count();
+                cards.push(Card {
+                    task: task.clone(),
+                    lines: task.line..task.line + 1 + len,
+                });
+            }
+            Column {
+                title: title.clone(),
+                line: *line,
+                cards,
+            }
+        })
+        .collect()
+}
+
+/// `:board` shows the tasks of the open note as a kanban board with a column per heading.
+///
+/// `Enter` picks up the selected card and puts it down again, in between `h`/`l` carry it
+/// to another column and `j`/`k` move it up or down. Putting it down moves the task in the
+/// note, Escape leaves the note as it was.
+#[derive(Clone, Debug)]
+pub struct Board {
+    /// The markdown of the note with the moves made so far
+    pub md: String,
+    pub columns: Vec<Column>,
+    pub column: usize,
+    pub card: usize,
+    pub carrying: bool,
+}
+
+impl Board {
+    pub fn new(md: String) -> Self {
+        Self {
+            columns: columns(&md),
+            md,
+            column: 0,
+            card: 0,
+            carrying: false,
+        }
+    }
+
+    /// Moves the lines `from` before the line `to`, `gap` separates them from the lines
+    /// around them so they become a list of their own.
+    fn relocate(&mut self, from: Range<usize>, mut to: usize, gap: bool) {
+        let mut lines: Vec<_> = self.md.split('\n').map(String::from).collect();
+        let mut moved: Vec<_> = lines.drain(from.clone()).collect();
+        if to > from.start {
+            to -= from.len();
+        }
+        let to = to.min(lines.len());
+        if gap {
+            moved.insert(0, String::new());
+            if lines.get(to).map_or(false, |line| !line.trim().is_empty()) {
+                moved.push(String::new());
+            }
+        }
+        lines.splice(to..to, moved);
+        self.md = lines.join("\n");
+        self.columns = columns(&self.md);
+    }
+
+    /// Carries the selected card to the end of the column `column`.
+    fn carry_to(&mut self, column: usize) {
+        let card = self.columns[self.column].cards[self.card].lines.clone();
+        let target = &self.columns[column];
+        match target.cards.last() {
+            Some(last) => {
+                let to = last.lines.end;
+                self.relocate(card, to, false);
+            }
+            None => {
+                let to = target.line + 1;
+                self.relocate(card, to, true);
+            }
+        }
+        // The moved lines may no longer be a card there, leaving the column empty
+        self.column = column.min(self.columns.len().saturating_sub(1));
+        self.card = self
+            .columns
+            .get(self.column)
+            .map_or(0, |column| column.cards.len().saturating_sub(1));
+    }
+
+    /// Swaps the selected card with the one above it, or below it if `down`.
+    fn carry_along(&mut self, down: bool) {
+        let cards = &self.columns[self.column].cards;
+        let card = cards[self.card].lines.clone();
+        if down {
+            let to = cards[self.card + 1].lines.end;
+            self.relocate(card, to, false);
+            self.card += 1;
+        } else {
+            let to = cards[self.card - 1].lines.start;
+            self.relocate(card, to, false);
+            self.card -= 1;
+        }
+    }
+}
+
+impl Commandee for Board {
+    fn command(&mut self, command: &Self::Command) -> Self::Response {
+        let cards = match self.columns.get(self.column) {
+            Some(column) => column.cards.len(),
+            None => return false,
+        };
+        let columns = self.columns.len();
+        match command {
+            Command::Left if self.column > 0 => {
+                if self.carrying && cards > 0 {
+                    self.carry_to(self.column - 1);
+                } else {
+                    self.column -= 1;
+                }
+            }
+            Command::Right if self.column + 1 < columns => {
+                if self.carrying && cards > 0 {
+                    self.carry_to(self.column + 1);
+                } else {
+                    self.column += 1;
+                }
+            }
+            Command::Up if self.card > 0 => {
+                if self.carrying {
+                    self.carry_along(false);
+                } else {
+                    self.card -= 1;
+                }
+            }
+            Command::Down if self.card + 1 < cards => {
+                if self.carrying {
+                    self.carry_along(true);
+                } else {
+                    self.card += 1;
+                }
+            }
+            _ => return false,
+        }
+        let cards = self
+            .columns
+            .get(self.column)
+            .map_or(0, |column| column.cards.len());
+        self.card = self.card.min(cards.saturating_sub(1));
+        true
+    }
+}
+
+impl Render for Board {
+    fn render(&self, _state: &ApplicationState) -> Html {
+        let columns = self.columns.iter().enumerate().map(|(i, column)| {
+            let cards = column.cards.iter().enumerate().map(|(j, card)| {
+                let selected = i == self.column && j == self.card;
+                let ring = match (selected, self.carrying) {
+                    (true, true) => Some(classes!("ring-2", "ring-yellow-400", "-rotate-1", "transform")),
+                    (true, false) => Some(classes!("ring-2", "ring-gray-400")),
+                    _ => None,
+                };
+                html! {
+                    <div class={classes!("rounded", "bg-gray-700", "p-2", "mb-2", ring, card.task.done.then_some(classes!("line-through", "text-gray-500")))}>
+                        <p>{&card.task.text}</p>
+                        {for card.task.due.as_ref().map(|due| html! {
+                            <p class={classes!("text-sm", "text-gray-400")}>{due}</p>
+                        })}
+                    </div>
+                }
+            });
+            html! {
+                <div class={classes!("flex-1", "min-w-0", "rounded", "bg-gray-800", "p-2")}>
+                    <p class={classes!("font-bold", "mb-2")}>
+                        {&column.title}
+                        <span class={classes!("text-gray-500")}>{format!(" {}", column.cards.len())}</span>
+                    </p>
+                    {for cards}
+                </div>
+            }
+        });
+        html! {
+            <div>
+                <p class={classes!("font-bold")}>
+                    {if self.carrying {
+                        "h/l and j/k carry the card, Enter puts it down"
+                    } else {
+                        "Enter picks up a card, Escape closes the board"
+                    }}
+                </p>
+                if self.columns.is_empty() {
+                    <p class={classes!("text-gray-500")}>{"(no tasks under a heading)"}</p>
+                }
+                <div class={classes!("flex", "gap-2", "mt-2")}>{for columns}</div>
+            </div>
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use yew::{classes, html, Html};
+
+use crate::document::{Document, Element};
+
+/// What kind of block the markdown `md` of a paragraph element is.
+fn kind(md: &str) -> &'static str {
+    let md = md.trim_start();
+    let ordered = md.chars().take_while(char::is_ascii_digit).count();
+    if md.starts_with("```") || md.starts_with("~~~") {
+        "Code block"
+    } else if md.starts_with('>') {
+        "Quote"
+    } else if md.starts_with("<!--") {
+        "Comment"
+    } else if md.starts_with(|c| matches!(c, '-' | '*' | '+')) && md[1..].starts_with(' ') {
+        "List"
+    } else if ordered > 0 && md[ordered..].starts_with(|c| matches!(c, '.' | ')')) {
+        "List"
+    } else {
+        "Paragraph"
+    }
+}
+
+/// Where the cursor is in the structure of `document`, from the outermost heading to the
+/// cell of a table, like `H2 Projects`, `Table`, `row 3, col 2`.
+pub fn crumbs(document: &Document) -> Vec<String> {
+    let active = document.active_element;
+    let mut headings: Vec<(u32, String)> = vec![];
+    for element in document.elements.iter().take(active + 1) {
+        if let Element::Heading(heading) = element {
+            headings.retain(|(level, _)| *level < heading.level);
+            let title = heading.content.to_md();
+            headings.push((
+                heading.level,
+                format!("H{} {}", heading.level, title.trim()),
+            ));
+        }
+    }
+    let mut crumbs: Vec<_> = headings.into_iter().map(|(_, title)| title).collect();
+    match document.elements.get(active) {
+        Some(Element::Table(table)) => {
+            crumbs.push("Table".to_owned());
+            match table.active_cell {
+                Some((x, 0)) => crumbs.push(format!("header, col {}", x + 1)),
+                Some((x, y)) => crumbs.push(format!("row {}, col {}", y, x + 1)),
+                None => {}
+            }
+        }
+        Some(Element::Definitions(list)) => {
+            crumbs.push("Definition list".to_owned());
+            match list.active_item.and_then(|i| list.items.get(i)) {
+                Some((true, _)) => crumbs.push("term".to_owned()),
+                Some((false, _)) => crumbs.push("definition".to_owned()),
+                None => {}
+            }
+        }
+        Some(Element::Details(details)) => {
+            crumbs.push("Details".to_owned());
+            match details.active_item {
+                Some(0) => crumbs.push("summary".to_owned()),
+                Some(_) => crumbs.push("body".to_owned()),
+                None => {}
+            }
+        }
+        Some(Element::Frontmatter(_)) => crumbs.push("Frontmatter".to_owned()),
+        Some(Element::Paragraph(paragraph)) => {
+            crumbs.push(kind(&paragraph.to_md()).to_owned());
+        }
+        _ => {}
+    }
+    crumbs
+}
+
+/// The [`crumbs`] of `document` as a bar above the note, shown by `:set breadcrumb`.
+pub fn render(document: &Document) -> Html {
+    let crumbs = crumbs(document);
+    let last = crumbs.len().saturating_sub(1);
+    let crumbs = crumbs.into_iter().enumerate().map(|(i, crumb)| {
+        html! {
+            <>
+                if i > 0 {
+                    <span class={classes!("mx-1", "text-gray-600")}>{"▸"}</span>
+                }
+                <span class={classes!((i == last).then_some("text-gray-300"))}>{crumb}</span>
+            </>
+        }
+    });
+    html! {
+        <nav class={classes!("sticky", "top-0", "z-10", "-mx-2", "-mt-2", "mb-2", "px-2", "truncate", "text-sm", "leading-6", "text-gray-500", "bg-gray-900", "bg-opacity-90")} aria-label="Location in the note">
+            {for crumbs}
+        </nav>
+    }
+}
@@ -0,0 +1,68 @@
+use crate::document::{Document, Markdown};
+
+/// How many recently closed notes `:ls` offers to reopen.
+const CLOSED: usize = 10;
+
+/// A note open in the background, keeping its unsaved changes.
+pub struct Buffer {
+    pub document: Document,
+    pub note: Option<String>,
+    /// Content of the note when it was last loaded or saved
+    pub base: Option<String>,
+}
+
+impl Buffer {
+    pub fn modified(&self) -> bool {
+        modified(&self.document, self.base.as_deref())
+    }
+}
+
+/// Whether `document` differs from `base`, the content it was loaded from.
+pub fn modified(document: &Document, base: Option<&str>) -> bool {
+    let md = document.to_md();
+    match base {
+        Some(base) => Document::from_md(base).to_md() != md,
+        None => !md.trim().is_empty(),
+    }
+}
+
+/// The buffers besides the open one and the notes closed with `:bd`, most recent first.
+#[derive(Default)]
+pub struct Buffers {
+    pub hidden: Vec<Buffer>,
+    pub closed: Vec<String>,
+}
+
+impl Buffers {
+    /// Keeps `buffer` as the alternate buffer, unnamed empty buffers are dropped.
+    pub fn push(&mut self, buffer: Buffer) {
+        if let Some(note) = &buffer.note {
+            self.hidden
+                .retain(|hidden| hidden.note.as_ref() != Some(note));
+            self.closed.retain(|closed| closed != note);
+        } else if !buffer.modified() {
+            return;
+        }
+        self.hidden.insert(0, buffer);
+    }
+
+    /// The hidden buffer of `note`, removed from the list.
+    pub fn take(&mut self, note: &str) -> Option<Buffer> {
+        let i = self
+            .hidden
+            .iter()
+            .position(|buffer| buffer.note.as_deref() == Some(note))?;
+        Some(self.hidden.remove(i))
+    }
+
+    /// The buffer toggled to with `:b#`, removed from the list.
+    pub fn alternate(&mut self) -> Option<Buffer> {
+        (!self.hidden.is_empty()).then(|| self.hidden.remove(0))
+    }
+
+    pub fn close(&mut self, note: String) {
+        self.closed.retain(|closed| closed != &note);
+        self.closed.insert(0, note);
+        self.closed.truncate(CLOSED);
+    }
+}
@@ -0,0 +1,110 @@
+use yew::{classes, html, Html};
+
+use crate::{
+    document::{Command, Commandee, Document, Render},
+    ApplicationState,
+};
+
+/// A quote starting with `> [!note] Title`, shown as a colored box while the cursor is not
+/// in it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Callout {
+    /// The type in lowercase, like `warning`
+    pub kind: String,
+    pub title: String,
+    /// `Some` if it can be collapsed with `[!note]-`, collapsed by default, or `[!note]+`,
+    /// with whether it starts expanded
+    pub open: Option<bool>,
+    /// The markdown of the quote after the first line
+    pub body: String,
+}
+
+impl Callout {
+    /// The callout the quote `text` is, `None` for any other block.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut lines = text.lines();
+        let first = lines.next()?.trim_start().strip_prefix('>')?.trim_start();
+        let (kind, rest) = first.strip_prefix("[!")?.split_once(']')?;
+        if kind.is_empty() || kind.contains(char::is_whitespace) {
+            return None;
+        }
+        let (open, title) = match rest.chars().next() {
+            Some('-') => (Some(false), &rest[1..]),
+            Some('+') => (Some(true), &rest[1..]),
+            _ => (None, rest),
+        };
+        let kind = kind.to_lowercase();
+        let title = match title.trim() {
+            "" => {
+                let mut chars = kind.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect())
+                    .unwrap_or_default()
+            }
+            title => title.to_owned(),
+        };
+        let body: Option<Vec<_>> = lines
+            .map(|line| {
+                let line = line.trim_start().strip_prefix('>')?;
+                Some(line.strip_prefix(' ').unwrap_or(line))
+            })
+            .collect();
+        Some(Self {
+            kind,
+            title,
+            open,
+            body: body?.join("\n"),
+        })
+    }
+
+    /// The icon and colors of the box of each kind, the aliases Obsidian knows included.
+    fn style(&self) -> (&'static str, &'static str) {
+        match self.kind.as_str() {
+            "tip" | "hint" | "important" => ("💡", "border-green-400"),
+            "success" | "check" | "done" => ("✔", "border-green-400"),
+            "warning" | "caution" | "attention" => ("⚠", "border-yellow-400"),
+            "danger" | "error" | "failure" | "fail" | "missing" | "bug" => ("⛔", "border-red-400"),
+            "question" | "help" | "faq" => ("?", "border-yellow-400"),
+            "example" => ("☰", "border-purple-400"),
+            "quote" | "cite" => ("❝", "border-gray-400"),
+            "todo" => ("☐", "border-blue-400"),
+            _ => ("ℹ", "border-blue-400"),
+        }
+    }
+}
+
+impl Render for Callout {
+    fn render(&self, state: &ApplicationState) -> Html {
+        let (icon, border) = self.style();
+        let title = html! {
+            <span class={classes!("font-bold")}>{icon}{" "}{&self.title}</span>
+        };
+        let body = if self.body.trim().is_empty() {
+            html! {}
+        } else {
+            let mut document = Document::from_md(&self.body);
+            document.command(&Command::CursorLeave);
+            html! {
+                <div class={classes!("mt-1")}>
+                    {for document.elements.iter().map(|element| element.render(state))}
+                </div>
+            }
+        };
+        let classes = classes!("border-l-4", border, "bg-gray-800", "pl-2", "py-1", "my-1");
+        match self.open {
+            Some(open) => html! {
+                <details open={open} class={classes}>
+                    <summary class={classes!("cursor-pointer")}>{title}</summary>
+                    {body}
+                </details>
+            },
+            None => html! {
+                <div class={classes}>
+                    <p>{title}</p>
+                    {body}
+                </div>
+            },
+        }
+    }
+}
@@ -0,0 +1,404 @@
+use std::ops::Range;
+
+use js_sys::{Array, JSON};
+use wasm_bindgen::JsValue;
+
+use crate::{frontmatter, promise::field, store::Storage};
+
+/// A work in the bibliography note of `:set bibliography=`.
+#[derive(Clone, Debug, Default, Hash, PartialEq)]
+pub struct Reference {
+    pub key: String,
+    /// The family and given names of the authors
+    pub authors: Vec<(String, String)>,
+    pub title: String,
+    pub year: String,
+    /// The journal, book or publisher it appeared in
+    pub container: String,
+    pub url: String,
+}
+
+impl Reference {
+    /// The authors and year a citation shows, like `Doe & Roe 2020`.
+    pub fn short(&self) -> String {
+        let authors = match self.authors.as_slice() {
+            [] => self.title.clone(),
+            [(one, _)] => one.clone(),
+            [(one, _), (two, _)] => format!("{} & {}", one, two),
+            [(first, _), ..] => format!("{} et al.", first),
+        };
+        format!("{} {}", authors, self.year())
+    }
+
+    fn year(&self) -> &str {
+        if self.year.is_empty() {
+            "n.d."
+        } else {
+            &self.year
+        }
+    }
+
+    /// The entry in the bibliography, like `Doe, J., & Roe, R. (2020). Title. Journal.`
+    pub fn full(&self) -> String {
+        let authors: Vec<_> = self
+            .authors
+            .iter()
+            .map(|(family, given)| {
+                let initials: Vec<_> = given
+                    .split(|c: char| c.is_whitespace() || c == '-')
+                    .filter_map(|name| name.chars().next())
+                    .map(|initial| format!("{}.", initial))
+                    .collect();
+                if initials.is_empty() {
+                    family.clone()
+                } else {
+                    format!("{}, {}", family, initials.join(" "))
+                }
+            })
+            .collect();
+        let mut full = match authors.as_slice() {
+            [] => String::new(),
+            [one] => format!("{} ", one),
+            [rest @ .., last] => format!("{}, & {} ", rest.join(", "), last),
+        };
+        full.push_str(&format!("({}). {}.", self.year(), self.title));
+        if !self.container.is_empty() {
+            full.push_str(&format!(" {}.", self.container));
+        }
+        if !self.url.is_empty() {
+            full.push_str(&format!(" {}", self.url));
+        }
+        full
+    }
+}
+
+/// Characters of a citekey besides letters and digits.
+fn key_char(c: char) -> bool {
+    c.is_alphanumeric() || "_:.#$%&-+?<>~/".contains(c)
+}
+
+/// The citekey at the start of `text`, without punctuation ending it.
+fn key(text: &str) -> &str {
+    let end = text.find(|c| !key_char(c)).unwrap_or(text.len());
+    text[..end].trim_end_matches(|c: char| c != '_' && c.is_ascii_punctuation())
+}
+
+/// A work cited in a `[@key]` citation.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Item {
+    pub key: String,
+    /// Text before the key, like `see`
+    pub prefix: String,
+    /// Text after the key, like `p. 33`
+    pub locator: String,
+    /// Only the year is shown, written as `[-@key]`
+    pub suppress_author: bool,
+}
+
+/// A citation of one or more works, `[@doe2020]` or `[see @doe2020, p. 33; @roe2019]`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Citation {
+    /// Byte range in the source including the brackets
+    pub range: Range<usize>,
+    pub items: Vec<Item>,
+}
+
+fn item(part: &str) -> Option<Item> {
+    let part = part.trim();
+    let at = part
+        .match_indices('@')
+        .map(|(at, _)| at)
+        .find(|&at| at == 0 || part[..at].ends_with(|c: char| c.is_whitespace() || c == '-'))?;
+    let key = key(&part[at + 1..]);
+    if key.is_empty() {
+        return None;
+    }
+    let mut prefix = &part[..at];
+    let suppress_author = prefix.ends_with('-');
+    if suppress_author {
+        prefix = &prefix[..prefix.len() - 1];
+    }
+    let rest = &part[at + 1 + key.len()..];
+    Some(Item {
+        key: key.to_owned(),
+        prefix: prefix.trim().to_owned(),
+        locator: rest.trim_start_matches(',').trim().to_owned(),
+        suppress_author,
+    })
+}
+
+/// The citations in `text`.
+pub fn citations(text: &str) -> Vec<Citation> {
+    let mut citations = vec![];
+    let mut offset = 0;
+    while let Some(start) = text[offset..].find('[').map(|start| offset + start) {
+        offset = start + 1;
+        let end = match text[offset..].find(|c| matches!(c, ']' | '[' | '\n')) {
+            Some(end) if text[offset + end..].starts_with(']') => offset + end,
+            _ => continue,
+        };
+        let inner = &text[offset..end];
+        // Links, footnotes and wiki links have their own syntax
+        let link = text[end + 1..].starts_with(|c| c == '(' || c == '[');
+        if link || text[..start].ends_with('[') || inner.starts_with('^') {
+            continue;
+        }
+        let items: Option<Vec<_>> = inner.split(';').map(item).collect();
+        if let Some(items) = items {
+            citations.push(Citation {
+                range: start..end + 1,
+                items,
+            });
+            offset = end + 1;
+        }
+    }
+    citations
+}
+
+/// How `citation` is shown, like `(see Doe 2020, p. 33; Roe 2019)`, `None` if a work is
+/// missing in `references`.
+pub fn format(citation: &Citation, references: &[Reference]) -> Option<String> {
+    let items = citation
+        .items
+        .iter()
+        .map(|item| {
+            let reference = references
+                .iter()
+                .find(|reference| reference.key == item.key)?;
+            let mut text = item.prefix.clone();
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            if item.suppress_author {
+                text.push_str(reference.year());
+            } else {
+                text.push_str(&reference.short());
+            }
+            if !item.locator.is_empty() {
+                text.push_str(", ");
+                text.push_str(&item.locator);
+            }
+            Some(text)
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(format!("({})", items.join("; ")))
+}
+
+/// The names of the authors in a BibTeX `author` field, `Doe, Jane and John Roe`.
+fn bibtex_authors(authors: &str) -> Vec<(String, String)> {
+    authors
+        .split(" and ")
+        .map(str::trim)
+        .filter(|author| !author.is_empty())
+        .map(|author| match author.split_once(',') {
+            Some((family, given)) => (family.trim().to_owned(), given.trim().to_owned()),
+            None => match author.rsplit_once(' ') {
+                Some((given, family)) => (family.to_owned(), given.trim().to_owned()),
+                None => (author.to_owned(), String::new()),
+            },
+        })
+        .collect()
+}
+
+/// The value of a field at the start of `text`, in braces, quotes or bare, with the rest.
+fn bibtex_value(text: &str) -> (String, &str) {
+    let text = text.trim_start();
+    let (close, start) = match text.chars().next() {
+        Some('{') => ('}', 1),
+        Some('"') => ('"', 1),
+        _ => {
+            let end = text.find(|c| c == ',' || c == '}').unwrap_or(text.len());
+            return (text[..end].trim().to_owned(), &text[end..]);
+        }
+    };
+    let mut depth = 0;
+    for (i, c) in text.char_indices().skip(start) {
+        match c {
+            '{' => depth += 1,
+            '}' if depth > 0 => depth -= 1,
+            c if c == close && depth == 0 => {
+                let value = text[start..i].replace(|c| matches!(c, '{' | '}' | '\\'), "");
+                return (
+                    value.split_whitespace().collect::<Vec<_>>().join(" "),
+                    &text[i + 1..],
+                );
+            }
+            _ => {}
+        }
+    }
+    (text[start..].to_owned(), "")
+}
+
+/// The entries of the BibTeX `bibtex`, like `@article{doe2020, author = {Doe, Jane}, …}`.
+pub fn parse_bibtex(bibtex: &str) -> Vec<Reference> {
+    let mut references = vec![];
+    let mut rest = bibtex;
+    while let Some(at) = rest.find('@') {
+        rest = &rest[at + 1..];
+        let open = match rest.find(|c| c == '{' || c == '(') {
+            Some(open) => open,
+            None => break,
+        };
+        let kind = rest[..open].trim().to_lowercase();
+        rest = &rest[open + 1..];
+        if matches!(kind.as_str(), "comment" | "string" | "preamble") || kind.contains(' ') {
+            continue;
+        }
+        let comma = match rest.find(',') {
+            Some(comma) => comma,
+            None => break,
+        };
+        let mut reference = Reference {
+            key: rest[..comma].trim().to_owned(),
+            ..Reference::default()
+        };
+        rest = &rest[comma + 1..];
+        loop {
+            rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+            if rest.is_empty() || rest.starts_with(|c| c == '}' || c == ')') {
+                break;
+            }
+            let equals = match rest.find('=') {
+                Some(equals) => equals,
+                None => break,
+            };
+            let name = rest[..equals].trim().to_lowercase();
+            let (value, after) = bibtex_value(&rest[equals + 1..]);
+            rest = after;
+            match name.as_str() {
+                "author" => reference.authors = bibtex_authors(&value),
+                "editor" if reference.authors.is_empty() => {
+                    reference.authors = bibtex_authors(&value)
+                }
+                "title" => reference.title = value,
+                "year" => reference.year = value,
+                "date" if reference.year.is_empty() => {
+                    reference.year = value.chars().take(4).collect()
+                }
+                "journal" | "journaltitle" | "booktitle" => reference.container = value,
+                "publisher" if reference.container.is_empty() => reference.container = value,
+                "url" => reference.url = value,
+                "doi" if reference.url.is_empty() => {
+                    reference.url = format!("https://doi.org/{}", value)
+                }
+                _ => {}
+            }
+        }
+        if !reference.key.is_empty() {
+            references.push(reference);
+        }
+    }
+    references
+}
+
+/// The entries of the CSL-JSON `json`, an array of items with an `id`.
+pub fn parse_csl(json: &str) -> Vec<Reference> {
+    let items = match JSON::parse(json) {
+        Ok(items) if Array::is_array(&items) => Array::from(&items),
+        _ => return vec![],
+    };
+    let text = |item: &JsValue, name: &str| field(item, &[name]).as_string().unwrap_or_default();
+    items
+        .iter()
+        .filter_map(|item| {
+            let key = text(&item, "id");
+            if key.is_empty() {
+                return None;
+            }
+            let authors = Array::from(&field(&item, &["author"]))
+                .iter()
+                .map(|author| match text(&author, "family") {
+                    family if family.is_empty() => (text(&author, "literal"), String::new()),
+                    family => (family, text(&author, "given")),
+                })
+                .collect();
+            let year = Array::from(&Array::from(&field(&item, &["issued", "date-parts"])).get(0))
+                .get(0)
+                .as_f64()
+                .map(|year| year.to_string())
+                .unwrap_or_default();
+            let doi = text(&item, "DOI");
+            let url = match text(&item, "URL") {
+                url if url.is_empty() && !doi.is_empty() => format!("https://doi.org/{}", doi),
+                url => url,
+            };
+            Some(Reference {
+                key,
+                authors,
+                title: text(&item, "title"),
+                year,
+                container: text(&item, "container-title"),
+                url,
+            })
+        })
+        .collect()
+}
+
+/// The bibliography note of `md`, the `bibliography` in its frontmatter or else `default`.
+pub fn bibliography<'a>(md: &'a str, default: &'a str) -> &'a str {
+    frontmatter::split(md)
+        .0
+        .and_then(|frontmatter| frontmatter::get(frontmatter, "bibliography"))
+        .unwrap_or(default)
+}
+
+/// The works in the bibliography note `note`, in BibTeX or CSL-JSON, which may be in a
+/// code block.
+pub fn load(store: &dyn Storage, note: &str) -> Vec<Reference> {
+    let md = match store.load(note) {
+        Some(md) => md,
+        None => return vec![],
+    };
+    let body: Vec<_> = frontmatter::split(&md)
+        .1
+        .lines()
+        .filter(|line| !line.trim_start().starts_with("```"))
+        .collect();
+    let body = body.join("\n");
+    if body.trim_start().starts_with('[') {
+        parse_csl(&body)
+    } else {
+        parse_bibtex(&body)
+    }
+}
+
+/// The citekey typed after an `@` in an unclosed `[` at the end of `before_cursor`.
+pub fn typed(before_cursor: &str) -> Option<&str> {
+    let (before, typed) = before_cursor.rsplit_once('@')?;
+    let open = before.rfind('[')?;
+    let unclosed = before[open..].find(']').is_none();
+    let starts_item = before.is_empty() || before.ends_with(|c: char| matches!(c, '[' | ' ' | '-'));
+    (unclosed && starts_item && typed.chars().all(key_char)).then_some(typed)
+}
+
+/// `md` with its citations written out and a list of the cited works appended, for
+/// exports.
+pub fn resolve(md: &str, references: &[Reference]) -> String {
+    let mut resolved = md.to_owned();
+    let mut cited: Vec<&Reference> = vec![];
+    for citation in citations(md).into_iter().rev() {
+        if let Some(text) = format(&citation, references) {
+            resolved.replace_range(citation.range.clone(), &text);
+            for item in &citation.items {
+                if let Some(reference) = references
+                    .iter()
+                    .find(|reference| reference.key == item.key)
+                {
+                    cited.push(reference);
+                }
+            }
+        }
+    }
+    if cited.is_empty() {
+        return resolved;
+    }
+    let mut entries: Vec<_> = cited.iter().map(|reference| reference.full()).collect();
+    entries.sort();
+    entries.dedup();
+    resolved.truncate(resolved.trim_end().len());
+    resolved.push_str("\n\n## References\n\n");
+    for entry in entries {
+        resolved.push_str(&format!("- {}\n", entry));
+    }
+    resolved
+}
@@ -0,0 +1,101 @@
+use js_sys::encode_uri_component;
+use wasm_bindgen::JsValue;
+use web_sys::window;
+use yew::Callback;
+
+use crate::{
+    date, frontmatter,
+    promise::{call, field},
+};
+
+/// The address the page at `url` is fetched from, through `proxy` of `:set clipproxy=` if
+/// set.
+///
+/// Most sites do not allow reading their pages from other sites, which a CORS proxy works
+/// around. `{url}` in `proxy` is replaced by the encoded url, which is appended otherwise.
+pub fn address(url: &str, proxy: Option<&str>) -> String {
+    match proxy {
+        Some(proxy) => {
+            let encoded = String::from(encode_uri_component(url));
+            if proxy.contains("{url}") {
+                proxy.replace("{url}", &encoded)
+            } else {
+                format!("{}{}", proxy, encoded)
+            }
+        }
+        None => url.to_owned(),
+    }
+}
+
+/// Fetches the HTML of the page at `url`, `done` receives it or why it could not be read.
+pub fn fetch(url: &str, proxy: Option<&str>, done: Callback<Result<String, String>>) {
+    let window: JsValue = window().unwrap().into();
+    let failed = if proxy.is_some() {
+        "The page could not be fetched through the proxy"
+    } else {
+        "The page could not be fetched, it might need a proxy set with :set clipproxy="
+    };
+    call(
+        &window,
+        "fetch",
+        &[address(url, proxy).into()],
+        move |response| {
+            let response = match response {
+                Ok(response) if field(&response, &["ok"]).as_bool() == Some(true) => response,
+                Ok(response) => {
+                    let status = field(&response, &["status"]).as_f64().unwrap_or_default();
+                    return done.emit(Err(format!("The page answered with status {}", status)));
+                }
+                Err(_) => return done.emit(Err(failed.to_owned())),
+            };
+            call(&response, "text", &[], move |text| {
+                done.emit(
+                    text.ok()
+                        .and_then(|text| text.as_string())
+                        .ok_or_else(|| failed.to_owned()),
+                )
+            });
+        },
+    );
+}
+
+/// The name of a note clipped from `url` titled `title`, without characters that would
+/// make it a path or break links to it.
+pub fn note_name(title: Option<&str>, url: &str) -> String {
+    let name = title.unwrap_or_else(|| {
+        url.trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .filter(|last| !last.is_empty())
+            .unwrap_or(url)
+    });
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | '|' | '#' | '[' | ']' | ':' => ' ',
+            c => c,
+        })
+        .collect();
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    if name.is_empty() {
+        "Clipping".to_owned()
+    } else {
+        name
+    }
+}
+
+/// The note of a page clipped from `url`, with where and when it was clipped in the
+/// frontmatter.
+pub fn note(title: Option<&str>, url: &str, md: &str) -> String {
+    let mut metadata = String::new();
+    if let Some(title) = title {
+        metadata = frontmatter::set(
+            &metadata,
+            "title",
+            &format!("\"{}\"", title.replace('"', "'")),
+        );
+    }
+    metadata = frontmatter::set(&metadata, "source", url);
+    metadata = frontmatter::set(&metadata, "clipped", &date::now("%F"));
+    format!("---\n{}\n---\n{}\n", metadata, md)
+}
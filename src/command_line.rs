@@ -0,0 +1,102 @@
+use std::collections::HashSet;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    document::{is_word, word_left},
+    TextStyle,
+};
+
+/// Movement and deletion on the command line.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Edit {
+    Left,
+    Right,
+    Home,
+    End,
+    WordLeft,
+    WordRight,
+    Backspace,
+    DeleteWord,
+    DeleteToStart,
+}
+
+/// The text typed in Command mode, with a cursor of its own apart from the document's.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CommandLine {
+    pub text: Vec<String>,
+    /// Index in `text` the next character is inserted at
+    pub cursor: usize,
+}
+
+impl CommandLine {
+    pub fn write(&mut self, text: &str) {
+        let graphemes: Vec<_> = text.graphemes(true).map(String::from).collect();
+        let len = graphemes.len();
+        self.text.splice(self.cursor..self.cursor, graphemes);
+        self.cursor += len;
+    }
+
+    pub fn edit(&mut self, edit: Edit) {
+        let cursor = self.cursor;
+        match edit {
+            Edit::Left => self.cursor = cursor.saturating_sub(1),
+            Edit::Right => self.cursor = (cursor + 1).min(self.text.len()),
+            Edit::Home => self.cursor = 0,
+            Edit::End => self.cursor = self.text.len(),
+            Edit::WordLeft => self.cursor = word_left(&self.text, cursor),
+            Edit::WordRight => self.cursor = word_right(&self.text, cursor),
+            Edit::Backspace => self.delete(cursor.saturating_sub(1)),
+            Edit::DeleteWord => self.delete(word_left(&self.text, cursor)),
+            Edit::DeleteToStart => self.delete(0),
+        }
+    }
+
+    /// Deletes from `start` to the cursor.
+    fn delete(&mut self, start: usize) {
+        self.text.drain(start..self.cursor);
+        self.cursor = start;
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// The characters with their byte offset as the [`Line`](crate::Line) component takes them.
+    pub fn characters(&self) -> Vec<(String, HashSet<TextStyle>, usize)> {
+        let mut offset = 0;
+        self.text
+            .iter()
+            .map(|c| {
+                offset += c.len();
+                (c.clone(), HashSet::new(), offset - c.len())
+            })
+            .collect()
+    }
+}
+
+impl ToString for CommandLine {
+    fn to_string(&self) -> String {
+        self.text.concat()
+    }
+}
+
+/// The start of the next word after `cursor`, past the rest of the current one and any
+/// whitespace.
+fn word_right(text: &[String], cursor: usize) -> usize {
+    let blank = |c: &String| c.trim().is_empty();
+    let mut i = cursor;
+    if let Some(c) = text.get(i).filter(|c| !blank(c)) {
+        let word = is_word(c);
+        while text
+            .get(i)
+            .map_or(false, |c| !blank(c) && is_word(c) == word)
+        {
+            i += 1;
+        }
+    }
+    while text.get(i).map_or(false, blank) {
+        i += 1;
+    }
+    i
+}
@@ -0,0 +1,62 @@
+use std::ops::Range;
+
+/// The byte ranges of the `<!-- comments -->` and `%% comments %%` in `text`, outside of
+/// code.
+///
+/// A comment that is not closed is no comment, so typing one does not hide the rest of
+/// the paragraph.
+pub fn ranges(text: &str) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    let mut fenced = false;
+    let mut offset = 0;
+    while offset < text.len() {
+        let rest = &text[offset..];
+        let line_start = offset == 0 || text[..offset].ends_with('\n');
+        if line_start && rest.trim_start().starts_with("```") {
+            fenced = !fenced;
+        }
+        if fenced || rest.starts_with('\n') {
+            offset += rest.find('\n').map_or(rest.len(), |end| end + 1);
+            continue;
+        }
+        if rest.starts_with('`') {
+            // A code span ends at the next run of as many backticks
+            let ticks = rest.len() - rest.trim_start_matches('`').len();
+            let fence = &rest[..ticks];
+            offset += match rest[ticks..].find(fence) {
+                Some(end) => ticks + end + ticks,
+                None => ticks,
+            };
+            continue;
+        }
+        let delimiters = if rest.starts_with("<!--") {
+            Some(("<!--", "-->"))
+        } else if rest.starts_with("%%") {
+            Some(("%%", "%%"))
+        } else {
+            None
+        };
+        if let Some((open, close)) = delimiters {
+            if let Some(end) = rest[open.len()..].find(close) {
+                let end = offset + open.len() + end + close.len();
+                ranges.push(offset..end);
+                offset = end;
+                continue;
+            }
+        }
+        offset += rest.chars().next().map_or(1, char::len_utf8);
+    }
+    ranges
+}
+
+/// `md` without its comments, for exports that should not show them.
+pub fn strip(md: &str) -> String {
+    let mut stripped = String::new();
+    let mut offset = 0;
+    for range in ranges(md) {
+        stripped.push_str(&md[offset..range.start]);
+        offset = range.end;
+    }
+    stripped.push_str(&md[offset..]);
+    stripped
+}
@@ -0,0 +1,115 @@
+use unicode_segmentation::UnicodeSegmentation;
+use yew::{classes, html, Html};
+
+use crate::{
+    citation,
+    document::{Command, Commandee, Document, Render},
+    wiki, ApplicationState,
+};
+
+/// Suggests completions for the text left of the cursor.
+pub trait Source {
+    /// The number of graphemes the suggestions replace and the suggestions themselves.
+    fn suggest(&self, before_cursor: &str, document: &Document) -> Option<(usize, Vec<String>)>;
+}
+
+/// Anchors of the headings after `](#`.
+pub struct HeadingAnchors;
+
+impl Source for HeadingAnchors {
+    fn suggest(&self, before_cursor: &str, document: &Document) -> Option<(usize, Vec<String>)> {
+        let (_, typed) = before_cursor.rsplit_once("](#")?;
+        if typed.contains(|c: char| c.is_whitespace() || c == ')') {
+            return None;
+        }
+        let anchors: Vec<_> = document
+            .anchors()
+            .into_iter()
+            .map(|(_, anchor)| anchor)
+            .filter(|anchor| anchor.starts_with(typed) && anchor != typed)
+            .collect();
+        Some((typed.graphemes(true).count(), anchors))
+    }
+}
+
+/// Names and aliases of the stored notes after `[[`.
+pub struct NoteNames(pub Vec<String>);
+
+impl Source for NoteNames {
+    fn suggest(&self, before_cursor: &str, _document: &Document) -> Option<(usize, Vec<String>)> {
+        let typed = wiki::typed(before_cursor)?;
+        let lowercase = typed.to_lowercase();
+        let names: Vec<_> = self
+            .0
+            .iter()
+            .filter(|name| name.to_lowercase().starts_with(&lowercase) && *name != typed)
+            .cloned()
+            .collect();
+        Some((typed.graphemes(true).count(), names))
+    }
+}
+
+/// Citekeys of the bibliography after `[@`.
+pub struct Citekeys(pub Vec<String>);
+
+impl Source for Citekeys {
+    fn suggest(&self, before_cursor: &str, _document: &Document) -> Option<(usize, Vec<String>)> {
+        let typed = citation::typed(before_cursor)?;
+        let keys: Vec<_> = self
+            .0
+            .iter()
+            .filter(|key| key.starts_with(typed) && *key != typed)
+            .cloned()
+            .collect();
+        Some((typed.graphemes(true).count(), keys))
+    }
+}
+
+/// Suggestions shown while typing, `C-n`/`C-p` select one and `Tab` inserts it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Completion {
+    pub items: Vec<String>,
+    pub active: usize,
+    /// Graphemes left of the cursor replaced by the selected item
+    pub typed: usize,
+}
+
+impl Completion {
+    pub fn new(sources: &[&dyn Source], before_cursor: &str, document: &Document) -> Option<Self> {
+        sources.iter().find_map(|source| {
+            let (typed, items) = source.suggest(before_cursor, document)?;
+            (!items.is_empty()).then_some(Self {
+                items,
+                active: 0,
+                typed,
+            })
+        })
+    }
+
+    pub fn selected(&self) -> Option<&str> {
+        self.items.get(self.active).map(String::as_str)
+    }
+}
+
+impl Commandee for Completion {
+    fn command(&mut self, command: &Self::Command) -> Self::Response {
+        match command {
+            Command::Up => self.active = self.active.checked_sub(1).unwrap_or(self.items.len() - 1),
+            Command::Down => self.active = (self.active + 1) % self.items.len(),
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl Render for Completion {
+    fn render(&self, _state: &ApplicationState) -> Html {
+        html! {
+            <div class={classes!("fixed", "bottom-10", "right-2", "bg-gray-700", "rounded", "ring-2", "ring-gray-400", "p-2")}>
+                {for self.items.iter().enumerate().map(|(i, item)| html!{
+                    <p class={classes!((i == self.active).then_some("bg-gray-500"))}>{item}</p>
+                })}
+            </div>
+        }
+    }
+}
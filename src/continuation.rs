@@ -0,0 +1,177 @@
+/// The text inserted for a line break continuing the block and where the cursor ends up.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Continuation {
+    /// Graphemes left of the cursor removed before inserting, to end an empty list item
+    pub remove: usize,
+    pub text: String,
+    /// Cursor position in `text` in graphemes
+    pub cursor: usize,
+}
+
+impl Continuation {
+    fn text(text: String) -> Self {
+        let cursor = text.chars().count();
+        Self {
+            remove: 0,
+            text,
+            cursor,
+        }
+    }
+}
+
+/// Cells of a table row, `None` if `line` is no row.
+fn cells(line: &str) -> Option<Vec<&str>> {
+    let line = line.trim();
+    let inner = line.strip_prefix('|')?.strip_suffix('|').unwrap_or(line);
+    let inner = inner.strip_prefix('|').unwrap_or(inner);
+    Some(inner.split('|').map(str::trim).collect())
+}
+
+fn is_separator(line: &str) -> bool {
+    cells(line).map_or(false, |cells| {
+        cells.iter().all(|cell| {
+            let cell = cell.trim_start_matches(':').trim_end_matches(':');
+            !cell.is_empty() && cell.chars().all(|c| c == '-')
+        })
+    })
+}
+
+/// The indented marker of the item following `line` and whether `line` is an empty item.
+fn list_item(line: &str) -> Option<(String, bool)> {
+    let indent = &line[..line.len() - line.trim_start().len()];
+    let rest = line.trim_start();
+    let (marker, rest) = if let Some(rest) = rest
+        .strip_prefix("- ")
+        .or_else(|| rest.strip_prefix("* "))
+        .or_else(|| rest.strip_prefix("+ "))
+    {
+        (format!("{} ", &line.trim_start()[..1]), rest)
+    } else {
+        let digits = rest.chars().take_while(char::is_ascii_digit).count();
+        let delimiter = rest[digits..].chars().next()?;
+        if digits == 0 || !matches!(delimiter, '.' | ')') || !rest[digits + 1..].starts_with(' ') {
+            return None;
+        }
+        let number: u64 = rest[..digits].parse().ok()?;
+        (format!("{}{} ", number + 1, delimiter), &rest[digits + 2..])
+    };
+    let (task, rest) = match rest
+        .strip_prefix("[ ] ")
+        .or_else(|| rest.strip_prefix("[x] "))
+        .or_else(|| rest.strip_prefix("[X] "))
+    {
+        Some(rest) => ("[ ] ", rest),
+        None => ("", rest),
+    };
+    Some((
+        format!("{}{}{}", indent, marker, task),
+        rest.trim().is_empty(),
+    ))
+}
+
+/// What to insert for a line break after `before_cursor`, the text of the block up to the
+/// cursor.
+///
+/// Table rows get an empty row with the same number of cells, list items and quotes are
+/// continued and an empty list item ends the list.
+pub fn continuation(before_cursor: &str) -> Continuation {
+    let mut lines = before_cursor.rsplit('\n');
+    let line = lines.next().unwrap_or_default();
+    let previous = lines.next();
+
+    let in_table = is_separator(line)
+        || (cells(line).is_some() && previous.map_or(false, |p| cells(p).is_some()));
+    if let (true, Some(row)) = (in_table, cells(line)) {
+        let text = format!("\n|{}", "  |".repeat(row.len()));
+        return Continuation {
+            remove: 0,
+            text,
+            cursor: 3,
+        };
+    }
+
+    if let Some((marker, empty)) = list_item(line) {
+        return if empty {
+            Continuation {
+                remove: line.chars().count(),
+                text: String::new(),
+                cursor: 0,
+            }
+        } else {
+            Continuation::text(format!("\n{}", marker))
+        };
+    }
+
+    let quote: String = line.chars().take_while(|&c| c == '>' || c == ' ').collect();
+    if quote.contains('>') {
+        return Continuation::text(format!("\n{}", quote));
+    }
+    Continuation::text("\n".to_owned())
+}
+
+/// Length of the list marker `line` starts with, like `- ` or `1. `.
+fn marker_len(line: &str) -> Option<usize> {
+    if ["- ", "* ", "+ "]
+        .iter()
+        .any(|marker| line.starts_with(marker))
+    {
+        return Some(2);
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    let rest = &line[digits..];
+    (digits > 0 && (rest.starts_with(". ") || rest.starts_with(") "))).then_some(digits + 2)
+}
+
+/// `text` pasted after `before_cursor`, fitted into the list item the cursor is in.
+///
+/// Pasted items become siblings of that item, keeping their nesting and numbered on from it,
+/// other pasted lines are indented to continue the item.
+pub fn paste(before_cursor: &str, text: &str) -> String {
+    let line = before_cursor.rsplit('\n').next().unwrap_or_default();
+    let (marker, empty) = match list_item(line) {
+        Some(item) => item,
+        None => return text.to_owned(),
+    };
+    let lines: Vec<_> = text.lines().collect();
+    let common = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or_default();
+    let lines: Vec<_> = lines
+        .into_iter()
+        .map(|line| line.get(common..).unwrap_or_default())
+        .collect();
+    let items = lines.iter().any(|line| marker_len(line).is_some());
+    if lines.len() < 2 && !items {
+        return text.to_owned();
+    }
+
+    let indent = &marker[..marker.len() - marker.trim_start().len()];
+    // Pasted items bring their own task box
+    let mut next = marker.strip_suffix("[ ] ").unwrap_or(&marker).to_owned();
+    let mut pasted = String::new();
+    for (i, line) in lines.into_iter().enumerate() {
+        if i > 0 || (items && !empty && marker_len(line).is_some()) {
+            pasted.push('\n');
+        }
+        match marker_len(line) {
+            Some(len) => {
+                if i > 0 || !empty {
+                    pasted.push_str(&next);
+                }
+                pasted.push_str(&line[len..]);
+                next = list_item(&format!("{}-", next)).map_or(next, |(next, _)| next);
+            }
+            None if i == 0 || line.trim().is_empty() => pasted.push_str(line),
+            // Nested lines of pasted items are already indented relative to them
+            None if items && line.starts_with(char::is_whitespace) => {
+                pasted.push_str(&format!("{}{}", indent, line))
+            }
+            None if items => pasted.push_str(&format!("{}{}", " ".repeat(next.len()), line)),
+            None => pasted.push_str(&format!("{}{}", " ".repeat(marker.len()), line)),
+        }
+    }
+    pasted
+}
@@ -0,0 +1,71 @@
+/// Header of the tables made by `:tablify`.
+const HEADER: [&str; 2] = ["Key", "Value"];
+
+/// The key and value of a list item `item` like `Name: value`, a bold `**Name:** value`
+/// keeps its emphasis around the key.
+fn key_value(item: &str) -> (String, String) {
+    let (key, value) = match item.split_once(':') {
+        Some((key, value)) => (key.trim().to_owned(), value.trim().to_owned()),
+        None => return (item.trim().to_owned(), String::new()),
+    };
+    for delimiter in ["**", "__", "*", "_"] {
+        if key.matches(delimiter).count() % 2 == 1 {
+            if let Some(value) = value.strip_prefix(delimiter) {
+                return (format!("{}{}", key, delimiter), value.trim().to_owned());
+            }
+        }
+    }
+    (key, value)
+}
+
+/// A two column table of the `key: value` items of the bullet list `md`, `None` if it is
+/// not one or has nested items.
+pub fn tablify(md: &str) -> Option<String> {
+    let rows = md
+        .lines()
+        .map(|line| {
+            let item = line
+                .strip_prefix("- ")
+                .or_else(|| line.strip_prefix("* "))
+                .or_else(|| line.strip_prefix("+ "))?;
+            let (key, value) = key_value(item);
+            Some([key, value].map(|cell| cell.replace('|', "\\|")))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    if rows.is_empty() {
+        return None;
+    }
+    let mut lines = vec![
+        format!("| {} | {} |", HEADER[0], HEADER[1]),
+        "| --- | --- |".to_owned(),
+    ];
+    lines.extend(
+        rows.iter()
+            .map(|[key, value]| format!("| {} | {} |", key, value)),
+    );
+    Some(lines.join("\n"))
+}
+
+/// A bullet list with a `key: value` item for every row but the header of a table, the
+/// cells after the first one make up the value.
+pub fn listify(rows: &[Vec<String>]) -> String {
+    rows.iter()
+        .skip(1)
+        .map(|row| {
+            let key = row.first().map(String::as_str).unwrap_or_default();
+            let value = row[1.min(row.len())..]
+                .iter()
+                .filter(|cell| !cell.trim().is_empty())
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join(", ");
+            let item = if value.is_empty() {
+                key.to_owned()
+            } else {
+                format!("{}: {}", key, value)
+            };
+            format!("- {}", item.replace("\\|", "|"))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,73 @@
+use std::ops::Range;
+
+/// The length of the `{++`, `++}`, `{--` and `--}` delimiters.
+pub const DELIMITER: usize = 3;
+
+/// Whether a suggested change adds or removes its text.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    /// `{++added++}`
+    Addition,
+    /// `{--removed--}`
+    Deletion,
+}
+
+/// A pending CriticMarkup change, its `range` includes the delimiters.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Change {
+    pub kind: Kind,
+    pub range: Range<usize>,
+}
+
+impl Change {
+    /// The range of the text added or removed, between the delimiters.
+    pub fn content(&self) -> Range<usize> {
+        self.range.start + DELIMITER..self.range.end - DELIMITER
+    }
+
+    /// What is left of the change in `text` once it is accepted or rejected.
+    pub fn resolve<'a>(&self, text: &'a str, accept: bool) -> &'a str {
+        if accept == (self.kind == Kind::Addition) {
+            &text[self.content()]
+        } else {
+            ""
+        }
+    }
+}
+
+/// The suggested additions and deletions in `text` by position.
+pub fn changes(text: &str) -> Vec<Change> {
+    let mut changes = vec![];
+    let mut offset = 0;
+    while let Some(start) = text[offset..].find('{').map(|start| offset + start) {
+        offset = start + 1;
+        let (kind, close) = match &text[start..] {
+            rest if rest.starts_with("{++") => (Kind::Addition, "++}"),
+            rest if rest.starts_with("{--") => (Kind::Deletion, "--}"),
+            _ => continue,
+        };
+        if let Some(end) = text[start + DELIMITER..].find(close) {
+            let end = start + DELIMITER + end + DELIMITER;
+            changes.push(Change {
+                kind,
+                range: start..end,
+            });
+            offset = end;
+        }
+    }
+    changes
+}
+
+/// `text` with all its changes accepted or rejected, and how many there were.
+pub fn resolve_all(text: &str, accept: bool) -> (String, usize) {
+    let changes = changes(text);
+    let mut resolved = String::new();
+    let mut offset = 0;
+    for change in &changes {
+        resolved.push_str(&text[offset..change.range.start]);
+        resolved.push_str(change.resolve(text, accept));
+        offset = change.range.end;
+    }
+    resolved.push_str(&text[offset..]);
+    (resolved, changes.len())
+}
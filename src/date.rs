@@ -0,0 +1,70 @@
+use js_sys::Date;
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+const WEEKDAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+pub const ISO: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// The current local time formatted with the strftime like `format`.
+pub fn now(format: &str) -> String {
+    format_date(&Date::new_0(), format)
+}
+
+/// Supports `%Y %y %m %d %e %H %I %M %S %p %B %b %A %a %F %T %%`.
+pub fn format_date(date: &Date, format: &str) -> String {
+    let mut out = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        let hours = date.get_hours();
+        match chars.next() {
+            Some('Y') => out.push_str(&date.get_full_year().to_string()),
+            Some('y') => out.push_str(&format!("{:02}", date.get_full_year() % 100)),
+            Some('m') => out.push_str(&format!("{:02}", date.get_month() + 1)),
+            Some('d') => out.push_str(&format!("{:02}", date.get_date())),
+            Some('e') => out.push_str(&date.get_date().to_string()),
+            Some('H') => out.push_str(&format!("{:02}", hours)),
+            Some('I') => out.push_str(&format!("{:02}", (hours + 11) % 12 + 1)),
+            Some('M') => out.push_str(&format!("{:02}", date.get_minutes())),
+            Some('S') => out.push_str(&format!("{:02}", date.get_seconds())),
+            Some('p') => out.push_str(if hours < 12 { "AM" } else { "PM" }),
+            Some('B') => out.push_str(MONTHS[date.get_month() as usize]),
+            Some('b') => out.push_str(&MONTHS[date.get_month() as usize][..3]),
+            Some('A') => out.push_str(WEEKDAYS[date.get_day() as usize]),
+            Some('a') => out.push_str(&WEEKDAYS[date.get_day() as usize][..3]),
+            Some('F') => out.push_str(&format_date(date, "%Y-%m-%d")),
+            Some('T') => out.push_str(&format_date(date, "%H:%M:%S")),
+            Some('%') => out.push('%'),
+            Some(other) => {
+                out.push('%');
+                out.push(other);
+            }
+            None => out.push('%'),
+        }
+    }
+    out
+}
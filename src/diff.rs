@@ -0,0 +1,157 @@
+use yew::{classes, html, Html};
+
+use crate::{document::Render, ApplicationState};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change<T> {
+    Equal(T),
+    Insert(T),
+    Delete(T),
+}
+
+/// Computes the changes turning `old` into `new` using the longest common subsequence.
+pub fn diff<T: PartialEq + Clone>(old: &[T], new: &[T]) -> Vec<Change<T>> {
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut changes = vec![];
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            changes.push(Change::Equal(old[i].clone()));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            changes.push(Change::Delete(old[i].clone()));
+            i += 1;
+        } else {
+            changes.push(Change::Insert(new[j].clone()));
+            j += 1;
+        }
+    }
+    changes.extend(old[i..].iter().cloned().map(Change::Delete));
+    changes.extend(new[j..].iter().cloned().map(Change::Insert));
+    changes
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DiffLine {
+    Equal(String),
+    Insert(String),
+    Delete(String),
+    /// A line that was modified, with the changes word by word
+    Changed(Vec<Change<String>>),
+}
+
+/// Read only view of the differences between two versions of a note.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diff {
+    pub title: String,
+    pub lines: Vec<DiffLine>,
+}
+
+impl Diff {
+    pub fn new(title: String, old: &str, new: &str) -> Self {
+        let old: Vec<_> = old.lines().collect();
+        let new: Vec<_> = new.lines().collect();
+        let mut lines = vec![];
+        let mut deleted = vec![];
+        let mut inserted = vec![];
+        for change in diff(&old, &new) {
+            match change {
+                Change::Delete(line) => deleted.push(line),
+                Change::Insert(line) => inserted.push(line),
+                Change::Equal(line) => {
+                    pair_changes(&mut lines, &mut deleted, &mut inserted);
+                    lines.push(DiffLine::Equal(line.to_owned()));
+                }
+            }
+        }
+        pair_changes(&mut lines, &mut deleted, &mut inserted);
+        Self { title, lines }
+    }
+}
+
+/// Turns a block of deleted and inserted lines into word level changes where they line up.
+fn pair_changes(lines: &mut Vec<DiffLine>, deleted: &mut Vec<&str>, inserted: &mut Vec<&str>) {
+    let paired = deleted.len().min(inserted.len());
+    for (old, new) in deleted.iter().zip(inserted.iter()) {
+        lines.push(DiffLine::Changed(diff(&words(old), &words(new))));
+    }
+    lines.extend(
+        deleted[paired..]
+            .iter()
+            .map(|line| DiffLine::Delete(line.to_string())),
+    );
+    lines.extend(
+        inserted[paired..]
+            .iter()
+            .map(|line| DiffLine::Insert(line.to_string())),
+    );
+    deleted.clear();
+    inserted.clear();
+}
+
+/// Splits a line into alternating runs of whitespace and non whitespace.
+fn words(line: &str) -> Vec<String> {
+    let mut words: Vec<String> = vec![];
+    let mut last_whitespace = None;
+    for c in line.chars() {
+        let whitespace = c.is_whitespace();
+        match words.last_mut() {
+            Some(word) if last_whitespace == Some(whitespace) => word.push(c),
+            _ => words.push(c.to_string()),
+        }
+        last_whitespace = Some(whitespace);
+    }
+    words
+}
+
+impl Render for DiffLine {
+    fn render(&self, _state: &ApplicationState) -> Html {
+        match self {
+            DiffLine::Equal(line) => html! {
+                <p>{"  "}{line}</p>
+            },
+            DiffLine::Insert(line) => html! {
+                <p class={classes!("bg-green-900", "text-green-300")}>{"+ "}{line}</p>
+            },
+            DiffLine::Delete(line) => html! {
+                <p class={classes!("bg-red-900", "text-red-300")}>{"- "}{line}</p>
+            },
+            DiffLine::Changed(words) => html! {
+                <p>
+                    {"~ "}
+                    {for words.iter().map(|word| match word {
+                        Change::Equal(word) => html!{<span>{word}</span>},
+                        Change::Insert(word) => html!{
+                            <span class={classes!("bg-green-900", "text-green-300")}>{word}</span>
+                        },
+                        Change::Delete(word) => html!{
+                            <span class={classes!("bg-red-900", "text-red-300", "line-through")}>{word}</span>
+                        },
+                    })}
+                </p>
+            },
+        }
+    }
+}
+
+impl Render for Diff {
+    fn render(&self, state: &ApplicationState) -> Html {
+        html! {
+            <div>
+                <p class={classes!("font-bold")}>{&self.title}</p>
+                {for self.lines.iter().map(|line| line.render(state))}
+            </div>
+        }
+    }
+}
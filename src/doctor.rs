@@ -0,0 +1,115 @@
+use std::collections::HashSet;
+
+use crate::{
+    document::{grapheme_index, Document, Element, Location, Markdown},
+    links::{self, Target},
+};
+
+/// Problems with the structure of `document` for `:doctor`, with where they start.
+///
+/// Reports skipped heading levels, headings without content, tables without a header and
+/// links to missing definitions or headings, as well as definitions nothing links to.
+pub fn diagnose(document: &Document) -> Vec<(String, Location, usize)> {
+    let mut diagnostics = vec![];
+    let elements = &document.elements;
+
+    let mut previous_level = None;
+    for (i, element) in elements.iter().enumerate() {
+        match element {
+            Element::Heading(heading) => {
+                let title = heading.content.text.concat();
+                if let Some(previous) = previous_level {
+                    if heading.level > previous + 1 {
+                        diagnostics.push((
+                            format!(
+                                "Heading \"{}\" skips from level {} to {}",
+                                title, previous, heading.level
+                            ),
+                            (i, None),
+                            0,
+                        ));
+                    }
+                }
+                previous_level = Some(heading.level);
+                let empty = match elements.get(i + 1) {
+                    Some(Element::Heading(next)) => next.level <= heading.level,
+                    Some(_) => false,
+                    None => true,
+                };
+                if empty {
+                    diagnostics.push((format!("Section \"{}\" is empty", title), (i, None), 0));
+                }
+            }
+            Element::Table(table) => {
+                let header_empty = (0..table.width).all(|x| {
+                    table
+                        .cell(x, 0)
+                        .map_or(true, |cell| cell.text.concat().trim().is_empty())
+                });
+                if header_empty {
+                    diagnostics.push(("Table has no header".to_owned(), (i, Some((0, 0))), 0));
+                }
+            }
+            Element::Definitions(_)
+            | Element::Details(_)
+            | Element::Frontmatter(_)
+            | Element::Paragraph(_) => {}
+        }
+    }
+
+    let md = document.to_md();
+    let definitions: HashSet<String> = links::definitions(&md)
+        .into_iter()
+        .map(|definition| links::normalize(&definition.id))
+        .collect();
+    let anchors: HashSet<String> = document
+        .anchors()
+        .into_iter()
+        .map(|(_, anchor)| anchor)
+        .collect();
+    let paragraphs = document.paragraphs();
+    let mut used = HashSet::new();
+    for (location, paragraph) in &paragraphs {
+        let source = paragraph.text.concat();
+        let index = grapheme_index(&paragraph.text);
+        for link in links::links(&source) {
+            let problem = match &link.target {
+                Target::Reference(id) => {
+                    let id = links::normalize(id);
+                    let missing = !definitions.contains(&id);
+                    used.insert(id);
+                    missing.then(|| format!("Link \"{}\" has no definition", link.text))
+                }
+                Target::Inline(destination) => destination
+                    .strip_prefix('#')
+                    .filter(|anchor| !anchors.contains(*anchor))
+                    .map(|anchor| {
+                        format!(
+                            "Link \"{}\" points to missing heading #{}",
+                            link.text, anchor
+                        )
+                    }),
+            };
+            if let Some(problem) = problem {
+                diagnostics.push((problem, *location, index(link.range.start)));
+            }
+        }
+    }
+    for (location, paragraph) in paragraphs {
+        let mut offset = 0;
+        for line in paragraph.text.split(|c| c == "\n") {
+            let unused = links::definition(&line.concat())
+                .filter(|definition| !used.contains(&links::normalize(&definition.id)));
+            if let Some(definition) = unused {
+                diagnostics.push((
+                    format!("Definition [{}] is not used", definition.id),
+                    location,
+                    offset,
+                ));
+            }
+            offset += line.len() + 1;
+        }
+    }
+    diagnostics.sort_by_key(|(_, location, cursor)| (*location, *cursor));
+    diagnostics
+}
@@ -1,21 +1,36 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    hash::{Hash, Hasher},
+    mem,
+    ops::Range,
+};
 
 use derive_more::Deref;
-use gloo_console::console_dbg as dbg;
-use pulldown_cmark::{Event, Tag};
+use pulldown_cmark::{Event, Parser, Tag};
 use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthStr;
-use yew::{classes, html, Classes, Html};
+use web_sys::window;
+use yew::{classes, events::MouseEvent, html, Callback, Classes, Html};
 use Command::*;
 
-use crate::ApplicationState;
+use crate::{
+    attachment,
+    callout::Callout,
+    citation, comment, critic, entity,
+    flavor::Flavor,
+    footnote, frontmatter, limits,
+    links::{self, Target},
+    log,
+    markdown::write_md,
+    pairs, query, render_cache, sentence, spell, tasks, transclusion, ApplicationState, TextStyle,
+};
 
 pub trait Markdown<'a> {
     fn parse_from_md<T>(md: &mut T) -> Self
     where
         T: Iterator<Item = Event<'a>>;
 
-    fn to_md(self) -> String;
+    fn to_md(&self) -> String;
 }
 
 pub trait Commandee {
@@ -28,38 +43,631 @@ pub trait Render {
     fn render(&self, state: &ApplicationState) -> Html;
 }
 
+/// The element of a paragraph and its cell if the element is a table.
+pub type Location = (usize, Option<(usize, usize)>);
+
 pub struct Document {
     pub elements: Vec<Element>,
     pub active_element: usize,
+    pub flavor: Flavor,
+    /// Anchors of the headings whose sections are folded
+    pub folded: Vec<String>,
+}
+
+impl Document {
+    /// Splits `md` into blocks, keeping the source of everything but tables.
+    ///
+    /// Text between blocks, like link reference definitions, is kept as paragraphs so it
+    /// survives a round trip. The extensions parsed depend on the `flavor` in the
+    /// frontmatter.
+    pub fn from_md(md: &str) -> Self {
+        let (frontmatter, md) = frontmatter::split(md);
+        let flavor = frontmatter
+            .and_then(|frontmatter| frontmatter::get(frontmatter, "flavor"))
+            .and_then(Flavor::from_name)
+            .unwrap_or_default();
+        let mut document = Self {
+            active_element: 0,
+            elements: Self::blocks(md, flavor),
+            flavor,
+            folded: vec![],
+        };
+        if document.elements.is_empty() {
+            document
+                .elements
+                .push(Element::Paragraph(Paragraph::default()));
+        }
+        // The cursor starts in the body
+        if let Some(frontmatter) = frontmatter {
+            let frontmatter = Element::Frontmatter(Paragraph::from(frontmatter));
+            document.elements.insert(0, frontmatter);
+            document.active_element = 1;
+        }
+        document.command(&CursorEnterH(false));
+        document
+    }
+
+    /// The `key: value` lines of the frontmatter.
+    pub fn frontmatter(&self) -> Option<String> {
+        match self.elements.first()? {
+            Element::Frontmatter(frontmatter) => Some(frontmatter.text.concat()),
+            _ => None,
+        }
+    }
+
+    /// The elements of the blocks of `md`, which has no frontmatter.
+    fn blocks(md: &str, flavor: Flavor) -> Vec<Element> {
+        let mut events = Parser::new_ext(md, flavor.options())
+            .into_offset_iter()
+            .peekable();
+        let mut elements = vec![];
+        let mut end = 0;
+        let push_gap = |elements: &mut Vec<Element>, gap: &str| {
+            let gap = gap.trim();
+            if !gap.is_empty() {
+                elements.push(Element::Paragraph(Paragraph::from(gap)));
+            }
+        };
+        while let Some((event, range)) = events.next() {
+            if range.start >= end {
+                push_gap(&mut elements, &md[end..range.start]);
+            }
+            let start = range.start;
+            end = end.max(range.end);
+            let mut ranges = vec![range];
+            let mut block = vec![event];
+            let mut depth = matches!(block[0], Event::Start(_)) as usize;
+            while depth > 0 {
+                let (event, range) = match events.next() {
+                    Some(next) => next,
+                    None => break,
+                };
+                match event {
+                    Event::Start(_) => depth += 1,
+                    Event::End(_) => depth -= 1,
+                    _ => {}
+                }
+                end = end.max(range.end);
+                ranges.push(range);
+                block.push(event);
+            }
+            // Html blocks are split into an event per line
+            if matches!(block[0], Event::Html(_)) {
+                while let Some((Event::Html(_), range)) = events.peek() {
+                    end = end.max(range.end);
+                    events.next();
+                }
+                // A details block goes on after blank lines up to its closing tag
+                if md[start..].starts_with("<details") {
+                    if let Some(length) = Details::length(&md[start..]) {
+                        end = end.max(start + length);
+                        while let Some((_, range)) = events.peek() {
+                            if range.start >= end {
+                                break;
+                            }
+                            end = end.max(range.end);
+                            events.next();
+                        }
+                    }
+                }
+            }
+            let source = md[start..end].trim_end();
+            elements.push(match block[0] {
+                Event::Start(Tag::Heading(level)) => {
+                    Element::Heading(Heading::from_source(level, source))
+                }
+                Event::Start(Tag::Table(_)) => {
+                    Element::Table(Table::from_source(&block, &ranges, md))
+                }
+                Event::Start(Tag::Paragraph) if flavor.definition_lists() => {
+                    match DefinitionList::parse(source) {
+                        Some(list) => Element::Definitions(list),
+                        None => Element::Paragraph(Paragraph::from(source)),
+                    }
+                }
+                Event::Html(_) => match Details::parse(source) {
+                    Some(details) => Element::Details(details),
+                    None => Element::Paragraph(Paragraph::from(source)),
+                },
+                _ => Element::Paragraph(Paragraph::from(source)),
+            });
+        }
+        push_gap(&mut elements, &md[end..]);
+        elements
+    }
+
+    /// Inserts `text` at the cursor, text of more than one block is parsed into elements
+    /// at once instead of being inserted into the paragraph grapheme by grapheme.
+    ///
+    /// The text after the cursor continues the last block pasted. In tables and headings,
+    /// and for text without a blank line, it is inserted like typed text.
+    pub fn paste(&mut self, text: &str) {
+        let index = self.active_element;
+        let paragraph = match self.elements.get_mut(index) {
+            Some(Element::Paragraph(paragraph)) if text.trim().contains("\n\n") => paragraph,
+            _ => {
+                self.command(&Insert(text.into()));
+                return;
+            }
+        };
+        let cursor = paragraph
+            .cursor
+            .unwrap_or_default()
+            .min(paragraph.text.len());
+        let after = paragraph.text.split_off(cursor).concat();
+        let md = format!("{}{}", paragraph.text.concat(), text);
+        let mut elements = Self::blocks(&md, self.flavor);
+        let ends_block = text.trim_end_matches(' ').ends_with("\n\n");
+        let after = after.trim_start_matches('\n');
+        let focus = match elements.last_mut() {
+            Some(Element::Paragraph(last)) if !ends_block => {
+                let cursor = last.text.len();
+                last.text.extend(after.graphemes(true).map(String::from));
+                (elements.len() - 1, None, cursor)
+            }
+            _ if !after.trim().is_empty() => {
+                elements.push(Element::Paragraph(Paragraph::from(after)));
+                (elements.len() - 1, None, 0)
+            }
+            Some(Element::Table(table)) => {
+                let cell = (table.width - 1, table.height - 1);
+                let cursor = table.cells.get(&cell).map_or(0, |cell| cell.text.len());
+                (elements.len() - 1, Some(cell), cursor)
+            }
+            Some(Element::Heading(heading)) => {
+                (elements.len() - 1, None, heading.content.text.len())
+            }
+            Some(Element::Definitions(list)) => {
+                let last = list.items.len() - 1;
+                let cursor = list.item(last).map_or(0, |item| item.text.len());
+                (elements.len() - 1, Some((0, last)), cursor)
+            }
+            Some(Element::Details(details)) => {
+                let item = details.first();
+                let cursor = details.item(item).map_or(0, |item| item.text.len());
+                (elements.len() - 1, Some((0, item)), cursor)
+            }
+            Some(Element::Paragraph(last) | Element::Frontmatter(last)) => {
+                (elements.len() - 1, None, last.text.len())
+            }
+            None => {
+                elements.push(Element::Paragraph(Paragraph::default()));
+                (0, None, 0)
+            }
+        };
+        let (element, cell, cursor) = focus;
+        self.elements.splice(index..index + 1, elements);
+        self.set_cursor((index + element, cell), cursor);
+    }
+
+    /// Switches to `flavor`, recording it in the frontmatter and parsing the note again.
+    pub fn set_flavor(&mut self, flavor: Flavor) {
+        self.set_frontmatter("flavor", flavor.name());
+        self.replace(&self.to_md());
+    }
+
+    /// Parses `md` as the new content, keeping the folded headings.
+    pub fn replace(&mut self, md: &str) {
+        let folded = mem::take(&mut self.folded);
+        *self = Self::from_md(md);
+        self.folded = folded;
+    }
+
+    /// Sets `key` in the frontmatter, adding one if the note has none.
+    pub fn set_frontmatter(&mut self, key: &str, value: &str) {
+        let frontmatter = self.frontmatter().unwrap_or_default();
+        self.replace_frontmatter(&frontmatter::set(&frontmatter, key, value));
+    }
+
+    /// Replaces the `key: value` lines of the frontmatter, adding one if the note has none.
+    pub fn replace_frontmatter(&mut self, frontmatter: &str) {
+        let mut replaced = Paragraph::from(frontmatter);
+        match self.elements.first_mut() {
+            Some(Element::Frontmatter(paragraph)) => {
+                replaced.cursor = paragraph
+                    .cursor
+                    .map(|cursor| cursor.min(replaced.text.len()));
+                *paragraph = replaced;
+            }
+            _ => {
+                self.elements.insert(0, Element::Frontmatter(replaced));
+                self.active_element += 1;
+            }
+        }
+    }
+
+    /// The spell language from the `spelllang` in the frontmatter.
+    pub fn spell_lang(&self) -> String {
+        self.frontmatter()
+            .and_then(|frontmatter| frontmatter::get(&frontmatter, "spelllang").map(String::from))
+            .unwrap_or_else(|| spell::DEFAULT_LANG.to_owned())
+    }
+
+    /// Indices of the headings with their unique anchor.
+    pub fn anchors(&self) -> Vec<(usize, String)> {
+        let mut anchors: Vec<(usize, String)> = vec![];
+        for (i, element) in self.elements.iter().enumerate() {
+            if let Element::Heading(heading) = element {
+                let slug = slug(&heading.content.text.concat());
+                let anchor = (0..)
+                    .map(|n| {
+                        if n == 0 {
+                            slug.clone()
+                        } else {
+                            format!("{}-{}", slug, n)
+                        }
+                    })
+                    .find(|anchor| anchors.iter().all(|(_, a)| a != anchor))
+                    .unwrap();
+                anchors.push((i, anchor));
+            }
+        }
+        anchors
+    }
+
+    /// Which elements are hidden in the section of a folded heading, the active one never is.
+    pub fn hidden(&self) -> Vec<bool> {
+        let mut hidden = vec![false; self.elements.len()];
+        if self.folded.is_empty() {
+            return hidden;
+        }
+        let anchors = self.anchors();
+        let mut fold = None;
+        for (i, element) in self.elements.iter().enumerate() {
+            if let Element::Heading(heading) = element {
+                if fold.map_or(false, |level| heading.level <= level) {
+                    fold = None;
+                }
+                let folded = anchors
+                    .iter()
+                    .any(|(heading, anchor)| *heading == i && self.folded.contains(anchor));
+                if fold.is_none() && folded {
+                    fold = Some(heading.level);
+                    continue;
+                }
+            }
+            hidden[i] = fold.is_some() && i != self.active_element;
+        }
+        hidden
+    }
+
+    /// Folds or unfolds the section the active element is in, moving the cursor to its
+    /// heading when it gets hidden.
+    ///
+    /// Returns `false` if it is not under a heading.
+    pub fn toggle_fold(&mut self) -> bool {
+        let anchors = self.anchors();
+        let (heading, anchor) = match anchors
+            .iter()
+            .rev()
+            .find(|(heading, _)| *heading <= self.active_element)
+        {
+            Some(found) => found.clone(),
+            None => return false,
+        };
+        match self.folded.iter().position(|folded| *folded == anchor) {
+            Some(i) => {
+                self.folded.remove(i);
+            }
+            None => {
+                self.folded.push(anchor);
+                if self.active_element != heading {
+                    self.elements[self.active_element].command(&CursorLeave);
+                    self.active_element = heading;
+                    self.elements[heading].command(&CursorEnterH(false));
+                }
+            }
+        }
+        true
+    }
+
+    /// The elements of the section the cursor is in, from the nearest heading above it to
+    /// the next heading of its level or higher.
+    pub fn section(&self) -> Option<Range<usize>> {
+        let (start, level) = self.elements[..=self.active_element]
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(i, element)| match element {
+                Element::Heading(heading) => Some((i, heading.level)),
+                _ => None,
+            })?;
+        let end = self.elements[start + 1..]
+            .iter()
+            .position(
+                |element| matches!(element, Element::Heading(heading) if heading.level <= level),
+            )
+            .map_or(self.elements.len(), |i| start + 1 + i);
+        Some(start..end)
+    }
+
+    /// Indices of the headings with their outline number like `1.2`, for `:set headingnumbers`.
+    ///
+    /// Numbering starts at the highest level used, a skipped level counts as 0.
+    pub fn heading_numbers(&self) -> Vec<(usize, String)> {
+        let headings: Vec<_> = self
+            .elements
+            .iter()
+            .enumerate()
+            .filter_map(|(i, element)| match element {
+                Element::Heading(heading) => Some((i, heading.level)),
+                _ => None,
+            })
+            .collect();
+        let top = headings.iter().map(|(_, level)| *level).min().unwrap_or(1);
+        let mut counters: Vec<usize> = vec![];
+        headings
+            .into_iter()
+            .map(|(i, level)| {
+                let depth = (level - top + 1) as usize;
+                counters.resize(depth, 0);
+                counters[depth - 1] += 1;
+                let number = counters
+                    .iter()
+                    .map(usize::to_string)
+                    .collect::<Vec<_>>()
+                    .join(".");
+                if depth == 1 {
+                    (i, format!("{}.", number))
+                } else {
+                    (i, number)
+                }
+            })
+            .collect()
+    }
+
+    /// All paragraphs in document order, table cells row by row.
+    pub fn paragraphs(&self) -> Vec<(Location, &Paragraph)> {
+        let mut paragraphs = vec![];
+        for (i, element) in self.elements.iter().enumerate() {
+            match element {
+                Element::Table(table) => {
+                    for y in 0..table.height {
+                        for x in 0..table.width {
+                            if let Some(cell) = table.cell(x, y) {
+                                paragraphs.push(((i, Some((x, y))), cell));
+                            }
+                        }
+                    }
+                }
+                Element::Heading(heading) => paragraphs.push(((i, None), &heading.content)),
+                Element::Frontmatter(paragraph) => paragraphs.push(((i, None), paragraph)),
+                Element::Definitions(list) => paragraphs.extend(
+                    list.items
+                        .iter()
+                        .enumerate()
+                        .map(|(item, (_, paragraph))| ((i, Some((0, item))), paragraph)),
+                ),
+                Element::Details(details) => {
+                    if let Some(summary) = &details.summary {
+                        paragraphs.push(((i, Some((0, 0))), summary));
+                    }
+                    paragraphs.push(((i, Some((0, 1))), &details.body));
+                }
+                Element::Paragraph(paragraph) => paragraphs.push(((i, None), paragraph)),
+            }
+        }
+        paragraphs
+    }
+
+    pub fn paragraphs_mut(&mut self) -> Vec<&mut Paragraph> {
+        self.elements
+            .iter_mut()
+            .flat_map(Element::paragraphs_mut)
+            .collect()
+    }
+
+    /// Whether there is a paragraph at `location`, which may be gone after edits.
+    pub fn contains(&self, (element, cell): Location) -> bool {
+        match (self.elements.get(element), cell) {
+            (Some(Element::Table(table)), Some(cell)) => table.cells.contains_key(&cell),
+            (Some(Element::Definitions(list)), Some((_, item))) => item < list.items.len(),
+            (Some(Element::Details(details)), Some((_, item))) => details.item(item).is_some(),
+            (Some(Element::Table(_) | Element::Definitions(_) | Element::Details(_)), None) => {
+                false
+            }
+            (Some(_), _) => true,
+            (None, _) => false,
+        }
+    }
+
+    /// Moves the cursor to `cursor` in the paragraph at `location`, if it is [`contains`]ed.
+    ///
+    /// [`contains`]: Self::contains
+    pub fn set_cursor(&mut self, (element, cell): Location, cursor: usize) {
+        if !self.contains((element, cell)) {
+            return;
+        }
+        match self.elements.get_mut(self.active_element) {
+            Some(Element::Table(table)) => {
+                if let Some(cell) = table.active_cell.take() {
+                    table.leave(cell);
+                }
+            }
+            Some(Element::Definitions(list)) => {
+                list.command(&CursorLeave);
+            }
+            Some(active) => {
+                active.command(&CursorLeave);
+            }
+            None => {}
+        }
+        self.active_element = element;
+        let paragraph = match (&mut self.elements[element], cell) {
+            (Element::Table(table), Some(cell)) => {
+                table.active_cell = Some(cell);
+                table.cells.get_mut(&cell).unwrap()
+            }
+            (Element::Definitions(list), Some((_, item))) => {
+                list.active_item = Some(item);
+                &mut list.items[item].1
+            }
+            (Element::Details(details), Some((_, item))) => {
+                // Jumping into the body of a closed block opens it
+                details.open |= item == 1;
+                details.active_item = Some(item);
+                details.item_mut(item)
+            }
+            (Element::Heading(heading), _) => &mut heading.content,
+            (Element::Frontmatter(paragraph), _) => paragraph,
+            (Element::Paragraph(paragraph), _) => paragraph,
+            (Element::Table(_) | Element::Definitions(_) | Element::Details(_), None) => return,
+        };
+        paragraph.cursor = Some(cursor.min(paragraph.text.len()));
+    }
+
+    /// The caption of the table at `index`, from a pandoc style `Table: caption` paragraph
+    /// right after it.
+    fn caption(&self, index: usize) -> Option<String> {
+        match self.elements.get(index + 1)? {
+            Element::Paragraph(paragraph) => {
+                let text = paragraph.text.concat();
+                let caption = text
+                    .strip_prefix("Table:")
+                    .or_else(|| text.strip_prefix(':'))?;
+                Some(caption.trim().to_owned())
+            }
+            _ => None,
+        }
+    }
+
+    /// The table holding the cursor.
+    pub fn active_table_mut(&mut self) -> Option<&mut Table> {
+        match self.elements.get_mut(self.active_element)? {
+            Element::Table(table) => Some(table),
+            _ => None,
+        }
+    }
+
+    /// The paragraph holding the cursor.
+    pub fn active_paragraph_mut(&mut self) -> Option<&mut Paragraph> {
+        match self.elements.get_mut(self.active_element)? {
+            Element::Table(table) => table.cells.get_mut(&table.active_cell?),
+            Element::Definitions(list) => Some(&mut list.items[list.active_item?].1),
+            Element::Details(details) => Some(details.item_mut(details.active_item?)),
+            Element::Heading(heading) => Some(&mut heading.content),
+            Element::Frontmatter(paragraph) => Some(paragraph),
+            Element::Paragraph(paragraph) => Some(paragraph),
+        }
+    }
 }
 
 impl Render for Document {
     fn render(&self, state: &ApplicationState) -> Html {
+        let anchors = self.anchors();
+        let numbers = if state.heading_numbers {
+            self.heading_numbers()
+        } else {
+            vec![]
+        };
+        let fingerprint = state.fingerprint();
+        let hidden = self.hidden();
+        // Notes over the limits only render the elements around the cursor
+        let visible = if state.degraded {
+            self.active_element.saturating_sub(limits::VISIBLE)
+                ..self.active_element + limits::VISIBLE + 1
+        } else {
+            0..self.elements.len()
+        };
+        let (above, below) = (
+            visible.start,
+            self.elements.len().saturating_sub(visible.end),
+        );
+        let more = |count: usize, place: &str| {
+            (count > 0).then(|| {
+                html! {
+                    <p class={classes!("text-gray-500")}>{format!("⋯ {} more blocks {}", count, place)}</p>
+                }
+            })
+        };
+        let folded = |i: usize| {
+            anchors
+                .iter()
+                .any(|(heading, anchor)| *heading == i && self.folded.contains(anchor))
+        };
         html! {
-            {for self.elements.iter().map(|e|e.render(state))}
+            <div class={classes!(state.sidenotes.then_some("xl:mr-72"))}>
+                {for more(above, "above")}
+                {for self.elements.iter().enumerate().filter(|(i, _)| !hidden[*i] && visible.contains(i)).map(|(i, e)| {
+                    let number = numbers
+                        .iter()
+                        .find(|(heading, _)| *heading == i)
+                        .map(|(_, number)| number.as_str());
+                    // Transcluded notes, query results and callouts show unless the cursor is
+                    // in them
+                    let text = match e {
+                        Element::Paragraph(paragraph) if i != self.active_element => {
+                            Some(paragraph.text.concat())
+                        }
+                        _ => None,
+                    };
+                    let render = || {
+                        if let Some(text) = &text {
+                            if let Some(note) = transclusion::target(text) {
+                                return transclusion::render(note, state);
+                            }
+                            if let Some(query) = query::source(text) {
+                                return query::render(query, state);
+                            }
+                            if let Some(callout) = Callout::parse(text) {
+                                return callout.render(state);
+                            }
+                        }
+                        match e {
+                            Element::Table(table) => table.render_captioned(state, self.caption(i)),
+                            Element::Heading(heading) => heading.render_numbered(state, number),
+                            e => e.render(state),
+                        }
+                    };
+                    // The active element changes with every keystroke, the others are reused
+                    let element = if i == self.active_element {
+                        render()
+                    } else {
+                        let key = render_cache::key((e, number, self.caption(i), fingerprint));
+                        state.cache.get_or_render(key, render)
+                    };
+                    let element = if folded(i) {
+                        html! {<>{element}<p class={classes!("text-gray-500")}>{"⋯"}</p></>}
+                    } else {
+                        element
+                    };
+                    match anchors.iter().find(|(heading, _)| *heading == i) {
+                        Some((_, anchor)) => html!{<div id={anchor.clone()}>{element}</div>},
+                        None => element,
+                    }
+                })}
+                {for more(below, "below")}
+            </div>
         }
     }
 }
 
 impl Commandee for Document {
     fn command(&mut self, command: &Self::Command) -> Self::Response {
-        let len = self.elements.len();
+        if self.elements.is_empty() {
+            return false;
+        }
+        // Folded sections are skipped
+        let hidden = self.hidden();
+        let previous = (0..self.active_element).rev().find(|&i| !hidden[i]);
+        let next = (self.active_element + 1..self.elements.len()).find(|&i| !hidden[i]);
         let element = &mut self.elements[self.active_element];
         match (command, element.command(command)) {
             (Command::Up | Command::Left, false) => {
-                if self.active_element > 0 {
+                if let Some(previous) = previous {
                     element.command(&CursorLeave);
-                    self.active_element -= 1;
+                    self.active_element = previous;
                     self.elements[self.active_element].command(&CursorEnterH(true));
                 }
             }
             (Command::Down | Command::Right, false) => {
-                if self.active_element < len - 1 {
+                if let Some(next) = next {
                     element.command(&CursorLeave);
-                    self.active_element += 1;
+                    self.active_element = next;
                     self.elements[self.active_element].command(&CursorEnterH(false));
                 }
             }
+            (_, true) => {}
             _ => return false,
         };
         true
@@ -75,6 +683,8 @@ impl<'a> Markdown<'a> for Document {
         let mut document = Self {
             active_element: 0,
             elements: vec![],
+            flavor: Flavor::default(),
+            folded: vec![],
         };
 
         while md.peek().is_some() {
@@ -86,8 +696,17 @@ impl<'a> Markdown<'a> for Document {
         document
     }
 
-    fn to_md(self) -> String {
-        todo!()
+    fn to_md(&self) -> String {
+        self.elements
+            .iter()
+            // A frontmatter emptied in the editor is left out
+            .filter(|element| {
+                !matches!(element, Element::Frontmatter(frontmatter)
+                    if frontmatter.text.concat().trim().is_empty())
+            })
+            .map(Element::to_md)
+            .collect::<Vec<_>>()
+            .join("\n\n")
     }
 }
 
@@ -110,6 +729,12 @@ pub enum Motion {
     Left,
     Down,
     Right,
+    /// To the start of the word left of the cursor, skipping whitespace first
+    WordLeft,
+    /// To the start of the line, or over the line break when already there
+    LineStart,
+    /// The visual selection
+    Selection,
 }
 
 #[non_exhaustive]
@@ -124,6 +749,14 @@ pub enum Command {
     CursorLeave,
     Insert(Characters),
     Delete(Motion),
+    /// Activates the element under the cursor
+    Enter,
+    /// Jumps to the delimiter matching the one under or after the cursor
+    MatchPair,
+    /// Moves to the start of the next sentence, or of this or the previous one if `false`
+    Sentence(bool),
+    /// Selects the sentence under the cursor, with the whitespace after it if `true`
+    SelectSentence(bool),
 }
 
 impl Command {
@@ -132,14 +765,95 @@ impl Command {
     }
 }
 
+#[derive(Hash)]
 pub enum Element {
     Table(Table),
+    Heading(Heading),
+    Definitions(DefinitionList),
+    Details(Details),
+    /// The `key: value` lines between `---` at the start of the note
+    Frontmatter(Paragraph),
+    /// Any other block, edited as its markdown source
+    Paragraph(Paragraph),
+}
+
+impl Element {
+    /// The paragraphs of the element, table cells by row.
+    pub fn paragraphs_mut(&mut self) -> Vec<&mut Paragraph> {
+        match self {
+            Element::Table(table) => {
+                let mut cells: Vec<_> = table.cells.iter_mut().collect();
+                cells.sort_by_key(|((x, y), _)| (*y, *x));
+                cells.into_iter().map(|(_, cell)| cell).collect()
+            }
+            Element::Heading(heading) => vec![&mut heading.content],
+            Element::Frontmatter(paragraph) => vec![paragraph],
+            Element::Definitions(list) => list
+                .items
+                .iter_mut()
+                .map(|(_, paragraph)| paragraph)
+                .collect(),
+            Element::Details(details) => {
+                let mut paragraphs: Vec<_> = details.summary.iter_mut().collect();
+                paragraphs.push(&mut details.body);
+                paragraphs
+            }
+            Element::Paragraph(paragraph) => vec![paragraph],
+        }
+    }
+
+    pub fn to_md(&self) -> String {
+        match self {
+            Element::Table(table) => table.to_md(),
+            Element::Heading(heading) => heading.to_md(),
+            Element::Definitions(list) => list.to_md(),
+            Element::Details(details) => details.to_md(),
+            Element::Frontmatter(frontmatter) => format!("---\n{}\n---", frontmatter.to_md()),
+            Element::Paragraph(paragraph) => paragraph.to_md(),
+        }
+    }
 }
 
 impl Render for Element {
     fn render(&self, state: &ApplicationState) -> Html {
         match self {
             Element::Table(table) => table.render(state),
+            Element::Heading(heading) => heading.render(state),
+            Element::Definitions(list) => list.render(state),
+            Element::Details(details) => details.render(state),
+            Element::Frontmatter(frontmatter) => html! {
+                <div class={classes!("text-sm", "text-gray-400", "whitespace-pre-wrap", "border-b", "border-gray-600", "pb-1", "mb-2")}>
+                    {frontmatter.render(state)}
+                </div>
+            },
+            Element::Paragraph(paragraph) if state.sidenotes => {
+                let text = paragraph.text.concat();
+                // Definitions move into the margin when there is room for it
+                let definition = footnote::is_definition(&text).then_some("xl:hidden");
+                let sidenotes = footnote::references(&text)
+                    .into_iter()
+                    .filter_map(|label| {
+                        let (label, note) = state.footnotes.iter().find(|(l, _)| *l == label)?;
+                        Some(html! {
+                            <aside class={classes!("hidden", "xl:block", "float-right", "clear-right", "w-64", "-mr-72", "text-sm", "text-gray-500")}>
+                                <sup>{label}</sup>{" "}{note}
+                            </aside>
+                        })
+                    });
+                html! {
+                    <p class={classes!("min-h-[1em]", definition)}>
+                        {for sidenotes}
+                        {paragraph.render(state)}
+                        {attachment::images(&text, &state.links, &state.attachments)}
+                    </p>
+                }
+            }
+            Element::Paragraph(paragraph) => html! {
+                <p class={classes!("min-h-[1em]")}>
+                    {paragraph.render(state)}
+                    {attachment::images(&paragraph.text.concat(), &state.links, &state.attachments)}
+                </p>
+            },
         }
     }
 }
@@ -152,6 +866,11 @@ impl Commandee for Element {
     fn command(&mut self, command: &Self::Command) -> Self::Response {
         match self {
             Element::Table(table) => table.command(command),
+            Element::Heading(heading) => heading.content.command(command),
+            Element::Definitions(list) => list.command(command),
+            Element::Details(details) => details.command(command),
+            Element::Frontmatter(frontmatter) => frontmatter.command(command),
+            Element::Paragraph(paragraph) => paragraph.command(command),
         }
     }
 }
@@ -166,6 +885,12 @@ impl<'a> Markdown<'a> for Option<Element> {
         if let Some(event) = md.peek() {
             match event {
                 Event::Start(Tag::Table(_)) => Some(Element::Table(Table::parse_from_md(&mut md))),
+                Event::Start(Tag::Heading(_)) => {
+                    Some(Element::Heading(Heading::parse_from_md(&mut md)))
+                }
+                Event::Start(_) | Event::Html(_) | Event::Rule => {
+                    Some(Element::Paragraph(Paragraph::parse_from_md(&mut md)))
+                }
                 _ => None,
                 // Event::End(_) => todo!(),
                 // Event::Text(_) => todo!(),
@@ -190,8 +915,8 @@ impl<'a> Markdown<'a> for Option<Element> {
         // document
     }
 
-    fn to_md(self) -> String {
-        todo!()
+    fn to_md(&self) -> String {
+        self.as_ref().map(Element::to_md).unwrap_or_default()
     }
 }
 
@@ -200,58 +925,273 @@ pub struct Table {
     // cells: Vec<Vec<TableCell>>,
     pub cells: HashMap<(usize, usize), Paragraph>,
     pub active_cell: Option<(usize, usize)>,
+    /// Where the cursor was when it last left each cell
+    pub last_cursors: HashMap<(usize, usize), usize>,
+    /// The column selected with `ic` in Visual mode
+    pub selected_column: Option<usize>,
+    /// The row `V` started selecting whole rows in, up to the row of the cursor
+    pub row_anchor: Option<usize>,
     pub height: usize,
     pub width: usize,
 }
 
+impl Hash for Table {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut cells: Vec<_> = self.cells.iter().collect();
+        cells.sort_by_key(|(position, _)| **position);
+        cells.hash(state);
+        self.active_cell.hash(state);
+        self.selected_column.hash(state);
+        self.row_anchor.hash(state);
+        self.height.hash(state);
+        self.width.hash(state);
+    }
+}
+
 impl Table {
     fn neighbor(&mut self, direction: &Command) -> Option<(usize, usize)> {
         if let Some(active_cell) = self.active_cell {
             Some(match direction {
-                Command::Up => (active_cell.0, active_cell.1 - 1),
-                Command::Left => (active_cell.0 - 1, active_cell.1),
-                Command::Down => (active_cell.0, active_cell.1 + 1),
-                Command::Right => (active_cell.0 + 1, active_cell.1),
+                Command::Up if active_cell.1 > 0 => (active_cell.0, active_cell.1 - 1),
+                Command::Left if active_cell.0 > 0 => (active_cell.0 - 1, active_cell.1),
+                Command::Down if active_cell.1 + 1 < self.height => {
+                    (active_cell.0, active_cell.1 + 1)
+                }
+                Command::Right if active_cell.0 + 1 < self.width => {
+                    (active_cell.0 + 1, active_cell.1)
+                }
                 _ => return None,
             })
         } else {
             None
         }
     }
-    fn cell(&self, x: usize, y: usize) -> Option<&Paragraph> {
+    pub fn cell(&self, x: usize, y: usize) -> Option<&Paragraph> {
         self.cells.get(&(x, y))
     }
-}
 
-impl Render for Table {
-    fn render(&self, state: &ApplicationState) -> Html {
-        html! {
-            <table class={classes!("table-auto")}>
-            {
-                for (0..self.height).map(|y| {
-                    html!{
-                        <tr>
-                        {
-                            for (0..self.width).map(|x| {
-                                html!{
-                                    <td class="border px-2 h-10">
-                                    {self.cell(x,y).map(|c|c.render(state)).unwrap_or_default()}
-                                    </td>
-                                }
-                            })
-                        }
-                        </tr>
-                    }
+    /// Moves the cursor out of `cell`, remembering its position.
+    fn leave(&mut self, cell: (usize, usize)) {
+        let paragraph = self.cells.get_mut(&cell).unwrap();
+        if let Some(cursor) = paragraph.cursor {
+            self.last_cursors.insert(cell, cursor);
+        }
+        paragraph.command(&CursorLeave);
+    }
+
+    /// Moves the cursor into `cell`, back to where it left it if it was there before.
+    fn enter(&mut self, cell: (usize, usize), command: &Command) {
+        let paragraph = self.cells.get_mut(&cell).unwrap();
+        match self.last_cursors.get(&cell) {
+            Some(cursor) => {
+                paragraph.cursor = Some((*cursor).min(paragraph.text.len().saturating_sub(1)))
+            }
+            None => {
+                paragraph.command(command);
+            }
+        }
+        self.active_cell = Some(cell);
+    }
+
+    /// The text of the cells in column `x`, top to bottom.
+    pub fn column(&self, x: usize) -> Vec<String> {
+        (0..self.height)
+            .map(|y| {
+                self.cell(x, y)
+                    .map(|cell| cell.text.concat())
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    /// Removes column `x`, moving the cursor to the column now in its place.
+    pub fn remove_column(&mut self, x: usize) -> Vec<String> {
+        let column = self.column(x);
+        let active_cell = self.active_cell.take();
+        if let Some(cell) = active_cell {
+            self.leave(cell);
+        }
+        self.cells.retain(|(cx, _), _| *cx != x);
+        self.last_cursors.retain(|(cx, _), _| *cx != x);
+        shift_columns(&mut self.cells, x + 1, false);
+        shift_columns(&mut self.last_cursors, x + 1, false);
+        self.width -= 1;
+        self.selected_column = None;
+        if let Some((cx, y)) = active_cell {
+            let cx = if cx > x { cx - 1 } else { cx };
+            self.enter((cx.min(self.width - 1), y), &CursorEnterH(false));
+        }
+        column
+    }
+
+    /// Inserts `column` before column `x`, cut or padded to the height, and moves the cursor
+    /// into it.
+    pub fn insert_column(&mut self, x: usize, column: &[String]) {
+        let active_cell = self.active_cell.take();
+        if let Some(cell) = active_cell {
+            self.leave(cell);
+        }
+        shift_columns(&mut self.cells, x, true);
+        shift_columns(&mut self.last_cursors, x, true);
+        for y in 0..self.height {
+            let text = column.get(y).map(String::as_str).unwrap_or_default();
+            self.cells.insert((x, y), Paragraph::from(text));
+        }
+        self.width += 1;
+        if let Some((_, y)) = active_cell {
+            self.enter((x, y), &CursorEnterH(false));
+        }
+    }
+
+    /// The rows selected with `V`.
+    pub fn selected_rows(&self) -> Option<Range<usize>> {
+        let (anchor, (_, y)) = (self.row_anchor?, self.active_cell?);
+        Some(anchor.min(y)..anchor.max(y) + 1)
+    }
+
+    /// The text of the cells in the rows `rows`, row by row.
+    pub fn rows(&self, rows: Range<usize>) -> Vec<Vec<String>> {
+        rows.map(|y| {
+            (0..self.width)
+                .map(|x| {
+                    self.cell(x, y)
+                        .map(|cell| cell.text.concat())
+                        .unwrap_or_default()
                 })
+                .collect()
+        })
+        .collect()
+    }
+
+    /// Removes the rows `rows`, moving the cursor to the row now in their place.
+    pub fn remove_rows(&mut self, rows: Range<usize>) -> Vec<Vec<String>> {
+        let removed = self.rows(rows.clone());
+        let active_cell = self.active_cell.take();
+        if let Some(cell) = active_cell {
+            self.leave(cell);
+        }
+        self.cells.retain(|(_, y), _| !rows.contains(y));
+        self.last_cursors.retain(|(_, y), _| !rows.contains(y));
+        shift_rows(&mut self.cells, rows.end, -(rows.len() as isize));
+        shift_rows(&mut self.last_cursors, rows.end, -(rows.len() as isize));
+        self.height -= rows.len();
+        self.row_anchor = None;
+        if let Some((x, _)) = active_cell {
+            self.enter((x, rows.start.min(self.height - 1)), &CursorEnterH(false));
+        }
+        removed
+    }
+
+    /// Inserts `rows` before row `y`, each cut or padded to the width, and moves the cursor
+    /// into the first of them.
+    pub fn insert_rows(&mut self, y: usize, rows: &[Vec<String>]) {
+        let active_cell = self.active_cell.take();
+        if let Some(cell) = active_cell {
+            self.leave(cell);
+        }
+        shift_rows(&mut self.cells, y, rows.len() as isize);
+        shift_rows(&mut self.last_cursors, y, rows.len() as isize);
+        for (i, row) in rows.iter().enumerate() {
+            for x in 0..self.width {
+                let text = row.get(x).map(String::as_str).unwrap_or_default();
+                self.cells.insert((x, y + i), Paragraph::from(text));
             }
+        }
+        self.height += rows.len();
+        if let Some((x, _)) = active_cell {
+            self.enter((x, y), &CursorEnterH(false));
+        }
+    }
+
+    /// Renders the table with `caption` for screen readers, which see the cells as a grid
+    /// with the one holding the cursor selected.
+    fn render_captioned(&self, state: &ApplicationState, caption: Option<String>) -> Html {
+        let rows = self.selected_rows();
+        let row = |y: usize| {
+            html! {
+                <tr role="row" aria-rowindex={(y + 1).to_string()}>
+                {
+                    for (0..self.width).map(|x| {
+                        let selected = self.selected_column == Some(x)
+                            || rows.as_ref().map_or(false, |rows| rows.contains(&y));
+                        let active = (self.active_cell == Some((x, y))).to_string();
+                        let classes = classes!(
+                            "border",
+                            state.theme.color(TextStyle::TableCell),
+                            "px-2",
+                            "h-10",
+                            selected.then_some("bg-blue-800")
+                        );
+                        let content = self.cell(x, y).map(|c| c.render(state)).unwrap_or_default();
+                        if y == 0 {
+                            // The header stays at the top of the viewport while long tables
+                            // scroll, covering the rows beneath it
+                            let sticky = (!selected).then(|| state.theme.editor_classes());
+                            html!{
+                                <th scope="col" role="columnheader" aria-selected={active} class={classes!(classes, "sticky", "top-0", "font-normal", "text-left", sticky)}>{content}</th>
+                            }
+                        } else {
+                            html!{<td role="gridcell" aria-selected={active} class={classes}>{content}</td>}
+                        }
+                    })
+                }
+                </tr>
+            }
+        };
+        html! {
+            <table role="grid" aria-rowcount={self.height.to_string()} aria-colcount={self.width.to_string()} class={classes!("table-auto")}>
+                {for caption.map(|caption| html!{<caption class="sr-only">{caption}</caption>})}
+                <thead role="rowgroup">{row(0)}</thead>
+                <tbody role="rowgroup">{for (1..self.height).map(row)}</tbody>
             </table>
         }
     }
 }
 
+/// Moves the entries of the columns from `from` on one column to the right, or to the left
+/// if not `right`.
+fn shift_columns<T>(map: &mut HashMap<(usize, usize), T>, from: usize, right: bool) {
+    *map = mem::take(map)
+        .into_iter()
+        .map(|((x, y), value)| match x {
+            x if x < from => ((x, y), value),
+            x if right => ((x + 1, y), value),
+            x => ((x - 1, y), value),
+        })
+        .collect();
+}
+
+/// Moves the entries of `map` in row `from` and below down by `by` rows, up if negative.
+fn shift_rows<T>(map: &mut HashMap<(usize, usize), T>, from: usize, by: isize) {
+    *map = mem::take(map)
+        .into_iter()
+        .map(|((x, y), value)| match y {
+            y if y < from => ((x, y), value),
+            y => ((x, (y as isize + by) as usize), value),
+        })
+        .collect();
+}
+
+impl Render for Table {
+    fn render(&self, state: &ApplicationState) -> Html {
+        self.render_captioned(state, None)
+    }
+}
+
 impl Commandee for Table {
     fn command(&mut self, command: &Self::Command) -> Self::Response {
         if let Some(active_cell) = self.active_cell {
+            match command {
+                CursorLeave => {
+                    self.leave(active_cell);
+                    return true;
+                }
+                CursorEnterH(_) | CursorEnterV(..) => {
+                    self.enter(active_cell, command);
+                    return true;
+                }
+                _ => {}
+            }
             let neighbor = self.neighbor(command);
             let cell = self
                 .cells
@@ -265,33 +1205,26 @@ impl Commandee for Table {
                     Some(neighbor),
                 ) => {
                     let cursor = cell.get_normalized_cursor().unwrap();
-                    cell.command(&CursorLeave);
-                    self.cells
-                        .get_mut(&neighbor)
-                        .unwrap()
-                        .command(&if command.horizontal() {
+                    self.leave(active_cell);
+                    self.enter(
+                        neighbor,
+                        &if command.horizontal() {
                             CursorEnterH(command == &Left)
                         } else {
                             CursorEnterV(cursor, command == &Up)
-                        });
-                    self.active_cell = Some(neighbor);
+                        },
+                    );
                     true
                 }
                 (Command::Left | Command::Right, true, _) => true,
                 (Command::Left | Command::Right, false, None) => false,
                 (_, true, _) => true,
                 (Delete(Motion::Left), ..) => true,
-                _ => todo!(),
+                _ => false,
             }
         } else {
             match command {
-                CursorEnterH(false) | CursorEnterV(_, false) => {
-                    self.active_cell = Some((0, 0));
-                    self.cells
-                        .get_mut(&self.active_cell.unwrap())
-                        .unwrap()
-                        .command(command);
-                }
+                CursorEnterH(false) | CursorEnterV(_, false) => self.enter((0, 0), command),
                 _ => return false,
             }
             true
@@ -299,6 +1232,34 @@ impl Commandee for Table {
     }
 }
 
+impl Table {
+    /// The table of `events` with the cells taken from their source in `md` by the `ranges`
+    /// of the events, so their markup and escapes are kept as written.
+    fn from_source(events: &[Event], ranges: &[Range<usize>], md: &str) -> Self {
+        let mut table = Self::parse_from_md(&mut events.iter().cloned());
+        let (mut x, mut y) = (0, 0);
+        for (event, range) in events.iter().zip(ranges) {
+            match event {
+                Event::Start(Tag::TableHead | Tag::TableRow) => x = 0,
+                Event::Start(Tag::TableCell) => {
+                    let source = md[range.clone()].trim();
+                    let source = source.strip_prefix('|').unwrap_or(source);
+                    let source = match source.strip_suffix('|') {
+                        Some(stripped) if !stripped.ends_with('\\') => stripped,
+                        _ => source,
+                    };
+                    table.cells.entry((x, y)).or_default().text =
+                        source.trim().graphemes(true).map(String::from).collect();
+                }
+                Event::End(Tag::TableCell) => x += 1,
+                Event::End(Tag::TableHead | Tag::TableRow) => y += 1,
+                _ => {}
+            }
+        }
+        table
+    }
+}
+
 impl<'a> Markdown<'a> for Table {
     fn parse_from_md<T>(md: &mut T) -> Self
     where
@@ -308,6 +1269,9 @@ impl<'a> Markdown<'a> for Table {
         let mut table = Table {
             cells: HashMap::new(),
             active_cell: None,
+            last_cursors: HashMap::new(),
+            selected_column: None,
+            row_anchor: None,
             height: 0,
             width: 0,
         };
@@ -319,26 +1283,404 @@ impl<'a> Markdown<'a> for Table {
             match md.next().unwrap() {
                 Event::Start(Tag::TableRow) => table.width = 0,
                 Event::End(Tag::TableCell) => table.width += 1,
-                Event::End(Tag::TableRow | Tag::TableHead) => {
-                    table.height += 1;
-                    dbg!(table.height);
-                }
+                Event::End(Tag::TableRow | Tag::TableHead) => table.height += 1,
                 Event::Text(text) => table
                     .cells
                     .entry((table.width, table.height))
                     .or_default()
                     .text
                     .extend(text.graphemes(true).map(String::from)),
-                e => {
-                    dbg!(e);
-                }
+                e => log::debug!("Ignored table event {:?}", e),
+            }
+        }
+        // Empty cells have no text events
+        for y in 0..table.height {
+            for x in 0..table.width {
+                table.cells.entry((x, y)).or_default();
             }
         }
         table
     }
 
-    fn to_md(self) -> String {
-        todo!()
+    /// The rows padded so the columns line up.
+    fn to_md(&self) -> String {
+        let text = |x: usize, y: usize| {
+            self.cell(x, y)
+                .map(|cell| cell.text.concat())
+                .unwrap_or_default()
+        };
+        let widths: Vec<usize> = (0..self.width)
+            .map(|x| {
+                (0..self.height)
+                    .map(|y| text(x, y).width())
+                    .max()
+                    .unwrap_or_default()
+                    .max(3)
+            })
+            .collect();
+        let row = |y: usize| {
+            widths
+                .iter()
+                .enumerate()
+                .map(|(x, width)| {
+                    let text = text(x, y);
+                    let padding = " ".repeat(width - text.width());
+                    format!("{}{}", text, padding)
+                })
+                .collect::<Vec<_>>()
+                .join(" | ")
+        };
+        let mut lines = vec![];
+        for y in 0..self.height {
+            lines.push(format!("| {} |", row(y)));
+            if y == 0 {
+                let separator: String = widths
+                    .iter()
+                    .map(|width| format!(" {} |", "-".repeat(*width)))
+                    .collect();
+                lines.push(format!("|{}", separator));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// A definition list, `Term` lines each followed by `: definition` lines.
+#[derive(PartialEq, Debug, Default, Hash)]
+pub struct DefinitionList {
+    /// The terms and definitions in order, with whether each is a term
+    pub items: Vec<(bool, Paragraph)>,
+    pub active_item: Option<usize>,
+}
+
+impl DefinitionList {
+    /// Parses the block `source`, `None` if it is no definition list.
+    ///
+    /// Lines indented under a definition continue it.
+    fn parse(source: &str) -> Option<Self> {
+        let mut items: Vec<(bool, String)> = vec![];
+        for line in source.lines() {
+            if let Some(definition) = line.strip_prefix(": ").or_else(|| line.strip_prefix(":\t")) {
+                items.last()?;
+                items.push((false, definition.trim().to_owned()));
+            } else if line.starts_with(char::is_whitespace) && !line.trim().is_empty() {
+                match items.last_mut() {
+                    Some((false, definition)) => {
+                        definition.push('\n');
+                        definition.push_str(line.trim());
+                    }
+                    _ => return None,
+                }
+            } else if line.trim().is_empty() || matches!(items.last(), Some((true, _))) {
+                return None;
+            } else {
+                items.push((true, line.trim().to_owned()));
+            }
+        }
+        if !matches!(items.last(), Some((false, _))) {
+            return None;
+        }
+        Some(Self {
+            items: items
+                .iter()
+                .map(|(term, text)| (*term, Paragraph::from(text.as_str())))
+                .collect(),
+            active_item: None,
+        })
+    }
+
+    pub fn item(&self, i: usize) -> Option<&Paragraph> {
+        self.items.get(i).map(|(_, paragraph)| paragraph)
+    }
+
+    fn enter(&mut self, i: usize, command: &Command) {
+        self.items[i].1.command(command);
+        self.active_item = Some(i);
+    }
+
+    fn to_md(&self) -> String {
+        self.items
+            .iter()
+            .map(|(term, paragraph)| {
+                let text = paragraph.text.concat();
+                if *term {
+                    text
+                } else {
+                    format!(": {}", text.replace('\n', "\n  "))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl Commandee for DefinitionList {
+    /// Moves between the terms and definitions like between the cells of a table column.
+    fn command(&mut self, command: &Self::Command) -> Self::Response {
+        let active = match self.active_item {
+            Some(active) => active,
+            None => {
+                match command {
+                    CursorEnterH(false) | CursorEnterV(_, false) => self.enter(0, command),
+                    CursorEnterH(true) | CursorEnterV(_, true) => {
+                        self.enter(self.items.len() - 1, command)
+                    }
+                    _ => return false,
+                }
+                return true;
+            }
+        };
+        if command == &CursorLeave {
+            self.items[active].1.command(command);
+            self.active_item = None;
+            return true;
+        }
+        let neighbor = match command {
+            Up | Left => active.checked_sub(1),
+            Down | Right => Some(active + 1).filter(|&next| next < self.items.len()),
+            _ => None,
+        };
+        let paragraph = &mut self.items[active].1;
+        match (command, paragraph.command(command), neighbor) {
+            (Up | Down | Left | Right, false, Some(neighbor)) => {
+                let cursor = paragraph.get_normalized_cursor().unwrap_or_default();
+                paragraph.command(&CursorLeave);
+                self.enter(
+                    neighbor,
+                    &if command.horizontal() {
+                        CursorEnterH(command == &Left)
+                    } else {
+                        CursorEnterV(cursor, command == &Up)
+                    },
+                );
+                true
+            }
+            (_, true, _) => true,
+            // Terms and definitions are not joined by deleting at their start
+            (Delete(Motion::Left), ..) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Render for DefinitionList {
+    fn render(&self, state: &ApplicationState) -> Html {
+        html! {
+            <dl>
+                {for self.items.iter().map(|(term, paragraph)| if *term {
+                    html! {<dt class={classes!("font-bold", "min-h-[1em]")}>{paragraph.render(state)}</dt>}
+                } else {
+                    html! {<dd class={classes!("ml-8", "min-h-[1em]")}>{paragraph.render(state)}</dd>}
+                })}
+            </dl>
+        }
+    }
+}
+
+/// A `<details>` block with its `<summary>`, whose body is edited as markdown.
+#[derive(PartialEq, Debug, Default, Hash)]
+pub struct Details {
+    /// `None` for a block without a `<summary>`, which is left without one
+    pub summary: Option<Paragraph>,
+    /// The opening `<summary>` tag as written, with its attributes
+    pub summary_tag: String,
+    pub body: Paragraph,
+    /// Whether the body is shown, written as `<details open>`
+    pub open: bool,
+    /// The summary at `0` or the body at `1` holding the cursor
+    pub active_item: Option<usize>,
+}
+
+impl Details {
+    /// The length of the `<details>` block at the start of `md` up to its matching
+    /// `</details>`, which may come after blank lines and nested blocks.
+    fn length(md: &str) -> Option<usize> {
+        let mut depth = 0;
+        let mut offset = 0;
+        while let Some(tag) = md[offset..].find("details") {
+            let tag = offset + tag;
+            offset = tag + "details".len();
+            if md[..tag].ends_with("</") {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(offset + md[offset..].find('>')? + 1);
+                }
+            } else if md[..tag].ends_with('<') {
+                depth += 1;
+            }
+        }
+        None
+    }
+
+    /// Parses the block `source`, `None` if it is no `<details>` block.
+    fn parse(source: &str) -> Option<Self> {
+        let source = source.trim();
+        let rest = source.strip_prefix("<details")?;
+        let (attributes, rest) = rest.split_once('>')?;
+        if !(attributes.is_empty() || attributes.starts_with(char::is_whitespace)) {
+            return None;
+        }
+        let body = rest.strip_suffix("</details>")?.trim();
+        let (summary, summary_tag, body) = match body.strip_prefix("<summary") {
+            Some(rest) if rest.starts_with(|c: char| c == '>' || c.is_whitespace()) => {
+                let (attributes, rest) = rest.split_once('>')?;
+                let (summary, body) = rest.split_once("</summary>")?;
+                let tag = format!("<summary{}>", attributes);
+                (Some(Paragraph::from(summary.trim())), tag, body)
+            }
+            _ => (None, String::new(), body),
+        };
+        Some(Self {
+            summary,
+            summary_tag,
+            body: Paragraph::from(body.trim()),
+            open: attributes
+                .split_whitespace()
+                .any(|attribute| attribute == "open"),
+            active_item: None,
+        })
+    }
+
+    pub fn item(&self, i: usize) -> Option<&Paragraph> {
+        match i {
+            0 => self.summary.as_ref(),
+            1 => Some(&self.body),
+            _ => None,
+        }
+    }
+
+    fn item_mut(&mut self, i: usize) -> &mut Paragraph {
+        match (i, &mut self.summary) {
+            (0, Some(summary)) => summary,
+            _ => &mut self.body,
+        }
+    }
+
+    /// The summary, or the body when there is none.
+    fn first(&self) -> usize {
+        if self.summary.is_some() {
+            0
+        } else {
+            1
+        }
+    }
+
+    fn enter(&mut self, i: usize, command: &Command) {
+        self.item_mut(i).command(command);
+        self.active_item = Some(i);
+    }
+
+    fn to_md(&self) -> String {
+        let open = if self.open { " open" } else { "" };
+        let mut md = format!("<details{}>\n", open);
+        if let Some(summary) = &self.summary {
+            let tag = match self.summary_tag.as_str() {
+                "" => "<summary>",
+                tag => tag,
+            };
+            md.push_str(&format!("{}{}</summary>\n", tag, summary.text.concat()));
+        }
+        let body = self.body.text.concat();
+        if !body.trim().is_empty() {
+            // The blank lines let the body be parsed as markdown
+            md.push('\n');
+            md.push_str(&body);
+            md.push_str("\n\n");
+        }
+        md.push_str("</details>");
+        md
+    }
+}
+
+impl Commandee for Details {
+    /// Moves between the summary and the body while it is open, `Enter` opens or closes
+    /// it.
+    fn command(&mut self, command: &Self::Command) -> Self::Response {
+        let first = self.first();
+        let last = if self.open { 1 } else { first };
+        let active = match self.active_item {
+            Some(active) => active,
+            None => {
+                match command {
+                    CursorEnterH(false) | CursorEnterV(_, false) => self.enter(first, command),
+                    CursorEnterH(true) | CursorEnterV(_, true) => self.enter(last, command),
+                    _ => return false,
+                }
+                return true;
+            }
+        };
+        match command {
+            CursorLeave => {
+                self.item_mut(active).command(command);
+                self.active_item = None;
+                return true;
+            }
+            Enter => {
+                self.open = !self.open;
+                if active == 1 && first == 0 {
+                    self.body.command(&CursorLeave);
+                    self.enter(0, &CursorEnterH(true));
+                }
+                return true;
+            }
+            _ => {}
+        }
+        let neighbor = match command {
+            Up | Left if active > first => Some(active - 1),
+            Down | Right if active < last => Some(active + 1),
+            _ => None,
+        };
+        let paragraph = self.item_mut(active);
+        match (command, paragraph.command(command), neighbor) {
+            (Up | Down | Left | Right, false, Some(neighbor)) => {
+                let cursor = paragraph.get_normalized_cursor().unwrap_or_default();
+                paragraph.command(&CursorLeave);
+                self.enter(
+                    neighbor,
+                    &if command.horizontal() {
+                        CursorEnterH(command == &Left)
+                    } else {
+                        CursorEnterV(cursor, command == &Up)
+                    },
+                );
+                true
+            }
+            (_, true, _) => true,
+            (Delete(Motion::Left), ..) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Render for Details {
+    /// A disclosure widget, the body is rendered as markdown unless the cursor is in it.
+    /// Without a summary the browser shows its own, and the body is shown while the cursor
+    /// is in it.
+    fn render(&self, state: &ApplicationState) -> Html {
+        let body = if self.active_item == Some(1) {
+            html! {<p class={classes!("min-h-[1em]")}>{self.body.render(state)}</p>}
+        } else {
+            let mut document = Document::from_md(&self.body.text.concat());
+            document.command(&Command::CursorLeave);
+            html! {
+                <div class={classes!("mt-1")}>
+                    {for document.elements.iter().map(|element| element.render(state))}
+                </div>
+            }
+        };
+        let summary = self.summary.as_ref().map(|summary| {
+            html! {
+                <summary class={classes!("cursor-pointer")} title="Enter opens or closes it">
+                    {summary.render(state)}
+                </summary>
+            }
+        });
+        html! {
+            <details open={self.open || self.active_item == Some(1)} class={classes!("border", "border-gray-600", "rounded", "px-2", "my-1")}>
+                {for summary}
+                {body}
+            </details>
+        }
     }
 }
 
@@ -367,10 +1709,14 @@ impl<'a> Markdown<'a> for Table {
 //     }
 // }
 
-#[derive(PartialEq, Debug, Default)]
+#[derive(PartialEq, Debug, Default, Hash)]
 pub struct Paragraph {
     pub text: Vec<String>,
     pub cursor: Option<usize>,
+    /// Start of the visual selection, which ends at the cursor
+    pub anchor: Option<usize>,
+    /// Labels of a `gs` jump shown over the characters
+    pub labels: Vec<(usize, String)>,
 }
 
 impl Paragraph {
@@ -398,26 +1744,311 @@ impl Paragraph {
         }
         self.cursor = Some(actual_cursor);
     }
+
+    /// The selected characters including the one under the cursor.
+    pub fn selection(&self) -> Option<Range<usize>> {
+        let (anchor, cursor) = (self.anchor?, self.cursor?);
+        Some(anchor.min(cursor)..(anchor.max(cursor) + 1).min(self.text.len()))
+    }
+
+    fn render_char(&self, i: usize, state: &ApplicationState, highlight: &Highlight) -> Html {
+        let selected = self
+            .selection()
+            .map_or(false, |selection| selection.contains(&i));
+        let matched = highlight.matches.iter().any(|range| range.contains(&i));
+        let linted = highlight.lint.iter().any(|range| range.contains(&i));
+        let misspelled = highlight.misspelled.iter().any(|range| range.contains(&i));
+        let overdue = highlight.overdue.iter().any(|range| range.contains(&i));
+        let code = highlight.code.iter().any(|range| range.contains(&i));
+        let classes = classes!(
+            code.then(|| state.theme.color(TextStyle::Code)),
+            linted.then_some(classes!("border-b-2", "border-dotted", "border-purple-400")),
+            misspelled.then_some(classes!("border-b-2", "border-dashed", "border-red-500")),
+            overdue.then_some(classes!("text-red-500", "font-bold")),
+            selected.then_some("bg-blue-800"),
+            (matched && !selected).then_some("bg-yellow-700"),
+            (highlight.pair == Some(i)).then_some(classes!("ring-1", "ring-gray-400"))
+        );
+        let span = |classes| match self.text[i].as_str() {
+            "\t" => tab_span(state.tab_width, classes),
+            c => char_span(c, classes),
+        };
+        if let Some((_, label)) = self.labels.iter().find(|(cursor, _)| *cursor == i) {
+            return html! {
+                <span class={classes!("relative")}>
+                    {span(classes)}
+                    <span class={classes!("absolute", "left-0", "top-0", "z-20", "px-px", "rounded", "bg-yellow-400", "text-black", "font-bold")}>
+                        {label}
+                    </span>
+                </span>
+            };
+        }
+        html! {
+            if self.cursor == Some(i) {
+                <span id={state.cursor_id} class={state.cursor_classes()}>
+                    {span(classes!(classes, "relative", "z-10"))}
+                </span>
+            } else {
+                {span(classes)}
+            }
+        }
+    }
+
+    /// The word under the cursor.
+    pub fn word_at_cursor(&self) -> Option<String> {
+        let cursor = self.cursor?;
+        if !is_word(self.text.get(cursor)?) {
+            return None;
+        }
+        let start = self.text[..cursor]
+            .iter()
+            .rposition(|c| !is_word(c))
+            .map_or(0, |i| i + 1);
+        let end = self.text[cursor..]
+            .iter()
+            .position(|c| !is_word(c))
+            .map_or(self.text.len(), |i| cursor + i);
+        Some(self.text[start..end].concat())
+    }
+
+    pub fn text_before_cursor(&self) -> String {
+        self.text[..self.cursor.unwrap_or_default().min(self.text.len())].concat()
+    }
+
+    /// The word directly left of the cursor.
+    pub fn word_before_cursor(&self) -> &[String] {
+        let cursor = self.cursor.unwrap_or_default().min(self.text.len());
+        let start = self.text[..cursor]
+            .iter()
+            .rposition(|c| !is_word(c))
+            .map_or(0, |i| i + 1);
+        &self.text[start..cursor]
+    }
+
+    /// Replaces the `len` characters left of the cursor with `with`.
+    pub fn replace_before_cursor(&mut self, len: usize, with: &Characters) {
+        if let Some(cursor) = &mut self.cursor {
+            let start = cursor.saturating_sub(len);
+            self.text.splice(start..*cursor, with.iter().cloned());
+            *cursor = start + with.len();
+        }
+    }
+}
+
+/// Where vim's `Ctrl+W` deletes to from `cursor`: over whitespace, then over a run of word
+/// or of other characters.
+pub fn word_left(text: &[String], cursor: usize) -> usize {
+    let blank = |c: &String| c.trim().is_empty();
+    let end = text[..cursor]
+        .iter()
+        .rposition(|c| !blank(c))
+        .map_or(0, |i| i + 1);
+    if end == 0 {
+        return 0;
+    }
+    let word = is_word(&text[end - 1]);
+    text[..end]
+        .iter()
+        .rposition(|c| blank(c) || is_word(c) != word)
+        .map_or(0, |i| i + 1)
+}
+
+/// The anchor of a heading: lowercase, without punctuation and with `-` for spaces.
+pub fn slug(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether a grapheme belongs to a word for abbreviations.
+pub fn is_word(grapheme: &str) -> bool {
+    grapheme
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '_' || c == '@')
+}
+
+impl<'a> Markdown<'a> for Paragraph {
+    /// Reads a single block, keeping its markdown source as text.
+    fn parse_from_md<T>(md: &mut T) -> Self
+    where
+        T: Iterator<Item = Event<'a>>,
+    {
+        let mut events = vec![];
+        let mut depth = 0;
+        for event in md {
+            match event {
+                Event::Start(_) => depth += 1,
+                Event::End(_) => depth -= 1,
+                _ => {}
+            }
+            events.push(event);
+            if depth == 0 {
+                break;
+            }
+        }
+        Self::from(write_md(events).as_str())
+    }
+
+    fn to_md(&self) -> String {
+        self.text.concat()
+    }
+}
+
+impl From<&str> for Paragraph {
+    fn from(text: &str) -> Self {
+        Self {
+            text: text.graphemes(true).map(String::from).collect(),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Default, Hash)]
+pub struct Heading {
+    pub level: u32,
+    pub content: Paragraph,
+}
+
+impl Heading {
+    /// A heading from its atx (`## Title`) or setext (`Title` underlined) source.
+    fn from_source(level: u32, source: &str) -> Self {
+        let text = if source.starts_with('#') {
+            let text = source.trim_start_matches('#').trim();
+            // An optional closing sequence has to be separated by a space
+            match text.trim_end_matches('#') {
+                stripped if stripped.is_empty() || stripped.ends_with(' ') => stripped.trim_end(),
+                _ => text,
+            }
+        } else {
+            source
+                .rsplit_once('\n')
+                .map_or(source, |(text, _)| text)
+                .trim()
+        };
+        Self {
+            level,
+            content: Paragraph::from(text),
+        }
+    }
+}
+
+impl<'a> Markdown<'a> for Heading {
+    fn parse_from_md<T>(md: &mut T) -> Self
+    where
+        T: Iterator<Item = Event<'a>>,
+    {
+        let level = match md.next() {
+            Some(Event::Start(Tag::Heading(level))) => level,
+            _ => 1,
+        };
+        let events = md.take_while(|event| !matches!(event, Event::End(Tag::Heading(_))));
+        Self {
+            level,
+            content: Paragraph::from(write_md(events).as_str()),
+        }
+    }
+
+    fn to_md(&self) -> String {
+        format!(
+            "{} {}",
+            "#".repeat(self.level as usize),
+            self.content.to_md()
+        )
+    }
+}
+
+impl Heading {
+    /// Renders the heading with its outline `number` in front, which is not part of the source.
+    pub fn render_numbered(&self, state: &ApplicationState, number: Option<&str>) -> Html {
+        let size = match self.level {
+            1 => "text-3xl",
+            2 => "text-2xl",
+            3 => "text-xl",
+            _ => "text-lg",
+        };
+        html! {
+            <p class={classes!("font-bold", "min-h-[1em]", size, state.theme.color(TextStyle::Heading))}>
+                {for number.map(|number| html! {
+                    <span class={classes!("mr-2", "text-gray-500", "select-none")}>{number}</span>
+                })}
+                {self.content.render(state)}
+            </p>
+        }
+    }
+}
+
+impl Render for Heading {
+    fn render(&self, state: &ApplicationState) -> Html {
+        self.render_numbered(state, None)
+    }
 }
 
 impl Commandee for Paragraph {
     fn command(&mut self, command: &Self::Command) -> Self::Response {
         match (command, &mut self.cursor) {
             (Left, Some(cursor)) if *cursor != 0 => *cursor -= 1,
-            (Right, Some(cursor)) if *cursor != self.text.len() - 1 => *cursor += 1,
-            (CursorLeave, Some(_)) => self.cursor = None,
+            (Right, Some(cursor)) if *cursor + 1 < self.text.len() => *cursor += 1,
+            (CursorLeave, Some(_)) => {
+                self.cursor = None;
+                self.anchor = None;
+            }
             (CursorEnterH(false), _) => self.cursor = Some(0),
-            (CursorEnterH(true), _) => self.cursor = Some(self.text.len() - 1),
+            (CursorEnterH(true), _) => self.cursor = Some(self.text.len().saturating_sub(1)),
             (CursorEnterV(cursor, _), _) => self.set_normalized_cursor(*cursor), // self.cursor = Some((*cursor).min(self.text.len() - 1)),
+            (Delete(Motion::WordLeft), Some(cursor)) if *cursor > 0 => {
+                let start = word_left(&self.text, *cursor);
+                self.text.drain(start..*cursor);
+                *cursor = start;
+            }
+            (Delete(Motion::LineStart), Some(cursor)) if *cursor > 0 => {
+                let start = self.text[..*cursor]
+                    .iter()
+                    .rposition(|c| c == "\n")
+                    .map_or(0, |i| i + 1);
+                let start = if start == *cursor { start - 1 } else { start };
+                self.text.drain(start..*cursor);
+                *cursor = start;
+            }
+            (Delete(Motion::Selection), Some(cursor)) => {
+                let anchor = match self.anchor.take() {
+                    Some(anchor) => anchor,
+                    None => return false,
+                };
+                let start = anchor.min(*cursor);
+                let end = (anchor.max(*cursor) + 1).min(self.text.len());
+                self.text.drain(start..end);
+                *cursor = start;
+            }
+            (Sentence(forward), Some(cursor)) => {
+                match sentence::motion(&self.text, *cursor, *forward) {
+                    Some(start) => *cursor = start,
+                    None => return false,
+                }
+            }
+            (SelectSentence(around), Some(cursor)) => {
+                match sentence::at(&self.text, *cursor, *around) {
+                    Some(sentence) => {
+                        self.anchor = Some(sentence.start);
+                        *cursor = sentence.end - 1;
+                    }
+                    None => return false,
+                }
+            }
             (Delete(Motion::Left), Some(cursor)) if *cursor > 0 => {
                 self.text.remove(*cursor - 1);
                 *cursor -= 1;
             }
+            (MatchPair, Some(cursor)) => match pairs::jump(&self.text, *cursor) {
+                Some(pair) => *cursor = pair,
+                None => return false,
+            },
             (Insert(chars), Some(cursor)) => {
-                if chars.0.contains(&"\n".to_string()) {
-                    todo!("line breaking")
-                }
-
                 let remainder = self.text.split_off(*cursor);
 
                 self.text.extend(chars.iter().map(String::from));
@@ -447,7 +2078,7 @@ impl Commandee for Paragraph {
                 // (move_action, new_lines)
             }
             _ => {
-                dbg!(&self, command);
+                log::debug!("Unhandled command {:?}", command);
                 return false;
             }
         }
@@ -455,6 +2086,14 @@ impl Commandee for Paragraph {
     }
 }
 
+/// A tab as wide as `width` characters.
+fn tab_span(width: usize, mut classes: Classes) -> Html {
+    classes.push("inline-block");
+    html! {
+        <span class={classes} style={format!("width: {}ch", width)}>{" "}</span>
+    }
+}
+
 fn char_span(c: &str, mut classes: Classes) -> Html {
     if c.width() > 1 {
         // dbg!(c);
@@ -477,19 +2116,374 @@ fn char_span(c: &str, mut classes: Classes) -> Html {
     //}
 }
 
+/// Maps byte offsets in the concatenated `text` to grapheme indices.
+pub fn grapheme_index(text: &[String]) -> impl Fn(usize) -> usize {
+    let mut offsets = Vec::with_capacity(text.len());
+    let mut offset = 0;
+    for grapheme in text {
+        offsets.push(offset);
+        offset += grapheme.len();
+    }
+    move |byte| offsets.partition_point(|&offset| offset < byte)
+}
+
+/// Ranges of bare urls in `text`.
+pub fn url_ranges(text: &[String]) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    let mut i = 0;
+    while i < text.len() {
+        let at_start = i == 0 || matches!(text[i - 1].as_str(), " " | "\n" | "(" | "<" | "\t");
+        let rest = text[i..text.len().min(i + 8)].concat();
+        if at_start && (rest.starts_with("http://") || rest.starts_with("https://")) {
+            let mut end = text[i..]
+                .iter()
+                .position(|c| matches!(c.as_str(), " " | "\n" | "\t" | ")" | ">"))
+                .map_or(text.len(), |length| i + length);
+            while end > i && matches!(text[end - 1].as_str(), "." | "," | ";" | ":" | "!" | "?") {
+                end -= 1;
+            }
+            ranges.push(i..end);
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+    ranges
+}
+
+impl Paragraph {
+    /// Ranges of the links in the text with the url they point to.
+    ///
+    /// Reference links are resolved with the definitions in `state`, bare urls are only
+    /// found outside of other links.
+    fn link_ranges(&self, state: &ApplicationState) -> Vec<(Range<usize>, String)> {
+        let source = self.text.concat();
+        let index = grapheme_index(&self.text);
+
+        let mut ranges: Vec<_> = links::links(&source)
+            .into_iter()
+            .filter_map(|link| {
+                let url = match &link.target {
+                    Target::Inline(destination) => links::href(destination),
+                    Target::Reference(id) => state.links.get(&links::normalize(id))?.clone(),
+                };
+                links::is_safe(&url).then(|| (index(link.range.start)..index(link.range.end), url))
+            })
+            .collect();
+        let bare: Vec<_> = url_ranges(&self.text)
+            .into_iter()
+            .filter(|url| {
+                ranges
+                    .iter()
+                    .all(|(link, _)| url.end <= link.start || url.start >= link.end)
+            })
+            .map(|url| {
+                let href = self.text[url.clone()].concat();
+                (url, href)
+            })
+            .collect();
+        ranges.extend(bare);
+        ranges.sort_by_key(|(range, _)| range.start);
+        ranges
+    }
+
+    /// Ranges of the citations outside of links, with how they are shown and the works they
+    /// cite, for those whose works are all in the bibliography.
+    fn citation_ranges(
+        &self,
+        state: &ApplicationState,
+        links: &[(Range<usize>, String)],
+    ) -> Vec<(Range<usize>, String, String)> {
+        if state.references.is_empty() {
+            return vec![];
+        }
+        let index = grapheme_index(&self.text);
+        citation::citations(&self.text.concat())
+            .into_iter()
+            .filter_map(|citation| {
+                let shown = citation::format(&citation, &state.references)?;
+                let works: Vec<_> = citation
+                    .items
+                    .iter()
+                    .filter_map(|item| {
+                        let key = &item.key;
+                        state
+                            .references
+                            .iter()
+                            .find(|reference| reference.key == *key)
+                    })
+                    .map(citation::Reference::full)
+                    .collect();
+                let range = index(citation.range.start)..index(citation.range.end);
+                links
+                    .iter()
+                    .all(|(link, _)| range.end <= link.start || range.start >= link.end)
+                    .then(|| (range, shown, works.join("\n")))
+            })
+            .collect()
+    }
+
+    /// Whether the cursor or selection is on `token`, so its source is shown to be edited.
+    fn editing(&self, token: &Range<usize>) -> bool {
+        self.cursor.map_or(false, |cursor| token.contains(&cursor))
+            || self.selection().map_or(false, |selection| {
+                selection.start < token.end && token.start < selection.end
+            })
+    }
+}
+
+/// Search matches, lint findings, due dates of overdue tasks and the delimiter matching
+/// the one under the cursor.
+struct Highlight {
+    /// Code spans and code blocks, tinted in the color of the theme
+    code: Vec<Range<usize>>,
+    /// Words not in the dictionary while `:set spell` is on
+    misspelled: Vec<Range<usize>>,
+    matches: Vec<Range<usize>>,
+    lint: Vec<Range<usize>>,
+    overdue: Vec<Range<usize>>,
+    pair: Option<usize>,
+}
+
+impl Paragraph {
+    /// Renders the characters in `range` with escapes and entities decoded, unless the
+    /// cursor or selection is on them so their source can be edited.
+    fn render_range(
+        &self,
+        range: Range<usize>,
+        state: &ApplicationState,
+        highlight: &Highlight,
+        decoded: &[(Range<usize>, String)],
+    ) -> Vec<Html> {
+        let overlaps = |a: &Range<usize>, b: &Range<usize>| a.start < b.end && b.start < a.end;
+        let code = |i: usize| highlight.code.iter().any(|range| range.contains(&i));
+        // Characters without highlight or cursor can be rendered as one run of text, which
+        // lets fonts join them into ligatures
+        let plain = |i: usize| {
+            let c = self.text[i].as_str();
+            state.ligatures
+                && self.cursor != Some(i)
+                && !self
+                    .selection()
+                    .map_or(false, |selection| selection.contains(&i))
+                && !highlight
+                    .matches
+                    .iter()
+                    .chain(&highlight.lint)
+                    .chain(&highlight.misspelled)
+                    .chain(&highlight.overdue)
+                    .any(|range| range.contains(&i))
+                && highlight.pair != Some(i)
+                && self.labels.iter().all(|(label, _)| *label != i)
+                && c != "\t"
+                && c.width() <= 1
+        };
+        let mut spans = vec![];
+        let mut i = range.start;
+        while i < range.end {
+            match decoded
+                .iter()
+                .find(|(token, _)| token.start == i && token.end <= range.end)
+            {
+                Some((token, c)) if !self.editing(token) => {
+                    let matched = highlight.matches.iter().any(|m| overlaps(m, token));
+                    spans.push(char_span(c, classes!(matched.then_some("bg-yellow-700"))));
+                    i = token.end;
+                }
+                None if plain(i) => {
+                    let start = i;
+                    while i < range.end
+                        && plain(i)
+                        && code(i) == code(start)
+                        && decoded.iter().all(|(token, _)| token.start != i)
+                    {
+                        i += 1;
+                    }
+                    let classes = code(start).then(|| state.theme.color(TextStyle::Code));
+                    spans.push(html! {<span class={classes!(classes)}>{self.text[start..i].concat()}</span>});
+                }
+                _ => {
+                    spans.push(self.render_char(i, state, highlight));
+                    i += 1;
+                }
+            }
+        }
+        spans
+    }
+}
+
+impl Paragraph {
+    /// Ranges of the code spans and code blocks.
+    fn code_ranges(&self) -> Vec<Range<usize>> {
+        let index = grapheme_index(&self.text);
+        let text = self.text.concat();
+        Parser::new(&text)
+            .into_offset_iter()
+            .filter_map(|(event, range)| match event {
+                Event::Code(_) | Event::Start(Tag::CodeBlock(_)) => {
+                    Some(index(range.start)..index(range.end))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Ranges of the comments the cursor is not in, which are hidden unless the
+    /// `showcomments` option is set.
+    fn comment_ranges(&self) -> Vec<Range<usize>> {
+        let index = grapheme_index(&self.text);
+        comment::ranges(&self.text.concat())
+            .into_iter()
+            .map(|range| index(range.start)..index(range.end))
+            .filter(|range| !self.editing(range))
+            .collect()
+    }
+
+    /// Ranges of the suggested changes the cursor is not in, with the text they add or
+    /// remove.
+    fn change_ranges(&self) -> Vec<(Range<usize>, critic::Kind, String)> {
+        let index = grapheme_index(&self.text);
+        let text = self.text.concat();
+        critic::changes(&text)
+            .into_iter()
+            .map(|change| {
+                let range = index(change.range.start)..index(change.range.end);
+                (range, change.kind, text[change.content()].to_owned())
+            })
+            .filter(|(range, _, _)| !self.editing(range))
+            .collect()
+    }
+
+    /// Renders the characters in `range` with the `replaced` tokens in it, like formatted
+    /// citations and hidden comments, shown as given.
+    fn render_text(
+        &self,
+        range: Range<usize>,
+        state: &ApplicationState,
+        highlight: &Highlight,
+        decoded: &[(Range<usize>, String)],
+        replaced: &[(Range<usize>, Html)],
+    ) -> Vec<Html> {
+        let mut spans = vec![];
+        let mut i = range.start;
+        for (token, shown) in replaced {
+            if token.start < i || token.end > range.end {
+                continue;
+            }
+            spans.extend(self.render_range(i..token.start, state, highlight, decoded));
+            spans.push(shown.clone());
+            i = token.end;
+        }
+        spans.extend(self.render_range(i..range.end, state, highlight, decoded));
+        spans
+    }
+}
+
 impl Render for Paragraph {
     fn render(&self, state: &ApplicationState) -> Html {
+        let highlight = Highlight {
+            code: self.code_ranges(),
+            misspelled: state
+                .spell
+                .as_ref()
+                .map(|dictionary| spell::misspelled(&self.text, dictionary))
+                .unwrap_or_default(),
+            matches: state
+                .search
+                .as_ref()
+                .map(|search| search.matches(&self.text))
+                .unwrap_or_default(),
+            lint: state
+                .lint
+                .as_ref()
+                .map(|lint| {
+                    lint.check(&self.text)
+                        .into_iter()
+                        .map(|finding| finding.range)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            overdue: tasks::overdue(&self.text, &state.today),
+            pair: self
+                .cursor
+                .and_then(|cursor| pairs::matching(&self.text, cursor)),
+        };
+        let mut decoded = entity::decoded_ranges(&self.text);
+        if state.flavor.smart_punctuation() {
+            decoded.extend(entity::smart_ranges(&self.text));
+        }
+        let mut spans = vec![];
+        let mut i = 0;
+        let links = self.link_ranges(state);
+        let mut replaced: Vec<_> = self
+            .citation_ranges(state, &links)
+            .into_iter()
+            .filter(|(range, _, _)| !self.editing(range))
+            .map(|(range, shown, works)| {
+                let shown = html! {
+                    <span title={works} class={classes!(state.theme.color(TextStyle::Link))}>{shown}</span>
+                };
+                (range, shown)
+            })
+            .collect();
+        replaced.extend(self.comment_ranges().into_iter().map(|range| {
+            let shown = if state.show_comments {
+                html! {<span class={classes!("opacity-50")}>{self.text[range.clone()].concat()}</span>}
+            } else {
+                html! {}
+            };
+            (range, shown)
+        }));
+        replaced.extend(self.change_ranges().into_iter().map(|(range, kind, content)| {
+            let shown = match kind {
+                critic::Kind::Addition => html! {
+                    <ins class={classes!("text-green-400")} title="Suggested addition">{content}</ins>
+                },
+                critic::Kind::Deletion => html! {
+                    <del class={classes!("text-red-400")} title="Suggested deletion">{content}</del>
+                },
+            };
+            (range, shown)
+        }));
+        replaced.sort_by_key(|(range, _)| range.start);
+        let text = |range| self.render_text(range, state, &highlight, &decoded, &replaced);
+        for (range, href) in links {
+            spans.extend(text(i..range.start));
+            let children: Html = self
+                .render_range(range.clone(), state, &highlight, &decoded)
+                .into_iter()
+                .collect();
+            spans.push(match href.strip_prefix('#') {
+                // Jumps to the heading without changing the url, which selects the note
+                Some(anchor) => {
+                    let anchor = anchor.to_owned();
+                    let onclick = Callback::from(move |e: MouseEvent| {
+                        e.prevent_default();
+                        if let Some(heading) = window()
+                            .and_then(|window| window.document())
+                            .and_then(|document| document.get_element_by_id(&anchor))
+                        {
+                            heading.scroll_into_view();
+                        }
+                    });
+                    html! {
+                        <a href={href.clone()} onclick={onclick} class={classes!(state.theme.color(TextStyle::Link), "underline")}>{children}</a>
+                    }
+                }
+                None => html! {
+                    <a href={href.clone()} target="_blank" class={classes!(state.theme.color(TextStyle::Link), "underline")}>{children}</a>
+                },
+            });
+            i = range.end;
+        }
+        spans.extend(text(i..self.text.len()));
         html! {
-            {for self.text.iter().enumerate().map(|(i, character)|
-                 html!{
-                     if self.cursor == Some(i) {
-                     <span class={classes!(
-                             (self.cursor == Some(i)).then_some(state.cursor_style.classes()))}>{char_span(character, classes!("relative", "z-10"))}</span>
-                     } else {
-                         {char_span(character,classes!())}
-                     }
-                 }
-            )}
+            <>
+            {for spans}
+            if self.cursor.map_or(false, |cursor| cursor >= self.text.len()) {
+                <span id={state.cursor_id} class={state.cursor_classes()}>{" "}</span>
+            }
+            </>
         }
     }
 }
@@ -0,0 +1,142 @@
+use std::ops::Range;
+
+const ENTITIES: &[(&str, &str)] = &[
+    ("amp", "&"),
+    ("lt", "<"),
+    ("gt", ">"),
+    ("quot", "\""),
+    ("apos", "'"),
+    ("nbsp", "\u{a0}"),
+    ("shy", "\u{ad}"),
+    ("copy", "©"),
+    ("reg", "®"),
+    ("trade", "™"),
+    ("hellip", "…"),
+    ("mdash", "—"),
+    ("ndash", "–"),
+    ("lsquo", "‘"),
+    ("rsquo", "’"),
+    ("ldquo", "“"),
+    ("rdquo", "”"),
+    ("laquo", "«"),
+    ("raquo", "»"),
+    ("deg", "°"),
+    ("plusmn", "±"),
+    ("times", "×"),
+    ("divide", "÷"),
+    ("middot", "·"),
+    ("bull", "•"),
+    ("sect", "§"),
+    ("para", "¶"),
+    ("euro", "€"),
+    ("pound", "£"),
+    ("yen", "¥"),
+    ("cent", "¢"),
+    ("larr", "←"),
+    ("rarr", "→"),
+    ("uarr", "↑"),
+    ("darr", "↓"),
+];
+
+/// The character of an entity without `&` and `;`, e.g. `amp` or `#x2014`.
+fn decode_entity(name: &str) -> Option<String> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number
+            .strip_prefix('x')
+            .or_else(|| number.strip_prefix('X'))
+        {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        // Like commonmark, invalid code points become the replacement character
+        let c = std::char::from_u32(code)
+            .filter(|&c| c != '\0')
+            .unwrap_or('\u{fffd}');
+        return Some(c.to_string());
+    }
+    ENTITIES
+        .iter()
+        .find(|(entity, _)| *entity == name)
+        .map(|(_, c)| c.to_string())
+}
+
+/// The index after the code span starting at `i`, or after its backticks if it is not closed.
+fn code_span_end(text: &[String], i: usize) -> usize {
+    let ticks = text[i..].iter().take_while(|c| *c == "`").count();
+    let closing = (i + ticks..text.len()).find(|&j| {
+        text[j..].iter().take_while(|c| *c == "`").count() == ticks && text[j - 1] != "`"
+    });
+    closing.map_or(i + ticks, |j| j + ticks)
+}
+
+/// Ranges of backslash escapes and entities in `text` with the character they stand for.
+///
+/// Code spans are skipped, as their content is taken literally.
+pub fn decoded_ranges(text: &[String]) -> Vec<(Range<usize>, String)> {
+    let mut ranges = vec![];
+    let mut i = 0;
+    while i < text.len() {
+        match text[i].as_str() {
+            "\\" => match text.get(i + 1) {
+                Some(next) if next.len() == 1 && next.chars().all(|c| c.is_ascii_punctuation()) => {
+                    ranges.push((i..i + 2, next.clone()));
+                    i += 2;
+                }
+                _ => i += 1,
+            },
+            "`" => i = code_span_end(text, i),
+            "&" => {
+                // Entities are at most 32 characters long
+                let end = text[i + 1..text.len().min(i + 33)]
+                    .iter()
+                    .position(|c| c == ";")
+                    .map(|length| i + 1 + length);
+                match end.and_then(|end| Some((end, decode_entity(&text[i + 1..end].concat())?))) {
+                    Some((end, decoded)) => {
+                        ranges.push((i..end + 1, decoded));
+                        i = end + 1;
+                    }
+                    None => i += 1,
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    ranges
+}
+
+/// Ranges of quotes, dashes and ellipses with their typographic character.
+///
+/// Quotes open after whitespace or opening brackets and close otherwise.
+pub fn smart_ranges(text: &[String]) -> Vec<(Range<usize>, String)> {
+    let mut ranges = vec![];
+    let mut i = 0;
+    while i < text.len() {
+        let opening = i == 0
+            || matches!(
+                text[i - 1].as_str(),
+                " " | "\n" | "\t" | "(" | "[" | "{" | "-" | "\u{a0}"
+            );
+        let run = |c: &str| text[i..].iter().take_while(|other| *other == c).count();
+        let (length, smart) = match text[i].as_str() {
+            "\\" => (2, None),
+            "`" => (code_span_end(text, i) - i, None),
+            "\"" => (1, Some(if opening { "“" } else { "”" })),
+            "'" => (1, Some(if opening { "‘" } else { "’" })),
+            // Dashes starting a line are list markers, rules or heading underlines
+            "-" if i > 0 && text[i - 1] != "\n" => match run("-") {
+                2 => (2, Some("–")),
+                3 => (3, Some("—")),
+                length => (length, None),
+            },
+            "-" => (run("-"), None),
+            "." if run(".") == 3 => (3, Some("…")),
+            _ => (1, None),
+        };
+        if let Some(smart) = smart {
+            ranges.push((i..i + length, smart.to_owned()));
+        }
+        i += length;
+    }
+    ranges
+}
@@ -0,0 +1,73 @@
+use std::ops::Range;
+
+use pulldown_cmark::{Event, Options, Parser, Tag};
+
+use crate::{
+    document::{grapheme_index, is_word},
+    sentence,
+};
+
+/// Ranges of `text` from the innermost scope outwards: the word, inline spans like
+/// emphasis, links and code, the sentence, the line and the whole paragraph.
+///
+/// A selection stays in its paragraph, the widest scope is all of it. The element and the
+/// document around it are no scopes, as a selection can not span more than one paragraph.
+fn scopes(text: &[String]) -> Vec<Range<usize>> {
+    let mut scopes = vec![];
+    let mut start = 0;
+    for (i, grapheme) in text.iter().enumerate() {
+        if !is_word(grapheme) {
+            if start < i {
+                scopes.push(start..i);
+            }
+            start = i + 1;
+        }
+    }
+    if start < text.len() {
+        scopes.push(start..text.len());
+    }
+
+    let source = text.concat();
+    let index = grapheme_index(text);
+    for (event, range) in Parser::new_ext(&source, Options::all()).into_offset_iter() {
+        if let Event::Code(_)
+        | Event::Start(Tag::Emphasis | Tag::Strong | Tag::Strikethrough | Tag::Link(..)) = event
+        {
+            scopes.push(index(range.start)..index(range.end));
+        }
+    }
+
+    scopes.extend(
+        sentence::sentences(text)
+            .into_iter()
+            .map(|sentence| sentence::inner(text, sentence))
+            .filter(|sentence| !sentence.is_empty()),
+    );
+
+    let mut line = 0;
+    for (i, grapheme) in text.iter().enumerate() {
+        if grapheme == "\n" {
+            let mut end = i;
+            while end > line && text[end - 1].trim().is_empty() {
+                end -= 1;
+            }
+            scopes.push(line..end);
+            line = i + 1;
+        }
+    }
+    scopes.push(line..text.len());
+    scopes.push(0..text.len());
+    scopes
+}
+
+/// The smallest scope of `text` around `selection` that is larger than it.
+pub fn grow(text: &[String], selection: Range<usize>) -> Option<Range<usize>> {
+    scopes(text)
+        .into_iter()
+        .filter(|scope| {
+            scope.start <= selection.start
+                && selection.end <= scope.end
+                && scope.len() > selection.len()
+        })
+        .min_by_key(Range::len)
+}
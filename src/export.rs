@@ -0,0 +1,82 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use wasm_bindgen::JsCast;
+use web_sys::{window, Blob, BlobPropertyBag, HtmlAnchorElement, Url};
+use yew::Callback;
+
+use crate::{attachment, offline, zip::Zip};
+
+/// Offers `content` as a file download.
+pub fn download(name: &str, mime: &str, content: &[u8]) {
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(content));
+    let blob =
+        Blob::new_with_u8_array_sequence_and_options(&parts, BlobPropertyBag::new().type_(mime))
+            .unwrap();
+    let url = Url::create_object_url_with_blob(&blob).unwrap();
+    let anchor: HtmlAnchorElement = window()
+        .unwrap()
+        .document()
+        .unwrap()
+        .create_element("a")
+        .unwrap()
+        .unchecked_into();
+    anchor.set_href(&url);
+    anchor.set_download(name);
+    anchor.click();
+    let _ = Url::revoke_object_url(&url);
+}
+
+/// Packs `notes` as `notes/<name>.md` and all stored attachments under `attachments/` into
+/// a zip archive for `done`, with how many attachments it holds.
+pub fn archive(notes: Vec<(String, String)>, done: Callback<(Vec<u8>, usize)>) {
+    let mut zip = Zip::default();
+    for (name, content) in notes {
+        zip.add(&format!("notes/{}.md", name), content.as_bytes());
+    }
+    let zip = Rc::new(RefCell::new(zip));
+    offline::attachments(Callback::from(move |attachments: Vec<(String, Blob)>| {
+        let finish = {
+            let zip = zip.clone();
+            let done = done.clone();
+            move |added| done.emit((zip.replace(Zip::default()).finish(), added))
+        };
+        if attachments.is_empty() {
+            return finish(0);
+        }
+        // Attachments left to read and attachments added
+        let progress = Rc::new(Cell::new((attachments.len(), 0)));
+        for (name, blob) in attachments {
+            let zip = zip.clone();
+            let progress = progress.clone();
+            let finish = finish.clone();
+            attachment::bytes(&blob, move |content| {
+                let (mut pending, mut added) = progress.get();
+                if let Some(content) = content {
+                    zip.borrow_mut()
+                        .add(&format!("attachments/{}", name), &content);
+                    added += 1;
+                }
+                pending -= 1;
+                progress.set((pending, added));
+                if pending == 0 {
+                    finish(added);
+                }
+            });
+        }
+    }));
+}
+
+/// Downloads the [`archive`] of `notes` as `vault.zip`, `done` receives how many
+/// attachments it holds.
+pub fn vault(notes: Vec<(String, String)>, done: Callback<usize>) {
+    archive(
+        notes,
+        Callback::from(move |(archive, attachments): (Vec<u8>, usize)| {
+            download("vault.zip", "application/zip", &archive);
+            done.emit(attachments);
+        }),
+    );
+}
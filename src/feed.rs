@@ -0,0 +1,188 @@
+use std::collections::HashSet;
+
+use web_sys::{DomParser, Element, SupportedType};
+
+use crate::{clip, import, store::Storage};
+
+/// The setting the urls of `:feed add` are kept in, one per line.
+const FEEDS: &str = "feeds";
+
+/// An RSS or Atom feed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Feed {
+    pub title: String,
+    pub entries: Vec<Entry>,
+}
+
+/// An item of an RSS feed or entry of an Atom feed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Entry {
+    /// The `guid` or `id`, else the link
+    pub id: String,
+    pub title: String,
+    pub link: String,
+    /// The date as the feed has it
+    pub date: String,
+    /// The content or summary as markdown
+    pub content: String,
+}
+
+/// The text of the first child of `element` named like one of `names`.
+fn child(element: &Element, names: &[&str]) -> Option<String> {
+    names.iter().find_map(|name| {
+        let children = element.children();
+        (0..children.length())
+            .filter_map(|i| children.item(i))
+            .find(|child| child.tag_name() == *name)
+            .map(|child| {
+                // Atom has HTML written as XML, everything else escapes it
+                if child.get_attribute("type").as_deref() == Some("xhtml") {
+                    child.inner_html()
+                } else {
+                    child.text_content().unwrap_or_default()
+                }
+            })
+            .map(|text| text.trim().to_owned())
+            .filter(|text| !text.is_empty())
+    })
+}
+
+/// The link to the page of an Atom entry.
+fn atom_link(entry: &Element) -> Option<String> {
+    let links = entry.children();
+    (0..links.length())
+        .filter_map(|i| links.item(i))
+        .filter(|link| link.tag_name() == "link")
+        .find(|link| {
+            matches!(
+                link.get_attribute("rel").as_deref(),
+                None | Some("alternate")
+            )
+        })
+        .and_then(|link| link.get_attribute("href"))
+}
+
+/// Reads the RSS or Atom feed `xml`.
+pub fn parse(xml: &str) -> Result<Feed, String> {
+    let document = DomParser::new()
+        .unwrap()
+        .parse_from_string(xml, SupportedType::TextXml)
+        .map_err(|_| "The feed could not be read".to_owned())?;
+    let root = document
+        .document_element()
+        .filter(|_| document.get_elements_by_tag_name("parsererror").length() == 0)
+        .ok_or_else(|| "The feed is no valid XML".to_owned())?;
+    let (channel, items) = match root.tag_name().as_str() {
+        "rss" | "rdf:RDF" => (document.get_elements_by_tag_name("channel").item(0), "item"),
+        "feed" => (Some(root.clone()), "entry"),
+        _ => return Err("The page is no RSS or Atom feed".to_owned()),
+    };
+    let title = channel
+        .and_then(|channel| child(&channel, &["title"]))
+        .unwrap_or_default();
+    let items = document.get_elements_by_tag_name(items);
+    let entries = (0..items.length())
+        .filter_map(|i| items.item(i))
+        .map(|item| {
+            let link = child(&item, &["link"])
+                .or_else(|| atom_link(&item))
+                .unwrap_or_default();
+            let content = child(
+                &item,
+                &["content:encoded", "content", "description", "summary"],
+            )
+            .map(|html| import::html_to_md(&html))
+            .unwrap_or_default();
+            Entry {
+                id: child(&item, &["guid", "id"]).unwrap_or_else(|| link.clone()),
+                title: child(&item, &["title"]).unwrap_or_else(|| link.clone()),
+                date: child(&item, &["pubDate", "published", "updated", "dc:date"])
+                    .unwrap_or_default(),
+                link,
+                content,
+            }
+        })
+        .collect();
+    Ok(Feed { title, entries })
+}
+
+/// The urls of the feeds added with `:feed add`.
+pub fn feeds(store: &dyn Storage) -> Vec<String> {
+    store
+        .setting(FEEDS)
+        .unwrap_or_default()
+        .lines()
+        .map(str::to_owned)
+        .collect()
+}
+
+pub fn set_feeds(store: &mut dyn Storage, feeds: &[String]) {
+    store.set_setting(FEEDS, &feeds.join("\n"));
+}
+
+/// The note of `entry` of `feed` from `url`.
+fn entry_note(url: &str, entry: &Entry) -> String {
+    let mut md = format!("---\nsource: {}\nfeed: {}\n", entry.link, url);
+    if !entry.date.is_empty() {
+        md.push_str(&format!("published: \"{}\"\n", entry.date));
+    }
+    md.push_str(&format!("---\n# {}\n\n{}\n", entry.title, entry.content));
+    md
+}
+
+/// What `:feed sync` did with a feed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Synced {
+    pub new: usize,
+    pub updated: usize,
+}
+
+/// Saves the entries of `feed` from `url` that are new since the last sync.
+///
+/// Each entry becomes a note in a folder named after the feed, which is updated when the
+/// entry changes, unless the note was deleted. With `digest` new entries are instead listed
+/// in the note `Feeds/<today>`.
+pub fn sync(url: &str, feed: &Feed, digest: bool, today: &str, store: &mut dyn Storage) -> Synced {
+    let seen_key = format!("feedseen:{}", url);
+    let seen = store.setting(&seen_key).unwrap_or_default();
+    let seen: HashSet<_> = seen.lines().collect();
+    let folder = clip::note_name(
+        Some(feed.title.as_str()).filter(|title| !title.is_empty()),
+        url,
+    );
+    let mut synced = Synced::default();
+    let mut listed = vec![];
+    for entry in &feed.entries {
+        let new = !seen.contains(entry.id.as_str());
+        if digest {
+            if new {
+                listed.push(format!("- [{}]({})", entry.title, entry.link));
+                synced.new += 1;
+            }
+            continue;
+        }
+        let note = format!(
+            "{}/{}",
+            folder,
+            clip::note_name(Some(entry.title.as_str()), &entry.link)
+        );
+        let content = entry_note(url, entry);
+        match store.load(&note) {
+            None if new => synced.new += 1,
+            Some(old) if old != content => synced.updated += 1,
+            _ => continue,
+        }
+        store.save(&note, &content);
+    }
+    if !listed.is_empty() {
+        let note = format!("Feeds/{}", today);
+        let mut content = store
+            .load(&note)
+            .unwrap_or_else(|| format!("# Feeds {}\n", today));
+        content.push_str(&format!("\n## {}\n\n{}\n", folder, listed.join("\n")));
+        store.save(&note, &content);
+    }
+    let ids: Vec<_> = feed.entries.iter().map(|entry| entry.id.as_str()).collect();
+    store.set_setting(&seen_key, &ids.join("\n"));
+    synced
+}
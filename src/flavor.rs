@@ -0,0 +1,59 @@
+use pulldown_cmark::Options;
+
+/// The markdown dialect of a note, set with `:set flavor=` and stored in its frontmatter.
+#[derive(Clone, Copy, Debug, PartialEq, Default, Hash)]
+pub enum Flavor {
+    CommonMark,
+    #[default]
+    Gfm,
+    /// Pandoc's common extensions, including smart punctuation
+    PandocLite,
+}
+
+impl Flavor {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "commonmark" => Self::CommonMark,
+            "gfm" => Self::Gfm,
+            "pandoc-lite" => Self::PandocLite,
+            _ => return None,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::CommonMark => "commonmark",
+            Self::Gfm => "gfm",
+            Self::PandocLite => "pandoc-lite",
+        }
+    }
+
+    /// The extensions the parser recognizes.
+    ///
+    /// Smart punctuation is left out, as typographic characters are only shown while
+    /// rendering and never written to the source.
+    pub fn options(&self) -> Options {
+        let gfm = Options::ENABLE_TABLES
+            | Options::ENABLE_TASKLISTS
+            | Options::ENABLE_STRIKETHROUGH
+            | Options::ENABLE_FOOTNOTES;
+        match self {
+            Self::CommonMark => Options::empty(),
+            Self::Gfm | Self::PandocLite => gfm,
+        }
+    }
+
+    pub fn footnotes(&self) -> bool {
+        self.options().contains(Options::ENABLE_FOOTNOTES)
+    }
+
+    /// Whether `Term` lines followed by `: definition` lines are definition lists.
+    pub fn definition_lists(&self) -> bool {
+        self == &Self::PandocLite
+    }
+
+    /// Whether quotes, dashes and ellipses are rendered as typographic characters.
+    pub fn smart_punctuation(&self) -> bool {
+        self == &Self::PandocLite
+    }
+}
@@ -0,0 +1,35 @@
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{window, FocusOptions, HtmlElement};
+use yew::Callback;
+
+/// Calls `callback` with `false` when the tab loses focus and `true` when it gets it back.
+pub fn on_change(callback: Callback<bool>) {
+    let window = window().unwrap();
+    for (event, focused) in [("blur", false), ("focus", true)] {
+        let callback = callback.clone();
+        let listener = Closure::wrap(Box::new(move || callback.emit(focused)) as Box<dyn FnMut()>);
+        window
+            .add_event_listener_with_callback(event, listener.as_ref().unchecked_ref())
+            .unwrap();
+        listener.forget();
+    }
+}
+
+/// Whether the focus fell back to the page from `editor`, like after closing an overlay,
+/// instead of being in an input like the command line.
+pub fn lost(editor: &HtmlElement) -> bool {
+    window()
+        .unwrap()
+        .document()
+        .and_then(|document| document.active_element())
+        .map_or(true, |active| {
+            active.tag_name() != "INPUT" && !editor.contains(Some(active.as_ref()))
+        })
+}
+
+/// Focuses `element` without scrolling it into view.
+pub fn focus(element: &HtmlElement) {
+    element
+        .focus_with_options(FocusOptions::new().prevent_scroll(true))
+        .unwrap();
+}
@@ -0,0 +1,48 @@
+/// The label and text of a line starting a footnote definition, `[^label]: text`.
+fn start(line: &str) -> Option<(&str, &str)> {
+    let rest = line.trim_start().strip_prefix("[^")?;
+    let (label, text) = rest.split_once("]:")?;
+    (!label.is_empty() && !label.contains(char::is_whitespace)).then_some((label, text.trim()))
+}
+
+/// Whether the block is made up of footnote definitions.
+pub fn is_definition(text: &str) -> bool {
+    text.lines().next().and_then(start).is_some()
+}
+
+/// Footnote definitions by label, continued by the lines following them.
+pub fn definitions(md: &str) -> Vec<(String, String)> {
+    let mut definitions: Vec<(String, String)> = vec![];
+    let mut continued = false;
+    for line in md.lines() {
+        if let Some((label, text)) = start(line) {
+            definitions.push((label.to_owned(), text.to_owned()));
+            continued = true;
+        } else if line.trim().is_empty() {
+            continued = false;
+        } else if continued {
+            let text = &mut definitions.last_mut().unwrap().1;
+            text.push(' ');
+            text.push_str(line.trim());
+        }
+    }
+    definitions
+}
+
+/// Labels of the footnotes referenced as `[^label]` in `text`.
+pub fn references(text: &str) -> Vec<String> {
+    let mut references = vec![];
+    let mut rest = text;
+    while let Some(start) = rest.find("[^") {
+        rest = &rest[start + 2..];
+        if let Some(end) = rest.find(']') {
+            let label = &rest[..end];
+            let definition = rest[end + 1..].starts_with(':');
+            if !label.is_empty() && !label.contains(char::is_whitespace) && !definition {
+                references.push(label.to_owned());
+            }
+            rest = &rest[end + 1..];
+        }
+    }
+    references
+}
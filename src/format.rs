@@ -0,0 +1,458 @@
+use std::ops::Range;
+
+use pulldown_cmark::{Event, Options, Parser, Tag};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    document::{Document, Element, Markdown, Paragraph},
+    lock,
+};
+
+/// Settings of the formatter run with `:fmt`, and before writing with `:set fmtonsave`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Format {
+    /// Delimiter of emphasis, `*` or `_`, set with `:set emphasis=`, strong emphasis
+    /// always uses `**`
+    pub emphasis: char,
+    /// Marker of bullet list items, set with `:set bullet=`
+    pub bullet: char,
+    /// Lines longer than this are wrapped, `0` turns it off, set with `:set textwidth=`
+    pub text_width: usize,
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Self {
+            emphasis: '*',
+            bullet: '-',
+            text_width: 0,
+        }
+    }
+}
+
+/// What `gq` reflows.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Reflow {
+    /// The text the cursor line is part of
+    Line,
+    /// The block the cursor is in
+    Paragraph,
+    /// The lines of the visual selection
+    Selection,
+    /// The blocks from the cursor to the end of the note
+    ToEnd,
+}
+
+fn is_code_block(md: &str) -> bool {
+    md.starts_with("```") || md.starts_with("~~~") || md.starts_with("    ") || md.starts_with('\t')
+}
+
+/// Whether `line` is a thematic break like `* * *`, which looks like a list item.
+fn is_break(line: &str) -> bool {
+    let mut marks = line.chars().filter(|c| !c.is_whitespace());
+    let first = marks.next();
+    matches!(first, Some('*' | '-' | '_'))
+        && marks.clone().count() >= 2
+        && marks.all(|c| Some(c) == first)
+}
+
+/// The quote markers and list marker `line` starts with and the prefix of the lines
+/// continuing it.
+fn prefixes(line: &str) -> (&str, String) {
+    let quote = line.len()
+        - line
+            .trim_start_matches(|c: char| c == '>' || c == ' ')
+            .len();
+    let rest = &line[quote..];
+    let marker = if ["- ", "* ", "+ "]
+        .iter()
+        .any(|marker| rest.starts_with(marker))
+    {
+        2
+    } else {
+        let digits = rest.chars().take_while(char::is_ascii_digit).count();
+        match &rest[digits..] {
+            after if digits > 0 && (after.starts_with(". ") || after.starts_with(") ")) => {
+                digits + 2
+            }
+            _ => 0,
+        }
+    };
+    let prefix = &line[..quote + marker];
+    let continuation = if line[..quote].contains('>') {
+        line[..quote].to_owned()
+    } else {
+        " ".repeat(quote + marker)
+    };
+    (prefix, continuation)
+}
+
+/// Whether `line` is left as it is by a reflow, like table rows and headings.
+fn is_fixed(line: &str) -> bool {
+    let content = line.trim_start_matches(|c: char| c == '>' || c.is_whitespace());
+    content.is_empty()
+        || content.starts_with(['|', '#', '<'].as_ref())
+        || (content.starts_with('[') && content.contains("]:"))
+        || is_break(content)
+}
+
+/// Whether each of `lines` is a code fence or code between fences, also in list items and
+/// quotes.
+fn fenced(lines: &[&str]) -> Vec<bool> {
+    let mut fenced = false;
+    lines
+        .iter()
+        .map(|line| {
+            let content = line.trim_start_matches(|c: char| c == '>' || c.is_whitespace());
+            let fence = content.starts_with("```") || content.starts_with("~~~");
+            fenced ^= fence;
+            fence || fenced
+        })
+        .collect()
+}
+
+/// Whether a line starting with `word` would start a block, like a list item, quote or
+/// heading, instead of continuing the one before.
+fn starts_block(word: &str) -> bool {
+    let digits = word.chars().take_while(char::is_ascii_digit).count();
+    matches!(word, "*" | "+")
+        || word.starts_with(['>', '|'].as_ref())
+        || word.chars().all(|c| c == '#')
+        || word.starts_with("```")
+        || word.starts_with("~~~")
+        || word.chars().all(|c| c == '-')
+        || word.chars().all(|c| c == '=')
+        || (digits > 0 && matches!(&word[digits..], "." | ")"))
+}
+
+/// What a reflow of the lines `lines` of `md`, a block which is no code block, does to the
+/// cursor at byte `cursor`.
+///
+/// Consecutive lines of a list item or quote are joined and wrapped to `width` like
+/// [`wrap`], code in fences and lines like table rows are kept. The cursor stays on the
+/// same character.
+pub fn reflow(
+    md: &str,
+    width: usize,
+    lines: Range<usize>,
+    cursor: Option<usize>,
+) -> (String, Option<usize>) {
+    let source: Vec<&str> = md.split('\n').collect();
+    // Lines of the text to reflow, which continue the line they start at
+    let mut units: Vec<Range<usize>> = vec![];
+    let fenced = fenced(&source);
+    let depth = |line: &str| {
+        line.chars()
+            .take_while(|&c| c == '>' || c == ' ')
+            .filter(|&c| c == '>')
+            .count()
+    };
+    for (i, line) in source.iter().enumerate() {
+        if fenced[i] || is_fixed(line) {
+            continue;
+        }
+        let continues = units.last().map_or(false, |unit| {
+            let previous = source[i - 1];
+            unit.end == i
+                && depth(previous) == depth(line)
+                && prefixes(line)
+                    .0
+                    .trim_start_matches(|c| c == '>' || c == ' ')
+                    .is_empty()
+                && !previous.ends_with('\\')
+                && !previous.ends_with("  ")
+        });
+        match units.last_mut() {
+            Some(unit) if continues => unit.end = i + 1,
+            _ => units.push(i..i + 1),
+        }
+    }
+    units.retain(|unit| unit.start < lines.end && lines.start < unit.end);
+
+    let mut reflowed = String::new();
+    let mut new_cursor = None;
+    let mut offset = 0;
+    let mut i = 0;
+    while i < source.len() {
+        if i > 0 {
+            reflowed.push('\n');
+        }
+        let unit = match units.iter().find(|unit| unit.start == i) {
+            Some(unit) => unit.clone(),
+            None => {
+                let line = source[i];
+                if let Some(cursor) = cursor.filter(|&c| offset <= c && c <= offset + line.len()) {
+                    new_cursor = Some(reflowed.len() + cursor - offset);
+                }
+                reflowed.push_str(line);
+                offset += line.len() + 1;
+                i += 1;
+                continue;
+            }
+        };
+        let (prefix, continuation) = prefixes(source[i]);
+        // The cursor is kept on the same character, counted without whitespace and prefixes
+        let mut before_cursor = None;
+        let mut count = 0;
+        let mut words = vec![];
+        for (j, line) in source[unit.clone()].iter().enumerate() {
+            let start = if j == 0 {
+                prefix.len()
+            } else {
+                line.len()
+                    - line
+                        .trim_start_matches(|c: char| c == '>' || c.is_whitespace())
+                        .len()
+            };
+            for (k, c) in line[start..].char_indices() {
+                if cursor == Some(offset + start + k) {
+                    before_cursor = Some(count);
+                }
+                count += usize::from(!c.is_whitespace());
+            }
+            if cursor == Some(offset + line.len()) && before_cursor.is_none() {
+                before_cursor = Some(count);
+            }
+            if cursor.map_or(false, |cursor| offset <= cursor && cursor < offset + start) {
+                before_cursor = Some(count);
+            }
+            words.extend(line[start..].split_whitespace());
+            offset += line.len() + 1;
+        }
+        let hard_break = source[unit.end - 1].ends_with("  ");
+        let mut wrapped = wrap(&format!("{}{}", prefix, words.join(" ")), width);
+        if hard_break {
+            wrapped.last_mut().unwrap().push_str("  ");
+        }
+        let mut count = 0;
+        for (j, line) in wrapped.iter().enumerate() {
+            if j > 0 {
+                reflowed.push('\n');
+            }
+            let start = if j == 0 {
+                prefix.len()
+            } else {
+                continuation.len()
+            };
+            reflowed.push_str(&line[..start]);
+            for c in line[start..].chars() {
+                if before_cursor == Some(count) && !c.is_whitespace() && new_cursor.is_none() {
+                    new_cursor = Some(reflowed.len());
+                }
+                count += usize::from(!c.is_whitespace());
+                reflowed.push(c);
+            }
+        }
+        if before_cursor.is_some() && new_cursor.is_none() {
+            new_cursor = Some(reflowed.len().saturating_sub(1));
+        }
+        i = unit.end;
+    }
+    (reflowed, new_cursor)
+}
+
+/// `line` broken at spaces into lines of at most `width` columns where possible.
+///
+/// It is not broken before words that would make the next line a block of its own, like
+/// `-` or `1.`.
+fn wrap(line: &str, width: usize) -> Vec<String> {
+    let (prefix, continuation) = prefixes(line);
+    let mut lines = vec![];
+    let mut current = prefix.to_owned();
+    let mut empty = true;
+    for word in line[prefix.len()..]
+        .split(' ')
+        .filter(|word| !word.is_empty())
+    {
+        if !empty && current.width() + 1 + word.width() > width && !starts_block(word) {
+            lines.push(current);
+            current = format!("{}{}", continuation, word);
+        } else {
+            if !empty {
+                current.push(' ');
+            }
+            current.push_str(word);
+        }
+        empty = false;
+    }
+    lines.push(current);
+    lines
+}
+
+impl Format {
+    /// `md` of a block with its emphasis delimiters replaced by the configured ones.
+    ///
+    /// `_` is not used inside words, where it would not be emphasis.
+    pub fn emphasis(&self, md: &str) -> String {
+        let mut bytes = md.as_bytes().to_vec();
+        let mut set = |index: usize, delimiter: char| {
+            let inside_word = |i: Option<usize>| {
+                i.and_then(|i| md[i..].chars().next())
+                    .map_or(false, char::is_alphanumeric)
+            };
+            let before = md[..index].char_indices().next_back().map(|(i, _)| i);
+            let after = (index + 1 < md.len()).then_some(index + 1);
+            let replaceable = matches!(bytes[index], b'*' | b'_');
+            if !replaceable || (delimiter == '_' && (inside_word(before) || inside_word(after))) {
+                return;
+            }
+            bytes[index] = delimiter as u8;
+        };
+        for (event, range) in Parser::new_ext(md, Options::all()).into_offset_iter() {
+            let (delimiter, len) = match event {
+                Event::Start(Tag::Emphasis) => (self.emphasis, 1),
+                Event::Start(Tag::Strong) => ('*', 2),
+                _ => continue,
+            };
+            for i in 0..len {
+                set(range.start + i, delimiter);
+                set(range.end - 1 - i, delimiter);
+            }
+        }
+        String::from_utf8(bytes).unwrap_or_else(|_| md.to_owned())
+    }
+
+    /// `line` with the configured bullet and without trailing whitespace, hard breaks
+    /// become a `\`.
+    fn line(&self, line: &str, last: bool) -> String {
+        let trimmed = line.trim_end();
+        let line = if !last && !trimmed.is_empty() && line.ends_with("  ") {
+            format!("{}\\", trimmed)
+        } else {
+            trimmed.to_owned()
+        };
+        let content = line.trim_start();
+        let indent = &line[..line.len() - content.len()];
+        if !is_break(content) && ["- ", "* ", "+ "].iter().any(|m| content.starts_with(m)) {
+            format!("{}{} {}", indent, self.bullet, &content[2..])
+        } else {
+            line
+        }
+    }
+
+    /// The markdown source of a block normalized, code blocks are left as they are, also
+    /// when they are fenced in a list item or quote.
+    pub fn block(&self, md: &str) -> String {
+        if is_code_block(md) {
+            return md.to_owned();
+        }
+        let md = self.emphasis(md);
+        let lines: Vec<_> = md.split('\n').collect();
+        let fenced = fenced(&lines);
+        let last = lines.len() - 1;
+        lines
+            .into_iter()
+            .enumerate()
+            .flat_map(|(i, line)| {
+                if fenced[i] {
+                    return vec![line.to_owned()];
+                }
+                let line = self.line(line, i == last);
+                // Tables, headings, html and link definitions can not be wrapped
+                let wrappable = !line.trim_start().starts_with(['|', '#', '<', '['].as_ref());
+                if self.text_width > 0 && wrappable && line.width() > self.text_width {
+                    wrap(&line, self.text_width)
+                } else {
+                    vec![line]
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Normalizes every block of `document`, tables are aligned whenever they are written.
+    /// Formats the elements of `document` outside of locked regions.
+    pub fn document(&self, document: &mut Document) {
+        let locked = lock::regions(document);
+        for (i, element) in document.elements.iter_mut().enumerate() {
+            if locked.iter().any(|region| region.contains(&i)) {
+                continue;
+            }
+            match element {
+                Element::Paragraph(paragraph) => {
+                    let md = self.block(&paragraph.to_md());
+                    replace(paragraph, &md);
+                }
+                Element::Heading(heading) => {
+                    let md = self.emphasis(heading.content.to_md().trim());
+                    replace(&mut heading.content, &md);
+                }
+                Element::Table(table) => {
+                    for cell in table.cells.values_mut() {
+                        let md = self.emphasis(cell.to_md().trim());
+                        replace(cell, &md);
+                    }
+                }
+                Element::Definitions(list) => {
+                    for (_, item) in &mut list.items {
+                        let md = self.emphasis(item.to_md().trim());
+                        replace(item, &md);
+                    }
+                }
+                Element::Details(details) => {
+                    if let Some(summary) = &mut details.summary {
+                        let md = self.emphasis(summary.to_md().trim());
+                        replace(summary, &md);
+                    }
+                    let md = self.block(&details.body.to_md());
+                    replace(&mut details.body, &md);
+                }
+                // Frontmatter is no markdown
+                Element::Frontmatter(_) => {}
+            }
+        }
+    }
+}
+
+/// Reflows what `scope` covers of `paragraph` to `width` with [`reflow`].
+pub fn reflow_paragraph(paragraph: &mut Paragraph, width: usize, scope: Reflow) {
+    let md = paragraph.to_md();
+    if is_code_block(&md) {
+        return;
+    }
+    let text = &paragraph.text;
+    let line = |grapheme: usize| {
+        text[..grapheme.min(text.len())]
+            .iter()
+            .filter(|c| *c == "\n")
+            .count()
+    };
+    let lines = match (scope, paragraph.selection()) {
+        (Reflow::Selection, Some(selection)) => {
+            line(selection.start)..line(selection.end.saturating_sub(1)) + 1
+        }
+        (Reflow::Line | Reflow::Selection, _) => {
+            let cursor = line(paragraph.cursor.unwrap_or_default());
+            cursor..cursor + 1
+        }
+        (Reflow::Paragraph | Reflow::ToEnd, _) => 0..usize::MAX,
+    };
+    let cursor = paragraph
+        .cursor
+        .map(|cursor| text[..cursor.min(text.len())].concat().len());
+    let (reflowed, cursor) = reflow(&md, width, lines, cursor);
+    if reflowed == md {
+        return;
+    }
+    paragraph.text = reflowed.graphemes(true).map(String::from).collect();
+    let len = paragraph.text.len();
+    if paragraph.cursor.is_some() {
+        let cursor = cursor.map_or(0, |cursor| reflowed[..cursor].graphemes(true).count());
+        paragraph.cursor = Some(cursor.min(len.saturating_sub(1)));
+    }
+    paragraph.anchor = None;
+}
+
+/// Replaces the text of `paragraph` with `md`, keeping the cursor where it still fits.
+fn replace(paragraph: &mut Paragraph, md: &str) {
+    if paragraph.to_md() == md {
+        return;
+    }
+    paragraph.text = md.graphemes(true).map(String::from).collect();
+    let len = paragraph.text.len();
+    if let Some(cursor) = &mut paragraph.cursor {
+        *cursor = (*cursor).min(len.saturating_sub(1));
+    }
+    paragraph.anchor = None;
+}
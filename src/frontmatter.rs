@@ -0,0 +1,83 @@
+/// Whether all lines of `block` are `key: value` entries, the items and indented lines of
+/// their values, or comments.
+fn is_entries(block: &str) -> bool {
+    let mut key = false;
+    block.lines().all(|line| {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            true
+        } else if line.starts_with(char::is_whitespace) || trimmed.starts_with('-') {
+            key
+        } else {
+            key = matches!(line.split_once(':'), Some((name, _)) if !name.trim().is_empty());
+            key
+        }
+    }) && key
+}
+
+/// Splits the frontmatter, `key: value` lines between `---` lines at the start of a note,
+/// off `md`.
+///
+/// Anything else between them is left in the note, like a thematic break followed by a
+/// heading underlined with `---`.
+pub fn split(md: &str) -> (Option<&str>, &str) {
+    let rest = match md.strip_prefix("---\n") {
+        Some(rest) => rest,
+        None => return (None, md),
+    };
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if matches!(line.trim_end(), "---" | "...") {
+            if !is_entries(&rest[..offset]) {
+                break;
+            }
+            let body = &rest[offset + line.len()..];
+            return (
+                Some(rest[..offset].trim_end()),
+                body.trim_start_matches('\n'),
+            );
+        }
+        offset += line.len();
+    }
+    (None, md)
+}
+
+/// The value of `key` in `frontmatter`.
+pub fn get<'a>(frontmatter: &'a str, key: &str) -> Option<&'a str> {
+    frontmatter.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        (name.trim() == key).then(|| value.trim().trim_matches('"'))
+    })
+}
+
+/// The entries of a list value of `key` in `frontmatter`, `[one, two]` or `one, two`.
+pub fn list(frontmatter: &str, key: &str) -> Vec<String> {
+    get(frontmatter, key)
+        .unwrap_or_default()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(',')
+        .map(|entry| entry.trim().trim_matches('"').trim_matches('\'').to_owned())
+        .filter(|entry| !entry.is_empty())
+        .collect()
+}
+
+/// `frontmatter` with `key` set to `value`, appended if it was missing.
+pub fn set(frontmatter: &str, key: &str, value: &str) -> String {
+    let entry = format!("{}: {}", key, value);
+    let mut found = false;
+    let mut lines: Vec<String> = frontmatter
+        .lines()
+        .map(|line| match line.split_once(':') {
+            Some((name, _)) if name.trim() == key => {
+                found = true;
+                entry.clone()
+            }
+            _ => line.to_owned(),
+        })
+        .collect();
+    if !found {
+        lines.push(entry);
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,64 @@
+use yew::{classes, html, Html};
+
+use crate::{document::Render, keymap::Keymap, ApplicationState, Mode};
+
+/// Cheat sheet of the keybindings per mode, optionally filtered.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Help {
+    pub filter: Option<String>,
+    /// Mode, keys and description of each binding
+    pub entries: Vec<(Mode, String, &'static str)>,
+}
+
+impl Help {
+    pub fn new(keymap: &Keymap, filter: Option<&str>) -> Self {
+        let filter = filter.map(str::to_lowercase);
+        let entries = [Mode::Normal, Mode::Visual, Mode::Insert, Mode::Command]
+            .into_iter()
+            .flat_map(|mode| keymap.bindings(mode))
+            .filter(|binding| {
+                filter.as_ref().map_or(true, |filter| {
+                    binding.key.to_lowercase().contains(filter)
+                        || binding.description.to_lowercase().contains(filter)
+                })
+            })
+            .map(|binding| (binding.mode, binding.key.clone(), binding.description))
+            .collect();
+        Self { filter, entries }
+    }
+}
+
+impl Render for Help {
+    fn render(&self, _state: &ApplicationState) -> Html {
+        let section = |mode: Mode| {
+            let entries: Vec<_> = self.entries.iter().filter(|e| e.0 == mode).collect();
+            html! {
+                if !entries.is_empty() {
+                    <>
+                        <p class={classes!("font-bold", "mt-4")}>{format!("{:?}", mode)}</p>
+                        <table>
+                            {for entries.iter().map(|(_, key, description)| html!{
+                                <tr>
+                                    <td class={classes!("pr-8", "text-yellow-300")}>{key}</td>
+                                    <td>{description}</td>
+                                </tr>
+                            })}
+                        </table>
+                    </>
+                }
+            }
+        };
+        html! {
+            <div>
+                <p class={classes!("text-2xl")}>{"Keybindings"}</p>
+                {self.filter.as_ref().map(|filter| html!{
+                    <p class={classes!("text-gray-500")}>{format!("matching \"{}\"", filter)}</p>
+                }).unwrap_or_default()}
+                {section(Mode::Normal)}
+                {section(Mode::Visual)}
+                {section(Mode::Insert)}
+                {section(Mode::Command)}
+            </div>
+        }
+    }
+}
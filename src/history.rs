@@ -0,0 +1,112 @@
+use js_sys::{Array, Date};
+use wasm_bindgen::JsValue;
+
+use crate::date;
+
+/// A snapshot of all stored notes made with `:commit`, kept in IndexedDB.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Commit {
+    /// A short hash of the commit, like `3f9a0c1`
+    pub id: String,
+    pub message: String,
+    /// Milliseconds since the epoch
+    pub time: f64,
+    /// The notes with their content
+    pub notes: Vec<(String, String)>,
+}
+
+/// 64 bit FNV-1a of `parts`.
+fn hash<'a>(parts: impl IntoIterator<Item = &'a str>) -> u64 {
+    let mut hash = 0xcbf29ce484222325u64;
+    for part in parts {
+        // Separates the parts, so moving text between them changes the hash
+        for &byte in part.as_bytes().iter().chain(&[0]) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+impl Commit {
+    pub fn new(message: &str, time: f64, mut notes: Vec<(String, String)>) -> Self {
+        notes.sort();
+        let time_text = time.to_string();
+        let parts = [message, time_text.as_str()].into_iter().chain(
+            notes
+                .iter()
+                .flat_map(|(note, content)| [note.as_str(), content.as_str()]),
+        );
+        Self {
+            id: format!("{:016x}", hash(parts))[..7].to_owned(),
+            message: message.to_owned(),
+            time,
+            notes,
+        }
+    }
+
+    /// The content of `note` in the commit.
+    pub fn note(&self, note: &str) -> Option<&str> {
+        self.notes
+            .iter()
+            .find(|(name, _)| name == note)
+            .map(|(_, content)| content.as_str())
+    }
+
+    /// The entry of the commit in `:commits`, starting with its id.
+    pub fn summary(&self) -> String {
+        let time = date::format_date(&Date::new(&JsValue::from_f64(self.time)), "%F %R");
+        format!(
+            "{} {} {} ({} notes)",
+            self.id,
+            time,
+            self.message,
+            self.notes.len()
+        )
+    }
+
+    /// The commit as it is stored in IndexedDB.
+    pub fn to_js(&self) -> JsValue {
+        let notes: Array = self
+            .notes
+            .iter()
+            .map(|(note, content)| {
+                Array::of2(&JsValue::from_str(note), &JsValue::from_str(content))
+            })
+            .collect();
+        Array::of4(
+            &self.id.as_str().into(),
+            &self.message.as_str().into(),
+            &self.time.into(),
+            &notes,
+        )
+        .into()
+    }
+
+    pub fn from_js(value: &JsValue) -> Option<Self> {
+        let value = Array::from(value);
+        let notes = Array::from(&value.get(3))
+            .iter()
+            .map(|note| {
+                let note = Array::from(&note);
+                Some((note.get(0).as_string()?, note.get(1).as_string()?))
+            })
+            .collect::<Option<_>>()?;
+        Some(Self {
+            id: value.get(0).as_string()?,
+            message: value.get(1).as_string()?,
+            time: value.get(2).as_f64()?,
+            notes,
+        })
+    }
+}
+
+/// The commit `id` is the start of, if only one commit is.
+pub fn find<'a>(commits: &'a [Commit], id: &str) -> Result<&'a Commit, String> {
+    let mut matching = commits.iter().filter(|commit| commit.id.starts_with(id));
+    match (matching.next(), matching.next()) {
+        (Some(commit), None) if !id.is_empty() => Ok(commit),
+        (Some(_), Some(_)) => Err(format!("{} is the start of more than one commit", id)),
+        _ => Err(format!("No commit {}", id)),
+    }
+}
@@ -0,0 +1,443 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use js_sys::Reflect;
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{
+    window, DomParser, Element, File, FileReader, HtmlInputElement, Node, SupportedType, Url,
+};
+use yew::Callback;
+
+use crate::{attachment, opml, zip};
+
+/// A file input, `configure` sets what it accepts.
+fn file_input(configure: impl FnOnce(&HtmlInputElement)) -> HtmlInputElement {
+    let document = window().unwrap().document().unwrap();
+    let input: HtmlInputElement = document.create_element("input").unwrap().unchecked_into();
+    input.set_type("file");
+    configure(&input);
+    input
+}
+
+/// Lets the user pick a file, `callback` receives its name and content.
+pub fn pick_file(accept: &str, callback: Callback<(String, String)>) {
+    let input = file_input(|input| input.set_accept(accept));
+    let picker = input.clone();
+    let onchange = Closure::wrap(Box::new(move || {
+        let file = match picker.files().and_then(|files| files.get(0)) {
+            Some(file) => file,
+            None => return,
+        };
+        let reader = FileReader::new().unwrap();
+        let name = file.name();
+        let callback = callback.clone();
+        let result = reader.clone();
+        let onload = Closure::wrap(Box::new(move || {
+            if let Some(content) = result.result().ok().and_then(|r| r.as_string()) {
+                callback.emit((name.clone(), content));
+            }
+        }) as Box<dyn FnMut()>);
+        reader.set_onload(Some(onload.as_ref().unchecked_ref()));
+        onload.forget();
+        reader.read_as_text(&file).unwrap();
+    }) as Box<dyn FnMut()>);
+    input.set_onchange(Some(onchange.as_ref().unchecked_ref()));
+    onchange.forget();
+    input.click();
+}
+
+/// Lets the user pick a zip archive, or a folder with `folder`, `callback` receives the files
+/// in it by their paths or why it could not be read.
+///
+/// Folders are picked with `webkitdirectory`, which unlike the File System Access API works
+/// in all browsers.
+pub fn pick_vault(folder: bool, callback: Callback<Result<Vec<(String, Vec<u8>)>, String>>) {
+    let input = file_input(|input| {
+        if folder {
+            let _ = Reflect::set(input, &"webkitdirectory".into(), &true.into());
+        } else {
+            input.set_accept(".zip,application/zip");
+        }
+    });
+    let picker = input.clone();
+    let onchange = Closure::wrap(Box::new(move || {
+        let files: Vec<File> = picker
+            .files()
+            .map(|files| (0..files.length()).filter_map(|i| files.get(i)).collect())
+            .unwrap_or_default();
+        if !folder {
+            if let Some(archive) = files.first() {
+                let callback = callback.clone();
+                let name = archive.name();
+                attachment::bytes(archive, move |content| {
+                    callback.emit(
+                        content
+                            .ok_or_else(|| format!("{} could not be read", name))
+                            .and_then(|content| zip::read(&content)),
+                    )
+                });
+            }
+            return;
+        }
+        if files.is_empty() {
+            return callback.emit(Ok(vec![]));
+        }
+        let pending = Rc::new(Cell::new(files.len()));
+        let read = Rc::new(RefCell::new(vec![]));
+        for file in files {
+            // The path in the picked folder, starting with its name
+            let path = Reflect::get(&file, &"webkitRelativePath".into())
+                .ok()
+                .and_then(|path| path.as_string())
+                .filter(|path| !path.is_empty())
+                .unwrap_or_else(|| file.name());
+            let callback = callback.clone();
+            let pending = pending.clone();
+            let read = read.clone();
+            attachment::bytes(&file, move |content| {
+                if let Some(content) = content {
+                    read.borrow_mut().push((path, content));
+                }
+                pending.set(pending.get() - 1);
+                if pending.get() == 0 {
+                    callback.emit(Ok(read.take()));
+                }
+            });
+        }
+    }) as Box<dyn FnMut()>);
+    input.set_onchange(Some(onchange.as_ref().unchecked_ref()));
+    onchange.forget();
+    input.click();
+}
+
+/// Converts a file to markdown based on its extension.
+pub fn to_md(name: &str, content: &str) -> String {
+    let extension = name.rsplit_once('.').map(|(_, e)| e).unwrap_or_default();
+    match extension.to_lowercase().as_str() {
+        "html" | "htm" => html_to_md(content),
+        "org" => org_to_md(content),
+        "opml" => opml::opml_to_md(content),
+        "md" | "markdown" => content.to_owned(),
+        _ => text_to_md(content),
+    }
+}
+
+/// Escapes everything in plain text that would be read as markdown.
+pub fn text_to_md(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            let mut escaped = String::new();
+            for (i, c) in line.chars().enumerate() {
+                if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']' | '<')
+                    || (i == 0 && matches!(c, '#' | '>' | '-' | '+' | '|'))
+                {
+                    escaped.push('\\');
+                }
+                escaped.push(c);
+            }
+            escaped
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn html_to_md(html: &str) -> String {
+    let parser = DomParser::new().unwrap();
+    let document = parser
+        .parse_from_string(html, SupportedType::TextHtml)
+        .unwrap();
+    let mut md = String::new();
+    if let Some(body) = document.body() {
+        write_html(&body, &mut md);
+    }
+    md.trim().to_owned()
+}
+
+/// The title and the main content as markdown of the page at `url` with `html`, leaving
+/// out what is around it like the navigation.
+pub fn page_to_md(html: &str, url: &str) -> (Option<String>, String) {
+    let parser = DomParser::new().unwrap();
+    let document = parser
+        .parse_from_string(html, SupportedType::TextHtml)
+        .unwrap();
+    let title = Some(document.title().trim().to_owned()).filter(|title| !title.is_empty());
+    let main = ["article", "main", "[role=main]"]
+        .iter()
+        .find_map(|selector| document.query_selector(selector).ok().flatten())
+        .or_else(|| document.body().map(Into::into));
+    let main = match main {
+        Some(main) => main,
+        None => return (title, String::new()),
+    };
+    if let Ok(around) = main.query_selector_all("nav, header, footer, aside, form, iframe") {
+        for i in 0..around.length() {
+            if let Some(element) = around
+                .item(i)
+                .and_then(|node| node.dyn_into::<Element>().ok())
+            {
+                element.remove();
+            }
+        }
+    }
+    // Links and images relative to the page would not be found from the note
+    if let Ok(links) = main.query_selector_all("a[href], img[src]") {
+        for i in 0..links.length() {
+            let element = match links
+                .item(i)
+                .and_then(|node| node.dyn_into::<Element>().ok())
+            {
+                Some(element) => element,
+                None => continue,
+            };
+            let name = if element.tag_name().eq_ignore_ascii_case("a") {
+                "href"
+            } else {
+                "src"
+            };
+            let link = element.get_attribute(name).unwrap_or_default();
+            if let Ok(absolute) = Url::new_with_base(&link, url) {
+                let _ = element.set_attribute(name, &absolute.href());
+            }
+        }
+    }
+    let mut md = String::new();
+    write_html(&main, &mut md);
+    (title, md.trim().to_owned())
+}
+
+/// Makes sure the next content starts a new block.
+fn block(md: &mut String) {
+    md.truncate(md.trim_end_matches(' ').len());
+    if !md.is_empty() && !md.ends_with("\n\n") {
+        md.push_str(if md.ends_with('\n') { "\n" } else { "\n\n" });
+    }
+}
+
+fn write_children(node: &Node, md: &mut String) {
+    let children = node.child_nodes();
+    for i in 0..children.length() {
+        if let Some(child) = children.item(i) {
+            write_html(&child, md);
+        }
+    }
+}
+
+fn inline_text(node: &Node) -> String {
+    let mut md = String::new();
+    write_children(node, &mut md);
+    md.trim().replace("\n\n", "\n")
+}
+
+fn write_html(node: &Node, md: &mut String) {
+    if node.node_type() == Node::TEXT_NODE {
+        let text = node.text_content().unwrap_or_default();
+        let mut last_whitespace = md.ends_with(char::is_whitespace) || md.is_empty();
+        for c in text.chars() {
+            if c.is_whitespace() {
+                if !last_whitespace {
+                    md.push(' ');
+                }
+                last_whitespace = true;
+            } else {
+                md.push(c);
+                last_whitespace = false;
+            }
+        }
+        return;
+    }
+    let element: &Element = match node.dyn_ref() {
+        Some(element) => element,
+        None => return,
+    };
+    let attribute = |name| element.get_attribute(name).unwrap_or_default();
+    match element.tag_name().to_lowercase().as_str() {
+        tag @ ("h1" | "h2" | "h3" | "h4" | "h5" | "h6") => {
+            block(md);
+            md.push_str(&"#".repeat(tag[1..].parse().unwrap()));
+            md.push(' ');
+            md.push_str(&inline_text(node));
+            block(md);
+        }
+        "p" | "div" | "section" | "article" | "main" => {
+            block(md);
+            write_children(node, md);
+            block(md);
+        }
+        "br" => md.push_str("\\\n"),
+        "hr" => {
+            block(md);
+            md.push_str("---");
+            block(md);
+        }
+        "strong" | "b" => md.push_str(&format!("**{}**", inline_text(node))),
+        "em" | "i" => md.push_str(&format!("_{}_", inline_text(node))),
+        "del" | "s" => md.push_str(&format!("~~{}~~", inline_text(node))),
+        "code" => md.push_str(&format!("`{}`", node.text_content().unwrap_or_default())),
+        "pre" => {
+            block(md);
+            md.push_str("```\n");
+            md.push_str(node.text_content().unwrap_or_default().trim_end());
+            md.push_str("\n```");
+            block(md);
+        }
+        "a" => md.push_str(&format!("[{}]({})", inline_text(node), attribute("href"))),
+        "img" => md.push_str(&format!("![{}]({})", attribute("alt"), attribute("src"))),
+        list @ ("ul" | "ol") => {
+            block(md);
+            let items = node.child_nodes();
+            let mut number = 1;
+            for i in 0..items.length() {
+                let item = match items.item(i) {
+                    Some(item) if item.node_name().eq_ignore_ascii_case("li") => item,
+                    _ => continue,
+                };
+                let marker = if list == "ol" {
+                    format!("{}. ", number)
+                } else {
+                    "- ".to_owned()
+                };
+                number += 1;
+                for (i, line) in inline_text(&item).lines().enumerate() {
+                    md.push_str(&if i == 0 {
+                        marker.clone()
+                    } else {
+                        " ".repeat(marker.len())
+                    });
+                    md.push_str(line.trim());
+                    md.push('\n');
+                }
+            }
+            block(md);
+        }
+        "blockquote" => {
+            block(md);
+            for line in inline_text(node).lines() {
+                md.push_str("> ");
+                md.push_str(line);
+                md.push('\n');
+            }
+            block(md);
+        }
+        "table" => {
+            block(md);
+            let rows = element.query_selector_all("tr").unwrap();
+            for y in 0..rows.length() {
+                let row: Element = match rows.item(y).and_then(|row| row.dyn_into().ok()) {
+                    Some(row) => row,
+                    None => continue,
+                };
+                let cells = row.query_selector_all("th, td").unwrap();
+                let cells: Vec<_> = (0..cells.length())
+                    .filter_map(|x| cells.item(x))
+                    .map(|cell| inline_text(&cell).replace('\n', " ").replace('|', "\\|"))
+                    .collect();
+                md.push_str(&format!("| {} |\n", cells.join(" | ")));
+                if y == 0 {
+                    md.push_str(&format!("|{}\n", " --- |".repeat(cells.len())));
+                }
+            }
+            block(md);
+        }
+        "script" | "style" | "head" | "template" => {}
+        _ => write_children(node, md),
+    }
+}
+
+/// Converts the common parts of Org mode: headings, emphasis, links, lists and tables.
+pub fn org_to_md(org: &str) -> String {
+    let mut md = vec![];
+    let mut in_source = false;
+    for line in org.lines() {
+        let trimmed = line.trim_start();
+        let keyword = trimmed.to_lowercase();
+        if keyword.starts_with("#+begin_src") || keyword.starts_with("#+begin_example") {
+            in_source = true;
+            md.push(format!(
+                "```{}",
+                trimmed.split_whitespace().nth(1).unwrap_or_default()
+            ));
+        } else if keyword.starts_with("#+end_src") || keyword.starts_with("#+end_example") {
+            in_source = false;
+            md.push("```".to_owned());
+        } else if in_source {
+            md.push(line.to_owned());
+        } else if keyword.starts_with("#+title:") {
+            md.push(format!(
+                "# {}",
+                org_inline(trimmed["#+title:".len()..].trim())
+            ));
+        } else if trimmed.starts_with("#+") || trimmed.starts_with("# ") {
+            // Other keywords and comments have no markdown equivalent
+        } else if let Some(stars) = heading_level(line) {
+            md.push(format!(
+                "{} {}",
+                "#".repeat(stars),
+                org_inline(line[stars..].trim())
+            ));
+        } else if trimmed.starts_with('|') {
+            if trimmed.starts_with("|-") {
+                let columns = trimmed.matches('+').count() + 1;
+                md.push(format!("|{}", " --- |".repeat(columns)));
+            } else {
+                md.push(org_inline(line));
+            }
+        } else {
+            md.push(org_inline(line));
+        }
+    }
+    md.join("\n")
+}
+
+fn heading_level(line: &str) -> Option<usize> {
+    let stars = line.chars().take_while(|&c| c == '*').count();
+    (stars > 0 && line[stars..].starts_with(' ')).then_some(stars)
+}
+
+/// Converts `*bold*`, `/italic/`, `=code=`, `~code~`, `+strike+` and `[[url][text]]`.
+fn org_inline(text: &str) -> String {
+    let mut md = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with("[[") {
+            if let Some(end) = rest.find("]]") {
+                let link = &rest[2..end];
+                md.push_str(&match link.split_once("][") {
+                    Some((url, description)) => format!("[{}]({})", description, url),
+                    None => format!("<{}>", link),
+                });
+                rest = &rest[end + 2..];
+                continue;
+            }
+        }
+        let at_word_start = md.is_empty() || md.ends_with(|c: char| !c.is_alphanumeric());
+        let markup = match c {
+            '*' => Some("**"),
+            '/' => Some("_"),
+            '=' | '~' => Some("`"),
+            '+' => Some("~~"),
+            _ => None,
+        };
+        if let (Some(markup), true) = (markup, at_word_start) {
+            let inner = &rest[1..];
+            if let Some(end) = inner.find(c) {
+                let content = &inner[..end];
+                let closes = inner[end + 1..]
+                    .chars()
+                    .next()
+                    .map_or(true, |next| !next.is_alphanumeric());
+                if !content.is_empty() && !content.starts_with(' ') && closes {
+                    md.push_str(markup);
+                    md.push_str(content);
+                    md.push_str(markup);
+                    rest = &inner[end + 1..];
+                    continue;
+                }
+            }
+        }
+        md.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    md
+}
@@ -0,0 +1,63 @@
+/// Indentation inserted with Tab, set with `:set tabwidth=` and `:set expandtab`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Indent {
+    pub tab_width: usize,
+    /// Insert spaces up to the next tab stop instead of a tab
+    pub expand_tab: bool,
+}
+
+impl Default for Indent {
+    fn default() -> Self {
+        Self {
+            tab_width: 4,
+            expand_tab: true,
+        }
+    }
+}
+
+/// The info string of the fenced code block the text ends in, empty if it has none.
+fn code_block_language(before_cursor: &str) -> Option<&str> {
+    let mut language = None;
+    for line in before_cursor.lines() {
+        let line = line.trim_start();
+        if line.starts_with("```") || line.starts_with("~~~") {
+            language = match language {
+                Some(_) => None,
+                None => Some(line.trim_start_matches(|c| c == '`' || c == '~').trim()),
+            };
+        }
+    }
+    language
+}
+
+/// The indentation step common for a language.
+fn language_indent(language: &str) -> Option<&'static str> {
+    let language = language.split_whitespace().next().unwrap_or_default();
+    Some(match language.to_lowercase().as_str() {
+        "go" | "make" | "makefile" => "\t",
+        "python" | "py" | "rust" | "rs" | "java" | "c" | "cpp" | "c++" | "cs" | "csharp"
+        | "php" | "kotlin" | "swift" | "sql" => "    ",
+        "javascript" | "js" | "jsx" | "typescript" | "ts" | "tsx" | "json" | "yaml" | "yml"
+        | "html" | "css" | "scss" | "ruby" | "rb" | "lua" | "nix" | "dart" | "haskell" | "hs"
+        | "elixir" | "ex" | "vue" => "  ",
+        _ => return None,
+    })
+}
+
+impl Indent {
+    /// What Tab inserts after `before_cursor`, the text of the block up to the cursor.
+    pub fn insert(&self, before_cursor: &str) -> String {
+        if let Some(indent) = code_block_language(before_cursor).and_then(language_indent) {
+            return indent.to_owned();
+        }
+        if !self.expand_tab {
+            return "\t".to_owned();
+        }
+        let line = before_cursor.rsplit('\n').next().unwrap_or_default();
+        let column: usize = line
+            .chars()
+            .map(|c| if c == '\t' { self.tab_width } else { 1 })
+            .sum();
+        " ".repeat(self.tab_width - column % self.tab_width.max(1))
+    }
+}
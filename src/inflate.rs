@@ -0,0 +1,179 @@
+/// Base lengths of the length symbols from 257 on.
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// The order the lengths of the code length code are stored in.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// The bits of `data`, least significant first as deflate stores them.
+struct Bits<'a> {
+    data: &'a [u8],
+    /// Position in bits
+    position: usize,
+}
+
+impl Bits<'_> {
+    fn bits(&mut self, count: u8) -> Option<u32> {
+        let mut value = 0;
+        for i in 0..count {
+            let byte = self.data.get(self.position / 8)?;
+            value |= u32::from(byte >> (self.position % 8) & 1) << i;
+            self.position += 1;
+        }
+        Some(value)
+    }
+
+    /// The next `count` whole bytes after skipping to a byte boundary.
+    fn bytes(&mut self, count: usize) -> Option<&[u8]> {
+        let start = (self.position + 7) / 8;
+        let bytes = self.data.get(start..start + count)?;
+        self.position = (start + count) * 8;
+        Some(bytes)
+    }
+}
+
+/// A canonical Huffman code given by the code length of each symbol.
+struct Huffman {
+    /// How many codes there are of each length
+    counts: [u16; 16],
+    /// The symbols ordered by code
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0; 16];
+        for &length in lengths {
+            counts[usize::from(length)] += 1;
+        }
+        counts[0] = 0;
+        let mut symbols: Vec<_> = (0..lengths.len() as u16)
+            .filter(|&symbol| lengths[usize::from(symbol)] != 0)
+            .collect();
+        symbols.sort_by_key(|&symbol| lengths[usize::from(symbol)]);
+        Self { counts, symbols }
+    }
+
+    fn decode(&self, bits: &mut Bits) -> Option<u16> {
+        // The first code of the current length and the index of its symbol
+        let (mut code, mut first, mut index) = (0, 0, 0);
+        for &count in &self.counts[1..] {
+            code |= bits.bits(1)? as u16;
+            if code < first + count {
+                return self.symbols.get(usize::from(index + code - first)).copied();
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        None
+    }
+}
+
+/// The literal/length and distance codes of a block with dynamic codes.
+fn dynamic(bits: &mut Bits) -> Option<(Huffman, Huffman)> {
+    let literals = bits.bits(5)? as usize + 257;
+    let distances = bits.bits(5)? as usize + 1;
+    let code_lengths = bits.bits(4)? as usize + 4;
+    let mut lengths = [0; 19];
+    for &symbol in &CODE_LENGTH_ORDER[..code_lengths] {
+        lengths[symbol] = bits.bits(3)? as u8;
+    }
+    let code = Huffman::new(&lengths);
+    let mut lengths = Vec::with_capacity(literals + distances);
+    while lengths.len() < literals + distances {
+        let (length, repeat) = match code.decode(bits)? {
+            length @ 0..=15 => (length as u8, 1),
+            16 => (*lengths.last()?, 3 + bits.bits(2)?),
+            17 => (0, 3 + bits.bits(3)?),
+            _ => (0, 11 + bits.bits(7)?),
+        };
+        lengths.extend((0..repeat).map(|_| length));
+    }
+    if lengths.len() > literals + distances {
+        return None;
+    }
+    Some((
+        Huffman::new(&lengths[..literals]),
+        Huffman::new(&lengths[literals..]),
+    ))
+}
+
+/// The fixed literal/length and distance codes.
+fn fixed() -> (Huffman, Huffman) {
+    let literals: Vec<_> = (0..288)
+        .map(|symbol| match symbol {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8,
+        })
+        .collect();
+    (Huffman::new(&literals), Huffman::new(&[5; 30]))
+}
+
+/// Decompresses raw deflate `data`, like the files of a zip archive, `None` if it is
+/// corrupt.
+pub fn inflate(data: &[u8]) -> Option<Vec<u8>> {
+    let mut bits = Bits { data, position: 0 };
+    let mut output = vec![];
+    loop {
+        let last = bits.bits(1)? == 1;
+        let (literals, distances) = match bits.bits(2)? {
+            0 => {
+                let header = bits.bytes(4)?;
+                let length = u16::from_le_bytes([header[0], header[1]]);
+                if length != !u16::from_le_bytes([header[2], header[3]]) {
+                    return None;
+                }
+                output.extend_from_slice(bits.bytes(usize::from(length))?);
+                if last {
+                    return Some(output);
+                }
+                continue;
+            }
+            1 => fixed(),
+            2 => dynamic(&mut bits)?,
+            _ => return None,
+        };
+        loop {
+            let symbol = usize::from(literals.decode(&mut bits)?);
+            if symbol < 256 {
+                output.push(symbol as u8);
+                continue;
+            } else if symbol == 256 {
+                break;
+            }
+            let symbol = symbol - 257;
+            let length =
+                usize::from(*LENGTH_BASE.get(symbol)?) + bits.bits(LENGTH_EXTRA[symbol])? as usize;
+            let symbol = usize::from(distances.decode(&mut bits)?);
+            let distance = usize::from(*DISTANCE_BASE.get(symbol)?)
+                + bits.bits(DISTANCE_EXTRA[symbol])? as usize;
+            if distance > output.len() {
+                return None;
+            }
+            // The copy can overlap what it appends
+            for _ in 0..length {
+                output.push(output[output.len() - distance]);
+            }
+        }
+        if last {
+            return Some(output);
+        }
+    }
+}
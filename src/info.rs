@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+
+use js_sys::Date;
+use wasm_bindgen::JsValue;
+use yew::{classes, html, Html};
+
+use crate::{
+    attachment::{self, Attachment},
+    date,
+    document::Render,
+    frontmatter,
+    quickfix::Quickfix,
+    store::Storage,
+    ApplicationState,
+};
+
+/// The tags of `md`, from `tags:` in the frontmatter and `#tag`s in the text outside of
+/// code.
+pub fn tags(md: &str) -> Vec<String> {
+    let (frontmatter, body) = frontmatter::split(md);
+    let mut tags = frontmatter
+        .map(|frontmatter| frontmatter::list(frontmatter, "tags"))
+        .unwrap_or_default();
+    let mut code = false;
+    for line in body.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            code = !code;
+        }
+        if code {
+            continue;
+        }
+        for word in line.split_whitespace() {
+            let tag = match word.strip_prefix('#') {
+                Some(tag) => tag.trim_end_matches(|c: char| c.is_ascii_punctuation()),
+                None => continue,
+            };
+            if tag.starts_with(char::is_alphabetic)
+                && tag
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '/'))
+            {
+                tags.push(tag.to_owned());
+            }
+        }
+    }
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+fn words(md: &str) -> usize {
+    frontmatter::split(md)
+        .1
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .count()
+}
+
+fn size(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{} bytes", bytes)
+    } else {
+        format!("{:.1} KiB", bytes as f64 / 1024.)
+    }
+}
+
+fn time(milliseconds: Option<f64>) -> String {
+    milliseconds.map_or_else(
+        || "unknown".to_owned(),
+        |milliseconds| date::format_date(&Date::new(&JsValue::from_f64(milliseconds)), "%F %T"),
+    )
+}
+
+/// What `:info` shows about the open note.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Info {
+    pub title: String,
+    /// Name and value of each entry
+    pub rows: Vec<(&'static str, String)>,
+    /// The attachments linked in the note, with whether they are missing
+    pub attachments: Vec<(String, bool)>,
+}
+
+impl Info {
+    /// The metadata of `note` with the content `md` of its buffer.
+    pub fn new(
+        note: Option<&str>,
+        md: &str,
+        store: &dyn Storage,
+        attachments: &HashMap<String, Attachment>,
+    ) -> Self {
+        let mut rows = vec![];
+        if let Some(note) = note {
+            rows.push(("Created", time(store.created(note))));
+            rows.push(("Modified", time(store.modified(note))));
+        }
+        rows.push(("Words", words(md).to_string()));
+        let tags = tags(md);
+        rows.push((
+            "Tags",
+            if tags.is_empty() {
+                "(none)".to_owned()
+            } else {
+                tags.join(", ")
+            },
+        ));
+        if let Some(note) = note {
+            let backlinks = Quickfix::backlinks(note, store).items.len();
+            rows.push(("Backlinks", backlinks.to_string()));
+            let stored = store.load(note).map_or(0, |stored| stored.len());
+            rows.push(("Stored", size(stored)));
+            if store.pinned(note) {
+                rows.push(("Pinned", "yes".to_owned()));
+            }
+            if store.archived(note) {
+                rows.push(("Archived", "yes".to_owned()));
+            }
+        }
+        let mut linked = attachment::linked(md);
+        linked.sort();
+        linked.dedup();
+        Self {
+            title: note.unwrap_or("[No Name]").to_owned(),
+            rows,
+            attachments: linked
+                .into_iter()
+                .map(|name| {
+                    let missing = attachments.get(&name) == Some(&Attachment::Missing);
+                    (name, missing)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Render for Info {
+    fn render(&self, _state: &ApplicationState) -> Html {
+        html! {
+            <div>
+                <p class={classes!("text-2xl")}>{&self.title}</p>
+                <table class={classes!("mt-4")}>
+                    {for self.rows.iter().map(|(name, value)| html! {
+                        <tr>
+                            <td class={classes!("pr-8", "text-yellow-300")}>{name}</td>
+                            <td>{value}</td>
+                        </tr>
+                    })}
+                </table>
+                <p class={classes!("font-bold", "mt-4")}>{format!("Attachments ({})", self.attachments.len())}</p>
+                {for self.attachments.iter().map(|(name, missing)| html! {
+                    <p class={classes!(missing.then_some("text-red-300"))}>
+                        {name}
+                        if *missing {
+                            {" (missing)"}
+                        }
+                    </p>
+                })}
+            </div>
+        }
+    }
+}
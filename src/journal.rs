@@ -0,0 +1,284 @@
+use crate::diff::{diff, Change};
+
+/// A change to the words of a note made between two autosaves.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Edit {
+    Insert(String),
+    /// The text that was removed, checked when the journal is replayed
+    Delete(String),
+}
+
+/// An [`Edit`] at the word `pos` of the note as it was before it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Op {
+    /// When it was recorded, in milliseconds since the epoch
+    pub at: f64,
+    pub pos: usize,
+    pub edit: Edit,
+}
+
+/// The edits made to a note since the version it was loaded or saved as, recorded with
+/// its swap copy so concurrent edits can be merged word by word.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Journal {
+    /// [`hash`] of the version the ops apply to
+    pub base: u64,
+    pub ops: Vec<Op>,
+}
+
+/// FNV-1a, the same across builds unlike the std hasher.
+pub fn hash(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// `text` split into runs of word characters or spaces, newlines and other characters on
+/// their own.
+///
+/// Consecutive words never join another, so a slice of words splits into the same words.
+pub fn words(text: &str) -> Vec<String> {
+    let class = |c: char| match c {
+        '\n' => 0,
+        c if c.is_alphanumeric() || c == '_' || c == '\'' => 1,
+        c if c.is_whitespace() => 2,
+        _ => 3,
+    };
+    let mut words: Vec<String> = vec![];
+    let mut last = None;
+    for c in text.chars() {
+        let class = class(c);
+        match words.last_mut() {
+            Some(word) if last == Some(class) && matches!(class, 1 | 2) => word.push(c),
+            _ => words.push(c.to_string()),
+        }
+        last = Some(class);
+    }
+    words
+}
+
+/// [`diff`] of the words that differ between the common start and end.
+fn changes(old: &[String], new: &[String]) -> Vec<Change<String>> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut changes: Vec<_> = old[..prefix].iter().cloned().map(Change::Equal).collect();
+    changes.extend(diff(
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    ));
+    changes.extend(old[old.len() - suffix..].iter().cloned().map(Change::Equal));
+    changes
+}
+
+/// Words `start..end` of the base replaced by `words`.
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+struct Hunk {
+    start: usize,
+    end: usize,
+    words: Vec<String>,
+}
+
+fn hunks(base: &[String], other: &[String]) -> Vec<Hunk> {
+    let mut hunks = vec![];
+    let mut current: Option<Hunk> = None;
+    let mut i = 0;
+    for change in changes(base, other) {
+        match change {
+            Change::Equal(_) => {
+                hunks.extend(current.take());
+                i += 1;
+            }
+            Change::Delete(_) => {
+                i += 1;
+                current
+                    .get_or_insert(Hunk {
+                        start: i - 1,
+                        end: i,
+                        words: vec![],
+                    })
+                    .end = i;
+            }
+            Change::Insert(word) => current
+                .get_or_insert(Hunk {
+                    start: i,
+                    end: i,
+                    words: vec![],
+                })
+                .words
+                .push(word),
+        }
+    }
+    hunks.extend(current);
+    hunks
+}
+
+/// Three way merge of `local` and `remote`, both edited from `base`.
+///
+/// Returns `None` if both changed the same words, or added different words at the same place.
+pub fn merge(base: &str, local: &str, remote: &str) -> Option<String> {
+    let base = words(base);
+    let mut all = hunks(&base, &words(local));
+    all.extend(hunks(&base, &words(remote)));
+    all.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let mut merged = vec![];
+    let mut pos = 0;
+    let mut last: Option<&Hunk> = None;
+    for hunk in &all {
+        if let Some(last) = last {
+            // Both sides made the same change
+            if last == hunk {
+                continue;
+            }
+            let inserted_at_same_place = last.start == last.end && hunk.start == hunk.end;
+            if hunk.start < pos || (inserted_at_same_place && last.start == hunk.start) {
+                return None;
+            }
+        }
+        merged.extend_from_slice(&base[pos..hunk.start]);
+        merged.extend_from_slice(&hunk.words);
+        pos = hunk.end;
+        last = Some(hunk);
+    }
+    merged.extend_from_slice(&base[pos..]);
+    Some(merged.concat())
+}
+
+impl Journal {
+    pub fn new(base: &str) -> Self {
+        Self {
+            base: hash(base),
+            ops: vec![],
+        }
+    }
+
+    pub fn starts_from(&self, base: &str) -> bool {
+        self.base == hash(base)
+    }
+
+    /// `base` with the ops applied, `None` if they do not fit it.
+    pub fn content(&self, base: &str) -> Option<String> {
+        let mut text = words(base);
+        for op in &self.ops {
+            match &op.edit {
+                Edit::Insert(inserted) => {
+                    let inserted = words(inserted);
+                    if op.pos > text.len() {
+                        return None;
+                    }
+                    text.splice(op.pos..op.pos, inserted);
+                }
+                Edit::Delete(deleted) => {
+                    let end = op.pos + words(deleted).len();
+                    if text.get(op.pos..end)?.concat() != *deleted {
+                        return None;
+                    }
+                    text.drain(op.pos..end);
+                }
+            }
+        }
+        Some(text.concat())
+    }
+
+    /// Adds the ops turning `from`, the last version recorded, into `to` at the time `at`.
+    pub fn record(&mut self, from: &str, to: &str, at: f64) {
+        let mut pos = 0;
+        for change in changes(&words(from), &words(to)) {
+            let last = self.ops.last_mut().filter(|op| op.at == at);
+            match change {
+                Change::Equal(_) => pos += 1,
+                Change::Delete(word) => match last {
+                    Some(Op {
+                        pos: start,
+                        edit: Edit::Delete(deleted),
+                        ..
+                    }) if *start == pos => deleted.push_str(&word),
+                    _ => self.ops.push(Op {
+                        at,
+                        pos,
+                        edit: Edit::Delete(word),
+                    }),
+                },
+                Change::Insert(word) => {
+                    match last {
+                        Some(Op {
+                            pos: start,
+                            edit: Edit::Insert(inserted),
+                            ..
+                        }) if *start + words(inserted).len() == pos => inserted.push_str(&word),
+                        _ => self.ops.push(Op {
+                            at,
+                            pos,
+                            edit: Edit::Insert(word),
+                        }),
+                    }
+                    pos += 1;
+                }
+            }
+        }
+    }
+
+    /// `content`, the version with the ops applied to `base`, merged with `remote`, the
+    /// version stored elsewhere since.
+    pub fn merge(&self, base: &str, remote: &str) -> Option<String> {
+        merge(base, &self.content(base)?, remote)
+    }
+
+    /// One line per op after the base, `at  +/-  pos  text` separated by tabs.
+    pub fn encode(&self) -> String {
+        let escape = |text: &str| {
+            text.replace('\\', "\\\\")
+                .replace('\n', "\\n")
+                .replace('\t', "\\t")
+        };
+        let mut lines = vec![self.base.to_string()];
+        lines.extend(self.ops.iter().map(|op| {
+            let (sign, text) = match &op.edit {
+                Edit::Insert(text) => ('+', text),
+                Edit::Delete(text) => ('-', text),
+            };
+            format!("{}\t{}\t{}\t{}", op.at, sign, op.pos, escape(text))
+        }));
+        lines.join("\n")
+    }
+
+    pub fn decode(journal: &str) -> Option<Self> {
+        let unescape = |text: &str| {
+            let mut unescaped = String::new();
+            let mut chars = text.chars();
+            while let Some(c) = chars.next() {
+                unescaped.push(match c {
+                    '\\' => match chars.next()? {
+                        'n' => '\n',
+                        't' => '\t',
+                        c => c,
+                    },
+                    c => c,
+                });
+            }
+            Some(unescaped)
+        };
+        let mut lines = journal.split('\n');
+        let base = lines.next()?.parse().ok()?;
+        let ops = lines
+            .map(|line| {
+                let mut fields = line.splitn(4, '\t');
+                let at = fields.next()?.parse().ok()?;
+                let sign = fields.next()?;
+                let pos = fields.next()?.parse().ok()?;
+                let text = unescape(fields.next()?)?;
+                let edit = match sign {
+                    "+" => Edit::Insert(text),
+                    "-" => Edit::Delete(text),
+                    _ => return None,
+                };
+                Some(Op { at, pos, edit })
+            })
+            .collect::<Option<_>>()?;
+        Some(Self { base, ops })
+    }
+}
@@ -0,0 +1,90 @@
+use crate::document::{is_word, Document, Location};
+
+/// Keys used for labels, home row first.
+const KEYS: &str = "asdfghjklqwertyuiopzxcvbnm";
+
+/// State of a `gs` jump, started in Normal mode.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Jump {
+    /// Waiting for the character to jump to, Enter jumps to word starts
+    Target,
+    /// Waiting for the label of a target, `typed` holds its keys so far
+    Labels { targets: Vec<Target>, typed: String },
+}
+
+/// A position in the document shown with a label.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Target {
+    pub label: String,
+    /// Index in [`Document::paragraphs`]
+    pub paragraph: usize,
+    pub location: Location,
+    pub cursor: usize,
+}
+
+/// `count` distinct labels, single keys if there are enough and pairs otherwise.
+fn labels(count: usize) -> Vec<String> {
+    let keys: Vec<char> = KEYS.chars().collect();
+    if count <= keys.len() {
+        return keys.iter().take(count).map(char::to_string).collect();
+    }
+    keys.iter()
+        .flat_map(|&first| {
+            keys.iter()
+                .map(move |&second| format!("{}{}", first, second))
+        })
+        .take(count)
+        .collect()
+}
+
+/// The occurrences of `key` in `document`, or the word starts for `Enter`, labeled.
+///
+/// Labels are assigned by distance from the cursor so the closest targets get the
+/// shortest ones.
+pub fn targets(document: &Document, key: &str) -> Vec<Target> {
+    let paragraphs = document.paragraphs();
+    let current = paragraphs
+        .iter()
+        .position(|(_, paragraph)| paragraph.cursor.is_some())
+        .unwrap_or_default();
+    let mut targets: Vec<_> = paragraphs
+        .iter()
+        .enumerate()
+        .flat_map(|(i, (location, paragraph))| {
+            let text = &paragraph.text;
+            (0..text.len())
+                .filter(move |&j| {
+                    if key == "Enter" {
+                        is_word(&text[j]) && (j == 0 || !is_word(&text[j - 1]))
+                    } else {
+                        text[j] == key
+                    }
+                })
+                .map(move |j| Target {
+                    label: String::new(),
+                    paragraph: i,
+                    location: *location,
+                    cursor: j,
+                })
+        })
+        .collect();
+    let cursor = paragraphs
+        .get(current)
+        .and_then(|(_, paragraph)| paragraph.cursor)
+        .unwrap_or_default();
+    let mut by_distance: Vec<_> = (0..targets.len()).collect();
+    by_distance.sort_by_key(|&i| {
+        let target = &targets[i];
+        (
+            (target.paragraph as isize - current as isize).abs(),
+            (target.cursor as isize - cursor as isize).abs(),
+        )
+    });
+    let labels = labels(by_distance.len());
+    for (i, label) in by_distance.into_iter().zip(labels) {
+        targets[i].label = label;
+    }
+    // Beyond the labels there are no keystrokes left to reach a target
+    targets.retain(|target| !target.label.is_empty());
+    targets
+}
@@ -0,0 +1,584 @@
+use crate::{
+    command_line::Edit,
+    document::{Command, Motion},
+    format::Reflow,
+    split::STEP,
+    Mode, Msg,
+};
+
+/// Keys bound to the messages they send in a mode.
+///
+/// Keys are written as their `KeyboardEvent.key` with `C-` and `A-` prefixes for Ctrl and Alt,
+/// sequences of keys are separated by spaces like `z g`. Keys can also be written as their
+/// `KeyboardEvent.code`, with an `S-` prefix for Shift, to bind them by position. The space
+/// bar is written `Space` and `<leader>` stands for [`Keymap::leader`].
+#[derive(Clone, Debug)]
+pub struct Binding {
+    pub mode: Mode,
+    pub key: String,
+    pub messages: Vec<Msg>,
+    pub description: &'static str,
+}
+
+/// The keyboard layout the default bindings are arranged for, set with `:set layout=`.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum Layout {
+    #[default]
+    Qwerty,
+    /// Movement on `hnei` where Colemak puts the QWERTY `hjkl`, Insert mode on `u` and
+    /// the next match on `k`
+    Colemak,
+    /// Letters are bound by the physical key in their QWERTY position, on any layout
+    Position,
+}
+
+impl Layout {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "qwerty" => Self::Qwerty,
+            "colemak" => Self::Colemak,
+            "position" => Self::Position,
+            _ => return None,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Qwerty => "qwerty",
+            Self::Colemak => "colemak",
+            Self::Position => "position",
+        }
+    }
+}
+
+/// The physical key of `key` if it is a letter, e.g. `C-KeyW` for `C-w` and `S-KeyN` for `N`.
+fn code(key: &str) -> String {
+    let (modifiers, letter) = match key.rfind('-') {
+        Some(i) if i + 1 < key.len() => key.split_at(i + 1),
+        _ => ("", key),
+    };
+    let mut chars = letter.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_alphabetic() => format!(
+            "{}{}Key{}",
+            modifiers,
+            if c.is_ascii_uppercase() { "S-" } else { "" },
+            c.to_ascii_uppercase()
+        ),
+        _ => key.to_owned(),
+    }
+}
+
+/// Splits the count typed before a key sequence, like `1 2` of `1 2 j`, off `keys`.
+pub fn split_count(keys: &str) -> (&str, &str) {
+    let mut end = 0;
+    for key in keys.split(' ') {
+        let digit = key.len() == 1 && key.chars().all(|c| c.is_ascii_digit());
+        if !digit || (end == 0 && key == "0") {
+            break;
+        }
+        end += key.len() + 1;
+    }
+    let count = keys[..end.min(keys.len())].trim_end();
+    (count, keys[count.len()..].trim_start())
+}
+
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    pub bindings: Vec<Binding>,
+    /// The key `<leader>` stands for in bindings, set with `:set leader=`
+    pub leader: String,
+}
+
+impl Keymap {
+    pub fn bind(&mut self, mode: Mode, key: &str, messages: Vec<Msg>, description: &'static str) {
+        self.unbind(mode, key);
+        self.bindings.push(Binding {
+            mode,
+            key: key.to_owned(),
+            messages,
+            description,
+        });
+    }
+
+    pub fn unbind(&mut self, mode: Mode, key: &str) {
+        self.bindings
+            .retain(|binding| binding.mode != mode || binding.key != key);
+    }
+
+    /// The default bindings arranged for `layout`.
+    pub fn new(layout: Layout) -> Self {
+        use Mode::*;
+        let mut keymap = Self::default();
+        match layout {
+            Layout::Qwerty => {}
+            Layout::Colemak => {
+                for key in ["j", "k", "l", "N"] {
+                    keymap.unbind(Normal, key);
+                }
+                keymap.bind(Normal, "n", vec![Msg::Cmd(Command::Down)], "Move down");
+                keymap.bind(Normal, "e", vec![Msg::Cmd(Command::Up)], "Move up");
+                keymap.bind(Normal, "i", vec![Msg::Cmd(Command::Right)], "Move right");
+                keymap.bind(Normal, "u", vec![Msg::Mode(Insert)], "Insert mode");
+                keymap.bind(Normal, "k", vec![Msg::SearchNext(false)], "Next match");
+                keymap.bind(Normal, "K", vec![Msg::SearchNext(true)], "Previous match");
+                for key in ["j", "k", "l", "i c", "i s"] {
+                    keymap.unbind(Visual, key);
+                }
+                keymap.bind(Visual, "n", vec![Msg::Cmd(Command::Down)], "Extend down");
+                keymap.bind(Visual, "e", vec![Msg::Cmd(Command::Up)], "Extend up");
+                keymap.bind(Visual, "i", vec![Msg::Cmd(Command::Right)], "Extend right");
+                keymap.bind(
+                    Visual,
+                    "u c",
+                    vec![Msg::SelectColumn],
+                    "Select table column",
+                );
+                keymap.bind(
+                    Visual,
+                    "u s",
+                    vec![Msg::Cmd(Command::SelectSentence(false))],
+                    "Select sentence",
+                );
+            }
+            Layout::Position => {
+                for binding in &mut keymap.bindings {
+                    binding.key = binding
+                        .key
+                        .split(' ')
+                        .map(code)
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                }
+            }
+        }
+        keymap
+    }
+
+    /// The keys of `binding` with `<leader>` replaced.
+    fn keys(&self, binding: &Binding) -> String {
+        binding.key.replace("<leader>", &self.leader)
+    }
+
+    pub fn lookup(&self, mode: Mode, key: &str) -> Option<&Binding> {
+        self.bindings
+            .iter()
+            .find(|binding| binding.mode == mode && self.keys(binding) == key)
+    }
+
+    /// Whether `keys` start a longer sequence bound in `mode`.
+    pub fn is_prefix(&self, mode: Mode, keys: &str) -> bool {
+        !self.continuations(mode, keys).is_empty()
+    }
+
+    /// The keys that can follow `keys` in `mode`, with the description of the binding they
+    /// complete or `None` if they start a longer sequence.
+    pub fn continuations(&self, mode: Mode, keys: &str) -> Vec<(String, Option<&'static str>)> {
+        let prefix = format!("{} ", keys);
+        let mut continuations: Vec<(String, Option<&'static str>)> = vec![];
+        for binding in self.bindings(mode) {
+            let keys = self.keys(binding);
+            let rest = match keys.strip_prefix(&prefix) {
+                Some(rest) => rest,
+                None => continue,
+            };
+            let (next, description) = match rest.split_once(' ') {
+                Some((next, _)) => (next, None),
+                None => (rest, Some(binding.description)),
+            };
+            if continuations.iter().all(|(key, _)| key != next) {
+                continuations.push((next.to_owned(), description));
+            }
+        }
+        continuations
+    }
+
+    pub fn bindings(&self, mode: Mode) -> impl Iterator<Item = &Binding> {
+        self.bindings
+            .iter()
+            .filter(move |binding| binding.mode == mode)
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        use Mode::*;
+        let mut keymap = Self {
+            bindings: vec![],
+            leader: "Space".to_owned(),
+        };
+        for mode in [Insert, Command] {
+            keymap.bind(mode, "Escape", vec![Msg::Mode(Normal)], "Normal mode");
+        }
+        keymap.bind(
+            Insert,
+            "ArrowLeft",
+            vec![Msg::CursorMove(-1, 0)],
+            "Move left",
+        );
+        keymap.bind(
+            Insert,
+            "ArrowDown",
+            vec![Msg::CursorMove(0, 1)],
+            "Move down",
+        );
+        keymap.bind(Insert, "ArrowUp", vec![Msg::CursorMove(0, -1)], "Move up");
+        keymap.bind(
+            Insert,
+            "ArrowRight",
+            vec![Msg::CursorMove(1, 0)],
+            "Move right",
+        );
+        let edits = [
+            ("ArrowLeft", Edit::Left, "Move left"),
+            ("ArrowRight", Edit::Right, "Move right"),
+            ("Home", Edit::Home, "Move to start"),
+            ("C-a", Edit::Home, "Move to start"),
+            ("End", Edit::End, "Move to end"),
+            ("C-e", Edit::End, "Move to end"),
+            ("C-ArrowLeft", Edit::WordLeft, "Move word left"),
+            ("A-b", Edit::WordLeft, "Move word left"),
+            ("C-ArrowRight", Edit::WordRight, "Move word right"),
+            ("A-f", Edit::WordRight, "Move word right"),
+            ("Backspace", Edit::Backspace, "Delete left"),
+            ("C-w", Edit::DeleteWord, "Delete word left"),
+            ("C-u", Edit::DeleteToStart, "Delete to start"),
+        ];
+        for (key, edit, description) in edits {
+            keymap.bind(Command, key, vec![Msg::CommandLine(edit)], description);
+        }
+        keymap.bind(
+            Insert,
+            "Enter",
+            vec![Msg::Newline],
+            "Line break, continuing lists and tables",
+        );
+        keymap.bind(
+            Insert,
+            "Backspace",
+            vec![Msg::Cmd(Command::Delete(Motion::Left))],
+            "Delete left",
+        );
+        keymap.bind(Insert, "Tab", vec![Msg::Tab], "Insert completion or indent");
+        keymap.bind(
+            Insert,
+            "C-w",
+            vec![Msg::Cmd(Command::Delete(Motion::WordLeft))],
+            "Delete word left",
+        );
+        keymap.bind(
+            Insert,
+            "C-u",
+            vec![Msg::Cmd(Command::Delete(Motion::LineStart))],
+            "Delete to line start",
+        );
+        keymap.bind(
+            Insert,
+            "C-o",
+            vec![Msg::Mode(Normal), Msg::NormalOnce],
+            "Run one Normal mode command",
+        );
+        // Vim's other key for it, `C-v` is left to the browser to paste
+        keymap.bind(
+            Insert,
+            "C-q",
+            vec![Msg::Literal],
+            "Type the next character without expanding abbreviations",
+        );
+        keymap.bind(
+            Insert,
+            "C-n",
+            vec![Msg::Completion(Command::Down)],
+            "Next completion",
+        );
+        keymap.bind(
+            Insert,
+            "C-p",
+            vec![Msg::Completion(Command::Up)],
+            "Previous completion",
+        );
+        keymap.bind(Normal, "Escape", vec![Msg::CloseOverlay], "Close overlay");
+        keymap.bind(Normal, "i", vec![Msg::Mode(Insert)], "Insert mode");
+        keymap.bind(Normal, "v", vec![Msg::Mode(Visual)], "Visual mode");
+        keymap.bind(Visual, "Escape", vec![Msg::Mode(Normal)], "Normal mode");
+        keymap.bind(Visual, "h", vec![Msg::Cmd(Command::Left)], "Extend left");
+        keymap.bind(Visual, "l", vec![Msg::Cmd(Command::Right)], "Extend right");
+        keymap.bind(
+            Visual,
+            "%",
+            vec![Msg::Cmd(Command::MatchPair)],
+            "Extend to matching delimiter",
+        );
+        keymap.bind(
+            Normal,
+            "A-ArrowUp",
+            vec![Msg::Mode(Visual), Msg::ExpandSelection(true)],
+            "Select enclosing scope",
+        );
+        keymap.bind(
+            Visual,
+            "A-ArrowUp",
+            vec![Msg::ExpandSelection(true)],
+            "Grow selection",
+        );
+        keymap.bind(
+            Visual,
+            "A-ArrowDown",
+            vec![Msg::ExpandSelection(false)],
+            "Shrink selection",
+        );
+        keymap.bind(
+            Visual,
+            "i c",
+            vec![Msg::SelectColumn],
+            "Select table column",
+        );
+        keymap.bind(
+            Visual,
+            "y",
+            vec![
+                Msg::YankColumn(false),
+                Msg::YankRows(false),
+                Msg::YankSelection,
+                Msg::Mode(Normal),
+            ],
+            "Yank selection or selected column or rows",
+        );
+        keymap.bind(
+            Visual,
+            "d",
+            vec![
+                Msg::YankColumn(true),
+                Msg::YankRows(true),
+                Msg::YankSelection,
+                Msg::Cmd(Command::Delete(Motion::Selection)),
+                Msg::Mode(Normal),
+            ],
+            "Delete selection or selected column or rows",
+        );
+        keymap.bind(
+            Normal,
+            "V",
+            vec![Msg::Mode(Visual), Msg::SelectRows],
+            "Select table rows or line",
+        );
+        keymap.bind(Visual, "j", vec![Msg::Cmd(Command::Down)], "Extend down");
+        keymap.bind(Visual, "k", vec![Msg::Cmd(Command::Up)], "Extend up");
+        keymap.bind(
+            Normal,
+            "(",
+            vec![Msg::Cmd(Command::Sentence(false))],
+            "Previous sentence",
+        );
+        keymap.bind(
+            Normal,
+            ")",
+            vec![Msg::Cmd(Command::Sentence(true))],
+            "Next sentence",
+        );
+        keymap.bind(
+            Visual,
+            "(",
+            vec![Msg::Cmd(Command::Sentence(false))],
+            "Extend to previous sentence",
+        );
+        keymap.bind(
+            Visual,
+            ")",
+            vec![Msg::Cmd(Command::Sentence(true))],
+            "Extend to next sentence",
+        );
+        for (key, around, description) in [
+            ("i s", false, "Select sentence"),
+            ("a s", true, "Select sentence with the space after it"),
+        ] {
+            keymap.bind(
+                Visual,
+                key,
+                vec![Msg::Cmd(Command::SelectSentence(around))],
+                description,
+            );
+        }
+        for (key, around, mode, description) in [
+            ("d i s", false, Normal, "Delete sentence"),
+            (
+                "d a s",
+                true,
+                Normal,
+                "Delete sentence with the space after it",
+            ),
+            ("c i s", false, Insert, "Change sentence"),
+            (
+                "c a s",
+                true,
+                Insert,
+                "Change sentence with the space after it",
+            ),
+        ] {
+            keymap.bind(
+                Normal,
+                key,
+                vec![
+                    Msg::Mode(Visual),
+                    Msg::Cmd(Command::SelectSentence(around)),
+                    Msg::Cmd(Command::Delete(Motion::Selection)),
+                    Msg::Mode(mode),
+                ],
+                description,
+            );
+        }
+        keymap.bind(
+            Normal,
+            "p",
+            vec![Msg::PasteColumn],
+            "Paste text, column or rows after the cursor",
+        );
+        keymap.bind(Normal, ":", vec![Msg::Mode(Command)], "Command mode");
+        keymap.bind(Normal, "h", vec![Msg::Cmd(Command::Left)], "Move left");
+        keymap.bind(Normal, "j", vec![Msg::Cmd(Command::Down)], "Move down");
+        keymap.bind(Normal, "k", vec![Msg::Cmd(Command::Up)], "Move up");
+        keymap.bind(Normal, "l", vec![Msg::Cmd(Command::Right)], "Move right");
+        keymap.bind(
+            Normal,
+            "%",
+            vec![Msg::Cmd(Command::MatchPair)],
+            "Jump to matching delimiter",
+        );
+        for key in ["C-6", "C-^"] {
+            keymap.bind(
+                Normal,
+                key,
+                vec![Msg::Execute("b#".to_owned())],
+                "Alternate buffer",
+            );
+        }
+        keymap.bind(Normal, "C-w h", vec![Msg::FocusPane(true)], "Left pane");
+        keymap.bind(Normal, "C-w l", vec![Msg::FocusPane(false)], "Right pane");
+        keymap.bind(Normal, "C-w >", vec![Msg::ResizePane(STEP)], "Widen pane");
+        keymap.bind(Normal, "C-w <", vec![Msg::ResizePane(-STEP)], "Narrow pane");
+        keymap.bind(
+            Normal,
+            "g s",
+            vec![Msg::Jump],
+            "Jump to a character, Enter for word starts",
+        );
+        let reflows = [
+            ("g q q", Reflow::Line, "Reflow line"),
+            ("g q i p", Reflow::Paragraph, "Reflow paragraph"),
+            ("g q a p", Reflow::Paragraph, "Reflow paragraph"),
+            ("g q G", Reflow::ToEnd, "Reflow to end of note"),
+        ];
+        for (keys, scope, description) in reflows {
+            keymap.bind(Normal, keys, vec![Msg::Reflow(scope)], description);
+        }
+        keymap.bind(
+            Visual,
+            "g q",
+            vec![Msg::Reflow(Reflow::Selection), Msg::Mode(Normal)],
+            "Reflow selected lines",
+        );
+        keymap.bind(
+            Normal,
+            "<leader> f f",
+            vec![Msg::Execute("find".to_owned())],
+            "Find note",
+        );
+        keymap.bind(
+            Normal,
+            "<leader> f b",
+            vec![Msg::Execute("ls".to_owned())],
+            "Find buffer",
+        );
+        let blocks = [
+            ("c", "codeblock", "Wrap in code block"),
+            ("q", "quote", "Wrap in quote"),
+            ("1", "heading 1", "Turn into heading 1"),
+            ("2", "heading 2", "Turn into heading 2"),
+            ("3", "heading 3", "Turn into heading 3"),
+        ];
+        for (key, command, description) in blocks {
+            let key = format!("<leader> b {}", key);
+            keymap.bind(
+                Normal,
+                &key,
+                vec![Msg::Execute(command.to_owned())],
+                description,
+            );
+            keymap.bind(
+                Visual,
+                &key,
+                vec![Msg::Execute(command.to_owned()), Msg::Mode(Normal)],
+                description,
+            );
+        }
+        keymap.bind(
+            Normal,
+            "<leader> w",
+            vec![Msg::Execute("w".to_owned())],
+            "Write note",
+        );
+        keymap.bind(
+            Normal,
+            "z g",
+            vec![Msg::Execute("spellgood".to_owned())],
+            "Add word to the personal dictionary",
+        );
+        keymap.bind(
+            Normal,
+            "z a",
+            vec![Msg::Execute("fold".to_owned())],
+            "Fold or unfold section",
+        );
+        keymap.bind(
+            Normal,
+            "z R",
+            vec![Msg::Execute("unfold".to_owned())],
+            "Unfold all sections",
+        );
+        keymap.bind(
+            Normal,
+            "z w",
+            vec![Msg::Execute("spellwrong".to_owned())],
+            "Remove word from the personal dictionary",
+        );
+        keymap.bind(
+            Normal,
+            "Enter",
+            vec![Msg::Cmd(Command::Enter)],
+            "Open selection or transcluded note",
+        );
+        keymap.bind(
+            Normal,
+            "?",
+            vec![Msg::Execute("help keys".to_owned())],
+            "Show keybindings",
+        );
+        keymap.bind(
+            Normal,
+            "/",
+            vec![Msg::Mode(Command), Msg::Write("/".to_owned())],
+            "Search",
+        );
+        keymap.bind(Normal, "n", vec![Msg::SearchNext(false)], "Next match");
+        keymap.bind(Normal, "N", vec![Msg::SearchNext(true)], "Previous match");
+        keymap.bind(
+            Normal,
+            "*",
+            vec![Msg::SearchWord(false)],
+            "Search word under cursor",
+        );
+        keymap.bind(
+            Normal,
+            "#",
+            vec![Msg::SearchWord(true)],
+            "Search word under cursor backwards",
+        );
+        keymap.bind(
+            Command,
+            "Enter",
+            vec![Msg::ExecuteCommand, Msg::Mode(Normal)],
+            "Execute command",
+        );
+        keymap
+    }
+}
@@ -0,0 +1,49 @@
+use crate::document::Document;
+
+pub const DEFAULT_CHARACTERS: usize = 200_000;
+pub const DEFAULT_ELEMENTS: usize = 2_000;
+/// How many elements before and after the active one are rendered in a note over the
+/// limits.
+pub const VISIBLE: usize = 50;
+
+/// How large a note can get before only the elements around the cursor are rendered and
+/// lint and ligatures are left out, set with `:set maxchars=` and `:set maxelements=`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Limits {
+    pub characters: usize,
+    pub elements: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            characters: DEFAULT_CHARACTERS,
+            elements: DEFAULT_ELEMENTS,
+        }
+    }
+}
+
+impl Limits {
+    /// Which limit `document` is over, `None` if it is within them.
+    pub fn exceeded(&self, document: &Document) -> Option<String> {
+        let characters: usize = document
+            .paragraphs()
+            .iter()
+            .map(|(_, paragraph)| paragraph.text.len())
+            .sum();
+        let elements = document.elements.len();
+        if characters > self.characters {
+            Some(format!(
+                "The note has {} characters, more than maxchars={}",
+                characters, self.characters
+            ))
+        } else if elements > self.elements {
+            Some(format!(
+                "The note has {} blocks, more than maxelements={}",
+                elements, self.elements
+            ))
+        } else {
+            None
+        }
+    }
+}
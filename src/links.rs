@@ -0,0 +1,303 @@
+use std::ops::Range;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Target {
+    /// `[text](destination)`
+    Inline(String),
+    /// `[text][id]`, `[text][]` or `[text]`
+    Reference(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Link {
+    /// Byte range of the whole link in the source
+    pub range: Range<usize>,
+    pub text: String,
+    pub target: Target,
+    pub image: bool,
+}
+
+/// A link reference definition, `[id]: destination`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Definition {
+    pub id: String,
+    pub destination: String,
+}
+
+/// Ids of references are case insensitive and ignore repeated whitespace.
+pub fn normalize(id: &str) -> String {
+    id.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// The url of a link destination, without its title.
+pub fn href(destination: &str) -> String {
+    let url = destination.split_whitespace().next().unwrap_or_default();
+    url.trim_start_matches('<').trim_end_matches('>').to_owned()
+}
+
+/// Whether `href` may be followed, web and mail links, anchors and relative links.
+///
+/// Notes can come from elsewhere, so links running scripts like `javascript:` are shown as
+/// text instead.
+pub fn is_safe(href: &str) -> bool {
+    // Browsers ignore whitespace in the scheme
+    let href: String = href.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+    match href.find(|c| matches!(c, ':' | '/' | '?' | '#')) {
+        Some(end) if href[end..].starts_with(':') => {
+            matches!(
+                href[..end].to_lowercase().as_str(),
+                "http" | "https" | "mailto"
+            )
+        }
+        _ => true,
+    }
+}
+
+/// Index of the bracket closing the one at `open`.
+fn closing(source: &str, open: usize, close: u8) -> Option<usize> {
+    let bytes = source.as_bytes();
+    let opening = bytes[open];
+    let mut depth = 0;
+    let mut i = open;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'\n' if bytes.get(i + 1) == Some(&b'\n') => return None,
+            c if c == opening => depth += 1,
+            c if c == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+fn link_at(source: &str, start: usize) -> Option<Link> {
+    let bytes = source.as_bytes();
+    let image = start > 0 && bytes[start - 1] == b'!';
+    let text_end = closing(source, start, b']')?;
+    let text = &source[start + 1..text_end];
+    if text.starts_with('^') {
+        return None;
+    }
+    let begin = if image { start - 1 } else { start };
+    let (target, end) = match bytes.get(text_end + 1) {
+        Some(b'(') => {
+            let end = closing(source, text_end + 1, b')')?;
+            (
+                Target::Inline(source[text_end + 2..end].trim().to_owned()),
+                end + 1,
+            )
+        }
+        Some(b'[') => {
+            let end = closing(source, text_end + 1, b']')?;
+            let id = &source[text_end + 2..end];
+            let id = if id.is_empty() { text } else { id };
+            (Target::Reference(id.to_owned()), end + 1)
+        }
+        Some(b':') => return None,
+        _ => (Target::Reference(text.to_owned()), text_end + 1),
+    };
+    Some(Link {
+        range: begin..end,
+        text: text.to_owned(),
+        target,
+        image,
+    })
+}
+
+/// All links in `source` outside of code spans.
+pub fn links(source: &str) -> Vec<Link> {
+    let bytes = source.as_bytes();
+    let mut links = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'`' => {
+                let ticks = bytes[i..].iter().take_while(|&&c| c == b'`').count();
+                let fence = "`".repeat(ticks);
+                i = source[i + ticks..]
+                    .find(&fence)
+                    .map_or(i + ticks, |end| i + ticks + end + ticks);
+            }
+            b'[' => match link_at(source, i) {
+                Some(link) => {
+                    i = link.range.end;
+                    links.push(link);
+                }
+                None => i += 1,
+            },
+            _ => i += 1,
+        }
+    }
+    links
+}
+
+pub fn definition(line: &str) -> Option<Definition> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    let line = line.trim_start_matches(' ');
+    if indent > 3 || !line.starts_with('[') {
+        return None;
+    }
+    let end = line.find("]:")?;
+    let id = &line[1..end];
+    let destination = line[end + 2..].trim();
+    if id.is_empty() || id.starts_with('^') || destination.is_empty() {
+        return None;
+    }
+    Some(Definition {
+        id: id.to_owned(),
+        destination: destination.to_owned(),
+    })
+}
+
+fn is_fence(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("```") || line.starts_with("~~~")
+}
+
+/// Applies `f` to every part of `md` outside of fenced code blocks.
+fn outside_code(md: &str, mut f: impl FnMut(&str) -> String) -> String {
+    let mut out = String::new();
+    let mut segment = String::new();
+    let mut in_code = false;
+    for line in md.split_inclusive('\n') {
+        if is_fence(line) {
+            if !in_code {
+                out.push_str(&f(&segment));
+                segment.clear();
+            }
+            in_code = !in_code;
+            out.push_str(line);
+        } else if in_code {
+            out.push_str(line);
+        } else {
+            segment.push_str(line);
+        }
+    }
+    out.push_str(&f(&segment));
+    out
+}
+
+pub fn definitions(md: &str) -> Vec<Definition> {
+    let mut definitions = vec![];
+    outside_code(md, |segment| {
+        definitions.extend(segment.lines().filter_map(definition));
+        String::new()
+    });
+    definitions
+}
+
+fn write_link(link: &Link, target: &str) -> String {
+    format!(
+        "{}[{}]{}",
+        if link.image { "!" } else { "" },
+        link.text,
+        target
+    )
+}
+
+/// Replaces all reference links with inline links and removes the definitions.
+pub fn to_inline(md: &str) -> String {
+    let definitions = definitions(md);
+    let md = outside_code(md, |segment| {
+        let mut segment: String = segment
+            .split_inclusive('\n')
+            .filter(|line| definition(line).is_none())
+            .collect();
+        for link in links(&segment).into_iter().rev() {
+            if let Target::Reference(id) = &link.target {
+                if let Some(definition) = definitions
+                    .iter()
+                    .find(|definition| normalize(&definition.id) == normalize(id))
+                {
+                    let inline = write_link(&link, &format!("({})", definition.destination));
+                    segment.replace_range(link.range.clone(), &inline);
+                }
+            }
+        }
+        segment
+    });
+    md.trim_end().to_owned()
+}
+
+/// Replaces all inline links with reference links, defined at the end of the note.
+pub fn to_reference(md: &str) -> String {
+    let mut definitions = definitions(md);
+    let mut added = vec![];
+    let md = outside_code(md, |segment| {
+        let mut replacements = vec![];
+        for link in links(segment) {
+            if let Target::Inline(destination) = &link.target {
+                let id = match definitions
+                    .iter()
+                    .find(|definition| &definition.destination == destination)
+                {
+                    Some(definition) => definition.id.clone(),
+                    None => {
+                        let id = (1..)
+                            .map(|i: usize| i.to_string())
+                            .find(|id| definitions.iter().all(|d| normalize(&d.id) != *id))
+                            .unwrap();
+                        let definition = Definition {
+                            id: id.clone(),
+                            destination: destination.clone(),
+                        };
+                        definitions.push(definition.clone());
+                        added.push(definition);
+                        id
+                    }
+                };
+                replacements.push((link.range.clone(), write_link(&link, &format!("[{}]", id))));
+            }
+        }
+        let mut segment = segment.to_owned();
+        for (range, reference) in replacements.into_iter().rev() {
+            segment.replace_range(range, &reference);
+        }
+        segment
+    });
+    let mut md = md.trim_end().to_owned();
+    if !added.is_empty() {
+        md.push('\n');
+        for definition in &added {
+            md.push_str(&format!(
+                "\n[{}]: {}",
+                definition.id, definition.destination
+            ));
+        }
+    }
+    md
+}
+
+/// Changes the destination of `id`, adding the definition if it does not exist.
+pub fn set_definition(md: &str, id: &str, destination: &str) -> String {
+    let mut found = false;
+    let md = outside_code(md, |segment| {
+        segment
+            .split_inclusive('\n')
+            .map(|line| match definition(line) {
+                Some(definition) if normalize(&definition.id) == normalize(id) => {
+                    found = true;
+                    let newline = if line.ends_with('\n') { "\n" } else { "" };
+                    format!("[{}]: {}{}", definition.id, destination, newline)
+                }
+                _ => line.to_owned(),
+            })
+            .collect()
+    });
+    if found {
+        md
+    } else {
+        format!("{}\n\n[{}]: {}", md.trim_end(), id, destination)
+    }
+}
@@ -0,0 +1,255 @@
+use std::ops::Range;
+
+use crate::document::is_word;
+
+/// A check of the prose lint, enabled with `:set lint=`.
+#[derive(Clone, Copy, Debug, PartialEq, Hash)]
+pub enum Rule {
+    /// A form of "to be" followed by a past participle
+    Passive,
+    /// A sentence with more words than [`Lint::sentence_length`]
+    LongSentence,
+    /// The same word twice in a row
+    Repeated,
+    /// A word that weakens a statement without adding to it
+    Weasel,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 4] = [
+        Rule::Passive,
+        Rule::LongSentence,
+        Rule::Repeated,
+        Rule::Weasel,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|rule| rule.name() == name)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rule::Passive => "passive",
+            Rule::LongSentence => "long",
+            Rule::Repeated => "repeated",
+            Rule::Weasel => "weasel",
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            Rule::Passive => "Passive voice",
+            Rule::LongSentence => "Long sentence",
+            Rule::Repeated => "Repeated word",
+            Rule::Weasel => "Weasel word",
+        }
+    }
+}
+
+const BE: &[&str] = &["am", "is", "are", "was", "were", "be", "been", "being"];
+
+const IRREGULAR_PARTICIPLES: &[&str] = &[
+    "awoken",
+    "begun",
+    "bitten",
+    "blown",
+    "broken",
+    "brought",
+    "built",
+    "bought",
+    "caught",
+    "chosen",
+    "done",
+    "drawn",
+    "driven",
+    "eaten",
+    "fallen",
+    "felt",
+    "forgiven",
+    "forgotten",
+    "found",
+    "frozen",
+    "given",
+    "gone",
+    "gotten",
+    "grown",
+    "heard",
+    "held",
+    "hidden",
+    "kept",
+    "known",
+    "laid",
+    "led",
+    "left",
+    "lost",
+    "made",
+    "meant",
+    "met",
+    "paid",
+    "put",
+    "read",
+    "ridden",
+    "risen",
+    "run",
+    "said",
+    "seen",
+    "sent",
+    "set",
+    "shaken",
+    "shown",
+    "shut",
+    "sold",
+    "spent",
+    "spoken",
+    "stolen",
+    "struck",
+    "sung",
+    "taken",
+    "taught",
+    "thought",
+    "thrown",
+    "told",
+    "understood",
+    "woken",
+    "won",
+    "worn",
+    "written",
+];
+
+const WEASEL_WORDS: &[&str] = &[
+    "actually",
+    "basically",
+    "clearly",
+    "extremely",
+    "fairly",
+    "largely",
+    "many",
+    "mostly",
+    "obviously",
+    "quite",
+    "really",
+    "relatively",
+    "remarkably",
+    "several",
+    "significantly",
+    "simply",
+    "somewhat",
+    "surprisingly",
+    "various",
+    "vast",
+    "very",
+];
+
+/// A problem found by a [`Rule`] in the graphemes `range`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Finding {
+    pub rule: Rule,
+    pub range: Range<usize>,
+}
+
+/// The prose lint, underlining findings while the `lint` option is set and listing them
+/// with `:lint`.
+#[derive(Clone, Debug, PartialEq, Hash)]
+pub struct Lint {
+    pub rules: Vec<Rule>,
+    /// Sentences with more words are long, set with `:set sentencelength=`
+    pub sentence_length: usize,
+}
+
+impl Default for Lint {
+    fn default() -> Self {
+        Self {
+            rules: Rule::ALL.to_vec(),
+            sentence_length: 30,
+        }
+    }
+}
+
+/// The words of `text` with their range, lowercase and without markup, grouped by sentence.
+///
+/// Code spans are skipped.
+pub fn sentences(text: &[String]) -> Vec<Vec<(Range<usize>, String)>> {
+    let mut sentences = vec![vec![]];
+    let mut i = 0;
+    while i < text.len() {
+        let c = text[i].as_str();
+        if c == "`" {
+            i += 1 + text[i + 1..]
+                .iter()
+                .position(|c| c == "`")
+                .map_or(0, |end| end + 1);
+        } else if is_word(c) {
+            let start = i;
+            while i < text.len() && (is_word(&text[i]) || text[i] == "'") {
+                i += 1;
+            }
+            let word = text[start..i].concat().to_lowercase();
+            let word = word.trim_matches(|c| c == '_' || c == '\'');
+            if !word.is_empty() {
+                sentences
+                    .last_mut()
+                    .unwrap()
+                    .push((start..i, word.to_owned()));
+            }
+        } else {
+            let ends_sentence = matches!(c, "." | "!" | "?")
+                && text.get(i + 1).map_or(true, |next| next.trim().is_empty());
+            if ends_sentence || (c == "\n" && text.get(i + 1).map_or(false, |next| next == "\n")) {
+                sentences.push(vec![]);
+            }
+            i += 1;
+        }
+    }
+    sentences.retain(|sentence| !sentence.is_empty());
+    sentences
+}
+
+fn is_participle(word: &str) -> bool {
+    (word.len() > 3 && word.ends_with("ed")) || IRREGULAR_PARTICIPLES.contains(&word)
+}
+
+impl Lint {
+    pub fn check(&self, text: &[String]) -> Vec<Finding> {
+        let mut findings = vec![];
+        let enabled = |rule| self.rules.contains(&rule);
+        for sentence in sentences(text) {
+            if enabled(Rule::LongSentence) && sentence.len() > self.sentence_length {
+                findings.push(Finding {
+                    rule: Rule::LongSentence,
+                    range: sentence[0].0.start..sentence[sentence.len() - 1].0.end,
+                });
+            }
+            for (i, (range, word)) in sentence.iter().enumerate() {
+                let previous = i.checked_sub(1).map(|i| &sentence[i]);
+                if enabled(Rule::Repeated) && previous.map_or(false, |(_, p)| p == word) {
+                    findings.push(Finding {
+                        rule: Rule::Repeated,
+                        range: range.clone(),
+                    });
+                }
+                if enabled(Rule::Weasel) && WEASEL_WORDS.contains(&word.as_str()) {
+                    findings.push(Finding {
+                        rule: Rule::Weasel,
+                        range: range.clone(),
+                    });
+                }
+                if enabled(Rule::Passive) && BE.contains(&word.as_str()) {
+                    // An adverb may come between, as in "was quickly written"
+                    let participle = match sentence.get(i + 1) {
+                        Some((_, adverb)) if adverb.ends_with("ly") => sentence.get(i + 2),
+                        next => next,
+                    }
+                    .filter(|(_, next)| is_participle(next));
+                    if let Some((end, _)) = participle {
+                        findings.push(Finding {
+                            rule: Rule::Passive,
+                            range: range.start..end.end,
+                        });
+                    }
+                }
+            }
+        }
+        findings.sort_by_key(|finding| finding.range.start);
+        findings
+    }
+}
@@ -0,0 +1,59 @@
+use std::ops::Range;
+
+use crate::{
+    document::{Document, Element},
+    toc,
+};
+
+/// Comments around blocks that can not be edited, like a generated table of contents.
+pub const START: &str = "<!-- lock -->";
+pub const END: &str = "<!-- /lock -->";
+
+/// The elements of `document` between lock markers, one without an end locks the rest.
+///
+/// The markers themselves can still be edited, removing one unlocks the region. A table of
+/// contents between `<!-- toc -->` markers is locked the same way.
+pub fn regions(document: &Document) -> Vec<Range<usize>> {
+    let mut regions = vec![];
+    let mut start = None;
+    for (i, element) in document.elements.iter().enumerate() {
+        for line in element.to_md().lines().map(str::trim) {
+            if line == START || line == toc::START {
+                start = start.or(Some(i + 1));
+            } else if line == END || line == toc::END {
+                if let Some(start) = start.take() {
+                    regions.push(start..i);
+                }
+            }
+        }
+    }
+    regions.extend(start.map(|start| start..document.elements.len()));
+    regions
+}
+
+/// Whether the element `element` of `document` is in a locked region.
+pub fn is_locked(document: &Document, element: usize) -> bool {
+    touches(document, element..element + 1)
+}
+
+/// Whether any of the `elements` of `document` is in a locked region.
+pub fn touches(document: &Document, elements: Range<usize>) -> bool {
+    regions(document)
+        .iter()
+        .any(|region| region.start < elements.end && elements.start < region.end)
+}
+
+/// The markdown of the locked regions of `document`, which a change of the whole note has
+/// to leave as it is.
+pub fn contents(document: &Document) -> Vec<String> {
+    regions(document)
+        .into_iter()
+        .map(|region| {
+            document.elements[region]
+                .iter()
+                .map(Element::to_md)
+                .collect::<Vec<_>>()
+                .join("\n\n")
+        })
+        .collect()
+}
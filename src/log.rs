@@ -0,0 +1,108 @@
+use std::{cell::RefCell, collections::VecDeque};
+
+/// How many entries the log keeps before dropping the oldest.
+const CAPACITY: usize = 500;
+
+/// Severity of a log entry, `:log <level>` shows this level and above.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum Level {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "debug" => Self::Debug,
+            "info" => Self::Info,
+            "warn" => Self::Warn,
+            "error" => Self::Error,
+            _ => return None,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Debug => "debug",
+            Self::Info => "info",
+            Self::Warn => "warn",
+            Self::Error => "error",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub level: Level,
+    /// The module that logged the entry
+    pub target: &'static str,
+    pub message: String,
+}
+
+thread_local! {
+    static ENTRIES: RefCell<VecDeque<Entry>> = RefCell::new(VecDeque::new());
+}
+
+/// Records `message` for `:log` and writes it to the browser console.
+///
+/// Used through the [`debug!`], [`info!`], [`warn!`] and [`error!`] macros.
+pub fn record(level: Level, target: &'static str, message: String) {
+    let line = format!("{}: {}", target, message);
+    match level {
+        Level::Debug => gloo_console::debug!(line),
+        Level::Info => gloo_console::info!(line),
+        Level::Warn => gloo_console::warn!(line),
+        Level::Error => gloo_console::error!(line),
+    }
+    ENTRIES.with(|entries| {
+        let mut entries = entries.borrow_mut();
+        if entries.len() == CAPACITY {
+            entries.pop_front();
+        }
+        entries.push_back(Entry {
+            level,
+            target,
+            message,
+        });
+    });
+}
+
+/// The recorded entries of `level` and above, oldest first.
+pub fn entries(level: Level) -> Vec<Entry> {
+    ENTRIES.with(|entries| {
+        entries
+            .borrow()
+            .iter()
+            .filter(|entry| entry.level >= level)
+            .cloned()
+            .collect()
+    })
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        $crate::log::record($crate::log::Level::Debug, module_path!(), format!($($arg)*))
+    };
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => {
+        $crate::log::record($crate::log::Level::Info, module_path!(), format!($($arg)*))
+    };
+}
+
+macro_rules! warning {
+    ($($arg:tt)*) => {
+        $crate::log::record($crate::log::Level::Warn, module_path!(), format!($($arg)*))
+    };
+}
+
+macro_rules! error {
+    ($($arg:tt)*) => {
+        $crate::log::record($crate::log::Level::Error, module_path!(), format!($($arg)*))
+    };
+}
+
+pub(crate) use {debug, error, info, warning};
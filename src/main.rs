@@ -1,32 +1,260 @@
 #![feature(derive_default_enum, bool_to_option, associated_type_defaults)]
 use std::{
     cell::Cell,
-    collections::HashSet,
-    iter::FromIterator,
-    ops::{Deref, DerefMut},
+    collections::{HashMap, HashSet},
+    iter::{self, FromIterator},
+    mem,
+    ops::{Deref, DerefMut, Range},
+    rc::Rc,
 };
 
 use document::{Command, Commandee, Markdown};
-use gloo_console::console_dbg;
+use gloo_timers::callback::{Interval, Timeout};
 use pulldown_cmark::{Options, Parser, Tag};
 use unicode_segmentation::UnicodeSegmentation;
 use uuid::Uuid;
-use web_sys::{window, HtmlInputElement};
+use wasm_bindgen::JsCast;
+use web_sys::{
+    window, ClipboardEvent, DragEvent, File, HtmlElement, ScrollIntoViewOptions,
+    ScrollLogicalPosition, Url,
+};
 use yew::prelude::*;
 
-use crate::document::{Document, Motion, Render};
+use crate::{
+    attachment::Attachment,
+    autocorrect::{CORRECTIONS, DEFAULT_CORRECTIONS},
+    backup::Schedule,
+    block::Block,
+    board::Board,
+    buffer::{self, Buffer, Buffers},
+    citation::Reference,
+    command_line::{CommandLine, Edit},
+    completion::{Citekeys, Completion, HeadingAnchors, NoteNames, Source},
+    diff::Diff,
+    document::{is_word, Characters, Document, Element, Paragraph, Render},
+    flavor::Flavor,
+    format::{self, Format, Reflow},
+    help::Help,
+    history::Commit,
+    indent::Indent,
+    info::Info,
+    jump::Jump,
+    keymap::{split_count, Keymap, Layout},
+    limits::{self, Limits},
+    lint::{Lint, Rule},
+    merge::Merge,
+    notification::{Notifications, Severity},
+    overlay::Overlay,
+    picker::Picker,
+    publish::Service,
+    quickfix::{GrepSub, Quickfix},
+    remote::{Remote, Transfer},
+    render_cache::{self, RenderCache},
+    replacements::{Replacements, ABBREVIATIONS},
+    search::{Case, Search, Substitute},
+    split::{Panes, Split},
+    start::{StartEntry, StartScreen},
+    status::Status,
+    store::{LocalStorage, Storage},
+    tasks::Tasks,
+    timer::Timer,
+    user_command::UserCommands,
+    webhook::Target,
+    which_key::Hints,
+};
 
+mod a11y;
+mod attachment;
+mod autocorrect;
+mod backup;
+mod block;
+mod board;
+mod breadcrumb;
+mod buffer;
+mod callout;
+mod citation;
+mod clip;
+mod command_line;
+mod comment;
+mod completion;
+mod continuation;
+mod convert;
+mod critic;
+mod date;
+mod diff;
+mod doctor;
 mod document;
+mod entity;
+mod expand;
+mod export;
+mod feed;
+mod flavor;
+mod focus;
+mod footnote;
+mod format;
+mod frontmatter;
+mod help;
+mod history;
+mod import;
+mod indent;
+mod inflate;
+mod info;
+mod journal;
+mod jump;
+mod keymap;
+mod limits;
+mod links;
+mod lint;
+mod lock;
+mod log;
+mod markdown;
+mod merge;
+mod minimap;
+mod notification;
+mod offline;
+mod opml;
+mod overlay;
+mod pairs;
+mod paste;
+mod picker;
+mod promise;
+mod publish;
+mod query;
+mod quickfix;
+mod reminder;
+mod remote;
+mod render_cache;
+mod replacements;
+mod search;
+mod secret;
+mod sentence;
+mod spell;
+mod split;
+mod start;
+mod status;
+mod store;
+mod swap;
+mod tasks;
+mod template;
+mod timer;
+mod title;
+mod toc;
+mod transclusion;
+mod tutor;
+mod user_command;
+mod vault;
+mod webhook;
+mod which_key;
+mod wiki;
+mod zip;
 
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
-enum Mode {
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum Mode {
     Insert,
     Normal,
     Command,
+    Visual,
 }
 
 pub struct ApplicationState {
     cursor_style: CursorStyle,
+    /// The id of the cursor's span, the other pane of a split has its own one
+    cursor_id: &'static str,
+    /// Urls of the link reference definitions by normalized id
+    links: HashMap<String, String>,
+    /// Footnote definitions by label
+    footnotes: Vec<(String, String)>,
+    /// Show footnotes in the margin next to their reference
+    sidenotes: bool,
+    /// Comments away from the cursor are dimmed instead of hidden
+    show_comments: bool,
+    /// Number headings like an outline
+    heading_numbers: bool,
+    /// Runs of text away from the cursor are rendered together so ligatures can form
+    ligatures: bool,
+    tab_width: usize,
+    /// Matches of the last search are highlighted
+    search: Option<Search>,
+    flavor: Flavor,
+    /// Findings are underlined while the `lint` option is set
+    lint: Option<Lint>,
+    theme: Theme,
+    /// Attachments linked in the open notes by name
+    attachments: HashMap<String, Attachment>,
+    /// The content of the notes transcluded with `![[note]]` by name, `None` if there is no
+    /// such note
+    transclusions: HashMap<String, Option<String>>,
+    /// The results of the ```` ```query ```` blocks in the open note by query
+    queries: HashMap<String, Result<Vec<query::Row>, String>>,
+    /// The works in the bibliography note, when the note has citations
+    references: Vec<Reference>,
+    /// The dictionary of the note's spell language while `:set spell` is on
+    spell: Option<spell::Dictionary>,
+    /// The `YYYY-MM-DD` date, due dates of open tasks before it are highlighted
+    today: String,
+    /// Elements rendered in the last frame, shared by both panes of a split
+    cache: Rc<RenderCache>,
+    /// The note is over the [`limits::Limits`], only the elements around the cursor are
+    /// rendered
+    degraded: bool,
+}
+
+impl ApplicationState {
+    /// The classes of the cursor, in the color of the theme.
+    fn cursor_classes(&self) -> Classes {
+        TextStyle::Cursor(self.cursor_style).forground_classes(Position::Single, self.theme)
+    }
+
+    /// A hash of everything here that changes how an element is rendered.
+    fn fingerprint(&self) -> u64 {
+        let mut links: Vec<_> = self.links.iter().collect();
+        links.sort();
+        let mut attachments: Vec<_> = self.attachments.iter().collect();
+        attachments.sort_by_key(|(name, _)| *name);
+        let mut transclusions: Vec<_> = self.transclusions.iter().collect();
+        transclusions.sort();
+        let mut queries: Vec<_> = self.queries.iter().collect();
+        queries.sort_by_key(|(query, _)| *query);
+        render_cache::key((
+            (
+                attachments,
+                transclusions,
+                queries,
+                &self.references,
+                self.spell.as_ref().map(spell::Dictionary::key),
+            ),
+            (
+                &self.today,
+                self.cursor_style,
+                self.cursor_id,
+                self.show_comments,
+            ),
+            links,
+            &self.footnotes,
+            self.sidenotes,
+            self.heading_numbers,
+            self.ligatures,
+            self.tab_width,
+            self.search
+                .as_ref()
+                .map(|search| (search.regex.as_str(), search.whole_word)),
+            self.flavor,
+            &self.lint,
+            self.theme,
+        ))
+    }
+}
+
+/// What the open notes show of the other notes, read once and again after the store
+/// changed.
+#[derive(Default)]
+struct Views {
+    /// The [`Storage::version`] and date it was read at
+    version: Option<(u64, String)>,
+    transclusions: HashMap<String, Option<String>>,
+    queries: HashMap<String, Result<Vec<query::Row>, String>>,
+    /// The notes wiki links are resolved against
+    index: Option<wiki::Index>,
 }
 
 #[allow(dead_code)]
@@ -39,18 +267,143 @@ impl Mode {
     }
 }
 
-#[derive(Debug)]
-enum Msg {
+#[derive(Debug, Clone)]
+pub enum Msg {
     CursorMove(i32, i32),
     CursorPos(Option<usize>, Option<usize>),
     Write(String),
     Mode(Mode),
     ExecuteCommand,
+    /// Executes the given command line
+    Execute(String),
     Cmd(Command),
+    CloseOverlay,
+    /// A file picked for import with its name and content
+    Import(String, String),
+    /// Moves to the left pane with `true` or the right one of a `:vsplit`
+    FocusPane(bool),
+    /// Widens the open pane by that many percent of the window, or narrows it
+    ResizePane(i32),
+    /// The divider between the panes was grabbed with `true` and let go with `false`
+    DragDivider(bool),
+    /// The mouse moved to that x coordinate
+    MouseMove(i32),
+    /// Reflows text to `:set textwidth=`, 80 columns if it is not set
+    Reflow(Reflow),
+    /// The notes copied to IndexedDB, read at startup
+    Mirrored(Vec<(String, String)>),
+    /// Files pasted or dropped into the note
+    Attach(Vec<File>),
+    /// The name of an attachment that was stored, or why it was not
+    Attached(Result<String, String>),
+    /// `vault.zip` was downloaded with that many notes and attachments
+    VaultExported(usize, usize),
+    /// An attachment of `:import vault` was kept under the name, `None` if it could not be
+    /// stored
+    VaultAttachment(String, Option<String>),
+    /// The files of the vault picked by `:import vault` by their paths, or why it could not
+    /// be read
+    ImportVault(Result<Vec<(String, Vec<u8>)>, String>),
+    /// A second of the `:timer` passed
+    TimerTick,
+    /// It is time for a backup of `:set backup=` with a number of minutes
+    Backup,
+    /// A backup asked for with `:backup` when `true` or a scheduled one was written to that
+    /// file, or why not
+    BackedUp(bool, Result<String, String>),
+    /// The name of the backup folder picked with `:backup folder`, or why none was
+    BackupFolder(Result<String, String>),
+    /// What `:commit` stored, or why it was not
+    Committed(Result<String, String>),
+    /// The commits read for `:commits`
+    Commits(Vec<Commit>),
+    /// The commits read for `:checkout` of a commit and note
+    Checkout(String, Option<String>, Vec<Commit>),
+    /// The token of `:token` encrypted to be stored, `None` if that failed
+    TokenEncrypted(Option<String>),
+    /// The decrypted token for a `:push` when `true` or a `:pull`
+    SyncToken(bool, Option<String>),
+    /// The notes in the repository by their id for a `:push` when `true` or a `:pull`, with
+    /// the token
+    RemoteListed(bool, String, Result<HashMap<String, String>, String>),
+    /// A note of a `:push` or `:pull` was sent or fetched, or why not
+    Transferred(Result<Transfer, String>),
+    /// The HTML of the page at the url for `:clip`, or why it could not be fetched
+    Clipped(String, Result<String, String>),
+    /// The feed at the url for `:feed sync`, with whether its entries go into a digest, or
+    /// why it could not be fetched
+    FeedFetched(String, bool, Result<String, String>),
+    /// Why a saved note could not be sent to its webhook
+    WebhookFailed(String),
+    /// The url `:publish` uploaded the note to, or why it failed
+    Published(String, Result<String, String>),
+    /// The page was scrolled, which moves the viewport in the minimap
+    Scrolled,
+    /// A block of the minimap was clicked, moving the cursor to its element
+    JumpElement(usize),
+    /// Grows the selection to the enclosing word, span, sentence, line or paragraph, or
+    /// shrinks it back
+    ExpandSelection(bool),
+    /// An attachment was fetched for showing, as an object url or `None` if it is missing
+    AttachmentLoaded(String, Option<String>),
+    Paste(String),
+    /// Moves through the completions with `Up`/`Down`, `Enter` inserts the selected one
+    Completion(Command),
+    /// Repeats the last search, in the opposite direction if `true`
+    SearchNext(bool),
+    /// Searches for the word under the cursor, backwards if `true`
+    SearchWord(bool),
+    /// Breaks the line, continuing lists, quotes and tables
+    Newline,
+    /// Inserts the selected completion or indents
+    Tab,
+    /// The page is left normally, so no swap copies of this tab are needed for recovery
+    PageHide,
+    /// The page is shown again from the back-forward cache after [`Msg::PageHide`]
+    PageShow,
+    /// This tab is still running, so its swap copies are no leftovers
+    Heartbeat,
+    /// The tab lost focus with `false` or got it back with `true`
+    Focus(bool),
+    /// Keys typed so far of a sequence like `z g`
+    Pending(String),
+    /// The next character typed does not expand an abbreviation
+    Literal,
+    /// Input detected as pasted key by key has stopped
+    Pasted,
+    /// Starts a `gs` jump to a character shown with labels
+    Jump,
+    /// A key typed during a jump, the target character or a label
+    JumpKey(String),
+    /// Returns to Insert mode after the next Normal mode command
+    NormalOnce,
+    /// Hides the toast with the id
+    Dismiss(usize),
+    /// Moves or deletes on the command line
+    CommandLine(Edit),
+    /// Selects the table column under the cursor in Visual mode
+    SelectColumn,
+    /// Yanks the selected table column, deleting it if `true`
+    YankColumn(bool),
+    /// Inserts the yanked column after the one under the cursor, the yanked rows below it or
+    /// the yanked text after it
+    PasteColumn,
+    /// Selects the table row under the cursor in Visual mode, or the line outside of tables
+    SelectRows,
+    /// Yanks the selected table rows, deleting them if `true`
+    YankRows(bool),
+    /// Yanks the text selected in Visual mode
+    YankSelection,
+    /// The dictionary of a spell language kept in IndexedDB, `None` if it was not downloaded
+    DictionaryCached(String, Option<String>),
+    /// The dictionary of a spell language downloaded from `:set spellurl=`, or why not
+    DictionaryFetched(String, Result<String, String>),
 }
 
 struct Keypress {
     key: String,
+    /// The physical key, like `KeyA` wherever the layout puts `a`
+    code: String,
     alt: bool,
     ctrl: bool,
     shift: bool,
@@ -60,6 +413,7 @@ impl From<&KeyboardEvent> for Keypress {
     fn from(ke: &KeyboardEvent) -> Self {
         Self {
             key: ke.key(),
+            code: ke.code(),
             alt: ke.alt_key(),
             ctrl: ke.ctrl_key(),
             shift: ke.shift_key(),
@@ -70,6 +424,7 @@ impl From<&KeyboardEvent> for Keypress {
 #[allow(dead_code)]
 struct KeyRef<'a> {
     key: &'a str,
+    code: &'a str,
     alt: bool,
     ctrl: bool,
     shift: bool,
@@ -85,12 +440,34 @@ impl KeyRef<'_> {
     fn insertable(&self) -> bool {
         self.key.graphemes(true).count() == 1 && !self.alt && !self.ctrl
     }
+
+    /// The key as written in the [`Keymap`], e.g. `C-w`.
+    fn notation(&self) -> String {
+        format!(
+            "{}{}{}",
+            if self.ctrl { "C-" } else { "" },
+            if self.alt { "A-" } else { "" },
+            if self.key == " " { "Space" } else { self.key }
+        )
+    }
+
+    /// The physical key as written in the [`Keymap`], e.g. `C-KeyW`.
+    fn code_notation(&self) -> String {
+        format!(
+            "{}{}{}{}",
+            if self.ctrl { "C-" } else { "" },
+            if self.alt { "A-" } else { "" },
+            if self.shift { "S-" } else { "" },
+            self.code
+        )
+    }
 }
 
 impl Keypress {
     fn as_ref(&self) -> KeyRef {
         KeyRef {
             key: &self.key,
+            code: &self.code,
             alt: self.alt,
             ctrl: self.ctrl,
             shift: self.shift,
@@ -106,6 +483,8 @@ enum TextStyle {
     Cursor(CursorStyle),
     Table,
     TableCell,
+    Link,
+    Heading,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -126,9 +505,8 @@ impl Position {
 }
 
 impl TextStyle {
-    fn forground_classes(self, position: Position) -> Classes {
-        console_dbg!((self, position));
-        match self {
+    fn forground_classes(self, position: Position, theme: Theme) -> Classes {
+        let mut classes = match self {
             TextStyle::Italic => classes!["italic"],
             TextStyle::Bold => classes!["font-bold"],
             TextStyle::Cursor(cursor_style) => cursor_style.classes(), //&["text-gray-900", "rounded", "bg-red-300"],
@@ -144,8 +522,11 @@ impl TextStyle {
                     .is_last()
                     .then_some(classes!["border-r-2", "-mr-px"])
             ],
+            TextStyle::Link => classes!["underline"],
             _ => classes![],
-        }
+        };
+        classes.push(theme.color(self));
+        classes
     }
     fn background_classes(self, position: Position) -> Classes {
         match self {
@@ -188,9 +569,6 @@ impl TextLine {
     fn len(&self) -> usize {
         self.characters.len()
     }
-    fn clear(&mut self) {
-        self.characters.clear()
-    }
     fn char_len(&self) -> usize {
         self.characters.iter().map(|(s, ..)| s.len()).sum()
     }
@@ -296,61 +674,223 @@ struct Model {
     // highlighting: Vec<(TextStyle, Range<usize>)>,
     // lines: Vec<(String, usize, NodeRef, Vec<(TextStyle, Range<usize>)>)>,
     lines: Vec<TextLine>,
-    command: TextLine,
+    command: CommandLine,
     mode: Mode,
     font: String,
+    /// Whether the font may join characters into ligatures, set for each font
+    ligatures: bool,
+    theme: Theme,
     document: Document,
+    /// Name of the open note, `None` until it is first written
+    note: Option<String>,
+    /// Content of the note when it was last loaded or saved, used to detect conflicts
+    base: Option<String>,
+    store: Box<dyn Storage>,
+    buffers: Buffers,
+    /// The other buffer shown with `:vsplit`
+    split: Option<Split>,
+    panes: Panes,
+    overlay: Option<Overlay>,
+    link: yew::html::Scope<Self>,
+    /// Identifies this tab, whose swap copies other tabs leave alone
+    tab: String,
+    /// Sends [`Msg::Heartbeat`] every [`swap::HEARTBEAT`]
+    heartbeat: Interval,
+    keymap: Rc<Keymap>,
+    /// The current lesson while following the `:tutor`
+    lesson: Option<usize>,
+    /// Format of `:date` and the `@date` abbreviation
+    date_format: String,
+    sidenotes: bool,
+    show_comments: bool,
+    heading_numbers: bool,
+    format: Format,
+    /// Whether the note is formatted before it is written
+    format_on_save: bool,
+    completion: Option<Completion>,
+    search: Option<Search>,
+    /// Whether matches of the last search are highlighted, until `:noh`
+    highlight: bool,
+    /// Locations found by `:grep`, `:lint`, `:doctor` or `:backlinks`
+    quickfix: Quickfix,
+    status: Option<Status>,
+    case: Case,
+    indent: Indent,
+    /// Edits since startup, counted to write swap copies
+    edits: usize,
+    /// Keys typed so far of a key sequence
+    pending: String,
+    /// Clears `pending` when the next key of the sequence takes too long
+    pending_timeout: Option<Timeout>,
+    /// Milliseconds a key sequence waits for its next key, set with `:set timeoutlen=`
+    timeout_len: u32,
+    lint: Lint,
+    /// Whether lint findings are underlined
+    show_lint: bool,
+    user_commands: UserCommands,
+    abbreviations: Replacements,
+    /// Ctrl+V was typed, so the next character does not expand an abbreviation
+    literal: bool,
+    corrections: Replacements,
+    /// Whether words are corrected and capitalized while typing
+    autocorrect: bool,
+    /// Whether `:set paste` inserts what is typed and pasted verbatim
+    paste: bool,
+    /// Whether `:set suggest` records edits as CriticMarkup changes to be reviewed
+    suggesting: bool,
+    /// When the last keys were typed in insert mode
+    burst: paste::Burst,
+    /// Set while keys arrive too fast to be typed, like text a terminal pastes key by key
+    pasting: Option<Timeout>,
+    jump: Option<Jump>,
+    notifications: Notifications,
+    /// Insert mode was left with `Ctrl+O` for a single command
+    normal_once: bool,
+    /// Whether smooth scrolling and animations are turned off
+    reduced_motion: bool,
+    /// The cells of the table column yanked last
+    column: Option<Vec<String>>,
+    /// The cells of the table rows yanked last, pasted instead of the column if set
+    rows: Option<Vec<Vec<String>>>,
+    /// The text yanked last, only set while no column or rows are
+    yanked: Option<String>,
+    /// Whether the note is saved when the tab loses focus
+    autosave: bool,
+    /// Whether Insert mode is left when the tab loses focus
+    blur_normal: bool,
+    /// Where the page was scrolled to when the tab lost focus
+    blur_scroll: Option<f64>,
+    /// Whether the favicon shows unsaved changes of the open note
+    unsaved_icon: bool,
+    /// Whether leaving the page with unsaved changes asks for confirmation
+    confirm_close: bool,
+    /// Whether leaving the page would lose changes, read by the `beforeunload` handler
+    unsaved: Rc<Cell<bool>>,
+    render_cache: Rc<RenderCache>,
+    /// The largest attachment in KiB, set with `:set attachmentlimit=`
+    attachment_limit: u32,
+    attachments: HashMap<String, Attachment>,
+    /// Whether tasks that are due are shown as system notifications at startup
+    reminders: bool,
+    /// The countdown started with `:timer`
+    timer: Option<Timer>,
+    /// When the vault is written to the backup folder
+    backup: Schedule,
+    /// Sends [`Msg::Backup`] with `:set backup=` set to a number of minutes
+    backup_interval: Option<Interval>,
+    /// Whether a miniature of the note is shown on the right
+    minimap: bool,
+    limits: Limits,
+    /// Whether the open note is over the `limits`
+    degraded: bool,
+    /// Whether `:grep` and `:grepsub` also search archived notes
+    search_archive: bool,
+    /// Whether words missing in the dictionary are underlined
+    spell: bool,
+    /// Where dictionaries are downloaded from, with `{lang}` for the spell language
+    spell_url: Option<String>,
+    /// The dictionary of the spell language of the open note, once it was read
+    dictionary: Option<spell::Dictionary>,
+    /// The spell language whose dictionary was last asked for
+    dictionary_requested: Option<String>,
+    /// The vault being imported by `:import vault` while it asks about taken note names
+    vault_import: Option<vault::Import>,
+    /// The repository of `:push` and `:pull`
+    remote: Option<Remote>,
+    /// The running `:push` or `:pull`
+    sync: Option<remote::Progress>,
+    /// The note of `:set bibliography=` citations refer to, unless the frontmatter of the note
+    /// has a `bibliography`
+    bibliography: String,
+    /// The CORS proxy of `:set clipproxy=` pages for `:clip` are fetched through
+    clip_proxy: Option<String>,
+    /// The url of `:set webhook=` saved notes are sent to, unless their frontmatter has a
+    /// `webhook` of its own
+    webhook: Option<String>,
+    /// The `webhook` in the frontmatter of the note last saved, until `:webhook allow`
+    unconfirmed_webhook: Option<String>,
+    /// Where `:publish` uploads the note
+    publish: Service,
+    /// Whether the headings and block the cursor is in are shown above the note
+    breadcrumb: bool,
+    /// The selections grown from with `Alt+Up`, restored by `Alt+Down`
+    expansions: Vec<Range<usize>>,
+    views: Views,
 }
 
 impl Model {
-    fn handle_key_press(event: KeyboardEvent, mode: Mode) -> Option<<Model as Component>::Message> {
-        let ret = Some({
-            let key = Keypress::from(&event);
-            match mode {
-                Mode::Insert => match key.as_ref() {
-                    key if key == "Escape" => vec![Msg::Mode(Mode::Normal)],
-                    key if key == "Enter" => vec![Msg::Write("\n".to_owned())],
-                    key if key == "ArrowLeft" => vec![Msg::CursorMove(-1, 0)],
-                    key if key == "ArrowDown" => vec![Msg::CursorMove(0, 1)],
-                    key if key == "ArrowUp" => vec![Msg::CursorMove(0, -1)],
-                    key if key == "ArrowRight" => vec![Msg::CursorMove(1, 0)],
-                    key if key == "Backspace" => vec![Msg::Cmd(Command::Delete(Motion::Left))],
-                    // key if key.insertable() => vec![Msg::Write(key.key.to_owned())],
-                    key if key.insertable() => vec![Msg::Cmd(Command::Insert(key.key.into()))],
-                    a => {
-                        console_dbg!("Unknown keypress (insert)", a.key);
-                        return None;
-                    }
-                },
-                Mode::Normal => match key.as_ref() {
-                    key if key == "i" => vec![Msg::Mode(Mode::Insert)],
-                    key if key == ":" => vec![Msg::Mode(Mode::Command)],
-                    key if key == "h" => vec![Msg::Cmd(Command::Left)],
-                    key if key == "j" => vec![Msg::Cmd(Command::Down)],
-                    key if key == "k" => vec![Msg::Cmd(Command::Up)],
-                    key if key == "l" => vec![Msg::Cmd(Command::Right)],
-                    a => {
-                        console_dbg!("Unknown keypress (normal)", a.key);
-                        return None;
-                    }
-                },
-                Mode::Command => match key.as_ref() {
-                    key if key == "Escape" => vec![Msg::Mode(Mode::Normal)],
-                    key if key == "Enter" => vec![Msg::ExecuteCommand, Msg::Mode(Mode::Normal)],
-                    key if key == "ArrowLeft" => vec![Msg::CursorMove(-1, 0)],
-                    key if key == "ArrowDown" => vec![Msg::CursorMove(0, 1)],
-                    key if key == "ArrowUp" => vec![Msg::CursorMove(0, -1)],
-                    key if key == "ArrowRight" => vec![Msg::CursorMove(1, 0)],
-                    key if key.insertable() => vec![Msg::Write(key.key.to_owned())],
-                    a => {
-                        console_dbg!("Unknown keypress (command)", a.key);
-                        return None;
-                    }
-                },
+    fn handle_key_press(
+        event: KeyboardEvent,
+        mode: Mode,
+        keymap: &Keymap,
+        pending: &str,
+        jumping: bool,
+    ) -> Option<<Model as Component>::Message> {
+        let key = Keypress::from(&event);
+        let key = key.as_ref();
+        if matches!(key.key, "Shift" | "Control" | "Alt" | "Meta") {
+            return None;
+        }
+        if jumping {
+            event.prevent_default();
+            return Some(vec![Msg::JumpKey(key.key.to_owned())]);
+        }
+        let (count, keys) = split_count(pending);
+        let with_count = |notation: &str| {
+            if count.is_empty() {
+                notation.to_owned()
+            } else {
+                format!("{} {}", count, notation)
+            }
+        };
+        // Digits before a sequence in Normal and Visual mode are its count
+        let digit = key.notation();
+        let digit = digit.len() == 1
+            && digit.chars().all(|c| c.is_ascii_digit())
+            && (digit != "0" || !count.is_empty());
+        let counting = digit && keys.is_empty() && matches!(mode, Mode::Normal | Mode::Visual);
+        // Keys are bound by what they type or by their position
+        let notations = [key.notation(), key.code_notation()].map(|notation| {
+            if keys.is_empty() {
+                notation
+            } else {
+                format!("{} {}", keys, notation)
             }
         });
+        let binding = notations
+            .iter()
+            .find_map(|notation| keymap.lookup(mode, notation));
+        let prefix = notations
+            .iter()
+            .find(|notation| keymap.is_prefix(mode, notation));
+        let ret = if counting {
+            vec![Msg::Pending(with_count(&key.notation()))]
+        } else if let Some(binding) = binding {
+            // A count repeats the messages of the binding
+            let count = count.replace(' ', "").parse().unwrap_or(1);
+            let mut messages: Vec<_> = iter::repeat(binding.messages.clone())
+                .take(count)
+                .flatten()
+                .collect();
+            if !pending.is_empty() {
+                messages.insert(0, Msg::Pending(String::new()));
+            }
+            messages
+        } else if let Some(prefix) = prefix {
+            vec![Msg::Pending(with_count(prefix))]
+        } else if !pending.is_empty() {
+            // Unbound sequences are dropped
+            vec![Msg::Pending(String::new())]
+        } else if key.insertable() && mode == Mode::Insert {
+            vec![Msg::Cmd(Command::Insert(key.key.into()))]
+        } else if key.insertable() && mode == Mode::Command {
+            vec![Msg::Write(key.key.to_owned())]
+        } else {
+            log::debug!("Unknown keypress {:?} in {:?} mode", key.key, mode);
+            return None;
+        };
         event.prevent_default();
-        ret
+        Some(ret)
     }
     fn parse_md(&mut self) {
         let text = &self.lines.iter().collect::<String>();
@@ -371,6 +911,8 @@ impl Model {
                         Event::Start(Tag::Table(_)) => TextStyle::Table,
                         Event::Start(Tag::TableHead) => TextStyle::Bold,
                         Event::Start(Tag::TableCell) => TextStyle::TableCell,
+                        Event::Start(Tag::Link(..)) => TextStyle::Link,
+                        Event::Start(Tag::Heading(_)) => TextStyle::Heading,
 
                         _ => return None,
                     },
@@ -404,11 +946,3017 @@ impl Model {
     }
 
     fn execute(&mut self, command: String) {
-        for command in command.split_whitespace() {
-            if let Some((name, value)) = command.split_once('=') {
+        self.status = None;
+        let command = command.trim();
+        match self.user_commands.expand(command) {
+            Some(commands) => {
+                for command in commands {
+                    self.run(&command);
+                }
+            }
+            None => self.run(command),
+        }
+    }
+
+    /// Runs a built-in command.
+    fn run(&mut self, command: &str) {
+        let (name, argument) = command.split_once(' ').unwrap_or((command, ""));
+        let argument = argument.trim();
+        let argument = (!argument.is_empty()).then_some(argument);
+        match name {
+            "w" => self.write(argument),
+            "rename" => self.rename(argument),
+            "e" => self.edit(argument),
+            "diff" => self.diff(argument),
+            "delete" => self.delete(),
+            "b" | "buffer" => match argument {
+                Some("#") => self.alternate(),
+                Some(note) if self.note.as_deref() != Some(note) => self.edit(Some(note)),
+                Some(_) => {}
+                None => self.show(Status::error("No buffer given")),
+            },
+            "fmt" if self.unsuggested() => {}
+            "fmt" => {
+                self.format.document(&mut self.document);
+                toc::update(&mut self.document);
+            }
+            "fold" => {
+                if self.document.toggle_fold() {
+                    self.save_folds();
+                } else {
+                    self.show(Status::error("Not in a section under a heading"));
+                }
+            }
+            "unfold" => {
+                self.document.folded.clear();
+                self.save_folds();
+            }
+            "codeblock" | "quote" | "heading" | "tablify" | "listify" | "split-note"
+                if self.unsuggested() => {}
+            "codeblock" | "quote" | "heading" | "tablify" | "listify" if self.locked() => {}
+            "split-note" => self.split_note(argument),
+            "codeblock" => self.convert_block(Block::Code(argument.unwrap_or_default().to_owned())),
+            "quote" => self.convert_block(Block::Quote),
+            "heading" => match argument.and_then(|level| level.parse().ok()) {
+                Some(level @ 1..=6) => self.convert_block(Block::Heading(level)),
+                _ => self.show(Status::error("Usage: :heading <1-6>")),
+            },
+            "tablify" => {
+                let table = match self.document.elements.get(self.document.active_element) {
+                    Some(Element::Paragraph(paragraph)) => convert::tablify(&paragraph.to_md()),
+                    _ => None,
+                };
+                match table {
+                    Some(table) => self.replace_element(&table),
+                    None => self.show(Status::error("Not a list of key: value items")),
+                }
+            }
+            "listify" => match self.document.active_table_mut() {
+                Some(table) => {
+                    let list = convert::listify(&table.rows(0..table.height));
+                    self.replace_element(&list);
+                }
+                None => self.show(Status::error("Not in a table")),
+            },
+            "toc" => match argument {
+                Some("insert") if toc::update(&mut self.document) => {
+                    self.show(Status::info("Updated the table of contents"))
+                }
+                Some("insert") if self.locked() => {}
+                Some("insert") => {
+                    toc::insert(&mut self.document);
+                    self.edited();
+                }
+                _ => self.show(Status::error("Usage: :toc insert")),
+            },
+            "install" => {
+                if let Err(hint) = offline::install() {
+                    self.notify(Severity::Info, hint);
+                }
+            }
+            "vs" | "vsplit" => self.vsplit(argument),
+            "only" => {
+                if let Some(split) = self.split.take() {
+                    self.buffers.push(split.buffer);
+                }
+            }
+            "find" => {
+                let start = match argument {
+                    Some(query) => StartScreen::find(self.store.as_ref(), query),
+                    None => StartScreen::new(self.store.as_ref()),
+                };
+                self.overlay = Some(Overlay::Start(start));
+            }
+            "info" => {
+                let info = Info::new(
+                    self.note.as_deref(),
+                    &self.document.to_md(),
+                    self.store.as_ref(),
+                    &self.attachments,
+                );
+                self.overlay = Some(Overlay::Info(info));
+            }
+            "archive" | "unarchive" => self.archive(argument, name == "archive"),
+            "pin" | "unpin" => match argument.map(String::from).or_else(|| self.note.clone()) {
+                Some(note) if self.store.load(&note).is_some() => {
+                    self.store.set_pinned(&note, name == "pin");
+                    if let Some(Overlay::Start(start)) = &mut self.overlay {
+                        *start = StartScreen::new(self.store.as_ref());
+                    }
+                }
+                Some(note) => self.notify(Severity::Error, format!("No note {}", note)),
+                None => self.notify(Severity::Error, "The buffer is not a stored note"),
+            },
+            "commit" => self.commit(argument),
+            "push" => self.sync(true),
+            "pull" => self.sync(false),
+            "token" => match argument {
+                Some(token) => secret::encrypt(
+                    token.trim().to_owned(),
+                    self.link.callback(|token| vec![Msg::TokenEncrypted(token)]),
+                ),
+                None => self.notify(Severity::Error, "Give the token, like :token ghp_…"),
+            },
+            "commits" | "history" => {
+                offline::commits(self.link.callback(|commits| vec![Msg::Commits(commits)]))
+            }
+            "checkout" => match argument {
+                Some(argument) => {
+                    let (id, note) = match argument.split_once(' ') {
+                        Some((id, note)) => (id.to_owned(), Some(note.trim().to_owned())),
+                        None => (argument.to_owned(), None),
+                    };
+                    offline::commits(self.link.callback(move |commits| {
+                        vec![Msg::Checkout(id.clone(), note.clone(), commits)]
+                    }))
+                }
+                None => self.notify(Severity::Error, "Give a commit, like :checkout 3f9a0c1"),
+            },
+            "log" => match argument.map(log::Level::from_name) {
+                Some(None) => self.show(Status::error(
+                    "Unknown log level, use debug, info, warn or error",
+                )),
+                level => {
+                    let level = level.flatten().unwrap_or(log::Level::Debug);
+                    let entries = log::entries(level)
+                        .into_iter()
+                        .map(|entry| {
+                            format!(
+                                "[{}] {}: {}",
+                                entry.level.name(),
+                                entry.target,
+                                entry.message
+                            )
+                        })
+                        .collect();
+                    let title = format!("Log ({} and above)", level.name());
+                    self.overlay = Some(Overlay::Log(Picker::new(title, entries)));
+                }
+            },
+            "messages" => match argument {
+                Some("clear") => self.notifications.history.clear(),
+                _ => {
+                    let messages = self.notifications.messages();
+                    self.overlay = Some(Overlay::Messages(Picker::new("Messages", messages)));
+                }
+            },
+            "ls" | "buffers" => {
+                self.overlay = Some(Overlay::Buffers(Picker::new("Buffers", self.buffer_list())))
+            }
+            "recover" => self.recover(argument),
+            "swapdelete" => self.delete_swap(),
+            "command" => match argument.map(|argument| argument.split_once(' ')) {
+                Some(Some((name, replacement))) => {
+                    if let Err(error) =
+                        self.user_commands
+                            .define(self.store.as_mut(), name, replacement.trim())
+                    {
+                        self.show(Status::error(error));
+                    }
+                }
+                Some(None) => self.show(Status::error("No replacement given")),
+                None => {
+                    self.overlay = Some(Overlay::Commands(Picker::new(
+                        "User commands",
+                        self.user_commands.list(),
+                    )))
+                }
+            },
+            "abbrev" | "ab" | "iabbrev" | "iab" => match argument
+                .map(|argument| argument.split_once(' '))
+            {
+                Some(Some((abbreviation, expansion))) => {
+                    self.abbreviations
+                        .define(self.store.as_mut(), abbreviation, expansion.trim())
+                }
+                Some(None) => self.show(Status::error("No expansion given")),
+                None => {
+                    self.overlay = Some(Overlay::Commands(Picker::new(
+                        "Abbreviations",
+                        self.abbreviations.list(),
+                    )))
+                }
+            },
+            "correct" => match argument.map(|argument| argument.split_once(' ')) {
+                Some(Some((word, correction))) => {
+                    self.corrections
+                        .define(self.store.as_mut(), word, correction.trim())
+                }
+                Some(None) => self.show(Status::error("No correction given")),
+                None => {
+                    self.overlay = Some(Overlay::Commands(Picker::new(
+                        "Corrections",
+                        self.corrections.list(),
+                    )))
+                }
+            },
+            "uncorrect" => {
+                let word = argument.unwrap_or_default();
+                if !self.corrections.remove(self.store.as_mut(), word) {
+                    self.show(Status::error(format!("No correction for {}", word)));
+                }
+            }
+            "unabbrev" | "una" | "iunabbrev" | "iuna" => {
+                let abbreviation = argument.unwrap_or_default();
+                if !self.abbreviations.remove(self.store.as_mut(), abbreviation) {
+                    self.show(Status::error(format!("No abbreviation {}", abbreviation)));
+                }
+            }
+            "delcommand" => {
+                let name = argument.unwrap_or_default();
+                if !self.user_commands.remove(self.store.as_mut(), name) {
+                    self.show(Status::error(format!("No user command {}", name)));
+                }
+            }
+            "doctor" => self.fill_quickfix(Quickfix::new(
+                "Doctor",
+                self.note.as_deref(),
+                doctor::diagnose(&self.document),
+            )),
+            "lint" => self.fill_quickfix(Quickfix::lint(
+                &self.lint,
+                self.note.as_deref(),
+                &self.document,
+            )),
+            "grep" => self.grep(argument),
+            "backlinks" => match self.note.clone() {
+                Some(note) => self.fill_quickfix(Quickfix::backlinks(&note, self.store.as_ref())),
+                None => self.notify(Severity::Error, "No note name"),
+            },
+            "copen" | "cope" => self.overlay = Some(Overlay::Quickfix(self.quickfix.picker())),
+            "cnext" | "cn" => self.step_quickfix(false),
+            "cprev" | "cp" | "cprevious" => self.step_quickfix(true),
+            "spellgood" => self.spell_good(argument, true),
+            "spellwrong" => self.spell_good(argument, false),
+            "bd" | "q" | "quit" => self.close(false),
+            "bd!" | "q!" | "quit!" => self.close(true),
+            "trash" => {
+                self.overlay = Some(Overlay::Trash(Picker::new("Trash", self.store.trash())))
+            }
+            "import" if argument.map_or(false, |argument| argument.starts_with("vault")) => {
+                let folder = argument == Some("vault folder");
+                import::pick_vault(
+                    folder,
+                    self.link.callback(|files| vec![Msg::ImportVault(files)]),
+                )
+            }
+            "import" => import::pick_file(
+                ".html,.htm,.txt,.org,.md,.opml",
+                self.link
+                    .callback(|(name, content)| vec![Msg::Import(name, content)]),
+            ),
+            "export" => self.export(argument),
+            "publish" => self.publish(argument),
+            "feed" => self.feed(argument),
+            "webhook" => self.allow_webhook(argument),
+            "accept" => self.review(true, argument),
+            "reject" => self.review(false, argument),
+            "clip" => match argument {
+                Some(url) => {
+                    let url = url.trim();
+                    let clipped = url.to_owned();
+                    let done = self
+                        .link
+                        .callback(move |page| vec![Msg::Clipped(clipped.clone(), page)]);
+                    clip::fetch(url, self.clip_proxy.as_deref(), done);
+                    self.show(Status::info("Clipping…"));
+                }
+                None => self.notify(
+                    Severity::Error,
+                    "Give the url of the page, like :clip https://…",
+                ),
+            },
+            "backup" => match argument {
+                Some("folder") => {
+                    backup::choose(self.link.callback(|folder| vec![Msg::BackupFolder(folder)]))
+                }
+                None => self.backup(true),
+                Some(_) => self.notify(Severity::Error, "Unknown backup argument, use folder"),
+            },
+            "tutor" => self.open_lesson(0),
+            "timer" => self.timer(argument),
+            "board" => self.overlay = Some(Overlay::Board(Board::new(self.document.to_md()))),
+            "tasks" => {
+                let tasks = Tasks::new(self.store.as_ref(), &date::now("%F"));
+                self.overlay = Some(Overlay::Tasks(tasks))
+            }
+            "template" => match argument {
+                Some(name) => self.use_template(name),
+                None => {
+                    let templates = template::names(self.store.as_ref());
+                    self.overlay = Some(Overlay::Templates(Picker::new("Templates", templates)))
+                }
+            },
+            "date" if self.locked() => {}
+            "date" => {
+                let date = date::now(argument.unwrap_or(&self.date_format));
+                let insert = Command::Insert(date.into());
+                if !(self.suggesting && self.suggest(&insert)) {
+                    self.document.command(&insert);
+                }
+            }
+            "help" => {
+                let filter = argument
+                    .and_then(|argument| argument.strip_prefix("keys"))
+                    .map(str::trim)
+                    .filter(|filter| !filter.is_empty());
+                self.overlay = Some(Overlay::Help(Help::new(&self.keymap, filter)));
+            }
+            "links" => {
+                self.overlay = Some(Overlay::Links(Picker::new(
+                    "Link definitions",
+                    self.link_definitions(),
+                )))
+            }
+            "link" => self.link_command(argument.unwrap_or_default()),
+            "noh" | "nohlsearch" => self.highlight = false,
+            "grepsub" => self.grep_sub(argument),
+            "apply" => self.apply_grep_sub(),
+            _ if command.starts_with('/') => {
+                let (search, error) = Search::new(&command[1..], false, self.case);
+                self.search = Some(search);
+                self.search_next(false);
+                if let Some(error) = error {
+                    self.show(Status::error(error));
+                }
+            }
+            _ if Substitute::parse(command, self.case).is_some() && self.unsuggested() => {}
+            _ if Substitute::parse(command, self.case).is_some() => self.substitute(command),
+            "restore" => self.restore(argument),
+            "purge" => self.purge(argument),
+            _ => self.set(command),
+        }
+    }
+
+    fn write(&mut self, note: Option<&str>) {
+        if let Some(note) = note {
+            if self.note.is_none() {
+                self.store.remove_swap("");
+            }
+            if self.note.as_deref() != Some(note) {
+                self.base = None;
+            }
+            self.note = Some(note.to_owned());
+        }
+        let note = match &self.note {
+            Some(note) => note.clone(),
+            None => {
+                self.notify(Severity::Error, "No note name given");
+                return;
+            }
+        };
+
+        if let Some(Overlay::Merge(merge)) = &self.overlay {
+            let content = merge.resolved();
+            self.store.save(&note, &content);
+            self.store.remove_swap(&note);
+            self.document.replace(&content);
+            self.base = Some(content.clone());
+            self.overlay = None;
+            self.notify(Severity::Success, format!("Saved {}", note));
+            self.saved(&note, &content);
+            return;
+        }
+
+        // Formatting would change the note past its suggested changes
+        if self.format_on_save && !self.suggesting {
+            self.format.document(&mut self.document);
+        }
+        toc::update(&mut self.document);
+        let content = self.document.to_md();
+        if let Some(remote) = self
+            .base
+            .as_ref()
+            .and_then(|base| self.store.conflict(&note, base))
+        {
+            let merged = self
+                .base
+                .as_ref()
+                .and_then(|base| self.store.merge(&note, base, &content));
+            // The merge is shown to be checked, it is saved by writing again
+            if let Some(merged) = merged {
+                log::info!("{} changed since it was opened, merged word by word", note);
+                self.document.replace(&merged);
+                self.base = Some(remote);
+                self.notify(
+                    Severity::Info,
+                    format!(
+                        "{} was merged with the changes made elsewhere, :w saves it",
+                        note
+                    ),
+                );
+                return;
+            }
+            log::warning!("{} changed since it was opened, merging", note);
+            self.overlay = Some(Overlay::Merge(Merge::new(&content, &remote)));
+            return;
+        }
+        self.store.save(&note, &content);
+        self.store.remove_swap(&note);
+        self.base = Some(content.clone());
+        self.notify(Severity::Success, format!("Saved {}", note));
+        self.saved(&note, &content);
+    }
+
+    /// Backs up the vault after a save with `:set backup=save` and sends `note` to its
+    /// webhook.
+    fn saved(&mut self, note: &str, content: &str) {
+        if self.backup == Schedule::OnSave {
+            self.backup(false);
+        }
+        match webhook::target(content, self.webhook.as_deref(), self.store.as_ref()) {
+            Some(Target::Send(url)) => {
+                let failed = self.link.callback(|error| vec![Msg::WebhookFailed(error)]);
+                webhook::send(&url, note, content, failed);
+            }
+            Some(Target::Unconfirmed(url)) => {
+                self.notify(
+                    Severity::Info,
+                    format!(
+                        "{} has the webhook {}, send it there on save with :webhook allow",
+                        note, url
+                    ),
+                );
+                self.unconfirmed_webhook = Some(url);
+            }
+            None => {}
+        }
+    }
+
+    /// `:webhook allow` sends notes to the webhook in their frontmatter last asked about.
+    fn allow_webhook(&mut self, argument: Option<&str>) {
+        match (argument, self.unconfirmed_webhook.take()) {
+            (Some("allow"), Some(url)) => {
+                webhook::allow(self.store.as_mut(), &url);
+                self.notify(
+                    Severity::Success,
+                    format!("Notes are sent to {} when saved", url),
+                );
+            }
+            (Some("allow"), None) => self.notify(Severity::Error, "No webhook of a note to allow"),
+            (_, url) => {
+                self.unconfirmed_webhook = url;
+                self.notify(Severity::Error, "Use :webhook allow");
+            }
+        }
+    }
+
+    /// Writes the vault to the backup folder, asking for the permission again with `ask`.
+    fn backup(&mut self, ask: bool) {
+        let done = self
+            .link
+            .callback(move |result| vec![Msg::BackedUp(ask, result)]);
+        backup::run(self.stored_notes(), ask, done);
+    }
+
+    /// Restarts the timer of `:set backup=` with a number of minutes.
+    fn schedule_backup(&mut self) {
+        self.backup_interval = match self.backup {
+            Schedule::Every(minutes) => {
+                let link = self.link.clone();
+                Some(Interval::new(minutes.saturating_mul(60_000), move || {
+                    link.send_message(vec![Msg::Backup])
+                }))
+            }
+            _ => None,
+        };
+    }
+
+    /// Saves the note when the tab loses focus, or writes its swap copy if it has no name.
+    fn autosave(&mut self) {
+        if !buffer::modified(&self.document, self.base.as_deref()) {
+            return;
+        }
+        match &self.note {
+            Some(_) => self.write(None),
+            None => self.store.write_swap(&self.tab, "", &self.document.to_md()),
+        }
+    }
+
+    fn reflow(&mut self, scope: Reflow) {
+        let width = match self.format.text_width {
+            0 => 80,
+            width => width,
+        };
+        let start = self.document.active_element;
+        let end = match scope {
+            Reflow::ToEnd => self.document.elements.len(),
+            _ => start + 1,
+        };
+        for element in self.document.elements.iter_mut().take(end).skip(start) {
+            // Tables and headings can not be reflowed
+            if let Element::Paragraph(paragraph) = element {
+                format::reflow_paragraph(paragraph, width, scope);
+            }
+        }
+        self.edited();
+    }
+
+    /// What rendering `document` depends on besides its content.
+    fn state(&self, document: &Document, cursor_style: CursorStyle) -> ApplicationState {
+        let md = document.to_md();
+        let degraded = self.limits.exceeded(document).is_some();
+        ApplicationState {
+            cursor_style,
+            cursor_id: "cursor",
+            footnotes: if document.flavor.footnotes() {
+                footnote::definitions(&md)
+            } else {
+                vec![]
+            },
+            flavor: document.flavor,
+            lint: (self.show_lint && !degraded).then(|| self.lint.clone()),
+            theme: self.theme,
+            sidenotes: self.sidenotes,
+            show_comments: self.show_comments,
+            heading_numbers: self.heading_numbers,
+            ligatures: self.ligatures && !degraded,
+            tab_width: self.indent.tab_width,
+            search: self.highlight.then(|| self.search.clone()).flatten(),
+            links: links::definitions(&md)
+                .into_iter()
+                .map(|definition| {
+                    (
+                        links::normalize(&definition.id),
+                        links::href(&definition.destination),
+                    )
+                })
+                .collect(),
+            attachments: self.attachments.clone(),
+            transclusions: self.views.transclusions.clone(),
+            queries: self.views.queries.clone(),
+            // The bibliography is only read for notes citing something
+            spell: self
+                .dictionary
+                .clone()
+                .filter(|dictionary| self.spell && dictionary.lang == document.spell_lang()),
+            references: if citation::citations(&md).is_empty() {
+                vec![]
+            } else {
+                citation::load(
+                    self.store.as_ref(),
+                    citation::bibliography(&md, &self.bibliography),
+                )
+            },
+            today: date::now("%F"),
+            cache: self.render_cache.clone(),
+            degraded,
+        }
+    }
+
+    /// Reads what the open buffers transclude and query, all of it again once the notes
+    /// changed since it was read.
+    fn load_views(&mut self) {
+        let version = Some((self.store.version(), date::now("%F")));
+        if self.views.version != version {
+            // The index keeps what did not change
+            self.views = Views {
+                version,
+                index: self.views.index.take(),
+                ..Views::default()
+            };
+        }
+        let today = date::now("%F");
+        let split = self.split.as_ref().map(|split| &split.buffer.document);
+        for document in iter::once(&self.document).chain(split) {
+            transclusion::load(
+                document,
+                self.store.as_ref(),
+                &mut self.views.index,
+                &mut self.views.transclusions,
+            );
+            query::load(
+                document,
+                self.store.as_ref(),
+                &today,
+                &mut self.views.queries,
+            );
+        }
+    }
+
+    /// Reads the dictionary of the open note's spell language while `:set spell` is on, from
+    /// IndexedDB or else from `:set spellurl=`.
+    fn load_dictionary(&mut self) {
+        let lang = self.document.spell_lang();
+        let loaded = self
+            .dictionary
+            .as_ref()
+            .map_or(false, |dictionary| dictionary.lang == lang);
+        if !self.spell || loaded || self.dictionary_requested.as_ref() == Some(&lang) {
+            return;
+        }
+        self.dictionary_requested = Some(lang.clone());
+        let requested = lang.clone();
+        offline::dictionary(
+            &lang,
+            self.link
+                .callback(move |words| vec![Msg::DictionaryCached(requested.clone(), words)]),
+        );
+    }
+
+    /// The index of the notes wiki links resolve against, read again for the notes that
+    /// changed.
+    fn index(&mut self) -> &wiki::Index {
+        wiki::Index::current(&mut self.views.index, self.store.as_ref())
+    }
+
+    /// Warns when the open note grows over the limits, once until it is within them again.
+    fn check_limits(&mut self) {
+        let exceeded = self.limits.exceeded(&self.document);
+        if let (Some(reason), false) = (&exceeded, self.degraded) {
+            self.show(Status::error(format!(
+                "{}, only the blocks around the cursor are shown",
+                reason
+            )));
+        }
+        self.degraded = exceeded.is_some();
+    }
+
+    /// Shows `status` at the bottom until the next command, keeping it for `:messages`.
+    fn show(&mut self, status: Status) {
+        let severity = if status.error {
+            Severity::Error
+        } else {
+            Severity::Info
+        };
+        self.notifications.record(severity, status.message.clone());
+        self.status = Some(status);
+    }
+
+    /// Shows `message` as a toast that is dismissed after [`notification::DISMISS_AFTER`].
+    fn notify(&mut self, severity: Severity, message: impl Into<String>) {
+        let message = message.into();
+        match severity {
+            Severity::Error => log::error!("{}", message),
+            _ => log::info!("{}", message),
+        }
+        let id = self.notifications.push(severity, message);
+        let link = self.link.clone();
+        Timeout::new(notification::DISMISS_AFTER, move || {
+            link.send_message(vec![Msg::Dismiss(id)])
+        })
+        .forget();
+    }
+
+    /// Whether the active element is in a region locked with `<!-- lock -->`, which is
+    /// then told in the status line.
+    fn locked(&mut self) -> bool {
+        let active = self.document.active_element;
+        self.locks(active..active + 1)
+    }
+
+    /// Whether changing the note `before` to `after` keeps its locked regions as they are,
+    /// which is otherwise told in the status line.
+    fn keeps_locks(&mut self, before: &str, after: &str) -> bool {
+        let kept =
+            lock::contents(&Document::from_md(before)) == lock::contents(&Document::from_md(after));
+        if !kept {
+            self.show(Status::error(format!(
+                "This changes a part of the note that is read-only with {}",
+                lock::START
+            )));
+        }
+        kept
+    }
+
+    /// Whether any of the `elements` is in a locked region, which is then told in the status
+    /// line.
+    fn locks(&mut self, elements: Range<usize>) -> bool {
+        let locked = lock::touches(&self.document, elements);
+        if locked {
+            self.show(Status::error(format!(
+                "This part of the note is read-only, remove its {} to edit it",
+                lock::START
+            )));
+        }
+        locked
+    }
+
+    /// Counts an edit of the open buffer, writing its swap copy every [`swap::EDITS`] edits.
+    fn edited(&mut self) {
+        self.edits += 1;
+        self.check_limits();
+        if self.edits % swap::EDITS == 0 {
+            let note = self.note.as_deref().unwrap_or_default();
+            let content = self.document.to_md();
+            self.store.write_swap(&self.tab, note, &content);
+            if let (false, Some(base)) = (note.is_empty(), &self.base) {
+                self.store.record(note, base, &content);
+            }
+        }
+    }
+
+    /// The swap copies left behind by tabs that are no longer running.
+    fn leftover_swaps(&self) -> Vec<(String, String)> {
+        let now = js_sys::Date::now();
+        let running: Vec<String> = self
+            .store
+            .tabs()
+            .into_iter()
+            .filter(|(_, seen)| now - seen < swap::STALE)
+            .map(|(tab, _)| tab)
+            .collect();
+        self.store
+            .swaps()
+            .into_iter()
+            .filter(|(note, _)| {
+                !matches!(self.store.swap_tab(note), Some(tab) if running.contains(&tab))
+            })
+            .collect()
+    }
+
+    fn swap_names(&self) -> Vec<String> {
+        self.leftover_swaps()
+            .iter()
+            .map(|(note, _)| swap::name(note).to_owned())
+            .collect()
+    }
+
+    /// Opens the swap copy selected in the recovery prompt or of `argument`.
+    fn recover(&mut self, argument: Option<&str>) {
+        let swaps = self.leftover_swaps();
+        let swap = match (argument, &self.overlay) {
+            (Some(argument), _) => swaps.into_iter().find(|(note, _)| note == argument),
+            (None, Some(Overlay::Recover(picker))) => swaps.into_iter().nth(picker.active),
+            (None, _) => None,
+        };
+        match swap {
+            Some((note, content)) => {
+                let note = (!note.is_empty()).then_some(note);
+                let base = note.as_deref().and_then(|note| self.store.load(note));
+                self.open(&content, note, base);
+                self.show(Status::info("Recovered, write with :w to keep the changes"));
+            }
+            None => self.show(Status::error("No swap file to recover")),
+        }
+    }
+
+    /// Removes the swap copy selected in the recovery prompt.
+    fn delete_swap(&mut self) {
+        let swaps = self.leftover_swaps();
+        if let Some(Overlay::Recover(picker)) = &self.overlay {
+            if let Some((note, _)) = swaps.get(picker.active) {
+                self.store.remove_swap(note);
+            }
+        }
+        let names = self.swap_names();
+        if let Some(Overlay::Recover(picker)) = &mut self.overlay {
+            picker.set_items(names);
+        }
+    }
+
+    /// Opens `md` in a new buffer, `base` is the stored content of `note`.
+    fn open(&mut self, md: &str, note: Option<String>, base: Option<String>) {
+        let mut document = Document::from_md(md);
+        if let Some(note) = &note {
+            document.folded = self.store.folds(note);
+        }
+        self.switch(Buffer {
+            document,
+            note,
+            base,
+        });
+    }
+
+    /// Remembers the folded headings of the open note for the next time it is opened.
+    fn save_folds(&mut self) {
+        if let Some(note) = &self.note {
+            self.store.set_folds(note, &self.document.folded);
+        }
+    }
+
+    /// Makes `buffer` the open one, keeping the previous one as alternate buffer.
+    fn switch(&mut self, buffer: Buffer) {
+        let previous = self.replace_buffer(buffer);
+        if previous.note.is_none() || previous.note != self.note {
+            self.buffers.push(previous);
+        }
+        let note = &self.note;
+        self.buffers
+            .closed
+            .retain(|closed| Some(closed) != note.as_ref());
+    }
+
+    /// Replaces the open buffer without keeping it.
+    fn replace_buffer(&mut self, buffer: Buffer) -> Buffer {
+        self.overlay = None;
+        self.lesson = None;
+        let previous = Buffer {
+            document: mem::replace(&mut self.document, buffer.document),
+            note: mem::replace(&mut self.note, buffer.note),
+            base: mem::replace(&mut self.base, buffer.base),
+        };
+        self.check_limits();
+        previous
+    }
+
+    /// Opens `note` in a pane right of the open buffer, replacing the other pane.
+    fn vsplit(&mut self, note: Option<&str>) {
+        let note = match note.filter(|&note| self.note.as_deref() != Some(note)) {
+            Some(note) => note,
+            None => {
+                self.notify(Severity::Error, "No other note given");
+                return;
+            }
+        };
+        if let Some(split) = &self.split {
+            if split.buffer.note.as_deref() == Some(note) {
+                self.focus_pane(split.left);
+                return;
+            }
+        }
+        let buffer = self.buffers.take(note).unwrap_or_else(|| {
+            let content = self.store.load(note).unwrap_or_default();
+            let mut document = Document::from_md(&content);
+            document.folded = self.store.folds(note);
+            Buffer {
+                document,
+                note: Some(note.to_owned()),
+                base: Some(content),
+            }
+        });
+        let previous = self.replace_buffer(buffer);
+        if let Some(split) = self.split.replace(Split {
+            buffer: previous,
+            left: true,
+        }) {
+            self.buffers.push(split.buffer);
+        }
+    }
+
+    /// Makes the buffer of the pane on the `left` or right the open one.
+    fn focus_pane(&mut self, left: bool) {
+        match self.split.take() {
+            Some(split) if split.left == left => {
+                let previous = self.replace_buffer(split.buffer);
+                self.split = Some(Split {
+                    buffer: previous,
+                    left: !left,
+                });
+            }
+            split => self.split = split,
+        }
+    }
+
+    /// Toggles to the alternate buffer with `:b#` or Ctrl+6.
+    fn alternate(&mut self) {
+        match self.buffers.alternate() {
+            Some(buffer) => self.switch(buffer),
+            None => self.show(Status::error("No alternate buffer")),
+        }
+    }
+
+    /// Closes the open buffer with `:bd`, unsaved changes are only discarded with `:bd!`.
+    fn close(&mut self, force: bool) {
+        if !force && buffer::modified(&self.document, self.base.as_deref()) {
+            self.show(Status::error(
+                "The buffer has unsaved changes, write them with :w or discard them with :bd!",
+            ));
+            return;
+        }
+        self.store
+            .remove_swap(self.note.as_deref().unwrap_or_default());
+        if let Some(note) = self.note.take() {
+            self.buffers.close(note);
+        }
+        self.open_alternate();
+    }
+
+    /// Replaces the open buffer with the alternate one or an empty one.
+    fn open_alternate(&mut self) {
+        let buffer = self.buffers.alternate().unwrap_or(Buffer {
+            document: Document::from_md(""),
+            note: None,
+            base: None,
+        });
+        self.replace_buffer(buffer);
+    }
+
+    /// Lines of `:ls`, the open buffer, the hidden ones and the recently closed notes.
+    fn buffer_list(&self) -> Vec<String> {
+        let name = |note: &Option<String>| note.as_deref().unwrap_or("[No Name]").to_owned();
+        let flag = |modified| if modified { " +" } else { "" };
+        iter::once(format!(
+            "%a {}{}",
+            name(&self.note),
+            flag(buffer::modified(&self.document, self.base.as_deref()))
+        ))
+        .chain(self.buffers.hidden.iter().enumerate().map(|(i, buffer)| {
+            format!(
+                "{}  {}{}",
+                if i == 0 { "#" } else { " " },
+                name(&buffer.note),
+                flag(buffer.modified())
+            )
+        }))
+        .chain(
+            self.buffers
+                .closed
+                .iter()
+                .map(|note| format!("   {} (closed)", note)),
+        )
+        .collect()
+    }
+
+    /// Switches to the buffer or reopens the closed note at `i` in [`Self::buffer_list`].
+    fn select_buffer(&mut self, i: usize) {
+        let hidden = self.buffers.hidden.len();
+        if i == 0 {
+            self.overlay = None;
+        } else if i <= hidden {
+            let buffer = self.buffers.hidden.remove(i - 1);
+            self.switch(buffer);
+        } else if let Some(note) = self.buffers.closed.get(i - 1 - hidden).cloned() {
+            self.edit(Some(&note));
+        }
+    }
+
+    fn edit(&mut self, note: Option<&str>) {
+        if let Some(buffer) = note
+            .filter(|&note| self.note.as_deref() != Some(note))
+            .and_then(|note| self.buffers.take(note))
+        {
+            // Hidden buffers keep their unsaved changes
+            self.switch(buffer);
+        } else if let Some(note) = note {
+            let content = self.store.load(note).unwrap_or_default();
+            self.open(&content, Some(note.to_owned()), Some(content.clone()));
+        } else {
+            self.notify(Severity::Error, "No note name given");
+        }
+    }
+
+    /// Shows the difference to `other` or to the last saved version of the current note.
+    fn diff(&mut self, other: Option<&str>) {
+        let (title, old) = match (other, &self.note) {
+            (Some(other), _) => (other.to_owned(), self.store.load(other)),
+            (None, Some(note)) => (format!("{} (last saved)", note), self.store.load(note)),
+            (None, None) => {
+                self.notify(Severity::Error, "No note to compare against");
+                return;
+            }
+        };
+        if let Some(old) = old {
+            self.overlay = Some(Overlay::Diff(Diff::new(
+                title,
+                &old,
+                &self.document.to_md(),
+            )));
+        } else {
+            self.notify(Severity::Error, format!("Note {} not found", title));
+        }
+    }
+
+    /// Acts on the entry selected in the overlay.
+    fn select(&mut self) {
+        match &self.overlay {
+            Some(Overlay::Start(start)) => match start.selected() {
+                Some(StartEntry::Note(note)) => self.edit(Some(&note)),
+                Some(StartEntry::New) => self.open("", None, None),
+                None => {}
+            },
+            Some(Overlay::Trash(_)) => self.restore(None),
+            Some(Overlay::Recover(_)) => self.recover(None),
+            Some(Overlay::Tasks(_)) => self.toggle_task(),
+            Some(Overlay::Board(_)) => self.pick_up_card(),
+            Some(Overlay::Templates(templates)) => {
+                if let Some(name) = templates.selected().map(String::from) {
+                    self.use_template(&name);
+                }
+            }
+            Some(Overlay::Notes(notes)) => {
+                if let Some(note) = notes.selected().map(String::from) {
+                    self.edit(Some(&note));
+                }
+            }
+            Some(Overlay::Commits(commits)) => {
+                if let Some(id) = commits
+                    .selected()
+                    .and_then(|commit| commit.split(' ').next())
+                {
+                    let id = id.to_owned();
+                    offline::commits(
+                        self.link.callback(move |commits| {
+                            vec![Msg::Checkout(id.clone(), None, commits)]
+                        }),
+                    );
+                }
+            }
+            Some(Overlay::Collision(choices)) => {
+                let (_, collision, all) = vault::CHOICES[choices.active];
+                if let Some(import) = &mut self.vault_import {
+                    import.choose(collision, all, self.store.as_mut());
+                }
+                self.continue_import();
+            }
+            Some(Overlay::Quickfix(picker)) => {
+                if picker.active < self.quickfix.items.len() {
+                    self.quickfix.current = picker.active;
+                    self.overlay = None;
+                    self.jump_quickfix();
+                }
+            }
+            Some(Overlay::Buffers(buffers)) => {
+                let i = buffers.active;
+                self.select_buffer(i);
+            }
+            Some(Overlay::GrepSub(_)) => {
+                if let Some(Overlay::GrepSub(grep_sub)) = &mut self.overlay {
+                    grep_sub.toggle();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Inserts `text`, a url pasted over a selection turns it into a link and pasted lines
+    /// continue the list the cursor is in.
+    fn paste(&mut self, text: &str) {
+        if self.verbatim() {
+            self.document.paste(text);
+            return;
+        }
+        let text = text.trim_end_matches('\n');
+        let url = text.trim();
+        let is_url = (url.starts_with("http://") || url.starts_with("https://"))
+            && !url.contains(char::is_whitespace);
+        let mut pasted = text.to_owned();
+        if let Some(paragraph) = self.document.active_paragraph_mut() {
+            if let (Some(selection), true) = (paragraph.selection(), is_url) {
+                let link = format!("[{}]({})", paragraph.text[selection.clone()].concat(), url);
+                let link: Characters = link.into();
+                paragraph
+                    .text
+                    .splice(selection.clone(), link.iter().cloned());
+                paragraph.cursor = Some(selection.start + link.len() - 1);
+                paragraph.anchor = None;
+                self.mode = Mode::Normal;
+                return;
+            }
+            pasted = continuation::paste(&paragraph.text_before_cursor(), text);
+        }
+        self.document.paste(&pasted);
+    }
+
+    fn tab(&mut self) {
+        if self.verbatim() {
+            self.document.command(&Command::Insert("\t".into()));
+            return;
+        }
+        if self.complete(&Command::Enter) {
+            return;
+        }
+        let indent = self.indent;
+        if let Some(paragraph) = self.document.active_paragraph_mut() {
+            let text = indent.insert(&paragraph.text_before_cursor());
+            paragraph.command(&Command::Insert(text.into()));
+        }
+    }
+
+    /// A line break at the cursor, `<br>` in tables where a newline would split the row.
+    fn line_break(&self) -> Command {
+        match self.document.elements.get(self.document.active_element) {
+            Some(Element::Table(_)) => Command::Insert("<br>".into()),
+            _ => Command::Insert("\n".into()),
+        }
+    }
+
+    fn newline(&mut self) {
+        if self.verbatim() || self.document.active_table_mut().is_some() {
+            self.document.command(&self.line_break());
+            return;
+        }
+        self.expand_abbreviation();
+        if let Some(paragraph) = self.document.active_paragraph_mut() {
+            let continuation = continuation::continuation(&paragraph.text_before_cursor());
+            let text: Characters = continuation.text.as_str().into();
+            paragraph.replace_before_cursor(continuation.remove, &text);
+            if let Some(cursor) = &mut paragraph.cursor {
+                *cursor -= text.len() - continuation.cursor;
+            }
+        }
+    }
+
+    /// Handles a key of a `gs` jump: the character to jump to, then the label of a target.
+    fn jump_key(&mut self, key: &str) {
+        let (targets, typed) = match (self.jump.take(), key) {
+            (_, "Escape") => (vec![], String::new()),
+            (Some(Jump::Target), key) => (jump::targets(&self.document, key), String::new()),
+            (Some(Jump::Labels { targets, typed }), key) => (targets, format!("{}{}", typed, key)),
+            (None, _) => return,
+        };
+        let candidates: Vec<_> = targets
+            .iter()
+            .filter(|target| target.label.starts_with(&typed))
+            .collect();
+        let mut paragraphs = self.document.paragraphs_mut();
+        for paragraph in &mut paragraphs {
+            paragraph.labels.clear();
+        }
+        match candidates.as_slice() {
+            [] if key != "Escape" => self.show(Status::error("No jump target")),
+            [] => {}
+            [target] => {
+                let (location, cursor) = (target.location, target.cursor);
+                self.document.set_cursor(location, cursor);
+            }
+            candidates => {
+                for target in candidates {
+                    paragraphs[target.paragraph]
+                        .labels
+                        .push((target.cursor, target.label[typed.len()..].to_owned()));
+                }
+                self.jump = Some(Jump::Labels { targets, typed });
+            }
+        }
+    }
+
+    fn select_column(&mut self) {
+        match self.document.active_table_mut() {
+            Some(table) => {
+                table.selected_column = table.active_cell.map(|(x, _)| x);
+                if let Some(paragraph) = self.document.active_paragraph_mut() {
+                    paragraph.anchor = None;
+                }
+            }
+            None => self.show(Status::error("Not in a table")),
+        }
+    }
+
+    /// Grows the selection to the next scope around it, or shrinks it to the one it was
+    /// grown from.
+    fn expand_selection(&mut self, grow: bool) {
+        let paragraph = match self.document.active_paragraph_mut() {
+            Some(paragraph) => paragraph,
+            None => return,
+        };
+        let selection = match paragraph.selection() {
+            Some(selection) => selection,
+            None => return,
+        };
+        let within = |inner: &Range<usize>| {
+            selection.start <= inner.start && inner.end <= selection.end && *inner != selection
+        };
+        let next = if grow {
+            // Selections made since the last expansion start over
+            if !self.expansions.last().map_or(true, within) {
+                self.expansions.clear();
+            }
+            let next = expand::grow(&paragraph.text, selection.clone());
+            if next.is_some() {
+                self.expansions.push(selection);
+            }
+            next
+        } else {
+            while self.expansions.last().map_or(false, |last| !within(last)) {
+                self.expansions.pop();
+            }
+            self.expansions.pop()
+        };
+        match next.filter(|next| !next.is_empty()) {
+            Some(next) => {
+                paragraph.anchor = Some(next.start);
+                paragraph.cursor = Some(next.end - 1);
+            }
+            // Selections span no more than one paragraph
+            None if grow => self.show(Status::error(
+                "The selection can not grow past its paragraph",
+            )),
+            None => {}
+        }
+    }
+
+    /// Yanks the column selected with `ic`, removing it from the table if `delete`.
+    fn yank_column(&mut self, delete: bool) {
+        let table = match self.document.active_table_mut() {
+            Some(table) => table,
+            None => return,
+        };
+        let x = match table.selected_column.take() {
+            Some(x) => x,
+            None => return,
+        };
+        if !delete {
+            self.column = Some(table.column(x));
+            self.rows = None;
+            self.yanked = None;
+        } else if table.width > 1 {
+            self.column = Some(table.remove_column(x));
+            self.rows = None;
+            self.yanked = None;
+            self.edited();
+        } else {
+            self.show(Status::error("Can't delete the only column"));
+        }
+    }
+
+    /// Selects whole rows of the table starting with the one under the cursor, outside of
+    /// tables the line under the cursor.
+    fn select_rows(&mut self) {
+        if let Some(table) = self.document.active_table_mut() {
+            table.row_anchor = table.active_cell.map(|(_, y)| y);
+            if let Some(paragraph) = self.document.active_paragraph_mut() {
+                paragraph.anchor = None;
+            }
+        } else if let Some(paragraph) = self.document.active_paragraph_mut() {
+            let cursor = match paragraph.cursor {
+                Some(cursor) => cursor,
+                None => return,
+            };
+            let text = &paragraph.text;
+            let start = text[..cursor.min(text.len())]
+                .iter()
+                .rposition(|c| c == "\n")
+                .map_or(0, |i| i + 1);
+            let end = text[cursor.min(text.len())..]
+                .iter()
+                .position(|c| c == "\n")
+                .map_or(text.len(), |i| cursor + i);
+            if start < end {
+                paragraph.anchor = Some(start);
+                paragraph.cursor = Some(end - 1);
+            }
+        }
+    }
+
+    /// Replaces the active element with the block `md`, moving the cursor to its start.
+    fn replace_element(&mut self, md: &str) {
+        let element = Document::from_md(md).elements.remove(0);
+        let cell = matches!(
+            element,
+            Element::Table(_) | Element::Definitions(_) | Element::Details(_)
+        )
+        .then_some((0, 0));
+        let active = self.document.active_element;
+        self.document.elements[active] = element;
+        self.document.set_cursor((active, cell), 0);
+        self.edited();
+    }
+
+    /// The target of the wiki link or transclusion under the cursor.
+    fn wiki_link(&self) -> Option<String> {
+        let paragraph = match self.document.elements.get(self.document.active_element)? {
+            Element::Paragraph(paragraph) | Element::Frontmatter(paragraph) => paragraph,
+            Element::Heading(heading) => &heading.content,
+            Element::Definitions(list) => list.item(list.active_item?)?,
+            Element::Details(details) => details.item(details.active_item?)?,
+            Element::Table(_) => return None,
+        };
+        let cursor = paragraph.cursor?.min(paragraph.text.len());
+        let cursor = paragraph.text[..cursor].concat().len();
+        wiki::links(&paragraph.text.concat())
+            .into_iter()
+            .find(|link| link.range.contains(&cursor))
+            .map(|link| link.target)
+    }
+
+    /// Opens the note `target` resolves to by name or alias, a new one if there is none and
+    /// lets pick one if it is ambiguous.
+    fn follow_wiki_link(&mut self, target: &str) {
+        let notes = self.index().resolve(target);
+        match notes.as_slice() {
+            [] => self.edit(Some(target)),
+            [note] => {
+                let note = note.clone();
+                self.edit(Some(&note));
+            }
+            _ => {
+                let title = format!("[[{}]] could link to", target);
+                self.overlay = Some(Overlay::Notes(Picker::new(title, notes)));
+            }
+        }
+    }
+
+    /// Archives `note`, or the open note, hiding it from the start screen, `:find` and
+    /// `:grep` while it can still be opened and linked to, or restores it.
+    fn archive(&mut self, note: Option<&str>, archive: bool) {
+        let note = match note.map(String::from).or_else(|| self.note.clone()) {
+            Some(note) if self.store.load(&note).is_some() => note,
+            Some(note) => {
+                self.notify(Severity::Error, format!("No note {}", note));
+                return;
+            }
+            None => {
+                self.notify(Severity::Error, "The buffer is not a stored note");
+                return;
+            }
+        };
+        if self.store.archived(&note) == archive {
+            let state = if archive { "already" } else { "not" };
+            self.notify(Severity::Error, format!("{} is {} archived", note, state));
+            return;
+        }
+        self.store.set_archived(&note, archive);
+        if let Some(Overlay::Start(start)) = &mut self.overlay {
+            *start = StartScreen::new(self.store.as_ref());
+        }
+        let done = if archive { "Archived" } else { "Unarchived" };
+        self.notify(Severity::Success, format!("{} {}", done, note));
+    }
+
+    /// Renames the open note to `name`, pointing the wiki links in the other notes to it.
+    fn rename(&mut self, name: Option<&str>) {
+        let (from, to) = match (&self.note, name) {
+            (Some(from), Some(to)) => (from.clone(), to.to_owned()),
+            (None, _) => {
+                self.notify(Severity::Error, "The buffer is not a stored note");
+                return;
+            }
+            (_, None) => {
+                self.notify(Severity::Error, "No note name given");
+                return;
+            }
+        };
+        if self.store.load(&to).is_some() {
+            self.notify(Severity::Error, format!("{} already exists", to));
+            return;
+        }
+        let index = wiki::Index::new(self.store.as_ref());
+        let mut updated = 0;
+        for other in self
+            .store
+            .notes()
+            .into_iter()
+            .filter(|other| *other != from)
+        {
+            let md = self.store.load(&other).unwrap_or_default();
+            if let Some(md) = wiki::rename(&md, &from, &to, &index) {
+                self.store.save(&other, &md);
+                updated += 1;
+            }
+        }
+        // Unsaved changes stay unsaved under the new name
+        let stored = self.store.load(&from).unwrap_or_default();
+        self.store.save(&to, &stored);
+        let folds = self.store.folds(&from);
+        self.store.set_folds(&to, &folds);
+        let pinned = self.store.pinned(&from);
+        self.store.set_pinned(&to, pinned);
+        let archived = self.store.archived(&from);
+        self.store.set_archived(&to, archived);
+        self.store.remove_swap(&from);
+        self.store.delete(&from);
+        self.store.purge(&from);
+        self.note = Some(to.clone());
+        self.notify(
+            Severity::Success,
+            format!("Renamed to {}, updated the links in {} notes", to, updated),
+        );
+    }
+
+    /// Moves the section under the cursor into the new note `name`, named after its heading
+    /// if not given, and links to it in its place.
+    fn split_note(&mut self, name: Option<&str>) {
+        let section = match self.document.section() {
+            Some(section) => section,
+            None => {
+                self.show(Status::error("Not in a section under a heading"));
+                return;
+            }
+        };
+        if self.locks(section.clone()) {
+            return;
+        }
+        let name = match (name, &self.document.elements[section.start]) {
+            (Some(name), _) => name.to_owned(),
+            (None, Element::Heading(heading)) => heading.content.text.concat().trim().to_owned(),
+            _ => unreachable!(),
+        };
+        if name.is_empty() {
+            self.show(Status::error("No note name given"));
+            return;
+        }
+        if self.store.load(&name).is_some() {
+            self.show(Status::error(format!("{} already exists", name)));
+            return;
+        }
+        let md = self.document.elements[section.clone()]
+            .iter()
+            .map(Element::to_md)
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        self.store.save(&name, &md);
+        let link = format!("[[{}]]", name);
+        let start = section.start;
+        self.document.elements.splice(
+            section,
+            [Element::Paragraph(Paragraph::from(link.as_str()))],
+        );
+        self.document.set_cursor((start, None), 0);
+        self.edited();
+        self.show(Status::info(format!("Moved the section to {}", name)));
+    }
+
+    /// Turns the selection, or the paragraph or heading under the cursor, into `block`,
+    /// splitting the text around the selection into blocks of its own.
+    fn convert_block(&mut self, block: Block) {
+        let index = self.document.active_element;
+        let (text, selection) = match self.document.elements.get(index) {
+            Some(Element::Paragraph(paragraph)) => (paragraph.text.clone(), paragraph.selection()),
+            Some(Element::Heading(heading)) => (heading.content.text.clone(), None),
+            _ => {
+                self.show(Status::error("Not in a paragraph or heading"));
+                return;
+            }
+        };
+        let selection = selection.unwrap_or(0..text.len());
+        let parse = |md: &str| {
+            if md.trim().is_empty() {
+                vec![]
+            } else {
+                Document::from_md(md).elements
+            }
+        };
+        let before = parse(&text[..selection.start].concat());
+        let converted = parse(&block.wrap(&text[selection.clone()].concat()));
+        let after = parse(&text[selection.end..].concat());
+        let focus = index + before.len();
+        self.document.elements.splice(
+            index..index + 1,
+            before.into_iter().chain(converted).chain(after),
+        );
+        self.document.set_cursor((focus, None), 0);
+        self.edited();
+    }
+
+    /// Yanks the rows selected with `V`, removing them from the table if `delete`.
+    fn yank_rows(&mut self, delete: bool) {
+        let table = match self.document.active_table_mut() {
+            Some(table) => table,
+            None => return,
+        };
+        let rows = match table.selected_rows() {
+            Some(rows) => rows,
+            None => return,
+        };
+        table.row_anchor = None;
+        if !delete {
+            self.rows = Some(table.rows(rows));
+            self.column = None;
+            self.yanked = None;
+        } else if rows.start > 0 {
+            self.rows = Some(table.remove_rows(rows));
+            self.column = None;
+            self.yanked = None;
+            self.edited();
+        } else {
+            self.show(Status::error("Can't delete the header row"));
+        }
+    }
+
+    /// Yanks the text selected in Visual mode, outside of tables or within a cell.
+    fn yank_selection(&mut self) {
+        let paragraph = match self.document.active_paragraph_mut() {
+            Some(paragraph) => paragraph,
+            None => return,
+        };
+        if let Some(selection) = paragraph.selection() {
+            self.yanked = Some(paragraph.text[selection].concat());
+            self.column = None;
+            self.rows = None;
+        }
+    }
+
+    fn paste_column(&mut self) {
+        if let Some(mut text) = self.yanked.clone() {
+            // The text goes into the cell, where a newline would split the row
+            if self.document.active_table_mut().is_some() {
+                text = text.trim_end_matches('\n').replace('\n', "<br>");
+            }
+            if let Some(paragraph) = self.document.active_paragraph_mut() {
+                // Like in vim the text goes after the character under the cursor
+                let len = paragraph.text.len();
+                paragraph.cursor = paragraph.cursor.map(|cursor| (cursor + 1).min(len));
+            }
+            self.document.paste(&text);
+            self.edited();
+            return;
+        }
+        if let Some(rows) = &self.rows {
+            match self.document.active_table_mut() {
+                Some(table) => {
+                    let y = table.active_cell.map_or(table.height, |(_, y)| y + 1);
+                    table.insert_rows(y, rows);
+                    self.edited();
+                }
+                None => self.show(Status::error("Not in a table")),
+            }
+            return;
+        }
+        let column = match &self.column {
+            Some(column) => column,
+            None => {
+                self.show(Status::error("Nothing yanked"));
+                return;
+            }
+        };
+        match self.document.active_table_mut() {
+            Some(table) => {
+                let x = table.active_cell.map_or(table.width, |(x, _)| x + 1);
+                table.insert_column(x, column);
+                self.edited();
+            }
+            None => self.show(Status::error("Not in a table")),
+        }
+    }
+
+    /// Whether typing `chars` doubles a space, which `:set autocorrect` prevents.
+    fn doubles_space(&mut self, chars: &Characters) -> bool {
+        !self.verbatim()
+            && self.autocorrect
+            && !self.literal
+            && chars.concat() == " "
+            && self
+                .document
+                .active_paragraph_mut()
+                .map_or(false, |paragraph| {
+                    autocorrect::doubled_space(&paragraph.text_before_cursor())
+                })
+    }
+
+    /// Whether typed and pasted text is inserted verbatim, without expanding abbreviations,
+    /// autocorrect, completion or continuing lists and indentation.
+    ///
+    /// Turned on with `:set paste`, and while keys arrive too fast to be typed, which
+    /// leaves the options as they were once the input stops.
+    fn verbatim(&self) -> bool {
+        self.paste || self.pasting.is_some()
+    }
+
+    /// Records a key typed in insert mode, detecting text pasted key by key.
+    fn typed(&mut self) {
+        if self.burst.key(js_sys::Date::now()) || self.pasting.is_some() {
+            let link = self.link.clone();
+            self.pasting = Some(Timeout::new(paste::IDLE, move || {
+                link.send_message(vec![Msg::Pasted])
+            }));
+        }
+    }
+
+    /// Expands the word before the cursor if it is an abbreviation, otherwise corrects it
+    /// while `:set autocorrect` is on.
+    fn expand_abbreviation(&mut self) {
+        if mem::take(&mut self.literal) || self.verbatim() {
+            return;
+        }
+        let date = date::now(&self.date_format);
+        if let Some(paragraph) = self.document.active_paragraph_mut() {
+            let word = paragraph.word_before_cursor().concat();
+            let before = paragraph.text_before_cursor();
+            let before = &before[..before.len() - word.len()];
+            let expansion = match word.as_str() {
+                "@date" => Some(date),
+                word => self.abbreviations.get(word).map(String::from).or_else(|| {
+                    self.autocorrect
+                        .then(|| autocorrect::correct(&self.corrections, word, before))
+                        .flatten()
+                }),
+            };
+            if let Some(expansion) = expansion {
+                paragraph.replace_before_cursor(word.graphemes(true).count(), &expansion.into());
+            }
+        }
+    }
+
+    fn link_definitions(&self) -> Vec<String> {
+        links::definitions(&self.document.to_md())
+            .into_iter()
+            .map(|definition| format!("[{}]: {}", definition.id, definition.destination))
+            .collect()
+    }
+
+    /// `:link add <id> <url>`, `:link edit [id] <url>`, `:link inline` and
+    /// `:link reference`, editing without an id changes the definition selected in `:links`.
+    fn link_command(&mut self, argument: &str) {
+        let md = self.document.to_md();
+        let arguments: Vec<_> = argument.split_whitespace().collect();
+        let selected = match &self.overlay {
+            Some(Overlay::Links(links)) => links
+                .selected()
+                .and_then(links::definition)
+                .map(|definition| definition.id),
+            _ => None,
+        };
+        let md = match arguments.as_slice() {
+            ["inline"] => links::to_inline(&md),
+            ["reference"] => links::to_reference(&md),
+            ["add" | "edit", id, url] => links::set_definition(&md, id, url),
+            ["edit", url] if selected.is_some() => {
+                links::set_definition(&md, selected.as_deref().unwrap(), url)
+            }
+            _ => {
+                self.notify(
+                    Severity::Error,
+                    "Usage: :link add|edit <id> <url>, :link inline|reference",
+                );
+                return;
+            }
+        };
+        self.document.replace(&md);
+        let definitions = self.link_definitions();
+        if let Some(Overlay::Links(links)) = &mut self.overlay {
+            links.set_items(definitions);
+        }
+    }
+
+    /// Updates the suggestions for the text left of the cursor.
+    fn refresh_completion(&mut self) {
+        let before_cursor = match self.document.active_paragraph_mut() {
+            Some(paragraph)
+                if self.mode == Mode::Insert && !self.paste && self.pasting.is_none() =>
+            {
+                paragraph.text_before_cursor()
+            }
+            _ => {
+                self.completion = None;
+                return;
+            }
+        };
+        // Notes are only read once a wiki link or citation is being typed
+        let notes = wiki::typed(&before_cursor).map(|_| NoteNames(self.index().names()));
+        let citekeys = citation::typed(&before_cursor).map(|_| {
+            let md = self.document.to_md();
+            let references = citation::load(
+                self.store.as_ref(),
+                citation::bibliography(&md, &self.bibliography),
+            );
+            Citekeys(
+                references
+                    .into_iter()
+                    .map(|reference| reference.key)
+                    .collect(),
+            )
+        });
+        let mut sources: Vec<&dyn Source> = vec![&HeadingAnchors];
+        sources.extend(notes.as_ref().map(|notes| notes as &dyn Source));
+        sources.extend(citekeys.as_ref().map(|citekeys| citekeys as &dyn Source));
+        self.completion = Completion::new(&sources, &before_cursor, &self.document);
+    }
+
+    fn complete(&mut self, command: &Command) -> bool {
+        let completion = match &mut self.completion {
+            Some(completion) => completion,
+            None => return false,
+        };
+        if command != &Command::Enter {
+            return completion.command(command);
+        }
+        if let (Some(item), Some(paragraph)) =
+            (completion.selected(), self.document.active_paragraph_mut())
+        {
+            paragraph.replace_before_cursor(completion.typed, &item.into());
+        }
+        self.completion = None;
+        true
+    }
+
+    /// Moves the cursor to the next match of the last search.
+    fn search_next(&mut self, reverse: bool) {
+        let search = match &self.search {
+            Some(search) => search,
+            None => {
+                self.show(Status::error("No previous search"));
+                return;
+            }
+        };
+        let paragraphs = self.document.paragraphs();
+        let current = paragraphs
+            .iter()
+            .enumerate()
+            .find_map(|(i, (_, paragraph))| Some((i, paragraph.cursor?)))
+            .unwrap_or_default();
+        let matches: Vec<_> = paragraphs
+            .iter()
+            .enumerate()
+            .flat_map(|(i, (location, paragraph))| {
+                search
+                    .matches(&paragraph.text)
+                    .into_iter()
+                    .map(move |range| ((i, range.start), *location))
+            })
+            .collect();
+        let next = if search.backward != reverse {
+            matches
+                .iter()
+                .rev()
+                .find(|(position, _)| *position < current)
+                .or_else(|| matches.last())
+        } else {
+            matches
+                .iter()
+                .find(|(position, _)| *position > current)
+                .or_else(|| matches.first())
+        };
+        self.highlight = true;
+        match next {
+            Some(&((_, cursor), location)) => self.document.set_cursor(location, cursor),
+            None => self.show(Status::error("Pattern not found")),
+        }
+    }
+
+    /// Adds `word` or the word under the cursor to the personal dictionary of the note's
+    /// spell language, or removes it if `good` is `false`.
+    fn spell_good(&mut self, word: Option<&str>, good: bool) {
+        let word = match word.map(String::from).or_else(|| {
+            self.document
+                .active_paragraph_mut()
+                .and_then(|paragraph| paragraph.word_at_cursor())
+        }) {
+            Some(word) => word,
+            None => {
+                self.show(Status::error("No word under the cursor"));
+                return;
+            }
+        };
+        let lang = self.document.spell_lang();
+        let store = self.store.as_mut();
+        self.show(match good {
+            true if spell::add_good(store, &lang, &word) => {
+                Status::info(format!("Added \"{}\" to the {} dictionary", word, lang))
+            }
+            true => Status::error(format!(
+                "\"{}\" is already in the {} dictionary",
+                word, lang
+            )),
+            false if spell::remove_good(store, &lang, &word) => {
+                Status::info(format!("Removed \"{}\" from the {} dictionary", word, lang))
+            }
+            false => Status::error(format!("\"{}\" is not in the {} dictionary", word, lang)),
+        });
+        if let Some(dictionary) = &mut self.dictionary {
+            if dictionary.lang == lang {
+                dictionary.refresh(self.store.as_ref());
+            }
+        }
+    }
+
+    fn search_word(&mut self, backward: bool) {
+        match self
+            .document
+            .active_paragraph_mut()
+            .and_then(|paragraph| paragraph.word_at_cursor())
+        {
+            Some(word) => {
+                self.search = Some(Search::word(&word, backward, self.case));
+                self.search_next(false);
+            }
+            None => self.show(Status::error("No word under the cursor")),
+        }
+    }
+
+    /// Replaces the quickfix list with `quickfix` and shows it, or says it is empty.
+    fn fill_quickfix(&mut self, quickfix: Quickfix) {
+        if quickfix.items.is_empty() {
+            self.notify(Severity::Info, format!("{}: nothing found", quickfix.title));
+        } else {
+            self.overlay = Some(Overlay::Quickfix(quickfix.picker()));
+        }
+        self.quickfix = quickfix;
+    }
+
+    /// `:grep <pattern>` lists the matches in every stored note.
+    fn grep(&mut self, argument: Option<&str>) {
+        let pattern = match argument {
+            Some(pattern) => pattern,
+            None => {
+                self.notify(Severity::Error, "Usage: :grep <pattern>");
+                return;
+            }
+        };
+        let (search, error) = Search::new(pattern, false, self.case);
+        if let Some(error) = error {
+            self.notify(Severity::Error, error);
+        }
+        self.fill_quickfix(Quickfix::grep(
+            &search,
+            self.store.as_ref(),
+            self.search_archive,
+        ));
+    }
+
+    /// Opens the note of the current quickfix item and moves the cursor to it.
+    fn jump_quickfix(&mut self) {
+        let item = match self.quickfix.items.get(self.quickfix.current) {
+            Some(item) => item.clone(),
+            None => return,
+        };
+        self.quickfix.visited = true;
+        if let Some(note) = &item.note {
+            if self.note.as_ref() != Some(note) {
+                self.edit(Some(note));
+            }
+        }
+        if self.note == item.note {
+            // Edits since the list was filled may have removed what the item points to
+            if !self.document.contains(item.location) {
+                self.quickfix.items.remove(self.quickfix.current);
+                self.quickfix.current = self
+                    .quickfix
+                    .current
+                    .min(self.quickfix.items.len().saturating_sub(1));
+                self.quickfix.visited = false;
+                self.show(Status::error("The item is gone, removed it from the list"));
+                return;
+            }
+            self.document.set_cursor(item.location, item.cursor);
+            self.show(Status::info(format!(
+                "({} of {}) {}",
+                self.quickfix.current + 1,
+                self.quickfix.items.len(),
+                item.text
+            )));
+        }
+    }
+
+    /// `:cnext` and `:cprev` jump to the next or previous quickfix item.
+    fn step_quickfix(&mut self, backward: bool) {
+        if self.quickfix.step(backward).is_some() {
+            self.jump_quickfix();
+        } else if self.quickfix.items.is_empty() {
+            self.notify(Severity::Error, "Quickfix list is empty");
+        } else {
+            self.notify(Severity::Error, "No more items");
+        }
+    }
+
+    /// `:grepsub <pattern> <replacement>` previews the replacement in every note.
+    fn grep_sub(&mut self, argument: Option<&str>) {
+        let (pattern, replacement) = match argument {
+            Some(argument) => argument.split_once(' ').unwrap_or((argument, "")),
+            None => {
+                self.show(Status::error("Usage: :grepsub <pattern> <replacement>"));
+                return;
+            }
+        };
+        match Substitute::new(pattern, replacement, self.case) {
+            Ok(substitute) => {
+                let grep_sub = GrepSub::new(substitute, self.store.as_ref(), self.search_archive);
+                if grep_sub.notes.is_empty() {
+                    self.show(Status::error("Pattern not found in any note"));
+                } else {
+                    self.overlay = Some(Overlay::GrepSub(grep_sub));
+                }
+            }
+            Err(error) => self.show(Status::error(error)),
+        }
+    }
+
+    /// Replaces in the notes checked in the `:grepsub` preview.
+    fn apply_grep_sub(&mut self) {
+        let grep_sub = match self.overlay.take() {
+            Some(Overlay::GrepSub(grep_sub)) => grep_sub,
+            overlay => {
+                self.overlay = overlay;
+                self.show(Status::error("No :grepsub to apply"));
+                return;
+            }
+        };
+        let mut count = 0;
+        for note in grep_sub.selected() {
+            if let Some(content) = self.store.load(note) {
+                let replaced = grep_sub.substitute.replace_lines(&content);
+                self.store.save(note, &replaced);
+                count += 1;
+                self.change_buffers(note, &replaced, |md| grep_sub.substitute.replace_lines(md));
+            }
+        }
+        self.show(Status::info(format!("Replaced in {} notes", count)));
+    }
+
+    /// Applies `change` to the buffers of `note` after it was saved as `saved` elsewhere.
+    ///
+    /// Unsaved changes of the buffers are kept.
+    fn change_buffers(&mut self, note: &str, saved: &str, change: impl Fn(&str) -> String) {
+        if self.note.as_deref() == Some(note) {
+            self.document.replace(&change(&self.document.to_md()));
+            self.base = Some(saved.to_owned());
+        }
+        let split = self.split.as_mut().map(|split| &mut split.buffer);
+        for buffer in self.buffers.hidden.iter_mut().chain(split) {
+            if buffer.note.as_deref() == Some(note) {
+                buffer.document.replace(&change(&buffer.document.to_md()));
+                buffer.base = Some(saved.to_owned());
+            }
+        }
+    }
+
+    /// Notifies about the open tasks of all notes that are due today or overdue.
+    fn remind(&mut self) {
+        let today = date::now("%F");
+        let due: Vec<_> = Tasks::new(self.store.as_ref(), &today)
+            .tasks
+            .into_iter()
+            .filter(|task| {
+                !task.done
+                    && task
+                        .due
+                        .as_deref()
+                        .map_or(false, |due| due <= today.as_str())
+            })
+            .collect();
+        if due.is_empty() {
+            return;
+        }
+        if !reminder::remind(&due) {
+            self.notify(
+                Severity::Warning,
+                format!("{} tasks are due, see :tasks", due.len()),
+            );
+        }
+    }
+
+    /// Picks up the card selected on the `:board`, or puts it down moving its task in the note.
+    fn pick_up_card(&mut self) {
+        let board = match &mut self.overlay {
+            Some(Overlay::Board(board)) => board,
+            _ => return,
+        };
+        if !board.carrying {
+            board.carrying = board
+                .columns
+                .get(board.column)
+                .map_or(false, |column| !column.cards.is_empty());
+            return;
+        }
+        board.carrying = false;
+        let (md, before) = (board.md.clone(), self.document.to_md());
+        if md == before {
+            return;
+        }
+        if self.keeps_locks(&before, &md) {
+            self.document.replace(&md);
+            self.edited();
+        } else {
+            // Back to where the cards are in the note
+            self.overlay = Some(Overlay::Board(Board::new(before)));
+        }
+    }
+
+    /// `:timer 25m` counts down in the status line, `:timer stop` cancels the countdown and
+    /// `:timer` tells how much time is left.
+    fn timer(&mut self, argument: Option<&str>) {
+        let length = match argument {
+            Some("stop") => {
+                match self.timer.take() {
+                    Some(_) => self.notify(Severity::Info, "Timer stopped"),
+                    None => self.notify(Severity::Error, "No timer running"),
+                }
+                return;
+            }
+            Some(length) => length,
+            None => {
+                match &self.timer {
+                    Some(timer) => {
+                        let left = timer.remaining(js_sys::Date::now());
+                        self.show(Status::info(format!("{} left", left)));
+                    }
+                    None => self.show(Status::error("Usage: :timer <length>, like 25m")),
+                }
+                return;
+            }
+        };
+        let seconds = match timer::parse(length) {
+            Some(seconds) => seconds,
+            None => {
+                self.show(Status::error("Lengths look like 25m, 90s or 1h30m"));
+                return;
+            }
+        };
+        let task = self.document.active_paragraph_mut().and_then(|paragraph| {
+            let cursor = paragraph.cursor?.min(paragraph.text.len());
+            let start = paragraph.text[..cursor]
+                .iter()
+                .rposition(|c| c == "\n")
+                .map_or(0, |i| i + 1);
+            let end = paragraph.text[cursor..]
+                .iter()
+                .position(|c| c == "\n")
+                .map_or(paragraph.text.len(), |i| cursor + i);
+            let line = paragraph.text[start..end].concat();
+            (!tasks::parse("", &line).is_empty()).then_some(line)
+        });
+        let link = self.link.clone();
+        self.timer = Some(Timer {
+            end: js_sys::Date::now() + seconds as f64 * 1000.,
+            length: length.to_owned(),
+            note: self.note.clone(),
+            task,
+            tick: Interval::new(timer::TICK, move || link.send_message(vec![Msg::TimerTick])),
+        });
+    }
+
+    /// Ends the `:timer`, notifying about it and logging the session in its note.
+    fn finish_timer(&mut self) {
+        let timer = match self.timer.take() {
+            Some(timer) => timer,
+            None => return,
+        };
+        let message = format!("The {} timer is done", timer.length);
+        if !reminder::show(message.clone(), "Time for a break".to_owned()) {
+            self.notify(Severity::Success, message);
+        }
+        let now = date::now("%F %H:%M");
+        let log = |md: &str| timer::log(md, timer.task.as_deref(), &timer.length, &now);
+        if self.note == timer.note {
+            let md = self.document.to_md();
+            if self.keeps_locks(&md, &log(&md)) {
+                let task = timer.task.as_deref();
+                timer::log_document(&mut self.document, task, &timer.length, &now);
+                self.edited();
+            }
+        } else if let Some(note) = &timer.note {
+            if let Some(content) = self.store.load(note) {
+                let logged = log(&content);
+                if !self.keeps_locks(&content, &logged) {
+                    return;
+                }
+                self.store.save(note, &logged);
+                self.change_buffers(note, &logged, log);
+            }
+        }
+    }
+
+    /// Ticks or unticks the task selected in `:tasks` in its note.
+    fn toggle_task(&mut self) {
+        let task = match &self.overlay {
+            Some(Overlay::Tasks(tasks)) => tasks.selected().cloned(),
+            _ => None,
+        };
+        let task = match task {
+            Some(task) => task,
+            None => return,
+        };
+        let content = self.store.load(&task.note);
+        let toggled = content
+            .as_deref()
+            .and_then(|content| tasks::toggle(content, &task));
+        match toggled {
+            Some(toggled)
+                if !self.keeps_locks(content.as_deref().unwrap_or_default(), &toggled) => {}
+            Some(toggled) => {
+                self.store.save(&task.note, &toggled);
+                self.change_buffers(&task.note, &toggled, |md| {
+                    tasks::toggle(md, &task).unwrap_or_else(|| md.to_owned())
+                });
+            }
+            None => self.notify(
+                Severity::Error,
+                format!("The task is no longer in {}", task.note),
+            ),
+        }
+        if let Some(Overlay::Tasks(tasks)) = &mut self.overlay {
+            tasks.refresh(self.store.as_ref());
+        }
+    }
+
+    fn substitute(&mut self, command: &str) {
+        let substitute = match Substitute::parse(command, self.case) {
+            Some(Ok(substitute)) => substitute,
+            Some(Err(error)) => {
+                self.show(Status::error(error));
+                return;
+            }
+            None => return,
+        };
+        if !substitute.all_paragraphs && self.locked() {
+            return;
+        }
+        let count: usize = if substitute.all_paragraphs {
+            // Locked regions are left out
+            let locked = lock::regions(&self.document);
+            self.document
+                .elements
+                .iter_mut()
+                .enumerate()
+                .filter(|(i, _)| !locked.iter().any(|region| region.contains(i)))
+                .flat_map(|(_, element)| element.paragraphs_mut())
+                .map(|paragraph| substitute.apply(paragraph))
+                .sum()
+        } else {
+            self.document
+                .active_paragraph_mut()
+                .map_or(0, |paragraph| substitute.apply(paragraph))
+        };
+        self.show(if count == 0 {
+            Status::error("Pattern not found")
+        } else {
+            Status::info(format!("{} substitutions", count))
+        });
+    }
+
+    fn open_lesson(&mut self, lesson: usize) {
+        self.open(tutor::LESSONS[lesson].md, None, None);
+        self.lesson = Some(lesson);
+    }
+
+    /// Moves on to the next lesson once the current one is completed.
+    fn check_lesson(&mut self) {
+        if let Some(lesson) = self.lesson {
+            if (tutor::LESSONS[lesson].done)(&self.document.to_md()) {
+                self.open_lesson(lesson + 1);
+            }
+        }
+    }
+
+    /// Snapshots the stored notes with `message` for `:commits` and `:checkout`.
+    fn commit(&mut self, message: Option<&str>) {
+        let message = message
+            .map(|message| message.trim().trim_matches('"').trim())
+            .filter(|message| !message.is_empty());
+        let message = match message {
+            Some(message) => message,
+            None => {
+                self.notify(
+                    Severity::Error,
+                    "A commit needs a message, like :commit \"First draft\"",
+                );
+                return;
+            }
+        };
+        let commit = Commit::new(message, js_sys::Date::now(), self.stored_notes());
+        let mut committed = format!("Committed {} with {} notes", commit.id, commit.notes.len());
+        if buffer::modified(&self.document, self.base.as_deref()) {
+            committed.push_str(", leaving out the changes not written yet");
+        }
+        let done = self.link.callback(move |stored| {
+            vec![Msg::Committed(if stored {
+                Ok(committed.clone())
+            } else {
+                Err("The commit could not be stored".to_owned())
+            })]
+        });
+        offline::commit(&commit, done);
+    }
+
+    /// Opens `note`, by default the open one, as it was in the commit `id`, to be written
+    /// with `:w`.
+    fn checkout(&mut self, id: &str, note: Option<&str>, commits: &[Commit]) {
+        let note = match note.map(String::from).or_else(|| self.note.clone()) {
+            Some(note) => note,
+            None => {
+                self.notify(Severity::Error, "No note to check out");
+                return;
+            }
+        };
+        // Opening the note again replaces its buffer
+        if self.note.as_ref() == Some(&note)
+            && buffer::modified(&self.document, self.base.as_deref())
+        {
+            return self.notify(
+                Severity::Error,
+                format!("{} has unsaved changes, write or revert them first", note),
+            );
+        }
+        let commit = match history::find(commits, id) {
+            Ok(commit) => commit,
+            Err(error) => {
+                self.notify(Severity::Error, error);
+                return;
+            }
+        };
+        match commit.note(&note) {
+            Some(content) => {
+                let base = self.store.load(&note);
+                self.open(content, Some(note.clone()), base);
+                self.show(Status::info(format!(
+                    "Checked out {} from {}, write with :w to keep it",
+                    note, commit.id
+                )));
+            }
+            None => self.notify(
+                Severity::Error,
+                format!("{} is not in commit {}", note, commit.id),
+            ),
+        }
+    }
+
+    /// Starts a `:push` with `push` or a `:pull` of the notes to or from the repository of
+    /// `:set remote=`.
+    fn sync(&mut self, push: bool) {
+        if self.remote.is_none() {
+            self.notify(
+                Severity::Error,
+                "No repository, set one with :set remote=github:owner/repo",
+            );
+        } else if self.sync.is_some() {
+            self.notify(Severity::Error, "A :push or :pull is still running");
+        } else if let Some(token) = self.store.setting("remotetoken") {
+            let done = self
+                .link
+                .callback(move |token| vec![Msg::SyncToken(push, token)]);
+            secret::decrypt(&token, done);
+        } else {
+            self.notify(
+                Severity::Error,
+                "No token for the repository, set one with :token",
+            );
+        }
+    }
+
+    /// Sends or fetches the notes that changed on one side since the last sync.
+    ///
+    /// The id of each note in the repository at the last sync is kept to tell which side
+    /// changed. Notes changed on both are left for the user to merge.
+    fn transfer(&mut self, push: bool, token: &str, remote_notes: HashMap<String, String>) {
+        let remote = match &self.remote {
+            Some(remote) => remote.clone(),
+            None => return,
+        };
+        let stored: HashMap<_, _> = self.stored_notes().into_iter().collect();
+        let local: HashMap<_, _> = stored
+            .iter()
+            .map(|(note, content)| (note.clone(), remote::blob_sha(content)))
+            .collect();
+        let synced = |note: &str| self.store.setting(&format!("synced:{}", note));
+        let plan = if push {
+            remote::plan(&local, &remote_notes, synced)
+        } else {
+            remote::plan(&remote_notes, &local, synced)
+        };
+        for (note, sha) in &plan.unchanged {
+            self.store.set_setting(&format!("synced:{}", note), sha);
+        }
+        let mut sync = remote::Progress {
+            push,
+            pending: plan.transfers.len(),
+            conflicts: plan.conflicts,
+            ..remote::Progress::default()
+        };
+        if push {
+            // Taken from the end, so they are uploaded in order
+            sync.uploads = plan
+                .transfers
+                .into_iter()
+                .rev()
+                .map(|note| {
+                    let content = stored.get(&note).cloned().unwrap_or_default();
+                    let sha = remote_notes.get(&note).cloned();
+                    (note, content, sha)
+                })
+                .collect();
+            sync.token = token.to_owned();
+        } else {
+            for note in plan.transfers {
+                let done = self
+                    .link
+                    .callback(|transfer| vec![Msg::Transferred(transfer)]);
+                remote::download(&remote, token, note, done);
+            }
+        }
+        self.sync = Some(sync);
+        self.upload_next();
+        self.transferred(None);
+    }
+
+    /// Starts the next upload of a running `:push`.
+    fn upload_next(&mut self) {
+        let (remote, sync) = match (&self.remote, &mut self.sync) {
+            (Some(remote), Some(sync)) => (remote, sync),
+            _ => return,
+        };
+        if let Some((note, content, sha)) = sync.uploads.pop() {
+            let done = self
+                .link
+                .callback(|transfer| vec![Msg::Transferred(transfer)]);
+            remote::upload(remote, &sync.token, (note, content), sha, done);
+        }
+    }
+
+    /// Records a transfer of the running sync, notifying once all are done.
+    fn transferred(&mut self, transfer: Option<Result<Transfer, String>>) {
+        let finished = transfer.is_some();
+        match transfer {
+            Some(Ok(transfer)) => {
+                self.store
+                    .set_setting(&format!("synced:{}", transfer.note), &transfer.sha);
+                if let Some(content) = &transfer.content {
+                    self.store.save(&transfer.note, content);
+                    // The open note is only updated if it has no changes to lose
+                    if self.note.as_ref() == Some(&transfer.note)
+                        && !buffer::modified(&self.document, self.base.as_deref())
+                    {
+                        self.document.replace(content);
+                        self.base = Some(content.clone());
+                    }
+                }
+                if let Some(sync) = &mut self.sync {
+                    sync.pending -= 1;
+                    sync.transferred += 1;
+                }
+            }
+            Some(Err(error)) => {
+                if let Some(sync) = &mut self.sync {
+                    sync.pending -= 1;
+                    sync.errors.push(error);
+                }
+            }
+            None => {}
+        }
+        if finished {
+            self.upload_next();
+        }
+        if let Some(sync) = self.sync.take() {
+            if sync.pending > 0 {
+                self.sync = Some(sync);
+            } else if sync.conflicts.is_empty() && sync.errors.is_empty() {
+                self.notify(Severity::Success, sync.summary());
+            } else {
+                self.notify(Severity::Error, sync.summary());
+            }
+        }
+    }
+
+    /// All stored notes with their content.
+    fn stored_notes(&self) -> Vec<(String, String)> {
+        self.store
+            .notes()
+            .into_iter()
+            .filter_map(|note| {
+                let content = self.store.load(&note)?;
+                Some((note, content))
+            })
+            .collect()
+    }
+
+    /// Whether `:set suggest` is on, which an edit that can not be recorded as a suggested
+    /// change is then refused for in the status line.
+    fn unsuggested(&mut self) -> bool {
+        if self.suggesting {
+            self.show(Status::error(
+                "Only typing, deleting and pasting text are suggested, :set nosuggest to edit",
+            ));
+        }
+        self.suggesting
+    }
+
+    /// Records typing or deleting at the cursor as a CriticMarkup change while
+    /// `:set suggest` is on, `false` if it is made as usual, like typing in an addition.
+    /// Deletes that can not be recorded, like joining paragraphs, are refused.
+    fn suggest(&mut self, command: &Command) -> bool {
+        let paragraph = match self.document.active_paragraph_mut() {
+            Some(paragraph) => paragraph,
+            None => return false,
+        };
+        let cursor = match paragraph.cursor {
+            Some(cursor) => cursor.min(paragraph.text.len()),
+            None => return false,
+        };
+        let index = document::grapheme_index(&paragraph.text);
+        let changes: Vec<_> = critic::changes(&paragraph.text.concat())
+            .into_iter()
+            .map(|change| {
+                let range = index(change.range.start)..index(change.range.end);
+                (change.kind, range)
+            })
+            .collect();
+        let in_addition = |range: Range<usize>| {
+            changes.iter().any(|(kind, change)| {
+                *kind == critic::Kind::Addition
+                    && change.start + critic::DELIMITER <= range.start
+                    && range.end + critic::DELIMITER <= change.end
+            })
+        };
+        let graphemes =
+            |text: &str| -> Vec<String> { text.graphemes(true).map(String::from).collect() };
+        match command {
+            Command::Insert(chars) => {
+                if in_addition(cursor..cursor) {
+                    return false;
+                }
+                let mut added = graphemes("{++");
+                added.extend(chars.iter().cloned());
+                added.extend(graphemes("++}"));
+                paragraph.text.splice(cursor..cursor, added);
+                paragraph.cursor = Some(cursor + critic::DELIMITER + chars.len());
+            }
+            Command::Delete(_) => {
+                // What the command would delete, found by running it on a copy
+                let mut probe = Paragraph {
+                    text: paragraph.text.clone(),
+                    cursor: Some(cursor),
+                    anchor: paragraph.anchor,
+                    labels: vec![],
+                };
+                let selection = paragraph.anchor.map_or(false, |anchor| anchor != cursor);
+                if !probe.command(command) {
+                    // Joining with the element next to it is no change within the paragraph
+                    return self.unsuggested();
+                }
+                let start = probe.cursor.unwrap_or(cursor);
+                let end = start + paragraph.text.len() - probe.text.len();
+                if start == end || in_addition(start..end) {
+                    return false;
+                }
+                // Changes are not nested, the cursor moves over them instead
+                let overlapped = changes
+                    .iter()
+                    .find(|(_, change)| change.start < end && start < change.end);
+                if let Some((_, change)) = overlapped {
+                    if selection {
+                        return self.unsuggested();
+                    }
+                    paragraph.anchor = None;
+                    paragraph.cursor = Some(start.min(change.start));
+                    return true;
+                }
+                paragraph.anchor = None;
+                let removed: Vec<_> = paragraph.text.drain(start..end).collect();
+                let before = changes
+                    .iter()
+                    .find(|(kind, change)| *kind == critic::Kind::Deletion && change.end == start);
+                let after = changes
+                    .iter()
+                    .find(|(kind, change)| *kind == critic::Kind::Deletion && change.start == end);
+                match (before, after) {
+                    (Some((_, before)), _) => {
+                        let at = before.end - critic::DELIMITER;
+                        paragraph.text.splice(at..at, removed);
+                        paragraph.cursor = Some(before.start);
+                    }
+                    (None, Some(_)) => {
+                        let at = start + critic::DELIMITER;
+                        paragraph.text.splice(at..at, removed);
+                        paragraph.cursor = Some(start);
+                    }
+                    (None, None) => {
+                        let mut deleted = graphemes("{--");
+                        deleted.extend(removed);
+                        deleted.extend(graphemes("--}"));
+                        paragraph.text.splice(start..start, deleted);
+                        paragraph.cursor = Some(start);
+                    }
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// `:accept` and `:reject` the suggested change under the cursor, or with `all` every
+    /// change in the note.
+    fn review(&mut self, accept: bool, argument: Option<&str>) {
+        let verb = if accept { "Accepted" } else { "Rejected" };
+        if argument == Some("all") {
+            let (md, count) = critic::resolve_all(&self.document.to_md(), accept);
+            if count == 0 {
+                return self.notify(Severity::Info, "The note has no suggested changes");
+            }
+            if !self.keeps_locks(&self.document.to_md(), &md) {
+                return;
+            }
+            self.document.replace(&md);
+            self.edited();
+            return self.notify(Severity::Success, format!("{} {} changes", verb, count));
+        } else if argument.is_some() {
+            return self.notify(
+                Severity::Error,
+                "Give all or nothing for the change under the cursor",
+            );
+        }
+        if self.locked() {
+            return;
+        }
+        let resolved = self.document.active_paragraph_mut().and_then(|paragraph| {
+            let text = paragraph.text.concat();
+            let cursor = paragraph.cursor?.min(paragraph.text.len());
+            let cursor = paragraph.text[..cursor].concat().len();
+            let change = critic::changes(&text)
+                .into_iter()
+                .find(|change| change.range.start <= cursor && cursor < change.range.end)?;
+            let resolved = format!(
+                "{}{}{}",
+                &text[..change.range.start],
+                change.resolve(&text, accept),
+                &text[change.range.end..]
+            );
+            let start = text[..change.range.start].graphemes(true).count();
+            *paragraph = Paragraph::from(resolved.as_str());
+            paragraph.cursor = Some(start);
+            Some(())
+        });
+        if resolved.is_none() {
+            return self.notify(Severity::Error, "No suggested change under the cursor");
+        }
+        self.edited();
+        self.show(Status::info(format!("{} the change", verb)));
+    }
+
+    fn export(&mut self, format: Option<&str>) {
+        let name = self.note.as_deref().unwrap_or("untitled");
+        match format {
+            Some("opml") => export::download(
+                &format!("{}.opml", name),
+                "text/x-opml",
+                opml::md_to_opml(name, &self.document.to_md()).as_bytes(),
+            ),
+            Some(format @ ("md" | "html")) => {
+                let md = self.document.to_md();
+                let references = citation::load(
+                    self.store.as_ref(),
+                    citation::bibliography(&md, &self.bibliography),
+                );
+                let md = citation::resolve(&md, &references);
+                if format == "md" {
+                    export::download(&format!("{}.md", name), "text/markdown", md.as_bytes());
+                } else {
+                    let body = frontmatter::split(&md).1;
+                    let html = publish::html(name, body, self.document.flavor);
+                    export::download(&format!("{}.html", name), "text/html", html.as_bytes());
+                }
+            }
+            Some("vault") => {
+                let notes = self.stored_notes();
+                let count = notes.len();
+                let done = self
+                    .link
+                    .callback(move |attachments| vec![Msg::VaultExported(count, attachments)]);
+                export::vault(notes, done);
+            }
+            _ => self.notify(
+                Severity::Error,
+                "Unknown export format, use md, html, opml or vault",
+            ),
+        }
+    }
+
+    /// Uploads the open note without its frontmatter to the service of `:set publish=`,
+    /// rendered to HTML with `html`.
+    fn publish(&mut self, format: Option<&str>) {
+        let note = match &self.note {
+            Some(note) => note.clone(),
+            None => return self.notify(Severity::Error, "The buffer is not a stored note"),
+        };
+        let md = self.document.to_md();
+        let body = frontmatter::split(&md).1;
+        // Gists take no folders in file names
+        let file = note.replace('/', "-");
+        let (file, content) = match format {
+            None => (format!("{}.md", file), body.to_owned()),
+            Some("html") => (
+                format!("{}.html", file),
+                publish::html(&note, body, self.document.flavor),
+            ),
+            Some(_) => return self.notify(Severity::Error, "Unknown format, use :publish html"),
+        };
+        let done = self
+            .link
+            .callback(move |url| vec![Msg::Published(note.clone(), url)]);
+        let token = self.store.setting("remotetoken");
+        publish::publish(&self.publish, token, file, content, done);
+        self.show(Status::info("Publishing…"));
+    }
+
+    /// `:feed add <url>` and `:feed remove <url>` change the feeds, `:feed sync [digest]`
+    /// saves their new entries.
+    fn feed(&mut self, argument: Option<&str>) {
+        let (action, url) = match argument {
+            Some(argument) => argument.split_once(' ').unwrap_or((argument, "")),
+            None => ("", ""),
+        };
+        let url = url.trim();
+        let mut feeds = feed::feeds(self.store.as_ref());
+        match action {
+            "add" if url.starts_with("https://") || url.starts_with("http://") => {
+                if feeds.iter().any(|feed| feed == url) {
+                    return self.notify(Severity::Info, format!("{} was added before", url));
+                }
+                feeds.push(url.to_owned());
+                feed::set_feeds(self.store.as_mut(), &feeds);
+                self.notify(Severity::Success, format!("Added the feed {}", url));
+            }
+            "add" => self.notify(
+                Severity::Error,
+                "Give the url of the feed, like :feed add https://…",
+            ),
+            "remove" if feeds.iter().any(|feed| feed == url) => {
+                feeds.retain(|feed| feed != url);
+                feed::set_feeds(self.store.as_mut(), &feeds);
+                self.notify(Severity::Success, format!("Removed the feed {}", url));
+            }
+            "remove" => self.notify(Severity::Error, format!("No feed {}", url)),
+            "sync" if feeds.is_empty() => {
+                self.notify(Severity::Error, "No feeds, add one with :feed add <url>")
+            }
+            "sync" if url.is_empty() || url == "digest" => {
+                let digest = url == "digest";
+                for url in feeds {
+                    let fetched = url.clone();
+                    let done = self.link.callback(move |feed| {
+                        vec![Msg::FeedFetched(fetched.clone(), digest, feed)]
+                    });
+                    clip::fetch(&url, self.clip_proxy.as_deref(), done);
+                }
+                self.show(Status::info("Syncing the feeds…"));
+            }
+            "" if !feeds.is_empty() => self.notify(Severity::Info, feeds.join(", ")),
+            "" => self.notify(Severity::Info, "No feeds, add one with :feed add <url>"),
+            _ => self.notify(
+                Severity::Error,
+                "Usage: :feed add <url>, :feed remove <url> or :feed sync [digest]",
+            ),
+        }
+    }
+
+    /// Adds `url` to the `published` list in the frontmatter of `note`.
+    fn record_published(&mut self, note: &str, url: &str) {
+        let append = |frontmatter: &str| {
+            let mut urls = frontmatter::list(frontmatter, "published");
+            urls.push(url.to_owned());
+            frontmatter::set(frontmatter, "published", &format!("[{}]", urls.join(", ")))
+        };
+        if self.note.as_deref() == Some(note) {
+            let frontmatter = append(&self.document.frontmatter().unwrap_or_default());
+            self.document.replace_frontmatter(&frontmatter);
+            self.edited();
+        } else if let Some(content) = self.store.load(note) {
+            let (frontmatter, body) = frontmatter::split(&content);
+            let content = format!(
+                "---\n{}\n---\n{}",
+                append(frontmatter.unwrap_or_default()),
+                body
+            );
+            self.store.save(note, &content);
+        }
+    }
+
+    /// Stores the attachments among the `files` of a vault, then imports its notes.
+    ///
+    /// An attachment whose name is taken by a different file is stored under a free name,
+    /// which the links in the imported notes are changed to.
+    fn import_vault(&mut self, files: Vec<(String, Vec<u8>)>) {
+        let (notes, attachments) = vault::split(files);
+        self.vault_import = Some(vault::Import::new(notes, attachments.len()));
+        if attachments.is_empty() {
+            return self.continue_import();
+        }
+        for (name, content) in attachments {
+            let original = name.clone();
+            let done = self
+                .link
+                .callback(move |stored| vec![Msg::VaultAttachment(original.clone(), stored)]);
+            attachment::keep(&name, content, done);
+        }
+    }
+
+    /// Imports notes of the vault until one whose name is taken, which is asked about.
+    fn continue_import(&mut self) {
+        let import = match &mut self.vault_import {
+            Some(import) => import,
+            None => return,
+        };
+        match import.advance(self.store.as_mut()) {
+            Some(note) => {
+                let choices = vault::CHOICES
+                    .iter()
+                    .map(|(choice, ..)| String::from(*choice));
+                self.overlay = Some(Overlay::Collision(Picker::new(
+                    format!("{} already exists", note),
+                    choices.collect(),
+                )));
+            }
+            None => {
+                self.overlay = None;
+                let summary = import.summary();
+                self.vault_import = None;
+                self.notify(Severity::Success, summary);
+            }
+        }
+    }
+
+    /// Moves the open note to the trash, opening the alternate buffer or an empty unnamed
+    /// one.
+    ///
+    /// Notes with unsaved changes or also open in the split pane are kept.
+    fn delete(&mut self) {
+        let note = match &self.note {
+            Some(note) => note.clone(),
+            None => return self.notify(Severity::Error, "The buffer is not a stored note"),
+        };
+        if buffer::modified(&self.document, self.base.as_deref()) {
+            return self.notify(
+                Severity::Error,
+                format!("{} has unsaved changes, write or revert them first", note),
+            );
+        }
+        if let Some(split) = &self.split {
+            if split.buffer.note.as_ref() == Some(&note) {
+                return self.notify(
+                    Severity::Error,
+                    format!("{} is also open in the split, :only closes it", note),
+                );
+            }
+        }
+        self.note = None;
+        self.store.delete(&note);
+        self.open_alternate();
+        self.notify(Severity::Success, format!("Moved {} to the trash", note));
+    }
+
+    /// Fills the empty buffer with the template `name`, or a new one if it has content.
+    fn use_template(&mut self, name: &str) {
+        let md = match template::get(name, self.store.as_ref()) {
+            Some(md) => md,
+            None => {
+                self.notify(Severity::Error, format!("No template {}", name));
+                return;
+            }
+        };
+        let title = self.note.as_deref().unwrap_or("Untitled");
+        let md = template::expand(&md, &date::now(&self.date_format), title);
+        if self.document.to_md().trim().is_empty() {
+            self.replace_buffer(Buffer {
+                document: Document::from_md(&md),
+                note: self.note.clone(),
+                base: self.base.clone(),
+            });
+            self.edited();
+        } else {
+            self.open(&md, None, None);
+        }
+    }
+
+    /// The note given as `argument` or the one selected in the trash.
+    fn trashed_note(&self, argument: Option<&str>) -> Option<String> {
+        match (argument, &self.overlay) {
+            (Some(note), _) => Some(note.to_owned()),
+            (None, Some(Overlay::Trash(trash))) => trash.selected().map(String::from),
+            _ => None,
+        }
+    }
+
+    fn refresh_trash(&mut self) {
+        if let Some(Overlay::Trash(trash)) = &mut self.overlay {
+            trash.set_items(self.store.trash());
+        }
+    }
+
+    fn restore(&mut self, argument: Option<&str>) {
+        match self.trashed_note(argument) {
+            Some(note) if self.store.load(&note).is_some() => self.notify(
+                Severity::Error,
+                format!("A note named {} already exists", note),
+            ),
+            Some(note) => {
+                self.store.restore(&note);
+                self.notify(Severity::Success, format!("Restored {}", note));
+            }
+            None => self.notify(Severity::Error, "No note to restore"),
+        }
+        self.refresh_trash();
+    }
+
+    fn purge(&mut self, argument: Option<&str>) {
+        if let Some(note) = self.trashed_note(argument) {
+            self.store.purge(&note);
+        } else {
+            self.notify(Severity::Error, "No note to purge");
+        }
+        self.refresh_trash();
+    }
+
+    fn set(&mut self, command: &str) {
+        for command in options(command).iter().map(String::as_str) {
+            if let Some((name, value)) = command.split_once('=') {
+                match name {
+                    "font" => {
+                        self.font = value.to_owned();
+                        let ligatures = self.store.setting(&format!("ligatures:{}", value));
+                        self.ligatures = ligatures.as_deref() == Some("true");
+                    }
+                    "dateformat" => self.date_format = value.to_owned(),
+                    "lint" => {
+                        let rules: Option<Vec<_>> = value.split(',').map(Rule::from_name).collect();
+                        match rules {
+                            Some(rules) => self.lint.rules = rules,
+                            None => self.show(Status::error(
+                                "Unknown lint rule, use passive, long, repeated or weasel",
+                            )),
+                        }
+                    }
+                    "emphasis" => match value {
+                        "*" | "_" => self.format.emphasis = value.chars().next().unwrap(),
+                        _ => self.show(Status::error("emphasis must be * or _")),
+                    },
+                    "bullet" => match value {
+                        "-" | "*" | "+" => self.format.bullet = value.chars().next().unwrap(),
+                        _ => self.show(Status::error("bullet must be -, * or +")),
+                    },
+                    "textwidth" => match value.parse() {
+                        Ok(width) => self.format.text_width = width,
+                        _ => self.show(Status::error("textwidth must be a number")),
+                    },
+                    "attachmentlimit" => match value.parse() {
+                        Ok(limit) => {
+                            self.attachment_limit = limit;
+                            self.store.set_setting("attachmentlimit", value);
+                        }
+                        _ => self.show(Status::error("attachmentlimit must be a number of KiB")),
+                    },
+                    "maxchars" | "maxelements" => match value.parse() {
+                        Ok(limit) => {
+                            if name == "maxchars" {
+                                self.limits.characters = limit;
+                            } else {
+                                self.limits.elements = limit;
+                            }
+                            self.store.set_setting(name, value);
+                            self.check_limits();
+                        }
+                        _ => self.show(Status::error(format!("{} must be a number", name))),
+                    },
+                    "sentencelength" => match value.parse() {
+                        Ok(length) => self.lint.sentence_length = length,
+                        _ => self.show(Status::error("sentencelength must be a number")),
+                    },
+                    "spelllang" | "spl" => self.document.set_frontmatter("spelllang", value),
+                    "flavor" => match Flavor::from_name(value) {
+                        Some(flavor) => self.document.set_flavor(flavor),
+                        None => self.show(Status::error(
+                            "Unknown flavor, use commonmark, gfm or pandoc-lite",
+                        )),
+                    },
+                    "tabwidth" | "tabstop" | "ts" => match value.parse() {
+                        Ok(width) if width > 0 => self.indent.tab_width = width,
+                        _ => self.show(Status::error("tabwidth must be a positive number")),
+                    },
+                    "remote" => match Remote::parse(value) {
+                        Some(remote) => {
+                            self.remote = Some(remote);
+                            self.store.set_setting("remote", value);
+                        }
+                        None => self.show(Status::error(
+                            "remote must be github:owner/repo or the https url of a repository",
+                        )),
+                    },
+                    "bibliography" if !value.is_empty() => {
+                        self.bibliography = value.to_owned();
+                        self.store.set_setting("bibliography", value);
+                    }
+                    "bibliography" => {
+                        self.show(Status::error("bibliography must be the name of a note"))
+                    }
+                    "spellurl" => {
+                        self.spell_url = Some(value.to_owned()).filter(|url| !url.is_empty());
+                        self.store.set_setting("spellurl", value);
+                        // A dictionary that was missing is looked for again
+                        self.dictionary_requested = None;
+                    }
+                    "clipproxy" => {
+                        self.clip_proxy = Some(value.to_owned()).filter(|proxy| !proxy.is_empty());
+                        self.store.set_setting("clipproxy", value);
+                    }
+                    "webhook" if value.is_empty() => {
+                        self.webhook = None;
+                        self.store.set_setting("webhook", "");
+                    }
+                    "webhook" if value.starts_with("https://") || value.starts_with("http://") => {
+                        self.webhook = Some(value.to_owned());
+                        self.store.set_setting("webhook", value);
+                    }
+                    "webhook" => self.show(Status::error("webhook must be the url of a webhook")),
+                    "publish" => match Service::from_name(value) {
+                        Some(service) => {
+                            self.publish = service;
+                            self.store.set_setting("publish", value);
+                        }
+                        None => self.show(Status::error(
+                            "publish must be gist or the url of a paste service",
+                        )),
+                    },
+                    "backup" => match Schedule::from_name(value) {
+                        Some(schedule) => {
+                            self.backup = schedule;
+                            self.store.set_setting("backup", &schedule.name());
+                            self.schedule_backup();
+                        }
+                        None => self.show(Status::error(format!(
+                            "backup must be off, save or a number of minutes up to {}",
+                            backup::MAX_MINUTES
+                        ))),
+                    },
+                    "leader" => {
+                        Rc::make_mut(&mut self.keymap).leader = value.to_owned();
+                        self.store.set_setting("leader", value);
+                    }
+                    "timeoutlen" | "tm" => match value.parse() {
+                        Ok(timeout_len) => self.timeout_len = timeout_len,
+                        _ => self.show(Status::error("timeoutlen must be a number")),
+                    },
+                    "layout" => match Layout::from_name(value) {
+                        Some(layout) => {
+                            let leader = self.keymap.leader.clone();
+                            self.keymap = Rc::new(Keymap::new(layout));
+                            Rc::make_mut(&mut self.keymap).leader = leader;
+                            self.store.set_setting("layout", layout.name());
+                        }
+                        None => self.show(Status::error(
+                            "Unknown layout, use qwerty, colemak or position",
+                        )),
+                    },
+                    "contrast" => {
+                        match value {
+                            "high" => self.theme = Theme::HighContrast,
+                            "normal" => self.theme = Theme::Dark,
+                            _ => {
+                                self.show(Status::error("Unknown contrast, use high or normal"));
+                                continue;
+                            }
+                        }
+                        self.store.set_setting("contrast", value);
+                    }
+                    "theme" => {
+                        if let Some(theme) = Theme::from_name(value) {
+                            self.theme = theme
+                        }
+                    }
+                    _ => self.show(Status::error(format!("Unknown option {}", name))),
+                }
+            } else {
+                // `name` enables, `noname` disables and `name!` toggles an option
+                let (name, value) = match command.strip_suffix('!') {
+                    Some(name) => (name, None),
+                    None => match command.strip_prefix("no") {
+                        Some(name) => (name, Some(false)),
+                        None => (command, Some(true)),
+                    },
+                };
+                let option = match name {
+                    "sidenotes" => &mut self.sidenotes,
+                    "showcomments" => &mut self.show_comments,
+                    "headingnumbers" => &mut self.heading_numbers,
+                    "fmtonsave" => &mut self.format_on_save,
+                    "ligatures" | "lig" => &mut self.ligatures,
+                    "ignorecase" | "ic" => &mut self.case.ignore,
+                    "smartcase" | "scs" => &mut self.case.smart,
+                    "expandtab" | "et" => &mut self.indent.expand_tab,
+                    "lint" => &mut self.show_lint,
+                    "autocorrect" | "ac" => &mut self.autocorrect,
+                    "paste" => &mut self.paste,
+                    "suggest" => &mut self.suggesting,
+                    "reducedmotion" | "rm" => &mut self.reduced_motion,
+                    "autosave" | "as" => &mut self.autosave,
+                    "blurnormal" => &mut self.blur_normal,
+                    "confirmclose" => &mut self.confirm_close,
+                    "unsavedicon" => &mut self.unsaved_icon,
+                    "reminders" => &mut self.reminders,
+                    "minimap" => &mut self.minimap,
+                    "breadcrumb" => &mut self.breadcrumb,
+                    "searcharchive" => &mut self.search_archive,
+                    "spell" => &mut self.spell,
+                    _ => {
+                        self.show(Status::error(format!("Unknown option {}", name)));
+                        continue;
+                    }
+                };
+                *option = value.unwrap_or(!*option);
+                let option = option.to_string();
                 match name {
-                    "font" => self.font = value.to_owned(),
-                    _ => todo!(),
+                    "reducedmotion" | "rm" => {
+                        self.store.set_setting("reducedmotion", &option);
+                        a11y::reduce_motion(self.reduced_motion);
+                    }
+                    "autosave" | "as" => self.store.set_setting("autosave", &option),
+                    "blurnormal" => self.store.set_setting("blurnormal", &option),
+                    "confirmclose" => self.store.set_setting("confirmclose", &option),
+                    "unsavedicon" => self.store.set_setting("unsavedicon", &option),
+                    "minimap" => self.store.set_setting("minimap", &option),
+                    "breadcrumb" => self.store.set_setting("breadcrumb", &option),
+                    "searcharchive" => self.store.set_setting("searcharchive", &option),
+                    "spell" => {
+                        self.store.set_setting("spell", &option);
+                        self.dictionary_requested = None;
+                    }
+                    "reminders" => {
+                        self.store.set_setting("reminders", &option);
+                        if self.reminders {
+                            self.remind();
+                        }
+                    }
+                    "ligatures" | "lig" => {
+                        let key = format!("ligatures:{}", self.font);
+                        self.store.set_setting(&key, &option)
+                    }
+                    _ => {}
                 }
             }
         }
@@ -419,18 +3967,56 @@ impl Component for Model {
     type Message = Vec<Msg>;
     type Properties = ();
 
-    fn create(_props: &yew::Context<Model>) -> Self {
-        let options = Options::ENABLE_TABLES | Options::ENABLE_TASKLISTS;
-        let mut parser = Parser::new_ext(
-            "
-| Hello | xD hasd asd a mosad rext heer | xD |
-| ----- | -- | -- |
-| test  | 1paragr🌷🎁💩😜👍🏳️‍🌈ap| 1  |
-            ",
-            options,
-        );
-        let mut document = Document::parse_from_md(&mut parser); //.into_offset_iter();
-        document.command(&Command::CursorEnterH(false));
+    fn create(ctx: &yew::Context<Model>) -> Self {
+        let store: Box<dyn Storage> = Box::new(LocalStorage::default());
+        let user_commands = UserCommands::load(store.as_ref());
+        let abbreviations = Replacements::load(store.as_ref(), ABBREVIATIONS, &[]);
+        let corrections = Replacements::load(store.as_ref(), CORRECTIONS, DEFAULT_CORRECTIONS);
+        let theme = match store.setting("contrast").as_deref() {
+            Some("high") => Theme::HighContrast,
+            _ => Theme::default(),
+        };
+        let layout = store
+            .setting("layout")
+            .and_then(|layout| Layout::from_name(&layout))
+            .unwrap_or_default();
+        let mut keymap = Keymap::new(layout);
+        if let Some(leader) = store.setting("leader") {
+            keymap.leader = leader;
+        }
+        let reduced_motion = store
+            .setting("reducedmotion")
+            .map_or_else(a11y::prefers_reduced_motion, |value| value == "true");
+        a11y::reduce_motion(reduced_motion);
+        let panes = Panes::from_setting(store.setting("panewidth"));
+        let ligatures = store.setting("ligatures:mononoki").as_deref() == Some("true");
+        let autosave = store.setting("autosave").as_deref() == Some("true");
+        let blur_normal = store.setting("blurnormal").as_deref() == Some("true");
+        let unsaved_icon = store.setting("unsavedicon").as_deref() == Some("true");
+        let confirm_close = store.setting("confirmclose").as_deref() != Some("false");
+        let reminders = store.setting("reminders").as_deref() == Some("true");
+        let minimap = store.setting("minimap").as_deref() == Some("true");
+        let breadcrumb = store.setting("breadcrumb").as_deref() != Some("false");
+        let search_archive = store.setting("searcharchive").as_deref() == Some("true");
+        let backup = store
+            .setting("backup")
+            .as_deref()
+            .and_then(Schedule::from_name)
+            .unwrap_or(Schedule::Off);
+        let limit = |name, default| {
+            store
+                .setting(name)
+                .and_then(|limit| limit.parse().ok())
+                .unwrap_or(default)
+        };
+        let limits = Limits {
+            characters: limit("maxchars", limits::DEFAULT_CHARACTERS),
+            elements: limit("maxelements", limits::DEFAULT_ELEMENTS),
+        };
+        let attachment_limit = store
+            .setting("attachmentlimit")
+            .and_then(|limit| limit.parse().ok())
+            .unwrap_or(attachment::DEFAULT_LIMIT);
         let mut s = Self {
             cursor_position: (0, 0),
             node_ref: NodeRef::default(),
@@ -449,10 +4035,106 @@ h\nThissiaodajdnkajbdsklajbdkajbdkjlasbdlkjabdwhpdajnlvnoampmönöaiofoaödnlaks
                 .lines()
                 .map(|s| s.into())
                 .collect(),
-                command: TextLine::default(),
+                command: CommandLine::default(),
             mode: Mode::Normal,
             font: "mononoki".to_string(), 
-            document
+            ligatures,
+            theme,
+            reduced_motion,
+            document: Document::from_md(""),
+            note: None,
+            base: None,
+            store,
+            buffers: Buffers::default(),
+            split: None,
+            panes,
+            overlay: None,
+            link: ctx.link().clone(),
+            tab: Uuid::new_v4().to_string(),
+            heartbeat: {
+                let link = ctx.link().clone();
+                Interval::new(swap::HEARTBEAT, move || {
+                    link.send_message(vec![Msg::Heartbeat])
+                })
+            },
+            keymap: Rc::new(keymap),
+            lesson: None,
+            date_format: date::ISO.to_owned(),
+            sidenotes: false,
+            show_comments: false,
+            heading_numbers: false,
+            format: Format::default(),
+            format_on_save: false,
+            completion: None,
+            search: None,
+            highlight: false,
+            quickfix: Quickfix::default(),
+            status: None,
+            case: Case::default(),
+            indent: Indent::default(),
+            edits: 0,
+            pending: String::new(),
+            pending_timeout: None,
+            timeout_len: which_key::TIMEOUT,
+            lint: Lint::default(),
+            show_lint: false,
+            user_commands,
+            abbreviations,
+            literal: false,
+            corrections,
+            autocorrect: false,
+            paste: false,
+            suggesting: false,
+            burst: paste::Burst::default(),
+            pasting: None,
+            jump: None,
+            normal_once: false,
+            notifications: Notifications::default(),
+            column: None,
+            rows: None,
+            yanked: None,
+            autosave,
+            blur_normal,
+            blur_scroll: None,
+            confirm_close,
+            unsaved_icon,
+            unsaved: Rc::default(),
+            render_cache: Rc::default(),
+            attachment_limit,
+            attachments: HashMap::new(),
+            views: Views::default(),
+            reminders,
+            timer: None,
+            minimap,
+            breadcrumb,
+            expansions: vec![],
+            limits,
+            degraded: false,
+            search_archive,
+            spell: store.setting("spell").as_deref() == Some("true"),
+            spell_url: store.setting("spellurl").filter(|url| !url.is_empty()),
+            dictionary: None,
+            dictionary_requested: None,
+            vault_import: None,
+            remote: store
+                .setting("remote")
+                .as_deref()
+                .and_then(Remote::parse),
+            sync: None,
+            bibliography: store
+                .setting("bibliography")
+                .filter(|note| !note.is_empty())
+                .unwrap_or_else(|| "Bibliography".to_owned()),
+            clip_proxy: store.setting("clipproxy").filter(|proxy| !proxy.is_empty()),
+            webhook: store.setting("webhook").filter(|url| !url.is_empty()),
+            unconfirmed_webhook: None,
+            publish: store
+                .setting("publish")
+                .as_deref()
+                .and_then(Service::from_name)
+                .unwrap_or(Service::Gist),
+            backup,
+            backup_interval: None,
             // document :Document{
             //     elements: vec![Element::Table(Table {
             //         cells: HashMap::from_iter(
@@ -503,12 +4185,52 @@ h\nThissiaodajdnkajbdsklajbdkajbdkjlasbdlkjabdwhpdajnlvnoampmönöaiofoaödnlaks
             // },
         };
         s.parse_md();
+        // `#<note>` in the url opens that note
+        let note = window()
+            .unwrap()
+            .location()
+            .hash()
+            .ok()
+            .and_then(|hash| js_sys::decode_uri_component(hash.trim_start_matches('#')).ok())
+            .map(String::from)
+            .filter(|note| !note.is_empty());
+        if note.is_some() {
+            s.edit(note.as_deref());
+        } else {
+            s.overlay = Some(Overlay::Start(StartScreen::new(s.store.as_ref())));
+        }
+        // Tabs that were not left normally stay recorded and stop being seen
+        let now = js_sys::Date::now();
+        for (tab, seen) in s.store.tabs() {
+            if now - seen >= swap::STALE {
+                s.store.set_tab(&tab, None);
+            }
+        }
+        if !s.leftover_swaps().is_empty() {
+            s.overlay = Some(Overlay::Recover(Picker::new(
+                "Swap files found, Enter recovers and :swapdelete removes one",
+                s.swap_names(),
+            )));
+        }
+        s.store.set_tab(&s.tab, Some(now));
+        swap::on_page_hide(ctx.link().callback(|_| vec![Msg::PageHide]));
+        swap::on_page_show(ctx.link().callback(|_| vec![Msg::PageShow]));
+        s.load_views();
+        swap::on_before_unload(s.unsaved.clone());
+        offline::register();
+        offline::mirrored(ctx.link().callback(|notes| vec![Msg::Mirrored(notes)]));
+        focus::on_change(ctx.link().callback(|focused| vec![Msg::Focus(focused)]));
+        minimap::on_scroll(ctx.link().callback(|_| vec![Msg::Scrolled]));
+        if s.reminders {
+            s.remind();
+        }
+        s.schedule_backup();
         s
     }
 
     fn update(&mut self, ctx: &Context<Self>, msgs: Self::Message) -> bool {
-        dbg!(&msgs);
         let mut ret = false;
+        let normal_once = self.normal_once;
         for msg in msgs {
             match msg {
                 Msg::CursorMove(x, y) => {
@@ -529,17 +4251,16 @@ h\nThissiaodajdnkajbdsklajbdkajbdkjlasbdlkjabdwhpdajnlvnoampmönöaiofoaödnlaks
                     ret |= last != self.cursor_position;
                 }
                 Msg::Write(text) if self.mode.is_command() => {
-                    let (cursor_movement, lines) = self
-                        .command
-                        .insert(self.cursor_position.0.min(self.command.len()), &text);
-                    assert!(lines.is_empty());
-                    // Maybe optimized
-                    // for line in new_lines.into_iter().rev() {
-                    //     self.lines.insert(self.cursor_position.1 + 1, line);
-                    // }
-                    self.update(ctx, cursor_movement);
-                    // self.cursor_position.0 += text.graphemes(true).count();
-                    self.parse_md();
+                    self.command.write(&text);
+                    ret = true;
+                }
+                // The command line holds a single line, so only the first one of a paste goes in
+                Msg::Paste(text) if self.mode.is_command() => {
+                    self.command.write(text.lines().next().unwrap_or_default());
+                    ret = true;
+                }
+                Msg::CommandLine(edit) => {
+                    self.command.edit(edit);
                     ret = true;
                 }
                 Msg::Write(text) => {
@@ -563,7 +4284,19 @@ h\nThissiaodajdnkajbdsklajbdkajbdkjlasbdlkjabdwhpdajnlvnoampmönöaiofoaödnlaks
                                 .0
                                 .min(self.lines[self.cursor_position.1].len() - 1);
                         }
+                        let cursor = (mode == Mode::Visual)
+                            .then(|| self.document.active_paragraph_mut())
+                            .flatten()
+                            .and_then(|paragraph| paragraph.cursor);
+                        if let Some(paragraph) = self.document.active_paragraph_mut() {
+                            paragraph.anchor = cursor;
+                        }
+                        if let Some(table) = self.document.active_table_mut() {
+                            table.selected_column = None;
+                            table.row_anchor = None;
+                        }
                         self.mode = mode;
+                        self.refresh_completion();
                         ret = true;
                     }
                 }
@@ -580,68 +4313,763 @@ h\nThissiaodajdnkajbdsklajbdkajbdkjlasbdlkjabdwhpdajnlvnoampmönöaiofoaödnlaks
                     self.command.clear();
                     ret = true
                 }
+                Msg::Execute(command) => {
+                    self.execute(command);
+                    ret = true
+                }
+                Msg::Cmd(Command::Enter) if self.overlay.is_some() => {
+                    self.select();
+                    ret = true;
+                }
+                Msg::Cmd(Command::Enter) if self.wiki_link().is_some() => {
+                    let target = self.wiki_link().unwrap_or_default();
+                    self.follow_wiki_link(&target);
+                    ret = true;
+                }
+                // Enter opens or closes a details block, which is written to the note
+                Msg::Cmd(Command::Enter)
+                    if matches!(
+                        self.document.elements.get(self.document.active_element),
+                        Some(Element::Details(_))
+                    ) && self.locked() =>
+                {
+                    ret = true
+                }
+                Msg::Cmd(Command::Insert(_) | Command::Delete(_))
+                | Msg::Newline
+                | Msg::Tab
+                | Msg::Paste(_)
+                | Msg::PasteColumn
+                | Msg::YankColumn(true)
+                | Msg::YankRows(true)
+                | Msg::Reflow(_)
+                | Msg::Attached(Ok(_))
+                    if self.overlay.is_none() && self.locked() =>
+                {
+                    ret = true
+                }
+                Msg::Reflow(_) | Msg::PasteColumn | Msg::YankColumn(true) | Msg::YankRows(true)
+                    if self.overlay.is_none() && self.unsuggested() =>
+                {
+                    ret = true
+                }
+                // Blocks can not be suggested, only text in the paragraph
+                Msg::Paste(text)
+                    if self.overlay.is_none()
+                        && text.trim().contains("\n\n")
+                        && self.unsuggested() =>
+                {
+                    ret = true
+                }
+                Msg::Cmd(command @ (Command::Insert(_) | Command::Delete(_)))
+                    if self.overlay.is_none() && self.suggesting && self.suggest(&command) =>
+                {
+                    self.edited();
+                    self.refresh_completion();
+                    ret = true;
+                }
+                Msg::Newline
+                    if self.overlay.is_none()
+                        && self.suggesting
+                        && self.suggest(&self.line_break()) =>
+                {
+                    self.edited();
+                    ret = true;
+                }
+                Msg::Paste(text)
+                    if self.overlay.is_none()
+                        && self.suggesting
+                        && self.suggest(&Command::Insert(text.as_str().into())) =>
+                {
+                    self.edited();
+                    ret = true;
+                }
+                Msg::Cmd(Command::Insert(chars))
+                    if self.overlay.is_none() && !chars.iter().all(|c| is_word(c)) =>
+                {
+                    self.typed();
+                    let doubled = self.doubles_space(&chars);
+                    self.expand_abbreviation();
+                    if !doubled {
+                        ret |= self.document.command(&Command::Insert(chars));
+                        self.edited();
+                    }
+                    self.check_lesson();
+                    self.refresh_completion();
+                }
                 Msg::Cmd(cmd) => {
-                    ret |= self.document.command(&cmd);
+                    self.literal = false;
+                    let handled = match &mut self.overlay {
+                        Some(overlay) => overlay.command(&cmd),
+                        None => self.document.command(&cmd),
+                    };
+                    ret |= handled;
+                    // Enter was only handled by opening or closing a details block
+                    let toggled = handled && cmd == Command::Enter;
+                    if self.overlay.is_none()
+                        && (toggled || matches!(cmd, Command::Insert(_) | Command::Delete(_)))
+                    {
+                        if matches!(cmd, Command::Insert(_)) {
+                            self.typed();
+                        }
+                        self.edited();
+                    }
+                    self.check_lesson();
+                    self.refresh_completion();
+                }
+                Msg::Completion(command) => ret |= self.complete(&command),
+                Msg::Newline if self.overlay.is_none() => {
+                    self.typed();
+                    self.newline();
+                    self.edited();
+                    self.refresh_completion();
+                    ret = true;
+                }
+                Msg::Newline => {}
+                Msg::Tab if self.overlay.is_none() => {
+                    self.typed();
+                    self.tab();
+                    self.edited();
+                    ret = true;
+                }
+                Msg::Tab => {}
+                Msg::SearchNext(reverse) => {
+                    self.search_next(reverse);
+                    ret = true;
+                }
+                Msg::SearchWord(backward) => {
+                    self.search_word(backward);
+                    ret = true;
+                }
+                Msg::CloseOverlay => {
+                    if let Some(Overlay::Collision(_)) = self.overlay {
+                        if let Some(import) = self.vault_import.take() {
+                            self.notify(
+                                Severity::Info,
+                                format!("Import stopped. {}", import.summary()),
+                            );
+                        }
+                    }
+                    ret |= self.overlay.take().is_some();
+                }
+                Msg::Paste(text) if self.overlay.is_none() => {
+                    self.paste(&text);
+                    self.edited();
+                    ret = true;
+                }
+                Msg::Paste(_) => {}
+                Msg::Pending(keys) => {
+                    let link = self.link.clone();
+                    self.pending_timeout = (!keys.is_empty()).then(|| {
+                        Timeout::new(self.timeout_len, move || {
+                            link.send_message(vec![Msg::Pending(String::new())])
+                        })
+                    });
+                    self.pending = keys;
+                    ret = true;
+                }
+                Msg::Literal => self.literal = true,
+                Msg::Pasted => {
+                    self.pasting = None;
+                    ret = true;
+                }
+                Msg::Jump if self.overlay.is_none() => self.jump = Some(Jump::Target),
+                Msg::Jump => {}
+                Msg::JumpKey(key) => self.jump_key(&key),
+                Msg::SelectColumn => {
+                    self.select_column();
+                    ret = true;
+                }
+                Msg::YankColumn(delete) => {
+                    self.yank_column(delete);
+                    ret = true;
+                }
+                Msg::SelectRows => {
+                    self.select_rows();
+                    ret = true;
+                }
+                Msg::YankRows(delete) => {
+                    self.yank_rows(delete);
+                    ret = true;
+                }
+                Msg::YankSelection => self.yank_selection(),
+                Msg::PasteColumn if self.overlay.is_none() => {
+                    self.paste_column();
+                    ret = true;
+                }
+                Msg::PasteColumn => {}
+                Msg::NormalOnce => self.normal_once = true,
+                Msg::Dismiss(id) => ret |= self.notifications.dismiss(id),
+                Msg::DictionaryCached(lang, Some(words)) => {
+                    self.dictionary = Some(spell::Dictionary::parse(
+                        &lang,
+                        &words,
+                        self.store.as_ref(),
+                    ));
+                    ret = true;
+                }
+                Msg::DictionaryCached(lang, None) => match &self.spell_url {
+                    Some(url) => {
+                        let fetched = lang.clone();
+                        let done = self.link.callback(move |words| {
+                            vec![Msg::DictionaryFetched(fetched.clone(), words)]
+                        });
+                        clip::fetch(&spell::address(url, &lang), None, done);
+                        self.show(Status::info(format!(
+                            "Downloading the {} dictionary…",
+                            lang
+                        )));
+                        ret = true;
+                    }
+                    None => self.notify(
+                        Severity::Error,
+                        format!(
+                            "No {} dictionary, set where to download it with :set spellurl=https://…/{{lang}}.dic",
+                            lang
+                        ),
+                    ),
+                },
+                Msg::DictionaryFetched(lang, Ok(words)) => {
+                    offline::keep_dictionary(&lang, &words);
+                    let dictionary = spell::Dictionary::parse(&lang, &words, self.store.as_ref());
+                    self.dictionary = Some(dictionary);
+                    self.notify(
+                        Severity::Success,
+                        format!("Downloaded the {} dictionary", lang),
+                    );
+                }
+                Msg::DictionaryFetched(lang, Err(error)) => self.notify(
+                    Severity::Error,
+                    format!("The {} dictionary could not be downloaded: {}", lang, error),
+                ),
+                Msg::PageHide => {
+                    for (note, _) in self.store.swaps() {
+                        if self.store.swap_tab(&note).as_ref() == Some(&self.tab) {
+                            self.store.remove_swap(&note);
+                        }
+                    }
+                    self.store.set_tab(&self.tab, None);
+                }
+                Msg::PageShow => {
+                    self.store.set_tab(&self.tab, Some(js_sys::Date::now()));
+                    if buffer::modified(&self.document, self.base.as_deref()) {
+                        let note = self.note.as_deref().unwrap_or_default();
+                        let content = self.document.to_md();
+                        self.store.write_swap(&self.tab, note, &content);
+                    }
+                    for buffer in self
+                        .buffers
+                        .hidden
+                        .iter()
+                        .filter(|buffer| buffer.modified())
+                    {
+                        let note = buffer.note.as_deref().unwrap_or_default();
+                        let content = buffer.document.to_md();
+                        self.store.write_swap(&self.tab, note, &content);
+                    }
+                }
+                Msg::Heartbeat => self.store.set_tab(&self.tab, Some(js_sys::Date::now())),
+                Msg::Focus(false) => {
+                    self.blur_scroll = window().unwrap().scroll_y().ok();
+                    if self.autosave {
+                        self.autosave();
+                        ret = true;
+                    }
+                    if self.blur_normal && self.mode == Mode::Insert {
+                        self.update(ctx, vec![Msg::Mode(Mode::Normal)]);
+                        ret = true;
+                    }
+                }
+                Msg::Focus(true) => {
+                    // Focusing the editor again would scroll the cursor into view, inputs
+                    // like the command line keep the focus
+                    let editor = self.node_ref.cast::<HtmlElement>().unwrap();
+                    if focus::lost(&editor) {
+                        focus::focus(&editor);
+                    }
+                    if let Some(y) = self.blur_scroll.take() {
+                        window().unwrap().scroll_to_with_x_and_y(0., y);
+                    }
+                }
+                Msg::FocusPane(left) => {
+                    self.focus_pane(left);
+                    ret = true;
+                }
+                Msg::ResizePane(delta) => {
+                    if let Some(split) = &self.split {
+                        self.panes.resize(if split.left { -delta } else { delta });
+                        self.store
+                            .set_setting("panewidth", &self.panes.left_width.to_string());
+                        ret = true;
+                    }
+                }
+                Msg::DragDivider(dragging) => {
+                    self.panes.dragging = dragging;
+                    if !dragging {
+                        self.store
+                            .set_setting("panewidth", &self.panes.left_width.to_string());
+                    }
+                }
+                Msg::MouseMove(x) if self.panes.dragging => {
+                    let width = window().unwrap().inner_width().unwrap().as_f64().unwrap();
+                    self.panes.drag_to(x, width);
+                    ret = true;
+                }
+                Msg::MouseMove(_) => {}
+                Msg::Reflow(scope) => {
+                    self.reflow(scope);
+                    ret = true;
+                }
+                Msg::Attach(files) => {
+                    for file in files {
+                        let done = self.link.callback(|result| vec![Msg::Attached(result)]);
+                        attachment::attach(file, self.attachment_limit, done);
+                    }
+                }
+                Msg::Attached(Ok(name)) => {
+                    let image = format!("![{}]({}{})", name, attachment::SCHEME, name);
+                    self.document
+                        .command(&Command::Insert(image.as_str().into()));
+                    self.edited();
+                    self.notify(Severity::Success, format!("Attached {}", name));
+                    ret = true;
+                }
+                Msg::Attached(Err(error)) => {
+                    self.notify(Severity::Error, error);
+                    ret = true;
                 }
+                Msg::TokenEncrypted(Some(token)) => {
+                    self.store.set_setting("remotetoken", &token);
+                    self.notify(Severity::Success, "Stored the token encrypted");
+                    ret = true;
+                }
+                Msg::TokenEncrypted(None) => {
+                    self.notify(Severity::Error, "The token could not be encrypted");
+                    ret = true;
+                }
+                Msg::SyncToken(push, Some(token)) => {
+                    if let Some(remote) = &self.remote {
+                        let listed = token.clone();
+                        let done = self.link.callback(move |notes| {
+                            vec![Msg::RemoteListed(push, listed.clone(), notes)]
+                        });
+                        remote::list(remote, &token, done);
+                    }
+                }
+                Msg::SyncToken(_, None) => {
+                    self.notify(
+                        Severity::Error,
+                        "The token could not be decrypted, set it again with :token",
+                    );
+                    ret = true;
+                }
+                Msg::RemoteListed(push, token, Ok(notes)) => {
+                    self.transfer(push, &token, notes);
+                    ret = true;
+                }
+                Msg::RemoteListed(_, _, Err(error)) => {
+                    self.notify(Severity::Error, error);
+                    ret = true;
+                }
+                Msg::Published(note, Ok(url)) => {
+                    self.record_published(&note, &url);
+                    self.show(Status::info(format!("Published at {}", url)));
+                    ret = true;
+                }
+                Msg::Published(_, Err(error)) => {
+                    self.notify(Severity::Error, error);
+                    ret = true;
+                }
+                Msg::Clipped(url, Ok(page)) => {
+                    let (title, md) = import::page_to_md(&page, &url);
+                    let mut note = clip::note_name(title.as_deref(), &url);
+                    if self.store.load(&note).is_some() {
+                        note = vault::free_name(&note, self.store.as_ref());
+                    }
+                    let content = clip::note(title.as_deref(), &url, &md);
+                    self.store.save(&note, &content);
+                    self.open(&content, Some(note.clone()), Some(content.clone()));
+                    self.notify(Severity::Success, format!("Clipped the page into {}", note));
+                    ret = true;
+                }
+                Msg::Clipped(_, Err(error)) => {
+                    self.notify(Severity::Error, error);
+                    ret = true;
+                }
+                Msg::FeedFetched(url, digest, Ok(xml)) => {
+                    match feed::parse(&xml) {
+                        Ok(feed) => {
+                            let today = date::now("%F");
+                            let synced =
+                                feed::sync(&url, &feed, digest, &today, self.store.as_mut());
+                            let title = Some(feed.title.as_str())
+                                .filter(|title| !title.is_empty())
+                                .unwrap_or(&url);
+                            self.notify(
+                                Severity::Success,
+                                format!(
+                                    "{}: {} new, {} updated",
+                                    title, synced.new, synced.updated
+                                ),
+                            );
+                        }
+                        Err(error) => self.notify(Severity::Error, format!("{}: {}", url, error)),
+                    }
+                    ret = true;
+                }
+                Msg::FeedFetched(url, _, Err(error)) => {
+                    self.notify(Severity::Error, format!("{}: {}", url, error));
+                    ret = true;
+                }
+                Msg::WebhookFailed(error) => {
+                    self.notify(Severity::Error, error);
+                    ret = true;
+                }
+                Msg::Transferred(transfer) => {
+                    self.transferred(Some(transfer));
+                    ret = true;
+                }
+                Msg::Committed(Ok(committed)) => {
+                    self.notify(Severity::Success, committed);
+                    ret = true;
+                }
+                Msg::Committed(Err(error)) => {
+                    self.notify(Severity::Error, error);
+                    ret = true;
+                }
+                Msg::Commits(commits) if commits.is_empty() => {
+                    self.notify(
+                        Severity::Info,
+                        "No commits yet, make one with :commit \"message\"",
+                    );
+                    ret = true;
+                }
+                Msg::Commits(commits) => {
+                    let entries = commits.iter().rev().map(Commit::summary).collect();
+                    self.overlay = Some(Overlay::Commits(Picker::new(
+                        "Commits, Enter checks out the open note",
+                        entries,
+                    )));
+                    ret = true;
+                }
+                Msg::Checkout(id, note, commits) => {
+                    self.checkout(&id, note.as_deref(), &commits);
+                    ret = true;
+                }
+                Msg::Backup => self.backup(false),
+                Msg::BackedUp(true, Ok(file)) => {
+                    self.notify(Severity::Success, format!("Backed up to {}", file));
+                    ret = true;
+                }
+                Msg::BackedUp(false, Ok(_)) => {}
+                Msg::BackedUp(_, Err(error)) => {
+                    self.show(Status::error(error));
+                    ret = true;
+                }
+                Msg::BackupFolder(Ok(folder)) => {
+                    self.notify(
+                        Severity::Success,
+                        format!("Backing up to the folder {}", folder),
+                    );
+                    self.backup(false);
+                    ret = true;
+                }
+                Msg::BackupFolder(Err(error)) => {
+                    self.notify(Severity::Error, error);
+                    ret = true;
+                }
+                Msg::ImportVault(Ok(files)) => {
+                    self.import_vault(files);
+                    ret = true;
+                }
+                Msg::ImportVault(Err(error)) => {
+                    self.notify(Severity::Error, error);
+                    ret = true;
+                }
+                Msg::VaultAttachment(name, stored) => {
+                    let ready = self
+                        .vault_import
+                        .as_mut()
+                        .map_or(false, |import| import.stored(&name, stored));
+                    if ready {
+                        self.continue_import();
+                    }
+                    ret = true;
+                }
+                Msg::VaultExported(notes, attachments) => {
+                    self.notify(
+                        Severity::Success,
+                        format!(
+                            "Exported {} notes and {} attachments to vault.zip",
+                            notes, attachments
+                        ),
+                    );
+                    ret = true;
+                }
+                Msg::Scrolled => ret |= self.minimap && self.overlay.is_none(),
+                Msg::ExpandSelection(grow) => {
+                    self.expand_selection(grow);
+                    ret = true;
+                }
+                Msg::JumpElement(element) => {
+                    let cell = matches!(
+                        self.document.elements.get(element),
+                        Some(Element::Table(_) | Element::Definitions(_) | Element::Details(_))
+                    )
+                    .then_some((0, 0));
+                    self.document.set_cursor((element, cell), 0);
+                    ret = true;
+                }
+                Msg::TimerTick => {
+                    if self
+                        .timer
+                        .as_ref()
+                        .map_or(false, |timer| timer.end <= js_sys::Date::now())
+                    {
+                        self.finish_timer();
+                    }
+                    ret = true;
+                }
+                Msg::AttachmentLoaded(name, url) => {
+                    let attachment = url.map_or(Attachment::Missing, Attachment::Loaded);
+                    if let Some(Attachment::Loaded(replaced)) = self.attachments.insert(name, attachment) {
+                        let _ = Url::revoke_object_url(&replaced);
+                    }
+                    ret = true;
+                }
+                Msg::Mirrored(notes) => {
+                    // Notes only kept in IndexedDB, like after localStorage was cleared
+                    let known: HashSet<_> = self
+                        .store
+                        .notes()
+                        .into_iter()
+                        .chain(self.store.trash())
+                        .collect();
+                    let missing: Vec<_> = notes
+                        .into_iter()
+                        .filter(|(note, _)| !known.contains(note))
+                        .collect();
+                    for (note, content) in &missing {
+                        self.store.save(note, content);
+                    }
+                    if !missing.is_empty() {
+                        self.notify(
+                            Severity::Info,
+                            format!("Restored {} notes from offline storage", missing.len()),
+                        );
+                        ret = true;
+                    }
+                }
+                Msg::Import(name, content) => {
+                    let stem = name
+                        .rsplit_once('.')
+                        .map_or(name.as_str(), |(stem, _)| stem);
+                    // `:w` would replace a note of that name without asking
+                    let mut note = stem.to_owned();
+                    if self.store.load(&note).is_some() {
+                        note = vault::free_name(&note, self.store.as_ref());
+                    }
+                    self.open(&import::to_md(&name, &content), Some(note), None);
+                    ret = true;
+                }
+            }
+        }
+        // The command after `Ctrl+O` is done once no key sequence or jump is left
+        if normal_once && self.normal_once && self.pending.is_empty() && self.jump.is_none() {
+            match self.mode {
+                Mode::Normal => {
+                    self.normal_once = false;
+                    self.update(ctx, vec![Msg::Mode(Mode::Insert)]);
+                }
+                Mode::Insert => self.normal_once = false,
+                Mode::Command | Mode::Visual => {}
             }
         }
+        if let Some(error) = self.store.take_error() {
+            self.show(Status::error(error));
+        }
+        self.load_views();
+        self.load_dictionary();
         true
     }
 
     fn rendered(&mut self, _: &Context<Self>, first_render: bool) {
-        // focus the text at page load to be able to accept keyboard input
-        if first_render {
-            let smth = self.node_ref.cast::<HtmlInputElement>().unwrap();
-            smth.focus().unwrap();
+        let modified = buffer::modified(&self.document, self.base.as_deref());
+        let unsaved = modified
+            || self.buffers.hidden.iter().any(Buffer::modified)
+            || self
+                .split
+                .as_ref()
+                .map_or(false, |split| split.buffer.modified());
+        self.unsaved.set(self.confirm_close && unsaved);
+        title::update(self.note.as_deref(), modified, self.unsaved_icon);
+
+        // Attachments are fetched the first time they are shown
+        let documents = iter::once(&self.document)
+            .chain(self.split.as_ref().map(|split| &split.buffer.document));
+        for document in documents {
+            for (_, paragraph) in document.paragraphs() {
+                for name in attachment::linked(&paragraph.text.concat()) {
+                    if !self.attachments.contains_key(&name) {
+                        self.attachments.insert(name.clone(), Attachment::Loading);
+                        let loaded = name.clone();
+                        let done = self
+                            .link
+                            .callback(move |url| vec![Msg::AttachmentLoaded(loaded.clone(), url)]);
+                        attachment::load(&name, done);
+                    }
+                }
+            }
         }
 
-        // scroll to cursor if out of view
+        // focus the editor at page load to be able to accept keyboard input, and again
+        // whenever focus fell back to the page, like after closing an overlay
+        let editor = self.node_ref.cast::<HtmlElement>().unwrap();
+        if first_render || focus::lost(&editor) {
+            focus::focus(&editor);
+        }
+
+        // scroll to cursor if out of view, as little as needed, also in a pane of the split
+        // which scrolls on its own
         // TODO add support for cursor_margins
-        // behavior: 'smooth'
-        if let Some(elem) = self.cursor_ref.take().cast::<web_sys::Element>() {
-            let window = window().unwrap();
-            let bounds = elem.get_bounding_client_rect();
-            if bounds.y() < 0. {
-                let y = window.scroll_y().unwrap() + bounds.y();
-                window.scroll_to_with_x_and_y(0., y);
-            } else if bounds.bottom() > window.inner_height().unwrap().as_f64().unwrap() {
-                let y = window.scroll_y().unwrap() + bounds.bottom()
-                    - window.inner_height().unwrap().as_f64().unwrap();
-                window.scroll_to_with_x_and_y(0., y);
-            }
+        let cursor = self
+            .cursor_ref
+            .take()
+            .cast::<web_sys::Element>()
+            .or_else(|| window()?.document()?.get_element_by_id("cursor"));
+        if let Some(elem) = cursor {
+            let mut options = ScrollIntoViewOptions::new();
+            options.block(ScrollLogicalPosition::Nearest);
+            elem.scroll_into_view_with_scroll_into_view_options(&options);
         }
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
+        self.render_cache.sweep();
         let mode = self.mode;
+        let keymap = self.keymap.clone();
+        let pending = self.pending.clone();
+        let jumping = self.jump.is_some();
         let keypress = ctx
             .link()
-            .batch_callback(move |e| Self::handle_key_press(e, mode));
+            .batch_callback(move |e| Self::handle_key_press(e, mode, &keymap, &pending, jumping));
+
+        let paste = ctx.link().batch_callback(|e: Event| {
+            let data = e.dyn_into::<ClipboardEvent>().ok()?.clipboard_data()?;
+            let files = attachment::files(&data);
+            if !files.is_empty() {
+                return Some(vec![Msg::Attach(files)]);
+            }
+            let text = data.get_data("text/plain").ok()?;
+            Some(vec![Msg::Paste(text)])
+        });
+        // Without this the browser opens dropped files instead
+        let drag_over = Callback::from(|e: DragEvent| e.prevent_default());
+        let drop_files = ctx.link().batch_callback(|e: DragEvent| {
+            e.prevent_default();
+            let files = attachment::files(&e.data_transfer()?);
+            (!files.is_empty()).then(|| vec![Msg::Attach(files)])
+        });
 
         let cursor_ref = NodeRef::default();
         self.cursor_ref.set(cursor_ref.clone());
-        let state = ApplicationState {
-            cursor_style: match self.mode {
-                Mode::Insert => CursorStyle::Insert,
-                Mode::Normal => CursorStyle::Box,
-                Mode::Command => CursorStyle::EmtyBox,
-            },
+        // Only a grabbed divider follows the mouse
+        let dragging = self.panes.dragging;
+        let drag = ctx.link().batch_callback(move |e: MouseEvent| {
+            dragging.then(|| vec![Msg::MouseMove(e.client_x())])
+        });
+        let drop = ctx
+            .link()
+            .batch_callback(move |_: MouseEvent| dragging.then(|| vec![Msg::DragDivider(false)]));
+
+        let cursor_style = match self.mode {
+            Mode::Insert => CursorStyle::Insert,
+            Mode::Normal => CursorStyle::Box,
+            Mode::Command => CursorStyle::EmtyBox,
+            Mode::Visual => CursorStyle::Box,
+        };
+        let state = self.state(&self.document, cursor_style);
+        let document = match &self.split {
+            Some(split) => {
+                // The cursor of the other pane is drawn like in Command mode
+                let mut other = self.state(&split.buffer.document, CursorStyle::EmtyBox);
+                other.cursor_id = "split-cursor";
+                let other = split.buffer.document.render(&other);
+                let width = |left| {
+                    let width = if left {
+                        self.panes.left_width
+                    } else {
+                        100 - self.panes.left_width
+                    };
+                    format!("width: {}%", width)
+                };
+                let other = html! {
+                    <div class={classes!("h-screen", "overflow-y-auto", "p-2", "opacity-75")} style={width(split.left)}>{other}</div>
+                };
+                let open = html! {
+                    <div class={classes!("h-screen", "overflow-y-auto", "p-2")} style={width(!split.left)}>{self.document.render(&state)}</div>
+                };
+                let grab = ctx.link().callback(|e: MouseEvent| {
+                    e.prevent_default();
+                    vec![Msg::DragDivider(true)]
+                });
+                let divider = html! {
+                    <div class={classes!("w-1", "-mx-px", "bg-gray-600", "cursor-col-resize", "hover:bg-blue-500")} onmousedown={grab}></div>
+                };
+                if split.left {
+                    html! {<div class={classes!("flex")}>{other}{divider}{open}</div>}
+                } else {
+                    html! {<div class={classes!("flex")}>{open}{divider}{other}</div>}
+                }
+            }
+            None => self.document.render(&state),
         };
 
         html! {
-            <div class={classes!("dark")} style={format!("font-family: {}, Hack, Noto, monospace; font-size: 20px; line-height: 30px", self.font)}>
-                <div ref={self.node_ref.clone()} style="min-height:100vh" class={classes!("bg-gray-200", "text-gray-800", "dark:bg-gray-900", "dark:text-gray-300", "wrap", "p-2")} onkeydown={keypress} tabindex="0">
+            <div class="dark" style={format!("font-family: {}, Hack, Noto, monospace; font-size: 20px; line-height: 30px; font-variant-ligatures: {}", self.font, if self.ligatures { "normal" } else { "none" })}>
+                <div ref={self.node_ref.clone()} style="min-height:100vh" class={classes!(self.theme.editor_classes(), "wrap", "p-2")} onkeydown={keypress} onpaste={paste} ondragover={drag_over} ondrop={drop_files} onmousemove={drag} onmouseup={drop} tabindex="-1" role="textbox" aria-multiline="true" aria-label="Note" aria-activedescendant="cursor">
+                        <div class={classes!("sr-only")} role="status" aria-live="polite">{a11y::mode(self.mode)}</div>
+                        <div class={classes!("sr-only")} aria-live="polite" aria-atomic="true">{a11y::character(&self.document)}</div>
                         <div class={classes!("fixed", "flex", "items-center", "justify-center", "h-1/3", "w-screen")}>
                             <div class={classes!("w-10/12", "object-center", "bg-gray-700", "rounded", "ring-2", "ring-gray-400", "p-2",(self.mode != Mode::Command).then(|| "hidden"))}>
 
-                                <Line line={self.command.characters.clone()} cursor={(self.mode == Mode::Command).then(|| (self.cursor_position.0, CursorStyle::Insert, cursor_ref.clone()))}>
+                                <Line line={self.command.characters()} theme={self.theme} cursor={(self.mode == Mode::Command).then(|| (self.command.cursor, CursorStyle::Insert, cursor_ref.clone()))}>
                                     <span class={classes!("font-bold")}>
                                         {":"}
                                     </span>
                                 </Line>
                             </div>
                         </div>
-                        {self.document.render(&state)}
+                        {
+                            match &self.overlay {
+                                Some(overlay) => overlay.render(&state),
+                                None if self.breadcrumb => html! {
+                                    <>{breadcrumb::render(&self.document)}{document}</>
+                                },
+                                None => document,
+                            }
+                        }
+                        {self.completion.as_ref().map(|completion| completion.render(&state)).unwrap_or_default()}
+                        {(!self.pending.is_empty()).then(|| Hints::new(&self.keymap, self.mode, &self.pending).render(&state)).unwrap_or_default()}
+                        if self.minimap && self.overlay.is_none() && !self.degraded {
+                            {minimap::render(&self.document, self.highlight.then(|| self.search.as_ref()).flatten(), ctx.link().callback(|element| vec![Msg::JumpElement(element)]))}
+                        }
+                        {self.notifications.render(&state)}
+                        {self.status.as_ref().map(|status| status.render(&state)).unwrap_or_default()}
+                        <div class={classes!("fixed", "bottom-0", "right-2", "z-30", "flex", "gap-4", "text-gray-400")}>
+                            if !self.pending.is_empty() {
+                                <span>{status::showcmd(&self.pending)}</span>
+                            }
+                            if self.verbatim() {
+                                <span title="Text is inserted verbatim">{"PASTE"}</span>
+                            }
+                            if self.suggesting {
+                                <span title="Edits are recorded as suggested changes">{"SUGGEST"}</span>
+                            }
+                            {for self.timer.as_ref().map(|timer| html! {
+                                <span title={format!("{} timer", timer.length)}>{timer.remaining(js_sys::Date::now())}</span>
+                            })}
+                        </div>
                     // <div style="height:0" class={classes!("text-transparent")}>
                     //     {for self.lines.iter().map(|line| html!{
                     //         <Line key={line.key.to_string()} line={line.characters.clone()} background=true cursor={None}/>
@@ -676,6 +5104,8 @@ struct LineProps {
     #[prop_or_default]
     background: bool,
     #[prop_or_default]
+    theme: Theme,
+    #[prop_or_default]
     children: Children,
 }
 
@@ -747,7 +5177,10 @@ impl Component for Line {
                         }
                     }))
                     .flat_map(|style| {
-                        style.forground_classes(style.positioned(&was_style, &will_style))
+                        style.forground_classes(
+                            style.positioned(&was_style, &will_style),
+                            props.theme,
+                        )
                     })
                     .collect();
                 was_style = style.clone();
@@ -768,7 +5201,7 @@ impl Component for Line {
             && !props.background
         {
             spans.push(html! {
-                <span ref={props.cursor.iter().cloned().next().unwrap().2} class={classes!(TextStyle::Cursor(props.cursor.as_ref().unwrap().1).forground_classes(Position::Single))}>{" "}</span>
+                <span ref={props.cursor.iter().cloned().next().unwrap().2} class={classes!(TextStyle::Cursor(props.cursor.as_ref().unwrap().1).forground_classes(Position::Single, props.theme))}>{" "}</span>
             });
         }
 
@@ -834,6 +5267,77 @@ impl CursorStyle {
     }
 }
 
+/// Color palette, selected with `:theme=<name>`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Theme {
+    #[default]
+    Dark,
+    HighContrast,
+}
+
+impl Theme {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::Dark),
+            "high-contrast" => Some(Self::HighContrast),
+            _ => None,
+        }
+    }
+
+    fn editor_classes(&self) -> Classes {
+        match self {
+            Theme::Dark => classes![
+                "bg-gray-200",
+                "text-gray-800",
+                "dark:bg-gray-900",
+                "dark:text-gray-300"
+            ],
+            Theme::HighContrast => classes!["bg-black", "text-white"],
+        }
+    }
+
+    /// The color role of a [`TextStyle`] in this theme.
+    fn color(&self, style: TextStyle) -> Classes {
+        match (self, style) {
+            (Theme::Dark, TextStyle::Code) => classes!["text-green-300"],
+            (Theme::Dark, TextStyle::Link) => classes!["text-blue-400"],
+            (Theme::Dark, TextStyle::Heading) => classes!["text-yellow-300"],
+            (Theme::HighContrast, TextStyle::Code) => classes!["text-green-400"],
+            (Theme::HighContrast, TextStyle::Link) => classes!["text-cyan-300"],
+            (Theme::HighContrast, TextStyle::Heading) => classes!["text-yellow-300", "font-bold"],
+            (Theme::HighContrast, TextStyle::Bold | TextStyle::Italic) => classes!["text-white"],
+            (Theme::HighContrast, TextStyle::Cursor(_)) => classes!["ring-2", "ring-yellow-300"],
+            (Theme::HighContrast, TextStyle::TableCell) => classes!["border-white"],
+            _ => classes![],
+        }
+    }
+}
+
+/// The options of `:set`, separated by whitespace that is not quoted or escaped, so
+/// `:set dateformat="%d. %B %Y"` and `:set dateformat=%d.\ %B\ %Y` keep their spaces.
+fn options(command: &str) -> Vec<String> {
+    let mut options = vec![];
+    let mut option = String::new();
+    let mut quoted = false;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => quoted = !quoted,
+            '\\' => option.extend(chars.next()),
+            c if c.is_whitespace() && !quoted => {
+                if !option.is_empty() {
+                    options.push(std::mem::take(&mut option));
+                }
+            }
+            c => option.push(c),
+        }
+    }
+    if !option.is_empty() {
+        options.push(option);
+    }
+    options
+}
+
 fn main() {
     yew::start_app::<Model>();
 }
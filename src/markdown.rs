@@ -0,0 +1,99 @@
+use pulldown_cmark::{CodeBlockKind, Event, Tag};
+
+/// Turns the events of a block back into markdown source.
+///
+/// Container blocks (quotes and list items) are written into their own buffer and
+/// indented when they end.
+pub fn write_md<'a>(events: impl IntoIterator<Item = Event<'a>>) -> String {
+    let mut buffers = vec![String::new()];
+    // `None` for bullet lists, otherwise the number of the next item
+    let mut lists: Vec<Option<u64>> = vec![];
+
+    for event in events {
+        let out = buffers.last_mut().expect("There is always a buffer");
+        match event {
+            Event::Start(Tag::Heading(level)) => {
+                out.push_str(&"#".repeat(level as usize));
+                out.push(' ');
+            }
+            Event::Start(Tag::BlockQuote | Tag::Item) => buffers.push(String::new()),
+            Event::End(Tag::BlockQuote) => {
+                let quote = buffers.pop().unwrap();
+                let out = buffers.last_mut().unwrap();
+                for line in quote.trim_end().lines() {
+                    out.push_str("> ");
+                    out.push_str(line);
+                    out.push('\n');
+                }
+            }
+            Event::Start(Tag::List(first)) => {
+                if !out.is_empty() && !out.ends_with('\n') {
+                    out.push('\n');
+                }
+                lists.push(first);
+            }
+            Event::End(Tag::List(_)) => {
+                lists.pop();
+            }
+            Event::End(Tag::Item) => {
+                let item = buffers.pop().unwrap();
+                let out = buffers.last_mut().unwrap();
+                let marker = match lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => "- ".to_owned(),
+                };
+                for (i, line) in item.trim_end().lines().enumerate() {
+                    if i == 0 {
+                        out.push_str(&marker);
+                    } else if !line.is_empty() {
+                        out.push_str(&" ".repeat(marker.len()));
+                    }
+                    out.push_str(line);
+                    out.push('\n');
+                }
+            }
+            Event::Start(Tag::CodeBlock(kind)) => {
+                out.push_str("```");
+                if let CodeBlockKind::Fenced(language) = kind {
+                    out.push_str(&language);
+                }
+                out.push('\n');
+            }
+            Event::End(Tag::CodeBlock(_)) => out.push_str("```\n"),
+            Event::End(Tag::Paragraph | Tag::Heading(_)) => out.push('\n'),
+            Event::Start(Tag::Emphasis) | Event::End(Tag::Emphasis) => out.push('_'),
+            Event::Start(Tag::Strong) | Event::End(Tag::Strong) => out.push_str("**"),
+            Event::Start(Tag::Strikethrough) | Event::End(Tag::Strikethrough) => out.push_str("~~"),
+            Event::Start(Tag::Link(..)) => out.push('['),
+            Event::Start(Tag::Image(..)) => out.push_str("!["),
+            Event::End(Tag::Link(_, url, title) | Tag::Image(_, url, title)) => {
+                out.push_str("](");
+                out.push_str(&url);
+                if !title.is_empty() {
+                    out.push_str(&format!(" \"{}\"", title));
+                }
+                out.push(')');
+            }
+            Event::Start(Tag::FootnoteDefinition(label)) => {
+                out.push_str(&format!("[^{}]: ", label))
+            }
+            Event::Text(text) | Event::Html(text) => out.push_str(&text),
+            Event::Code(code) => {
+                out.push('`');
+                out.push_str(&code);
+                out.push('`');
+            }
+            Event::FootnoteReference(label) => out.push_str(&format!("[^{}]", label)),
+            Event::SoftBreak => out.push('\n'),
+            Event::HardBreak => out.push_str("\\\n"),
+            Event::Rule => out.push_str("---\n"),
+            Event::TaskListMarker(checked) => out.push_str(if checked { "[x] " } else { "[ ] " }),
+            _ => {}
+        }
+    }
+
+    buffers.swap_remove(0).trim_end().to_owned()
+}
@@ -0,0 +1,158 @@
+use yew::{classes, html, Html};
+
+use crate::{
+    diff::{diff, Change},
+    document::{Command, Commandee, Render},
+    ApplicationState,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Local,
+    Remote,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Hunk {
+    Equal(Vec<String>),
+    Conflict {
+        local: Vec<String>,
+        remote: Vec<String>,
+        choice: Side,
+    },
+}
+
+/// Resolution of a conflict between the open buffer and a version saved elsewhere.
+///
+/// Conflicting hunks are selected with `j`/`k`, `h` keeps the local and `l` the remote
+/// lines, `:w` saves the resolved note.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Merge {
+    pub hunks: Vec<Hunk>,
+    /// Index of the selected conflict in `hunks`
+    pub active: Option<usize>,
+}
+
+impl Merge {
+    pub fn new(local: &str, remote: &str) -> Self {
+        let remote: Vec<_> = remote.lines().map(String::from).collect();
+        let local: Vec<_> = local.lines().map(String::from).collect();
+        let mut hunks = vec![];
+        for change in diff(&remote, &local) {
+            if let Change::Equal(line) = change {
+                match hunks.last_mut() {
+                    Some(Hunk::Equal(lines)) => lines.push(line),
+                    _ => hunks.push(Hunk::Equal(vec![line])),
+                }
+                continue;
+            }
+            if !matches!(hunks.last(), Some(Hunk::Conflict { .. })) {
+                hunks.push(Hunk::Conflict {
+                    local: vec![],
+                    remote: vec![],
+                    choice: Side::Local,
+                });
+            }
+            if let Some(Hunk::Conflict { local, remote, .. }) = hunks.last_mut() {
+                match change {
+                    Change::Insert(line) => local.push(line),
+                    Change::Delete(line) => remote.push(line),
+                    Change::Equal(_) => unreachable!(),
+                }
+            }
+        }
+        let active = hunks
+            .iter()
+            .position(|hunk| matches!(hunk, Hunk::Conflict { .. }));
+        Self { hunks, active }
+    }
+
+    pub fn resolved(&self) -> String {
+        self.hunks
+            .iter()
+            .flat_map(|hunk| match hunk {
+                Hunk::Equal(lines) => lines,
+                Hunk::Conflict {
+                    local,
+                    choice: Side::Local,
+                    ..
+                } => local,
+                Hunk::Conflict {
+                    remote,
+                    choice: Side::Remote,
+                    ..
+                } => remote,
+            })
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn conflicts(&self) -> impl Iterator<Item = usize> + '_ {
+        self.hunks
+            .iter()
+            .enumerate()
+            .filter(|(_, hunk)| matches!(hunk, Hunk::Conflict { .. }))
+            .map(|(i, _)| i)
+    }
+}
+
+impl Commandee for Merge {
+    fn command(&mut self, command: &Self::Command) -> Self::Response {
+        let active = match self.active {
+            Some(active) => active,
+            None => return false,
+        };
+        match command {
+            Command::Up => {
+                self.active = self
+                    .conflicts()
+                    .filter(|&i| i < active)
+                    .last()
+                    .or(Some(active))
+            }
+            Command::Down => self.active = self.conflicts().find(|&i| i > active).or(Some(active)),
+            Command::Left | Command::Right => {
+                if let Hunk::Conflict { choice, .. } = &mut self.hunks[active] {
+                    *choice = if command == &Command::Left {
+                        Side::Local
+                    } else {
+                        Side::Remote
+                    };
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+fn render_side(name: &str, lines: &[String], chosen: bool) -> Html {
+    html! {
+        <div class={classes!((!chosen).then_some(classes!("opacity-50", "line-through")))}>
+            <p class={classes!("text-gray-500")}>{name}</p>
+            {for lines.iter().map(|line| html!{<p>{line}</p>})}
+        </div>
+    }
+}
+
+impl Render for Merge {
+    fn render(&self, _state: &ApplicationState) -> Html {
+        html! {
+            <div>
+                {for self.hunks.iter().enumerate().map(|(i, hunk)| match hunk {
+                    Hunk::Equal(lines) => html!{
+                        {for lines.iter().map(|line| html!{<p>{line}</p>})}
+                    },
+                    Hunk::Conflict { local, remote, choice } => html!{
+                        <div class={classes!("rounded", (self.active == Some(i)).then_some(classes!("ring-2", "ring-red-300")))}>
+                            {render_side("<<<<<<< local", local, *choice == Side::Local)}
+                            {render_side("=======", remote, *choice == Side::Remote)}
+                            <p class={classes!("text-gray-500")}>{">>>>>>> remote"}</p>
+                        </div>
+                    },
+                })}
+            </div>
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::HashSet;
+
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::window;
+use yew::{classes, html, Callback, Html};
+
+use crate::{
+    document::{Document, Element},
+    search::Search,
+};
+
+/// Characters of a line that count as a full one in the minimap.
+const FULL_LINE: usize = 80;
+
+/// Calls `callback` when the page is scrolled.
+pub fn on_scroll(callback: Callback<()>) {
+    let listener = Closure::wrap(Box::new(move || callback.emit(())) as Box<dyn FnMut()>);
+    window()
+        .unwrap()
+        .add_event_listener_with_callback("scroll", listener.as_ref().unchecked_ref())
+        .unwrap();
+    listener.forget();
+}
+
+/// The top and height of the part of the page in view, in percent of the whole page.
+fn viewport() -> (f64, f64) {
+    let window = window().unwrap();
+    let page = window
+        .document()
+        .and_then(|document| document.document_element())
+        .map_or(1., |element| element.scroll_height() as f64)
+        .max(1.);
+    let top = window.scroll_y().unwrap_or_default();
+    let height = window
+        .inner_height()
+        .ok()
+        .and_then(|height| height.as_f64())
+        .unwrap_or(page);
+    (top / page * 100., (height / page).min(1.) * 100.)
+}
+
+/// A strip on the right with a miniature of `document` for `:set minimap`.
+///
+/// Every element is a block as tall as its lines and as wide as they are full, headings are
+/// marks and elements with matches of `search` are highlighted. Clicking a block emits
+/// the index of its element on `jump`.
+pub fn render(document: &Document, search: Option<&Search>, jump: Callback<usize>) -> Html {
+    let matched: HashSet<_> = search
+        .map(|search| {
+            document
+                .paragraphs()
+                .into_iter()
+                .filter(|(_, paragraph)| !search.matches(&paragraph.text).is_empty())
+                .map(|((element, _), _)| element)
+                .collect()
+        })
+        .unwrap_or_default();
+    let hidden = document.hidden();
+    let blocks = document
+        .elements
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !hidden[*i])
+        .map(|(i, element)| {
+            let md = element.to_md();
+            let lines = md.lines().count().max(1);
+            let density = (md.chars().count() / lines).min(FULL_LINE) * 100 / FULL_LINE;
+            let color = match element {
+                _ if matched.contains(&i) => "bg-yellow-400",
+                Element::Heading(_) => "bg-blue-400",
+                Element::Table(_) | Element::Definitions(_) | Element::Details(_) => "bg-gray-500",
+                Element::Frontmatter(_) | Element::Paragraph(_) => "bg-gray-600",
+            };
+            let (width, height) = match element {
+                Element::Heading(_) => (100, "h-0.5".to_owned()),
+                _ => (density.max(10), "h-full".to_owned()),
+            };
+            let jump = jump.clone();
+            html! {
+                <div class={classes!("flex", "items-center", "cursor-pointer", "py-px")} style={format!("flex: {} 1 0", lines)} onclick={move |_| jump.emit(i)}>
+                    <div class={classes!(color, height, (i == document.active_element).then_some(classes!("ring-1", "ring-white")))} style={format!("width: {}%", width)}></div>
+                </div>
+            }
+        });
+    let (top, height) = viewport();
+    html! {
+        <div class={classes!("fixed", "top-0", "right-0", "z-20", "h-screen", "w-16", "p-1", "flex", "flex-col", "bg-gray-900", "bg-opacity-50")} aria-hidden="true">
+            {for blocks}
+            <div class={classes!("absolute", "inset-x-0", "bg-white", "bg-opacity-10", "pointer-events-none")} style={format!("top: {}%; height: {}%", top, height)}></div>
+        </div>
+    }
+}
@@ -0,0 +1,118 @@
+use yew::{classes, html, Classes, Html};
+
+use crate::{document::Render, ApplicationState};
+
+/// How long a toast is shown, in milliseconds.
+pub const DISMISS_AFTER: u32 = 4000;
+/// How many past notifications `:messages` lists.
+const HISTORY: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Success => "success",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+
+    fn classes(&self) -> Classes {
+        match self {
+            Severity::Info => classes!("ring-gray-400"),
+            Severity::Success => classes!("ring-green-400"),
+            Severity::Warning => classes!("ring-yellow-400"),
+            Severity::Error => classes!("ring-red-400", "text-red-300"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Notification {
+    pub id: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Transient messages shown as toasts in the corner until dismissed, kept for `:messages`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Notifications {
+    pub toasts: Vec<Notification>,
+    pub history: Vec<Notification>,
+    next_id: usize,
+}
+
+impl Notifications {
+    /// Shows a toast, returning the id to dismiss it with.
+    pub fn push(&mut self, severity: Severity, message: impl Into<String>) -> usize {
+        let notification = Notification {
+            id: self.next_id,
+            severity,
+            message: message.into(),
+        };
+        self.next_id += 1;
+        self.keep(notification.clone());
+        self.toasts.push(notification);
+        self.next_id - 1
+    }
+
+    /// Adds a message shown elsewhere, like in the status line, to the history only.
+    pub fn record(&mut self, severity: Severity, message: impl Into<String>) {
+        let notification = Notification {
+            id: self.next_id,
+            severity,
+            message: message.into(),
+        };
+        self.next_id += 1;
+        self.keep(notification);
+    }
+
+    fn keep(&mut self, notification: Notification) {
+        self.history.push(notification);
+        if self.history.len() > HISTORY {
+            self.history.remove(0);
+        }
+    }
+
+    /// Hides the toast `id`, returning whether it was shown.
+    pub fn dismiss(&mut self, id: usize) -> bool {
+        let len = self.toasts.len();
+        self.toasts.retain(|toast| toast.id != id);
+        self.toasts.len() != len
+    }
+
+    /// Past notifications, newest first.
+    pub fn messages(&self) -> Vec<String> {
+        self.history
+            .iter()
+            .rev()
+            .map(|notification| {
+                format!(
+                    "[{}] {}",
+                    notification.severity.name(),
+                    notification.message
+                )
+            })
+            .collect()
+    }
+}
+
+impl Render for Notifications {
+    fn render(&self, _state: &ApplicationState) -> Html {
+        html! {
+            <div class={classes!("fixed", "top-2", "right-2", "z-30", "w-80", "space-y-2")} role="status" aria-live="polite">
+                {for self.toasts.iter().map(|toast| html!{
+                    <p class={classes!("p-2", "rounded", "bg-gray-700", "ring-2", toast.severity.classes())}>{&toast.message}</p>
+                })}
+            </div>
+        }
+    }
+}
@@ -0,0 +1,414 @@
+use std::cell::RefCell;
+
+use js_sys::{Array, Function, Reflect};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{window, Blob, Event, IdbDatabase, IdbObjectStore, IdbRequest, IdbTransactionMode};
+use yew::Callback;
+
+use crate::{attachment, history::Commit};
+
+const DATABASE: &str = "notething";
+const NOTES: &str = "notes";
+const ATTACHMENTS: &str = "attachments";
+const HANDLES: &str = "handles";
+const COMMITS: &str = "commits";
+const DICTIONARIES: &str = "dictionaries";
+
+thread_local! {
+    /// The `beforeinstallprompt` event held back until `:install`
+    static INSTALL: RefCell<Option<Event>> = RefCell::new(None);
+}
+
+/// Registers the service worker caching the app and holds back the browser's install
+/// prompt for `:install`.
+pub fn register() {
+    let window = window().unwrap();
+    let navigator = window.navigator();
+    // Not available in the desktop app or over plain http
+    if Reflect::has(&navigator, &"serviceWorker".into()).unwrap_or_default() {
+        let _ = navigator.service_worker().register("sw.js");
+    }
+    let listener = Closure::wrap(Box::new(|event: Event| {
+        event.prevent_default();
+        INSTALL.with(|install| *install.borrow_mut() = Some(event));
+    }) as Box<dyn FnMut(Event)>);
+    window
+        .add_event_listener_with_callback("beforeinstallprompt", listener.as_ref().unchecked_ref())
+        .unwrap();
+    listener.forget();
+}
+
+/// Shows the install prompt held back by [`register`], `Err` tells how to install instead.
+pub fn install() -> Result<(), &'static str> {
+    let standalone = window()
+        .and_then(|window| window.match_media("(display-mode: standalone)").ok())
+        .flatten()
+        .map_or(false, |query| query.matches());
+    if standalone {
+        return Err("Notething is already installed");
+    }
+    let event = INSTALL
+        .with(|install| install.borrow_mut().take())
+        .ok_or("The browser offers no install prompt, install from its menu instead")?;
+    // The prompt can only be shown once per event
+    Reflect::get(&event, &"prompt".into())
+        .ok()
+        .and_then(|prompt| prompt.dyn_into::<Function>().ok())
+        .and_then(|prompt| prompt.call0(&event).ok())
+        .map(|_| ())
+        .ok_or("The install prompt could not be shown")
+}
+
+fn database(request: &IdbRequest) -> Option<IdbDatabase> {
+    request.result().ok()?.dyn_into().ok()
+}
+
+/// Opens the IndexedDB database, creating its stores on first use, for `callback`.
+///
+/// Returns `false` if there is no IndexedDB, in which case `callback` is never called.
+fn open(callback: impl FnOnce(IdbDatabase) + 'static) -> bool {
+    // IndexedDB is missing in some private windows
+    let request = match window()
+        .and_then(|window| window.indexed_db().ok().flatten())
+        .and_then(|factory| factory.open_with_u32(DATABASE, 5).ok())
+    {
+        Some(request) => request,
+        None => return false,
+    };
+    let upgrading = request.clone();
+    request.set_onupgradeneeded(Some(
+        Closure::once_into_js(move || {
+            if let Some(database) = database(&upgrading) {
+                // Fails for the stores created by an earlier version
+                let _ = database.create_object_store(NOTES);
+                let _ = database.create_object_store(ATTACHMENTS);
+                let _ = database.create_object_store(HANDLES);
+                let _ = database.create_object_store(COMMITS);
+                let _ = database.create_object_store(DICTIONARIES);
+            }
+        })
+        .unchecked_ref(),
+    ));
+    let opened = request.clone();
+    request.set_onsuccess(Some(
+        Closure::once_into_js(move || {
+            if let Some(database) = database(&opened) {
+                callback(database)
+            }
+        })
+        .unchecked_ref(),
+    ));
+    true
+}
+
+fn object_store(
+    database: &IdbDatabase,
+    name: &str,
+    mode: IdbTransactionMode,
+) -> Option<IdbObjectStore> {
+    database
+        .transaction_with_str_and_mode(name, mode)
+        .ok()?
+        .object_store(name)
+        .ok()
+}
+
+/// Copies `content` of `note` to IndexedDB, or removes it there with `None`.
+pub fn mirror(note: &str, content: Option<&str>) {
+    let note = note.to_owned();
+    let content = content.map(String::from);
+    open(move |database| {
+        if let Some(store) = object_store(&database, NOTES, IdbTransactionMode::Readwrite) {
+            let key = JsValue::from_str(&note);
+            let _ = match content {
+                Some(content) => store.put_with_key(&Array::of2(&key, &content.into()), &key),
+                None => store.delete(&key),
+            };
+        }
+    });
+}
+
+/// Calls `callback` with the notes copied to IndexedDB and their content.
+pub fn mirrored(callback: Callback<Vec<(String, String)>>) {
+    open(move |database| {
+        let request = match object_store(&database, NOTES, IdbTransactionMode::Readonly)
+            .and_then(|store| store.get_all().ok())
+        {
+            Some(request) => request,
+            None => return,
+        };
+        let done = request.clone();
+        request.set_onsuccess(Some(
+            Closure::once_into_js(move || {
+                let entries = done.result().map(|result| Array::from(&result));
+                let notes = entries
+                    .iter()
+                    .flat_map(Array::iter)
+                    .filter_map(|entry| {
+                        let entry = Array::from(&entry);
+                        Some((entry.get(0).as_string()?, entry.get(1).as_string()?))
+                    })
+                    .collect();
+                callback.emit(notes);
+            })
+            .unchecked_ref(),
+        ));
+    });
+}
+
+/// Stores `blob` as the attachment `name`, or under a free name like `image-2.png` if that
+/// is taken, `done` gets the name it was stored as or `None` if that failed.
+pub fn attach(name: &str, blob: Blob, done: Callback<Option<String>>) {
+    let name = name.to_owned();
+    let failed = done.clone();
+    let opened = open(move |database| {
+        // Listed in the same transaction, so no other attachment can take the name meanwhile
+        let (store, keys) =
+            match object_store(&database, ATTACHMENTS, IdbTransactionMode::Readwrite)
+                .and_then(|store| Some((store.clone(), store.get_all_keys().ok()?)))
+            {
+                Some(found) => found,
+                None => return done.emit(None),
+            };
+        let listed = keys.clone();
+        let missing = done.clone();
+        keys.set_onsuccess(Some(
+            Closure::once_into_js(move || {
+                let taken: Vec<String> = listed
+                    .result()
+                    .map(|keys| {
+                        Array::from(&keys)
+                            .iter()
+                            .filter_map(|key| key.as_string())
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let name = attachment::free_name(&name, &taken);
+                let request = match store.put_with_key(&blob, &JsValue::from_str(&name)) {
+                    Ok(request) => request,
+                    Err(_) => return done.emit(None),
+                };
+                let stored = done.clone();
+                request.set_onsuccess(Some(
+                    Closure::once_into_js(move || stored.emit(Some(name))).unchecked_ref(),
+                ));
+                request.set_onerror(Some(
+                    Closure::once_into_js(move || done.emit(None)).unchecked_ref(),
+                ));
+            })
+            .unchecked_ref(),
+        ));
+        keys.set_onerror(Some(
+            Closure::once_into_js(move || missing.emit(None)).unchecked_ref(),
+        ));
+    });
+    if !opened {
+        failed.emit(None);
+    }
+}
+
+/// Calls `callback` with the attachment `name`, `None` if there is none.
+pub fn attachment(name: &str, callback: Callback<Option<Blob>>) {
+    let name = name.to_owned();
+    let failed = callback.clone();
+    let opened = open(move |database| {
+        let request = match object_store(&database, ATTACHMENTS, IdbTransactionMode::Readonly)
+            .and_then(|store| store.get(&JsValue::from_str(&name)).ok())
+        {
+            Some(request) => request,
+            None => return callback.emit(None),
+        };
+        let done = request.clone();
+        let missing = callback.clone();
+        request.set_onsuccess(Some(
+            Closure::once_into_js(move || {
+                let blob = done.result().ok().and_then(|blob| blob.dyn_into().ok());
+                callback.emit(blob)
+            })
+            .unchecked_ref(),
+        ));
+        request.set_onerror(Some(
+            Closure::once_into_js(move || missing.emit(None)).unchecked_ref(),
+        ));
+    });
+    if !opened {
+        failed.emit(None);
+    }
+}
+
+/// Calls `callback` with all stored attachments by name.
+pub fn attachments(callback: Callback<Vec<(String, Blob)>>) {
+    let failed = callback.clone();
+    let opened = open(move |database| {
+        let store = match object_store(&database, ATTACHMENTS, IdbTransactionMode::Readonly) {
+            Some(store) => store,
+            None => return callback.emit(vec![]),
+        };
+        // Both are in the order of the keys
+        let (keys, blobs) = match (store.get_all_keys(), store.get_all()) {
+            (Ok(keys), Ok(blobs)) => (keys, blobs),
+            _ => return callback.emit(vec![]),
+        };
+        let done = blobs.clone();
+        let missing = callback.clone();
+        blobs.set_onsuccess(Some(
+            Closure::once_into_js(move || {
+                let (keys, blobs) = match (keys.result(), done.result()) {
+                    (Ok(keys), Ok(blobs)) => (Array::from(&keys), Array::from(&blobs)),
+                    _ => return callback.emit(vec![]),
+                };
+                let attachments = keys
+                    .iter()
+                    .zip(blobs.iter())
+                    .filter_map(|(name, blob)| Some((name.as_string()?, blob.dyn_into().ok()?)))
+                    .collect();
+                callback.emit(attachments)
+            })
+            .unchecked_ref(),
+        ));
+        blobs.set_onerror(Some(
+            Closure::once_into_js(move || missing.emit(vec![])).unchecked_ref(),
+        ));
+    });
+    if !opened {
+        failed.emit(vec![]);
+    }
+}
+
+/// Keeps `handle`, like a folder picked with the File System Access API, as `name` across
+/// sessions.
+pub fn keep_handle(name: &str, handle: JsValue) {
+    let name = name.to_owned();
+    open(move |database| {
+        if let Some(store) = object_store(&database, HANDLES, IdbTransactionMode::Readwrite) {
+            let _ = store.put_with_key(&handle, &JsValue::from_str(&name));
+        }
+    });
+}
+
+/// Calls `callback` with the handle kept as `name`, `None` if there is none.
+pub fn handle(name: &str, callback: Callback<Option<JsValue>>) {
+    let name = name.to_owned();
+    let failed = callback.clone();
+    let opened = open(move |database| {
+        let request = match object_store(&database, HANDLES, IdbTransactionMode::Readonly)
+            .and_then(|store| store.get(&JsValue::from_str(&name)).ok())
+        {
+            Some(request) => request,
+            None => return callback.emit(None),
+        };
+        let done = request.clone();
+        let missing = callback.clone();
+        request.set_onsuccess(Some(
+            Closure::once_into_js(move || {
+                let handle = done.result().ok().filter(|handle| !handle.is_undefined());
+                callback.emit(handle)
+            })
+            .unchecked_ref(),
+        ));
+        request.set_onerror(Some(
+            Closure::once_into_js(move || missing.emit(None)).unchecked_ref(),
+        ));
+    });
+    if !opened {
+        failed.emit(None);
+    }
+}
+
+/// Keeps the downloaded dictionary `words` of the spell language `lang`.
+pub fn keep_dictionary(lang: &str, words: &str) {
+    let lang = lang.to_owned();
+    let words = JsValue::from_str(words);
+    open(move |database| {
+        if let Some(store) = object_store(&database, DICTIONARIES, IdbTransactionMode::Readwrite) {
+            let _ = store.put_with_key(&words, &JsValue::from_str(&lang));
+        }
+    });
+}
+
+/// Calls `callback` with the dictionary of `lang` kept with [`keep_dictionary`], `None` if
+/// there is none.
+pub fn dictionary(lang: &str, callback: Callback<Option<String>>) {
+    let lang = lang.to_owned();
+    let failed = callback.clone();
+    let opened = open(move |database| {
+        let request = match object_store(&database, DICTIONARIES, IdbTransactionMode::Readonly)
+            .and_then(|store| store.get(&JsValue::from_str(&lang)).ok())
+        {
+            Some(request) => request,
+            None => return callback.emit(None),
+        };
+        let done = request.clone();
+        let missing = callback.clone();
+        request.set_onsuccess(Some(
+            Closure::once_into_js(move || {
+                callback.emit(done.result().ok().and_then(|words| words.as_string()))
+            })
+            .unchecked_ref(),
+        ));
+        request.set_onerror(Some(
+            Closure::once_into_js(move || missing.emit(None)).unchecked_ref(),
+        ));
+    });
+    if !opened {
+        failed.emit(None);
+    }
+}
+
+/// Stores `commit`, `done` is called with whether that worked.
+pub fn commit(commit: &Commit, done: Callback<bool>) {
+    let (time, commit) = (commit.time, commit.to_js());
+    let failed = done.clone();
+    let opened = open(move |database| {
+        // Keyed by time, so they are listed in order
+        let request = match object_store(&database, COMMITS, IdbTransactionMode::Readwrite)
+            .and_then(|store| store.put_with_key(&commit, &time.into()).ok())
+        {
+            Some(request) => request,
+            None => return done.emit(false),
+        };
+        let stored = done.clone();
+        request.set_onsuccess(Some(
+            Closure::once_into_js(move || stored.emit(true)).unchecked_ref(),
+        ));
+        request.set_onerror(Some(
+            Closure::once_into_js(move || done.emit(false)).unchecked_ref(),
+        ));
+    });
+    if !opened {
+        failed.emit(false);
+    }
+}
+
+/// Calls `callback` with the commits, the oldest first.
+pub fn commits(callback: Callback<Vec<Commit>>) {
+    let failed = callback.clone();
+    let opened = open(move |database| {
+        let request = match object_store(&database, COMMITS, IdbTransactionMode::Readonly)
+            .and_then(|store| store.get_all().ok())
+        {
+            Some(request) => request,
+            None => return callback.emit(vec![]),
+        };
+        let done = request.clone();
+        let missing = callback.clone();
+        request.set_onsuccess(Some(
+            Closure::once_into_js(move || {
+                let commits = done.result().map(|result| Array::from(&result));
+                let commits = commits
+                    .iter()
+                    .flat_map(Array::iter)
+                    .filter_map(|commit| Commit::from_js(&commit))
+                    .collect();
+                callback.emit(commits);
+            })
+            .unchecked_ref(),
+        ));
+        request.set_onerror(Some(
+            Closure::once_into_js(move || missing.emit(vec![])).unchecked_ref(),
+        ));
+    });
+    if !opened {
+        failed.emit(vec![]);
+    }
+}
@@ -0,0 +1,117 @@
+use pulldown_cmark::{Event, Parser, Tag};
+use web_sys::{DomParser, Element, SupportedType};
+
+#[derive(Default)]
+struct Outline {
+    text: String,
+    children: Vec<Outline>,
+}
+
+/// Turns the (nested) lists of `md` into an OPML outline.
+pub fn md_to_opml(title: &str, md: &str) -> String {
+    let mut stack = vec![Outline::default()];
+    // Text after a nested list belongs to no outline
+    let mut in_text = false;
+    for event in Parser::new(md) {
+        match event {
+            Event::Start(Tag::Item) => {
+                stack.push(Outline::default());
+                in_text = true;
+            }
+            Event::End(Tag::Item) => {
+                let item = stack.pop().unwrap();
+                stack.last_mut().unwrap().children.push(item);
+                in_text = false;
+            }
+            Event::Start(Tag::List(_)) => in_text = false,
+            Event::Text(text) | Event::Code(text) if in_text && stack.len() > 1 => {
+                stack.last_mut().unwrap().text.push_str(&text)
+            }
+            Event::SoftBreak if in_text && stack.len() > 1 => {
+                stack.last_mut().unwrap().text.push(' ')
+            }
+            _ => {}
+        }
+    }
+
+    let mut opml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<opml version=\"2.0\">\n  <head>\n    <title>{}</title>\n  </head>\n  <body>\n",
+        escape(title)
+    );
+    for outline in &stack[0].children {
+        write_outline(outline, 2, &mut opml);
+    }
+    opml.push_str("  </body>\n</opml>\n");
+    opml
+}
+
+fn write_outline(outline: &Outline, depth: usize, opml: &mut String) {
+    let indent = "  ".repeat(depth);
+    if outline.children.is_empty() {
+        opml.push_str(&format!(
+            "{}<outline text=\"{}\"/>\n",
+            indent,
+            escape(&outline.text)
+        ));
+    } else {
+        opml.push_str(&format!(
+            "{}<outline text=\"{}\">\n",
+            indent,
+            escape(&outline.text)
+        ));
+        for child in &outline.children {
+            write_outline(child, depth + 1, opml);
+        }
+        opml.push_str(&format!("{}</outline>\n", indent));
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Turns the outlines of an OPML document into a nested list.
+pub fn opml_to_md(opml: &str) -> String {
+    let document = DomParser::new()
+        .unwrap()
+        .parse_from_string(opml, SupportedType::TextXml)
+        .unwrap();
+    let mut md = vec![];
+    if let Some(title) = document
+        .query_selector("head > title")
+        .ok()
+        .flatten()
+        .and_then(|title| title.text_content())
+    {
+        md.push(format!("# {}\n", title.trim()));
+    }
+    if let Some(body) = document.query_selector("body").ok().flatten() {
+        read_outlines(&body, 0, &mut md);
+    }
+    md.join("\n")
+}
+
+fn read_outlines(parent: &Element, depth: usize, md: &mut Vec<String>) {
+    let children = parent.children();
+    for i in 0..children.length() {
+        let outline = match children.item(i) {
+            Some(outline) if outline.tag_name() == "outline" => outline,
+            _ => continue,
+        };
+        let text = outline
+            .get_attribute("text")
+            .or_else(|| outline.get_attribute("title"))
+            .unwrap_or_default();
+        match outline
+            .get_attribute("htmlUrl")
+            .or_else(|| outline.get_attribute("xmlUrl"))
+        {
+            Some(url) => md.push(format!("{}- [{}]({})", "  ".repeat(depth), text, url)),
+            None => md.push(format!("{}- {}", "  ".repeat(depth), text)),
+        }
+        read_outlines(&outline, depth + 1, md);
+    }
+}
@@ -0,0 +1,106 @@
+use yew::Html;
+
+use crate::{
+    board::Board,
+    diff::Diff,
+    document::{Commandee, Render},
+    help::Help,
+    info::Info,
+    merge::Merge,
+    picker::Picker,
+    quickfix::GrepSub,
+    start::StartScreen,
+    tasks::Tasks,
+    ApplicationState,
+};
+
+/// Views shown instead of the document, closed with Escape.
+pub enum Overlay {
+    Diff(Diff),
+    Merge(Merge),
+    /// Deleted notes, restored with `:restore` and removed with `:purge`
+    Trash(Picker),
+    Start(StartScreen),
+    Help(Help),
+    /// Link reference definitions of the open note, changed with `:link`
+    Links(Picker),
+    GrepSub(GrepSub),
+    /// Open buffers and recently closed notes, switched to with Enter
+    Buffers(Picker),
+    /// Swap copies left by a crash, recovered with Enter
+    Recover(Picker),
+    /// The quickfix list shown with `:copen`, jumped to with Enter
+    Quickfix(Picker),
+    /// User commands listed by `:command` or abbreviations listed by `:abbrev`
+    Commands(Picker),
+    /// Past notifications listed by `:messages`
+    Messages(Picker),
+    /// Recorded log entries listed by `:log`
+    Log(Picker),
+    /// Templates listed by `:template`, used with Enter
+    Templates(Picker),
+    /// Tasks of all notes listed by `:tasks`, ticked with Enter
+    Tasks(Tasks),
+    /// Tasks of the open note by heading, shown by `:board`
+    Board(Board),
+    /// The notes an ambiguous `[[link]]` resolves to, opened with Enter
+    Notes(Picker),
+    /// Metadata of the open note shown by `:info`
+    Info(Info),
+    /// What to do with a note of an imported vault whose name is taken
+    Collision(Picker),
+    /// The commits listed by `:commits`, the open note is checked out of one with Enter
+    Commits(Picker),
+}
+
+impl Render for Overlay {
+    fn render(&self, state: &ApplicationState) -> Html {
+        match self {
+            Overlay::Diff(diff) => diff.render(state),
+            Overlay::Merge(merge) => merge.render(state),
+            Overlay::Trash(trash) => trash.render(state),
+            Overlay::Start(start) => start.render(state),
+            Overlay::Help(help) => help.render(state),
+            Overlay::Links(links) => links.render(state),
+            Overlay::GrepSub(grep_sub) => grep_sub.render(state),
+            Overlay::Buffers(buffers) => buffers.render(state),
+            Overlay::Recover(swaps) => swaps.render(state),
+            Overlay::Quickfix(quickfix) => quickfix.render(state),
+            Overlay::Commands(commands) => commands.render(state),
+            Overlay::Messages(messages) => messages.render(state),
+            Overlay::Log(log) => log.render(state),
+            Overlay::Templates(templates) => templates.render(state),
+            Overlay::Tasks(tasks) => tasks.render(state),
+            Overlay::Board(board) => board.render(state),
+            Overlay::Notes(notes) => notes.render(state),
+            Overlay::Info(info) => info.render(state),
+            Overlay::Collision(choices) => choices.render(state),
+            Overlay::Commits(commits) => commits.render(state),
+        }
+    }
+}
+
+impl Commandee for Overlay {
+    fn command(&mut self, command: &Self::Command) -> Self::Response {
+        match self {
+            Overlay::Diff(_) | Overlay::Help(_) | Overlay::Info(_) => false,
+            Overlay::Merge(merge) => merge.command(command),
+            Overlay::Trash(trash) => trash.command(command),
+            Overlay::Start(start) => start.command(command),
+            Overlay::Links(links) => links.command(command),
+            Overlay::GrepSub(grep_sub) => grep_sub.command(command),
+            Overlay::Buffers(buffers) => buffers.command(command),
+            Overlay::Recover(swaps) => swaps.command(command),
+            Overlay::Quickfix(quickfix) => quickfix.command(command),
+            Overlay::Commands(commands) => commands.command(command),
+            Overlay::Messages(messages) => messages.command(command),
+            Overlay::Log(log) => log.command(command),
+            Overlay::Templates(templates) => templates.command(command),
+            Overlay::Tasks(tasks) => tasks.command(command),
+            Overlay::Board(board) => board.command(command),
+            Overlay::Notes(notes) => notes.command(command),
+            Overlay::Collision(choices) => choices.command(command),
+            Overlay::Commits(commits) => commits.command(command),
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use pulldown_cmark::{Event, Options, Parser, Tag};
+
+use crate::document::grapheme_index;
+
+fn closing(open: &str) -> Option<&'static str> {
+    Some(match open {
+        "(" => ")",
+        "[" => "]",
+        "{" => "}",
+        _ => return None,
+    })
+}
+
+fn opening(close: &str) -> Option<&'static str> {
+    Some(match close {
+        ")" => "(",
+        "]" => "[",
+        "}" => "{",
+        _ => return None,
+    })
+}
+
+fn is_delimiter(c: &str) -> bool {
+    matches!(c, "(" | ")" | "[" | "]" | "{" | "}" | "*" | "_" | "`" | "~")
+}
+
+/// The bracket matching the one at `i`, skipping escaped brackets.
+fn matching_bracket(text: &[String], i: usize) -> Option<usize> {
+    let escaped = |j: usize| j > 0 && text[j - 1] == "\\";
+    let c = text[i].as_str();
+    let mut depth = 0;
+    if let Some(close) = closing(c) {
+        for (j, other) in text.iter().enumerate().skip(i) {
+            if escaped(j) {
+                continue;
+            }
+            if other == c {
+                depth += 1;
+            } else if other == close {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(j);
+                }
+            }
+        }
+    } else if let Some(open) = opening(c) {
+        for j in (0..=i).rev() {
+            if escaped(j) {
+                continue;
+            }
+            if text[j] == c {
+                depth += 1;
+            } else if text[j] == open {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(j);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Pairs of the opening and closing delimiter runs of emphasis, strikethrough and code.
+fn emphasis_pairs(text: &[String]) -> Vec<((usize, usize), (usize, usize))> {
+    let source = text.concat();
+    let index = grapheme_index(text);
+    let options = Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES;
+    Parser::new_ext(&source, options)
+        .into_offset_iter()
+        .filter_map(|(event, range)| {
+            let delimiter = match event {
+                Event::Start(Tag::Emphasis) => 1,
+                Event::Start(Tag::Strong) => 2,
+                Event::Start(Tag::Strikethrough) | Event::Code(_) => source[range.clone()]
+                    .chars()
+                    .take_while(|&c| c == '~' || c == '`')
+                    .count(),
+                _ => return None,
+            };
+            let (start, end) = (index(range.start), index(range.end));
+            (end >= start + 2 * delimiter)
+                .then_some(((start, start + delimiter), (end - delimiter, end)))
+        })
+        .collect()
+}
+
+/// The delimiter matching the one at `i`: brackets, emphasis, strikethrough and code.
+pub fn matching(text: &[String], i: usize) -> Option<usize> {
+    let c = text.get(i)?;
+    if closing(c).is_some() || opening(c).is_some() {
+        return matching_bracket(text, i);
+    }
+    if !is_delimiter(c) {
+        return None;
+    }
+    emphasis_pairs(text).into_iter().find_map(
+        |((open_start, open_end), (close_start, close_end))| {
+            if (open_start..open_end).contains(&i) {
+                Some(close_start + (i - open_start))
+            } else if (close_start..close_end).contains(&i) {
+                Some(open_start + (i - close_start))
+            } else {
+                None
+            }
+        },
+    )
+}
+
+/// Like vim's `%`, the match of the first delimiter on the line from `i` on.
+pub fn jump(text: &[String], i: usize) -> Option<usize> {
+    (i..text.len())
+        .take_while(|&j| j == i || text[j] != "\n")
+        .filter(|&j| is_delimiter(&text[j]))
+        .find_map(|j| matching(text, j))
+}
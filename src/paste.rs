@@ -0,0 +1,30 @@
+use std::collections::VecDeque;
+
+/// How many keys typed within [`WINDOW`] count as pasted rather than typed input.
+const BURST: usize = 12;
+/// Milliseconds the keys of a burst arrive in.
+const WINDOW: f64 = 100.;
+/// Milliseconds without input after which input detected as pasted is typed again.
+pub const IDLE: u32 = 300;
+
+/// When the last keys were typed, to tell input pasted by a terminal or a tool that types
+/// it from someone typing.
+#[derive(Debug, Default)]
+pub struct Burst {
+    /// In milliseconds since the epoch, the oldest first
+    keys: VecDeque<f64>,
+}
+
+impl Burst {
+    /// Records a key typed at `now`, returning whether it arrived in a burst.
+    pub fn key(&mut self, now: f64) -> bool {
+        while self.keys.front().map_or(false, |&key| now - key > WINDOW) {
+            self.keys.pop_front();
+        }
+        self.keys.push_back(now);
+        if self.keys.len() > BURST {
+            self.keys.pop_front();
+        }
+        self.keys.len() >= BURST
+    }
+}
@@ -0,0 +1,61 @@
+use yew::{classes, html, Html};
+
+use crate::{
+    document::{Command, Commandee, Render},
+    ApplicationState,
+};
+
+/// A titled list of entries, one of which is selected with `j`/`k`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Picker {
+    pub title: String,
+    pub items: Vec<String>,
+    pub active: usize,
+}
+
+impl Picker {
+    pub fn new(title: impl Into<String>, items: Vec<String>) -> Self {
+        Self {
+            title: title.into(),
+            items,
+            active: 0,
+        }
+    }
+
+    pub fn selected(&self) -> Option<&str> {
+        self.items.get(self.active).map(String::as_str)
+    }
+
+    /// Replaces the entries keeping the selection in bounds.
+    pub fn set_items(&mut self, items: Vec<String>) {
+        self.items = items;
+        self.active = self.active.min(self.items.len().saturating_sub(1));
+    }
+}
+
+impl Commandee for Picker {
+    fn command(&mut self, command: &Self::Command) -> Self::Response {
+        match command {
+            Command::Up if self.active > 0 => self.active -= 1,
+            Command::Down if self.active + 1 < self.items.len() => self.active += 1,
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl Render for Picker {
+    fn render(&self, _state: &ApplicationState) -> Html {
+        html! {
+            <div>
+                <p class={classes!("font-bold")}>{&self.title}</p>
+                if self.items.is_empty() {
+                    <p class={classes!("text-gray-500")}>{"(empty)"}</p>
+                }
+                {for self.items.iter().enumerate().map(|(i, item)| html!{
+                    <p class={classes!((i == self.active).then_some(classes!("bg-gray-700", "rounded")))}>{item}</p>
+                })}
+            </div>
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use std::{cell::RefCell, rc::Rc};
+
+use js_sys::{Array, Function, Object, Promise, Reflect};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+
+/// Calls `method` of `object`, `done` receives what the promise it returns settles with.
+pub fn call(
+    object: &JsValue,
+    method: &str,
+    arguments: &[JsValue],
+    done: impl FnOnce(Result<JsValue, JsValue>) + 'static,
+) {
+    let promise = Reflect::get(object, &method.into())
+        .ok()
+        .and_then(|method| method.dyn_into::<Function>().ok())
+        .and_then(|method| {
+            method
+                .apply(object, &arguments.iter().collect::<Array>())
+                .ok()
+        })
+        .and_then(|promise| promise.dyn_into::<Promise>().ok());
+    let promise = match promise {
+        Some(promise) => promise,
+        None => return done(Err(JsValue::UNDEFINED)),
+    };
+    // Only one of them is called
+    let done = Rc::new(RefCell::new(Some(done)));
+    let failed = done.clone();
+    let resolved = Closure::once(move |value: JsValue| {
+        if let Some(done) = done.borrow_mut().take() {
+            done(Ok(value))
+        }
+    });
+    let rejected = Closure::once(move |error: JsValue| {
+        if let Some(done) = failed.borrow_mut().take() {
+            done(Err(error))
+        }
+    });
+    let _ = promise.then2(&resolved, &rejected);
+    resolved.forget();
+    rejected.forget();
+}
+
+/// An object with `entries`, like the options of browser APIs.
+pub fn object(entries: &[(&str, JsValue)]) -> Object {
+    let object = Object::new();
+    for (key, value) in entries {
+        let _ = Reflect::set(&object, &(*key).into(), value);
+    }
+    object
+}
+
+/// The value at `path` in `object`, `undefined` if there is none.
+pub fn field(object: &JsValue, path: &[&str]) -> JsValue {
+    path.iter().fold(object.clone(), |object, key| {
+        Reflect::get(&object, &(*key).into()).unwrap_or(JsValue::UNDEFINED)
+    })
+}
@@ -0,0 +1,139 @@
+use js_sys::encode_uri_component;
+use pulldown_cmark::{html, Parser};
+use wasm_bindgen::JsValue;
+use web_sys::window;
+use yew::Callback;
+
+use crate::{
+    comment,
+    flavor::Flavor,
+    promise::{call, field, object},
+    remote, secret,
+};
+
+const GISTS: &str = "https://api.github.com/gists";
+
+/// Where `:publish` uploads notes, set with `:set publish=`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Service {
+    /// A secret GitHub gist, made with the token of `:token`
+    Gist,
+    /// A paste service taking the text as the form field `content` and answering with the
+    /// url of the paste, like `https://dpaste.com/api/v2/`
+    Paste(String),
+}
+
+impl Service {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "gist" => Some(Self::Gist),
+            url if url.starts_with("https://") || url.starts_with("http://") => {
+                Some(Self::Paste(url.to_owned()))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// `md` rendered as a standalone HTML page titled `title`, without its comments.
+pub fn html(title: &str, md: &str, flavor: Flavor) -> String {
+    let md = comment::strip(md);
+    let mut body = String::new();
+    html::push_html(&mut body, Parser::new_ext(&md, flavor.options()));
+    let title = title
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n{}</body>\n</html>\n",
+        title, body
+    )
+}
+
+fn gist(token: &str, file: &str, content: &str, done: Callback<Result<String, String>>) {
+    let files = object(&[(file, object(&[("content", content.into())]).into())]);
+    let body = object(&[
+        ("description", file.into()),
+        ("public", false.into()),
+        ("files", files.into()),
+    ]);
+    remote::request(GISTS, "POST", token, Some(body), move |gist| {
+        done.emit(match gist {
+            Ok(gist) => field(&gist, &["html_url"])
+                .as_string()
+                .ok_or_else(|| "GitHub did not answer with the url of the gist".to_owned()),
+            Err(401 | 403) => Err("GitHub refused the token, it needs the gist scope".to_owned()),
+            Err(0) => Err("GitHub could not be reached".to_owned()),
+            Err(status) => Err(format!("GitHub answered with status {}", status)),
+        })
+    });
+}
+
+fn paste(url: &str, content: &str, done: Callback<Result<String, String>>) {
+    let headers = object(&[("Content-Type", "application/x-www-form-urlencoded".into())]);
+    let body = format!("content={}", String::from(encode_uri_component(content)));
+    let init = object(&[
+        ("method", "POST".into()),
+        ("headers", headers.into()),
+        ("body", body.into()),
+    ]);
+    let window: JsValue = window().unwrap().into();
+    let failed = || Err("The paste service could not be reached".to_owned());
+    call(
+        &window,
+        "fetch",
+        &[url.into(), init.into()],
+        move |response| {
+            let response = match response {
+                Ok(response) if field(&response, &["ok"]).as_bool() == Some(true) => response,
+                Ok(response) => {
+                    let status = field(&response, &["status"]).as_f64().unwrap_or_default();
+                    return done.emit(Err(format!(
+                        "The paste service answered with status {}",
+                        status
+                    )));
+                }
+                Err(_) => return done.emit(failed()),
+            };
+            call(&response, "text", &[], move |text| {
+                let url = text
+                    .ok()
+                    .and_then(|text| text.as_string())
+                    .map(|text| text.trim().to_owned())
+                    .filter(|text| text.starts_with("http"));
+                done.emit(url.ok_or_else(|| {
+                    "The paste service did not answer with the url of the paste".to_owned()
+                }))
+            });
+        },
+    );
+}
+
+/// Uploads `content` as `file` to `service`, `done` receives the url it can be shared by.
+///
+/// `token` is the token of `:token` as it is stored, which gists need.
+pub fn publish(
+    service: &Service,
+    token: Option<String>,
+    file: String,
+    content: String,
+    done: Callback<Result<String, String>>,
+) {
+    match service {
+        Service::Gist => match token {
+            Some(token) => secret::decrypt(
+                &token,
+                Callback::from(move |token: Option<String>| match token {
+                    Some(token) => gist(&token, &file, &content, done.clone()),
+                    None => done.emit(Err(
+                        "The token could not be decrypted, set it again with :token".to_owned(),
+                    )),
+                }),
+            ),
+            None => done.emit(Err(
+                "Gists need a GitHub token, set one with :token".to_owned()
+            )),
+        },
+        Service::Paste(url) => paste(url, &content, done),
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use yew::{classes, html, Html};
+
+use crate::{
+    document::Document,
+    info,
+    store::Storage,
+    tasks::{self, Task},
+    ApplicationState,
+};
+
+/// The tasks a query with `status:` lists.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum State {
+    Todo,
+    Done,
+    Overdue,
+}
+
+/// The filters of a ```` ```query ```` block, all of which have to match.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Query {
+    /// `tag:#project`, the note has the tag
+    tags: Vec<String>,
+    /// `status:todo`, `status:done` or `status:overdue`, tasks are listed instead of notes
+    state: Option<State>,
+    /// `folder:projects`, the note is in the folder
+    folder: Option<String>,
+    /// Other words the note or task contains, ignoring case
+    words: Vec<String>,
+}
+
+/// A note or task a query matched.
+#[derive(Clone, Debug, Hash, PartialEq)]
+pub enum Row {
+    Note(String),
+    /// The note of the task, whether it is done and its text
+    Task(String, bool, String),
+}
+
+/// The query of a paragraph that is a ```` ```query ```` block, on one line or with the
+/// query on the lines in between.
+pub fn source(text: &str) -> Option<&str> {
+    let query = text.trim().strip_prefix("```query")?.strip_suffix("```")?;
+    (!query.contains("```")).then(|| query.trim())
+}
+
+impl Query {
+    pub fn parse(query: &str) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        for term in query.split_whitespace() {
+            match term.split_once(':') {
+                Some(("tag", tag)) => parsed.tags.push(tag.trim_start_matches('#').to_owned()),
+                Some(("status", state)) => {
+                    parsed.state = Some(match state {
+                        "todo" => State::Todo,
+                        "done" => State::Done,
+                        "overdue" => State::Overdue,
+                        _ => return Err("status must be todo, done or overdue".to_owned()),
+                    })
+                }
+                Some(("folder", folder)) => {
+                    parsed.folder = Some(folder.trim_end_matches('/').to_owned())
+                }
+                _ => parsed.words.push(term.to_lowercase()),
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn contains_words(&self, text: &str) -> bool {
+        let text = text.to_lowercase();
+        self.words.iter().all(|word| text.contains(word.as_str()))
+    }
+
+    fn matches_task(&self, task: &Task, state: State, today: &str) -> bool {
+        let status = match state {
+            State::Todo => !task.done,
+            State::Done => task.done,
+            State::Overdue => task.overdue(today),
+        };
+        status && self.contains_words(&task.text)
+    }
+
+    /// The stored notes or, with `status:`, their tasks that match, by note name.
+    pub fn run(&self, store: &dyn Storage, today: &str) -> Vec<Row> {
+        let mut notes = store.notes();
+        notes.sort();
+        let mut rows = vec![];
+        for note in notes {
+            let in_folder = self.folder.as_ref().map_or(true, |folder| {
+                note.strip_prefix(folder.as_str())
+                    .map_or(false, |rest| rest.starts_with('/'))
+            });
+            if !in_folder || store.archived(&note) {
+                continue;
+            }
+            let md = store.load(&note).unwrap_or_default();
+            let tags = info::tags(&md);
+            if !self.tags.iter().all(|tag| tags.contains(tag)) {
+                continue;
+            }
+            match self.state {
+                Some(state) => rows.extend(
+                    tasks::parse(&note, &md)
+                        .into_iter()
+                        .filter(|task| self.matches_task(task, state, today))
+                        .map(|task| Row::Task(task.note, task.done, task.text)),
+                ),
+                None if self.contains_words(&md) => rows.push(Row::Note(note)),
+                None => {}
+            }
+        }
+        rows
+    }
+}
+
+/// Runs the query blocks in `document` that are not in `loaded` yet, adding their results
+/// by query.
+pub fn load(
+    document: &Document,
+    store: &dyn Storage,
+    today: &str,
+    loaded: &mut HashMap<String, Result<Vec<Row>, String>>,
+) {
+    for (_, paragraph) in document.paragraphs() {
+        let query = match source(&paragraph.text.concat()) {
+            Some(query) if !loaded.contains_key(query) => query.to_owned(),
+            _ => continue,
+        };
+        let rows = Query::parse(&query).map(|parsed| parsed.run(store, today));
+        loaded.insert(query, rows);
+    }
+}
+
+/// The results of `query` as a list, in place of the block while the cursor is not in it.
+pub fn render(query: &str, state: &ApplicationState) -> Html {
+    let rows = match state.queries.get(query) {
+        Some(Ok(rows)) => rows,
+        Some(Err(error)) => {
+            return html! {
+                <p class={classes!("text-red-400")}>{format!("```query {}```: {}", query, error)}</p>
+            }
+        }
+        None => return html! {},
+    };
+    let count = rows.len();
+    let rows = rows.iter().map(|row| match row {
+        Row::Note(note) => html! {<li>{note}</li>},
+        Row::Task(note, done, text) => html! {
+            <li>
+                {if *done { "[x] " } else { "[ ] " }}{text}
+                <span class={classes!("text-gray-500", "ml-2")}>{note}</span>
+            </li>
+        },
+    });
+    html! {
+        <div class={classes!("border-l-4", "border-gray-600", "pl-2", "my-1")}>
+            <p class={classes!("text-sm", "text-gray-500")}>{format!("{} ({} found)", query, count)}</p>
+            if count == 0 {
+                <p class={classes!("text-gray-500")}>{"Nothing matches"}</p>
+            } else {
+                <ul class={classes!("list-disc", "pl-6")}>{for rows}</ul>
+            }
+        </div>
+    }
+}
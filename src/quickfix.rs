@@ -0,0 +1,301 @@
+use yew::{classes, html, Html};
+
+use crate::{
+    document::{grapheme_index, Command, Commandee, Document, Location, Render},
+    links::{self, Target},
+    lint::Lint,
+    picker::Picker,
+    search::{Search, Substitute},
+    store::Storage,
+    wiki, ApplicationState,
+};
+
+/// The lines of a note changed by a [`GrepSub`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct NoteChanges {
+    pub note: String,
+    /// Line numbers with the old and new line
+    pub lines: Vec<(usize, String, String)>,
+    /// Whether the replacement is applied to this note
+    pub selected: bool,
+}
+
+/// Preview of `:grepsub` across all stored notes.
+///
+/// Notes are selected with `j`/`k`, `Enter` includes or excludes one and `:apply`
+/// replaces in the included notes.
+#[derive(Clone, Debug)]
+pub struct GrepSub {
+    pub substitute: Substitute,
+    pub notes: Vec<NoteChanges>,
+    pub active: usize,
+}
+
+impl GrepSub {
+    /// The changes to the stored notes, with the archived ones only if `archived`.
+    pub fn new(substitute: Substitute, store: &dyn Storage, archived: bool) -> Self {
+        let notes = if archived {
+            store.notes()
+        } else {
+            store.listed()
+        };
+        let notes = notes
+            .into_iter()
+            .filter_map(|note| {
+                let lines = substitute.changed_lines(&store.load(&note)?);
+                (!lines.is_empty()).then_some(NoteChanges {
+                    note,
+                    lines,
+                    selected: true,
+                })
+            })
+            .collect();
+        Self {
+            substitute,
+            notes,
+            active: 0,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        if let Some(note) = self.notes.get_mut(self.active) {
+            note.selected = !note.selected;
+        }
+    }
+
+    pub fn selected(&self) -> impl Iterator<Item = &str> {
+        self.notes
+            .iter()
+            .filter(|note| note.selected)
+            .map(|note| note.note.as_str())
+    }
+}
+
+impl Commandee for GrepSub {
+    fn command(&mut self, command: &Self::Command) -> Self::Response {
+        match command {
+            Command::Up if self.active > 0 => self.active -= 1,
+            Command::Down if self.active + 1 < self.notes.len() => self.active += 1,
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl Render for GrepSub {
+    fn render(&self, _state: &ApplicationState) -> Html {
+        let count: usize = self.notes.iter().map(|note| note.lines.len()).sum();
+        html! {
+            <div>
+                <p class={classes!("font-bold")}>
+                    {format!("{} lines in {} notes, :apply replaces in the checked notes", count, self.notes.len())}
+                </p>
+                {for self.notes.iter().enumerate().map(|(i, note)| html!{
+                    <div class={classes!("rounded", "p-1", (i == self.active).then_some(classes!("ring-2", "ring-gray-400")))}>
+                        <p class={classes!("font-bold")}>
+                            {if note.selected { "[x] " } else { "[ ] " }}{&note.note}
+                        </p>
+                        {for note.lines.iter().map(|(number, old, new)| html!{
+                            <>
+                                <p class={classes!("bg-red-900", "text-red-300")}>{format!("{:>4} - {}", number, old)}</p>
+                                <p class={classes!("bg-green-900", "text-green-300")}>{format!("{:>4} + {}", number, new)}</p>
+                            </>
+                        })}
+                    </div>
+                })}
+            </div>
+        }
+    }
+}
+
+/// A position in a note with what was found there.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Item {
+    /// The note, `None` for an unnamed buffer
+    pub note: Option<String>,
+    pub location: Location,
+    pub cursor: usize,
+    pub text: String,
+}
+
+/// The list filled by `:grep`, `:lint`, `:doctor` and `:backlinks`.
+///
+/// `:copen` shows it with Enter jumping to an item, `:cnext` and `:cprev` step through it.
+#[derive(Clone, Debug, Default)]
+pub struct Quickfix {
+    pub title: String,
+    pub items: Vec<Item>,
+    pub current: usize,
+    /// Whether `current` was jumped to, the first `:cnext` goes to it otherwise
+    pub visited: bool,
+}
+
+/// The line of `text` around the grapheme `cursor`, shortened for a list.
+fn excerpt(text: &[String], cursor: usize) -> String {
+    let start = text[..cursor.min(text.len())]
+        .iter()
+        .rposition(|c| c == "\n")
+        .map_or(0, |i| i + 1);
+    let line: String = text[start..]
+        .iter()
+        .take_while(|c| *c != "\n")
+        .take(60)
+        .map(String::as_str)
+        .collect();
+    line.trim().to_owned()
+}
+
+impl Quickfix {
+    /// The `findings` in `note` with where they start.
+    pub fn new(
+        title: impl Into<String>,
+        note: Option<&str>,
+        findings: Vec<(String, Location, usize)>,
+    ) -> Self {
+        let items = findings
+            .into_iter()
+            .map(|(text, location, cursor)| Item {
+                note: note.map(String::from),
+                location,
+                cursor,
+                text,
+            })
+            .collect();
+        Self {
+            title: title.into(),
+            items,
+            current: 0,
+            visited: false,
+        }
+    }
+
+    /// The findings of `lint` in `document`, the open buffer of `note`.
+    pub fn lint(lint: &Lint, note: Option<&str>, document: &Document) -> Self {
+        let mut findings = vec![];
+        for (location, paragraph) in document.paragraphs() {
+            for finding in lint.check(&paragraph.text) {
+                let excerpt: String = paragraph.text[finding.range.clone()]
+                    .iter()
+                    .take(40)
+                    .map(|c| if c == "\n" { " " } else { c.as_str() })
+                    .collect();
+                let message = format!("{}: {}", finding.rule.message(), excerpt);
+                findings.push((message, location, finding.range.start));
+            }
+        }
+        Self::new("Lint", note, findings)
+    }
+
+    /// The matches of `search` in the stored notes, in the archived ones only if `archived`.
+    pub fn grep(search: &Search, store: &dyn Storage, archived: bool) -> Self {
+        let notes = if archived {
+            store.notes()
+        } else {
+            store.listed()
+        };
+        let mut items = vec![];
+        for note in notes {
+            let document = Document::from_md(&store.load(&note).unwrap_or_default());
+            for (location, paragraph) in document.paragraphs() {
+                for found in search.matches(&paragraph.text) {
+                    items.push(Item {
+                        note: Some(note.clone()),
+                        location,
+                        cursor: found.start,
+                        text: excerpt(&paragraph.text, found.start),
+                    });
+                }
+            }
+        }
+        Self {
+            title: format!("Grep {}", search.regex),
+            items,
+            current: 0,
+            visited: false,
+        }
+    }
+
+    /// The links to `note` from the other stored notes, by its name or `#` and its name, and
+    /// the wiki links resolving to it by its name or an alias.
+    pub fn backlinks(note: &str, store: &dyn Storage) -> Self {
+        let notes = wiki::Index::new(store);
+        let mut items = vec![];
+        for other in store.notes().into_iter().filter(|other| other != note) {
+            let document = Document::from_md(&store.load(&other).unwrap_or_default());
+            for (location, paragraph) in document.paragraphs() {
+                let source = paragraph.text.concat();
+                let index = grapheme_index(&paragraph.text);
+                for link in links::links(&source) {
+                    let linked = match &link.target {
+                        Target::Inline(destination) => {
+                            let href = links::href(destination);
+                            href == note || href.strip_prefix('#') == Some(note)
+                        }
+                        Target::Reference(_) => false,
+                    };
+                    if linked {
+                        let cursor = index(link.range.start);
+                        items.push(Item {
+                            note: Some(other.clone()),
+                            location,
+                            cursor,
+                            text: excerpt(&paragraph.text, cursor),
+                        });
+                    }
+                }
+                for link in wiki::links(&source) {
+                    if notes
+                        .resolve(&link.target)
+                        .iter()
+                        .any(|target| target == note)
+                    {
+                        let cursor = index(link.range.start);
+                        items.push(Item {
+                            note: Some(other.clone()),
+                            location,
+                            cursor,
+                            text: excerpt(&paragraph.text, cursor),
+                        });
+                    }
+                }
+            }
+        }
+        Self {
+            title: format!("Backlinks to {}", note),
+            items,
+            current: 0,
+            visited: false,
+        }
+    }
+
+    /// The list for the overlay, with the current item selected.
+    pub fn picker(&self) -> Picker {
+        let items = self
+            .items
+            .iter()
+            .map(|item| match &item.note {
+                Some(note) => format!("{}: {}", note, item.text),
+                None => item.text.clone(),
+            })
+            .collect();
+        let mut picker = Picker::new(&self.title, items);
+        picker.active = self.current;
+        picker
+    }
+
+    /// Moves to the next item, or the previous one if `backward`, and returns it.
+    ///
+    /// The first step after the list was filled stays at its first item.
+    pub fn step(&mut self, backward: bool) -> Option<&Item> {
+        let current = if !self.visited {
+            self.current
+        } else if backward {
+            self.current.checked_sub(1)?
+        } else {
+            self.current + 1
+        };
+        let item = self.items.get(current)?;
+        self.current = current;
+        Some(item)
+    }
+}
@@ -0,0 +1,56 @@
+use js_sys::Reflect;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{window, Notification, NotificationOptions, NotificationPermission};
+
+use crate::tasks::Task;
+
+/// How many tasks a reminder lists before it only counts them.
+const LISTED: usize = 5;
+
+/// Shows a system notification for the `tasks` that are due.
+///
+/// Returns `false` if the browser has no notifications or they were blocked.
+pub fn remind(tasks: &[Task]) -> bool {
+    let title = match tasks.len() {
+        1 => "1 task is due".to_owned(),
+        count => format!("{} tasks are due", count),
+    };
+    let mut body: Vec<_> = tasks
+        .iter()
+        .take(LISTED)
+        .map(|task| format!("{}: {}", task.note, task.text))
+        .collect();
+    if tasks.len() > LISTED {
+        body.push(format!("and {} more", tasks.len() - LISTED));
+    }
+    show(title, body.join("\n"))
+}
+
+/// Shows a system notification, asking for permission first.
+///
+/// Returns `false` if the browser has no notifications or they were blocked.
+pub fn show(title: String, body: String) -> bool {
+    let supported = window().map_or(false, |window| {
+        Reflect::has(&window, &"Notification".into()).unwrap_or_default()
+    });
+    if !supported {
+        return false;
+    }
+    let show = move || {
+        let _ = Notification::new_with_options(&title, NotificationOptions::new().body(&body));
+    };
+    match Notification::permission() {
+        NotificationPermission::Granted => show(),
+        NotificationPermission::Default => {
+            let asked = Closure::once_into_js(move |permission: JsValue| {
+                if permission.as_string().as_deref() == Some("granted") {
+                    show();
+                }
+            });
+            let _ =
+                Notification::request_permission_with_permission_callback(asked.unchecked_ref());
+        }
+        _ => return false,
+    }
+    true
+}
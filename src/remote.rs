@@ -0,0 +1,466 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use js_sys::{encode_uri_component, Array, Object, JSON};
+use wasm_bindgen::JsValue;
+use web_sys::window;
+use yew::Callback;
+
+use crate::promise::{call, field, object};
+
+/// The folder of the repository the notes are kept in, like in `:export vault`.
+const FOLDER: &str = "notes";
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// The SHA-1 of `data`.
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+    for block in message.chunks(64) {
+        let mut words = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            words[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            words[i] = (words[i - 3] ^ words[i - 8] ^ words[i - 14] ^ words[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (i, word) in words.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5a827999),
+                20..=39 => (b ^ c ^ d, 0x6ed9eba1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
+                _ => (b ^ c ^ d, 0xca62c1d6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (state, value) in state.iter_mut().zip([a, b, c, d, e]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+    let mut hash = [0; 20];
+    for (bytes, word) in hash.chunks_mut(4).zip(state) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    hash
+}
+
+/// The id git gives `content` as a file, which the repository lists for each of them.
+pub fn blob_sha(content: &str) -> String {
+    let mut blob = format!("blob {}\0", content.len()).into_bytes();
+    blob.extend_from_slice(content.as_bytes());
+    sha1(&blob)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+pub fn base64(data: &[u8]) -> String {
+    let mut encoded = String::new();
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64[(bits >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Decodes `encoded`, which may be split over lines as the repository sends it.
+pub fn from_base64(encoded: &str) -> Option<Vec<u8>> {
+    let mut decoded = vec![];
+    let (mut bits, mut count) = (0u32, 0);
+    for byte in encoded.bytes() {
+        if byte.is_ascii_whitespace() || byte == b'=' {
+            continue;
+        }
+        let value = BASE64.iter().position(|&c| c == byte)? as u32;
+        bits = bits << 6 | value;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            decoded.push((bits >> count) as u8);
+        }
+    }
+    Some(decoded)
+}
+
+/// Where `:push` and `:pull` sync the notes with, set with `:set remote=`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Remote {
+    pub github: bool,
+    /// The url of the contents endpoint of the repository
+    contents: String,
+    pub branch: Option<String>,
+}
+
+impl Remote {
+    /// Reads `github:owner/repo` or the url of a repository on GitHub or a Gitea server, like
+    /// `https://gitea.example.com/owner/repo`, followed by `#branch` for another branch than
+    /// the default one.
+    pub fn parse(remote: &str) -> Option<Self> {
+        let (remote, branch) = match remote.split_once('#') {
+            Some((remote, branch)) => (remote, Some(branch.to_owned())),
+            None => (remote, None),
+        };
+        let remote = remote.trim_end_matches('/');
+        let remote = remote.strip_suffix(".git").unwrap_or(remote);
+        let (server, repository) = match remote.strip_prefix("github:") {
+            Some(repository) => (None, repository),
+            None => {
+                let (scheme, rest) = remote.split_once("://")?;
+                let (host, repository) = rest.split_once('/')?;
+                // The token is sent with every request, so only a local server may do
+                // without TLS
+                let name = match host.rsplit_once(':') {
+                    Some((name, port)) if !port.ends_with(']') => name,
+                    _ => host,
+                };
+                let local = matches!(name, "localhost" | "127.0.0.1" | "[::1]");
+                if scheme != "https" && !(scheme == "http" && local) {
+                    return None;
+                }
+                let server = (host != "github.com").then(|| format!("{}://{}", scheme, host));
+                (server, repository)
+            }
+        };
+        match repository.split('/').collect::<Vec<_>>()[..] {
+            [owner, name] if !owner.is_empty() && !name.is_empty() => {}
+            _ => return None,
+        }
+        Some(Self {
+            github: server.is_none(),
+            contents: match server {
+                Some(server) => format!("{}/api/v1/repos/{}/contents", server, repository),
+                None => format!("https://api.github.com/repos/{}/contents", repository),
+            },
+            branch,
+        })
+    }
+
+    /// The url of `path` in the repository.
+    fn url(&self, path: &str) -> String {
+        let path: Vec<_> = path
+            .split('/')
+            .map(|segment| String::from(encode_uri_component(segment)))
+            .collect();
+        format!("{}/{}", self.contents, path.join("/"))
+    }
+
+    /// The url of `path` on the branch, for reading it.
+    fn read_url(&self, path: &str) -> String {
+        let mut url = self.url(path);
+        if let Some(branch) = &self.branch {
+            url.push_str(&format!("?ref={}", encode_uri_component(branch)));
+        }
+        url
+    }
+}
+
+fn path(note: &str) -> String {
+    format!("{}/{}.md", FOLDER, note)
+}
+
+/// What to do with the notes, by name, for a `:push` or `:pull`.
+#[derive(Debug, Default, PartialEq)]
+pub struct Plan {
+    /// The notes to send or fetch
+    pub transfers: Vec<String>,
+    /// The notes that changed on both sides since the last sync, which are left alone
+    pub conflicts: Vec<String>,
+    /// The notes already the same on both sides, with their id
+    pub unchanged: Vec<(String, String)>,
+}
+
+/// Plans sending the notes of `from` to `to`, both by the [`blob_sha`] of each note.
+///
+/// A note that differs is sent if `to` still has the version of the last sync, which
+/// `synced` gives, and left alone if only `to` changed it.
+pub fn plan(
+    from: &HashMap<String, String>,
+    to: &HashMap<String, String>,
+    synced: impl Fn(&str) -> Option<String>,
+) -> Plan {
+    let mut plan = Plan::default();
+    for (note, sha) in from {
+        match to.get(note) {
+            None => plan.transfers.push(note.clone()),
+            Some(other) if other == sha => plan.unchanged.push((note.clone(), sha.clone())),
+            Some(other) => match synced(note) {
+                Some(synced) if synced == *other => plan.transfers.push(note.clone()),
+                // The other direction sends the change of `to`
+                Some(synced) if synced == *sha => {}
+                _ => plan.conflicts.push(note.clone()),
+            },
+        }
+    }
+    plan.transfers.sort();
+    plan.conflicts.sort();
+    plan
+}
+
+/// A note sent to or fetched from the repository.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Transfer {
+    pub note: String,
+    /// Its [`blob_sha`] in the repository
+    pub sha: String,
+    /// The fetched content
+    pub content: Option<String>,
+}
+
+fn error(status: u16) -> String {
+    match status {
+        0 => "The repository could not be reached".to_owned(),
+        401 | 403 => "The repository refused the token".to_owned(),
+        404 => "The repository was not found".to_owned(),
+        409 | 422 => "The note changed in the repository meanwhile, :pull first".to_owned(),
+        status => format!("The repository answered with status {}", status),
+    }
+}
+
+/// Sends a request with `token` to `url`, `done` receives the json it answers with or the
+/// status it failed with, `0` if there was no answer.
+pub fn request(
+    url: &str,
+    method: &str,
+    token: &str,
+    body: Option<Object>,
+    done: impl FnOnce(Result<JsValue, u16>) + 'static,
+) {
+    let headers = object(&[
+        ("Authorization", format!("token {}", token).into()),
+        ("Accept", "application/json".into()),
+        ("Content-Type", "application/json".into()),
+    ]);
+    let mut init: Vec<(_, JsValue)> = vec![("method", method.into()), ("headers", headers.into())];
+    if let Some(body) = body.and_then(|body| JSON::stringify(&body).ok()) {
+        init.push(("body", body.into()));
+    }
+    let window: JsValue = window().unwrap().into();
+    call(
+        &window,
+        "fetch",
+        &[url.into(), object(&init).into()],
+        move |response| {
+            let response = match response {
+                Ok(response) => response,
+                Err(_) => return done(Err(0)),
+            };
+            let status = field(&response, &["status"]).as_f64().unwrap_or_default() as u16;
+            call(&response, "json", &[], move |json| match json {
+                Ok(json) if (200..300).contains(&status) => done(Ok(json)),
+                _ => done(Err(status)),
+            });
+        },
+    );
+}
+
+/// The notes in the repository, listed in pieces as each folder needs its own request.
+struct Listing {
+    remote: Remote,
+    token: String,
+    /// Folders still listed
+    pending: usize,
+    notes: HashMap<String, String>,
+    error: Option<String>,
+    done: Callback<Result<HashMap<String, String>, String>>,
+}
+
+fn list_folder(listing: Rc<RefCell<Listing>>, folder: String) {
+    let (url, token) = {
+        let listing = listing.borrow();
+        (listing.remote.read_url(&folder), listing.token.clone())
+    };
+    request(&url, "GET", &token, None, move |entries| {
+        let mut folders = vec![];
+        {
+            let mut listing = listing.borrow_mut();
+            match entries {
+                Ok(entries) => {
+                    for entry in Array::from(&entries).iter() {
+                        let text = |key| field(&entry, &[key]).as_string().unwrap_or_default();
+                        let path = text("path");
+                        match text("type").as_str() {
+                            "dir" => folders.push(path),
+                            "file" => {
+                                let note = path
+                                    .strip_prefix(&format!("{}/", FOLDER))
+                                    .and_then(|path| path.strip_suffix(".md"));
+                                if let Some(note) = note {
+                                    listing.notes.insert(note.to_owned(), text("sha"));
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                // Nothing was pushed yet
+                Err(404) if folder == FOLDER => {}
+                Err(status) => listing.error = Some(error(status)),
+            }
+            listing.pending += folders.len();
+            listing.pending -= 1;
+            if listing.pending == 0 {
+                let notes = std::mem::take(&mut listing.notes);
+                listing.done.emit(match listing.error.take() {
+                    Some(error) => Err(error),
+                    None => Ok(notes),
+                });
+            }
+        }
+        for folder in folders {
+            list_folder(listing.clone(), folder);
+        }
+    });
+}
+
+/// Calls `done` with the notes in the repository and their [`blob_sha`].
+pub fn list(remote: &Remote, token: &str, done: Callback<Result<HashMap<String, String>, String>>) {
+    let listing = Listing {
+        remote: remote.clone(),
+        token: token.to_owned(),
+        pending: 1,
+        notes: HashMap::new(),
+        error: None,
+        done,
+    };
+    list_folder(Rc::new(RefCell::new(listing)), FOLDER.to_owned());
+}
+
+/// Sends `content` of `note` to the repository, replacing the file with the id `sha`.
+pub fn upload(
+    remote: &Remote,
+    token: &str,
+    (note, content): (String, String),
+    sha: Option<String>,
+    done: Callback<Result<Transfer, String>>,
+) {
+    let message = match sha {
+        Some(_) => format!("Update {}", note),
+        None => format!("Add {}", note),
+    };
+    let mut body: Vec<(_, JsValue)> = vec![
+        ("message", message.into()),
+        ("content", base64(content.as_bytes()).into()),
+    ];
+    if let Some(branch) = &remote.branch {
+        body.push(("branch", branch.into()));
+    }
+    // Gitea creates files with POST and only replaces them with PUT
+    let method = if sha.is_some() || remote.github {
+        "PUT"
+    } else {
+        "POST"
+    };
+    if let Some(sha) = sha {
+        body.push(("sha", sha.into()));
+    }
+    let url = remote.url(&path(&note));
+    request(&url, method, token, Some(object(&body)), move |response| {
+        done.emit(match response {
+            Ok(response) => Ok(Transfer {
+                sha: field(&response, &["content", "sha"])
+                    .as_string()
+                    .unwrap_or_default(),
+                note,
+                content: None,
+            }),
+            Err(status) => Err(error(status)),
+        })
+    });
+}
+
+/// Fetches `note` from the repository.
+pub fn download(
+    remote: &Remote,
+    token: &str,
+    note: String,
+    done: Callback<Result<Transfer, String>>,
+) {
+    request(
+        &remote.read_url(&path(&note)),
+        "GET",
+        token,
+        None,
+        move |response| {
+            let response = match response {
+                Ok(response) => response,
+                Err(status) => return done.emit(Err(error(status))),
+            };
+            let content = field(&response, &["content"])
+                .as_string()
+                .and_then(|content| from_base64(&content))
+                .and_then(|content| String::from_utf8(content).ok());
+            done.emit(match content {
+                Some(content) => Ok(Transfer {
+                    sha: field(&response, &["sha"]).as_string().unwrap_or_default(),
+                    note,
+                    content: Some(content),
+                }),
+                None => Err(format!("{} in the repository is no text", note)),
+            })
+        },
+    );
+}
+
+/// A `:push` or `:pull` waiting for its transfers.
+#[derive(Default)]
+pub struct Progress {
+    pub push: bool,
+    /// Transfers still running or waiting
+    pub pending: usize,
+    /// Notes of a `:push` waiting for the upload before, with their content and the id
+    /// in the repository they replace, as GitHub refuses commits to a branch at once
+    pub uploads: Vec<(String, String, Option<String>)>,
+    /// The token the uploads are sent with
+    pub token: String,
+    pub transferred: usize,
+    pub conflicts: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+impl Progress {
+    /// The notification once all transfers are done.
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "{} {} notes",
+            if self.push { "Pushed" } else { "Pulled" },
+            self.transferred
+        );
+        if !self.conflicts.is_empty() {
+            summary.push_str(&format!(
+                ", {} changed on both sides: {}",
+                self.conflicts.len(),
+                self.conflicts.join(", ")
+            ));
+        }
+        if let Some(error) = self.errors.first() {
+            summary.push_str(&format!(", {} failed: {}", self.errors.len(), error));
+        }
+        summary
+    }
+}
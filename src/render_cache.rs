@@ -0,0 +1,42 @@
+use std::{
+    cell::RefCell,
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+
+use yew::Html;
+
+/// Rendered elements by a hash of their content and everything their rendering depends on.
+///
+/// Entries not used while rendering a frame are dropped at the start of the next one, so
+/// only the elements of the last [`Document`](crate::document::Document) renders are kept.
+#[derive(Default)]
+pub struct RenderCache {
+    current: RefCell<HashMap<u64, Html>>,
+    previous: RefCell<HashMap<u64, Html>>,
+}
+
+impl RenderCache {
+    /// The output cached for `key`, or else that of `render` which is cached.
+    pub fn get_or_render(&self, key: u64, render: impl FnOnce() -> Html) -> Html {
+        if let Some(html) = self.current.borrow().get(&key) {
+            return html.clone();
+        }
+        let cached = self.previous.borrow_mut().remove(&key);
+        let html = cached.unwrap_or_else(render);
+        self.current.borrow_mut().insert(key, html.clone());
+        html
+    }
+
+    /// Starts a frame, forgetting the entries that were not used in the last one.
+    pub fn sweep(&self) {
+        let current = self.current.take();
+        self.previous.replace(current);
+    }
+}
+
+pub fn key(value: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
@@ -0,0 +1,75 @@
+use crate::store::Storage;
+
+/// Setting of the abbreviations of `:abbrev`, which expand when a non-word character is
+/// typed after them unless it follows Ctrl+V.
+pub const ABBREVIATIONS: &str = "abbreviations";
+
+/// A user-editable table of words and what they are replaced with, stored in the settings
+/// under `setting`.
+///
+/// Used for the abbreviations of `:abbrev` and the corrections of `:correct`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replacements {
+    setting: &'static str,
+    replacements: Vec<(String, String)>,
+}
+
+impl Replacements {
+    /// The stored table, `defaults` until it is first changed.
+    pub fn load(store: &dyn Storage, setting: &'static str, defaults: &[(&str, &str)]) -> Self {
+        let replacements = match store.setting(setting) {
+            Some(stored) => stored
+                .lines()
+                .filter_map(|line| line.split_once(' '))
+                .map(|(word, replacement)| (word.to_owned(), replacement.to_owned()))
+                .collect(),
+            None => defaults
+                .iter()
+                .map(|(word, replacement)| (word.to_string(), replacement.to_string()))
+                .collect(),
+        };
+        Self {
+            setting,
+            replacements,
+        }
+    }
+
+    fn save(&self, store: &mut dyn Storage) {
+        let lines: Vec<_> = self
+            .replacements
+            .iter()
+            .map(|(word, replacement)| format!("{} {}", word, replacement))
+            .collect();
+        store.set_setting(self.setting, &lines.join("\n"));
+    }
+
+    pub fn define(&mut self, store: &mut dyn Storage, word: &str, replacement: &str) {
+        self.replacements.retain(|(defined, _)| defined != word);
+        self.replacements
+            .push((word.to_owned(), replacement.to_owned()));
+        self.save(store);
+    }
+
+    /// Removes `word`, `false` if it is not in the table.
+    pub fn remove(&mut self, store: &mut dyn Storage, word: &str) -> bool {
+        let len = self.replacements.len();
+        self.replacements.retain(|(defined, _)| defined != word);
+        self.save(store);
+        self.replacements.len() != len
+    }
+
+    pub fn get(&self, word: &str) -> Option<&str> {
+        self.replacements
+            .iter()
+            .find(|(defined, _)| defined == word)
+            .map(|(_, replacement)| replacement.as_str())
+    }
+
+    /// Lines of the listing command.
+    pub fn list(&self) -> Vec<String> {
+        self.replacements
+            .iter()
+            .map(|(word, replacement)| format!("{:<12} {}", word, replacement))
+            .collect()
+    }
+}
@@ -0,0 +1,252 @@
+use std::ops::Range;
+
+use regex::Regex;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::document::{grapheme_index, is_word, Paragraph};
+
+/// How letters of different case match, set with `:set ignorecase` and `:set smartcase`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Case {
+    pub ignore: bool,
+    /// Patterns containing upper case letters don't ignore case
+    pub smart: bool,
+}
+
+impl Case {
+    fn insensitive(&self, pattern: &str) -> bool {
+        self.ignore && !(self.smart && pattern.chars().any(char::is_uppercase))
+    }
+}
+
+/// Compiles a pattern, `\V` at its start matches the rest literally.
+///
+/// `\c` anywhere in the pattern ignores case and `\C` matches it, overriding `case`.
+fn compile(pattern: &str, case: Case) -> Result<Regex, regex::Error> {
+    let insensitive = if pattern.contains("\\c") {
+        true
+    } else if pattern.contains("\\C") {
+        false
+    } else {
+        case.insensitive(pattern)
+    };
+    let pattern = pattern.replace("\\c", "").replace("\\C", "");
+    let pattern = match pattern.strip_prefix("\\V") {
+        Some(literal) => regex::escape(literal),
+        None => pattern,
+    };
+    // The flag is part of the pattern so renders cached by it tell the cases apart
+    if insensitive {
+        Regex::new(&format!("(?i){}", pattern))
+    } else {
+        Regex::new(&pattern)
+    }
+}
+
+/// The last search, repeated with `n` and reversed with `N`.
+#[derive(Clone, Debug)]
+pub struct Search {
+    pub regex: Regex,
+    /// Only matches that are not part of a longer word, used by `*` and `#`
+    pub whole_word: bool,
+    pub backward: bool,
+}
+
+impl Search {
+    /// A search for a regex, invalid patterns are searched literally and their error
+    /// returned alongside.
+    pub fn new(pattern: &str, backward: bool, case: Case) -> (Self, Option<String>) {
+        let (regex, error) = match compile(pattern, case) {
+            Ok(regex) => (regex, None),
+            Err(error) => (
+                compile(&format!("\\V{}", pattern), case).unwrap(),
+                Some(format!("Invalid pattern, searching literally: {}", error)),
+            ),
+        };
+        let search = Self {
+            regex,
+            whole_word: false,
+            backward,
+        };
+        (search, error)
+    }
+
+    /// A search for `word`, which is not affected by smart case.
+    pub fn word(word: &str, backward: bool, case: Case) -> Self {
+        let case = Case {
+            smart: false,
+            ..case
+        };
+        Self {
+            regex: compile(&format!("\\V{}", word), case).unwrap(),
+            whole_word: true,
+            backward,
+        }
+    }
+
+    /// Grapheme ranges of the non empty matches in `text`.
+    pub fn matches(&self, text: &[String]) -> Vec<Range<usize>> {
+        let source = text.concat();
+        let index = grapheme_index(text);
+        self.regex
+            .find_iter(&source)
+            .map(|found| index(found.start())..index(found.end()))
+            .filter(|range| {
+                !range.is_empty()
+                    && (!self.whole_word
+                        || (range.start == 0 || !is_word(&text[range.start - 1]))
+                            && text.get(range.end).map_or(true, |next| !is_word(next)))
+            })
+            .collect()
+    }
+}
+
+/// `:s/pattern/replacement/flags`, on every paragraph when prefixed with `%`.
+///
+/// `\1` to `\9` in the replacement insert capture groups and `&` the whole match. The
+/// flags are `g` to replace every match instead of the first, `i` to ignore case and `I`
+/// to match it.
+#[derive(Clone, Debug)]
+pub struct Substitute {
+    pub regex: Regex,
+    /// The replacement in the syntax of [`Regex::replace`]
+    pub replacement: String,
+    pub global: bool,
+    pub all_paragraphs: bool,
+}
+
+/// Splits at unescaped `delimiter`s, removing the escapes of the delimiter.
+fn split_unescaped(text: &str, delimiter: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        let part = parts.last_mut().unwrap();
+        match c {
+            '\\' => match chars.next() {
+                Some(next) if next == delimiter => part.push(next),
+                Some(next) => {
+                    part.push('\\');
+                    part.push(next);
+                }
+                None => part.push('\\'),
+            },
+            c if c == delimiter => parts.push(String::new()),
+            c => part.push(c),
+        }
+    }
+    parts
+}
+
+/// Turns vim style `\1` and `&` into `${1}` and `${0}`, escaping `$`.
+fn replacement(text: &str) -> String {
+    let mut replacement = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(digit @ '0'..='9') => replacement.push_str(&format!("${{{}}}", digit)),
+                Some('n') => replacement.push('\n'),
+                Some('t') => replacement.push('\t'),
+                Some('$') => replacement.push_str("$$"),
+                Some(other) => replacement.push(other),
+                None => replacement.push('\\'),
+            },
+            '&' => replacement.push_str("${0}"),
+            '$' => replacement.push_str("$$"),
+            c => replacement.push(c),
+        }
+    }
+    replacement
+}
+
+impl Substitute {
+    /// `None` if `command` is no substitution.
+    pub fn parse(command: &str, case: Case) -> Option<Result<Self, String>> {
+        let (all_paragraphs, command) = match command.strip_prefix('%') {
+            Some(command) => (true, command),
+            None => (false, command),
+        };
+        let rest = command.strip_prefix('s')?;
+        let delimiter = rest.chars().next()?;
+        if delimiter.is_alphanumeric() || delimiter.is_whitespace() || delimiter == '\\' {
+            return None;
+        }
+        let parts = split_unescaped(&rest[delimiter.len_utf8()..], delimiter);
+        let pattern = &parts[0];
+        let flags = parts.get(2).map(String::as_str).unwrap_or_default();
+        if let Some(flag) = flags.chars().find(|flag| !matches!(flag, 'g' | 'i' | 'I')) {
+            return Some(Err(format!("Unknown flag: {}", flag)));
+        }
+        let case = match (flags.contains('i'), flags.contains('I')) {
+            (true, _) => Case {
+                ignore: true,
+                smart: false,
+            },
+            (_, true) => Case::default(),
+            _ => case,
+        };
+        Some(
+            compile(pattern, case)
+                .map(|regex| Self {
+                    regex,
+                    replacement: replacement(parts.get(1).map(String::as_str).unwrap_or_default()),
+                    global: flags.contains('g'),
+                    all_paragraphs,
+                })
+                .map_err(|error| format!("Invalid pattern: {}", error)),
+        )
+    }
+
+    /// Replaces every match in a whole note, as done by `:grepsub`.
+    pub fn new(pattern: &str, replacement_text: &str, case: Case) -> Result<Self, String> {
+        compile(pattern, case)
+            .map(|regex| Self {
+                regex,
+                replacement: replacement(replacement_text),
+                global: true,
+                all_paragraphs: true,
+            })
+            .map_err(|error| format!("Invalid pattern: {}", error))
+    }
+
+    /// The numbers of the lines in `text` that change with their old and new content.
+    pub fn changed_lines(&self, text: &str) -> Vec<(usize, String, String)> {
+        text.lines()
+            .enumerate()
+            .filter(|(_, line)| self.regex.is_match(line))
+            .map(|(i, line)| (i + 1, line.to_owned(), self.replace_line(line)))
+            .collect()
+    }
+
+    /// Replaces the matches line by line.
+    pub fn replace_lines(&self, text: &str) -> String {
+        text.split('\n')
+            .map(|line| self.replace_line(line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn replace_line(&self, line: &str) -> String {
+        let limit = if self.global { 0 } else { 1 };
+        self.regex
+            .replacen(line, limit, self.replacement.as_str())
+            .into_owned()
+    }
+
+    /// Replaces the matches in `paragraph`, returning the number of replacements.
+    pub fn apply(&self, paragraph: &mut Paragraph) -> usize {
+        let text = paragraph.text.concat();
+        let count = self.regex.find_iter(&text).count();
+        let count = if self.global { count } else { count.min(1) };
+        if count == 0 {
+            return 0;
+        }
+        let replaced = self.regex.replacen(&text, count, self.replacement.as_str());
+        paragraph.text = replaced.graphemes(true).map(String::from).collect();
+        if let Some(cursor) = &mut paragraph.cursor {
+            *cursor = (*cursor).min(paragraph.text.len().saturating_sub(1));
+        }
+        paragraph.anchor = None;
+        count
+    }
+}
@@ -0,0 +1,113 @@
+use js_sys::{Array, Function, Uint8Array};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::window;
+use yew::Callback;
+
+use crate::{
+    offline,
+    promise::{call, field, object},
+    remote::{base64, from_base64},
+};
+
+/// The name the key is kept by in IndexedDB.
+const KEY: &str = "token";
+/// Bytes of the initialization vector before each encrypted token
+const IV: usize = 12;
+
+fn subtle() -> JsValue {
+    field(&window().unwrap().into(), &["crypto", "subtle"])
+}
+
+/// Calls `done` with the key tokens are encrypted with, made on first use.
+///
+/// The key can not be exported, so the stored tokens can only be read in this browser.
+fn key(done: Callback<Option<JsValue>>) {
+    offline::handle(
+        KEY,
+        Callback::from(move |key: Option<JsValue>| {
+            if key.is_some() {
+                return done.emit(key);
+            }
+            let algorithm = object(&[("name", "AES-GCM".into()), ("length", 256.into())]);
+            let usages = Array::of2(&"encrypt".into(), &"decrypt".into());
+            let done = done.clone();
+            call(
+                &subtle(),
+                "generateKey",
+                &[algorithm.into(), false.into(), usages.into()],
+                move |key| {
+                    let key = key.ok();
+                    if let Some(key) = &key {
+                        offline::keep_handle(KEY, key.clone());
+                    }
+                    done.emit(key)
+                },
+            );
+        }),
+    );
+}
+
+/// Encrypts `token`, `done` receives it in base64 to be stored or `None` if that failed.
+pub fn encrypt(token: String, done: Callback<Option<String>>) {
+    key(Callback::from(move |key: Option<JsValue>| {
+        let key = match key {
+            Some(key) => key,
+            None => return done.emit(None),
+        };
+        let iv = Uint8Array::new_with_length(IV as u32);
+        let crypto = field(&window().unwrap().into(), &["crypto"]);
+        let random = field(&crypto, &["getRandomValues"])
+            .dyn_into::<Function>()
+            .ok()
+            .and_then(|random| random.call1(&crypto, &iv).ok());
+        if random.is_none() {
+            return done.emit(None);
+        }
+        let algorithm = object(&[("name", "AES-GCM".into()), ("iv", iv.clone().into())]);
+        let token = Uint8Array::from(token.as_bytes());
+        let done = done.clone();
+        call(
+            &subtle(),
+            "encrypt",
+            &[algorithm.into(), key, token.into()],
+            move |encrypted| {
+                done.emit(encrypted.ok().map(|encrypted| {
+                    let mut bytes = iv.to_vec();
+                    bytes.extend(Uint8Array::new(&encrypted).to_vec());
+                    base64(&bytes)
+                }))
+            },
+        );
+    }));
+}
+
+/// Decrypts a token from [`encrypt`], `done` receives `None` if that failed.
+pub fn decrypt(encrypted: &str, done: Callback<Option<String>>) {
+    let bytes = match from_base64(encrypted).filter(|bytes| bytes.len() > IV) {
+        Some(bytes) => bytes,
+        None => return done.emit(None),
+    };
+    key(Callback::from(move |key: Option<JsValue>| {
+        let key = match key {
+            Some(key) => key,
+            None => return done.emit(None),
+        };
+        let (iv, encrypted) = bytes.split_at(IV);
+        let algorithm = object(&[
+            ("name", "AES-GCM".into()),
+            ("iv", Uint8Array::from(iv).into()),
+        ]);
+        let done = done.clone();
+        call(
+            &subtle(),
+            "decrypt",
+            &[algorithm.into(), key, Uint8Array::from(encrypted).into()],
+            move |token| {
+                let token = token
+                    .ok()
+                    .and_then(|token| String::from_utf8(Uint8Array::new(&token).to_vec()).ok());
+                done.emit(token)
+            },
+        );
+    }));
+}
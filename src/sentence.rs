@@ -0,0 +1,79 @@
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::document::grapheme_index;
+
+/// Words ending with a full stop that do not end a sentence.
+const ABBREVIATIONS: &[&str] = &[
+    "approx", "cf", "Dr", "e.g", "etc", "Fig", "i.e", "Jr", "Mr", "Mrs", "Ms", "No", "Prof", "Sr",
+    "St", "vs",
+];
+
+/// Whether `sentence` only ends in the full stop of an abbreviation or an initial like `J.`.
+fn abbreviated(sentence: &str) -> bool {
+    let word = match sentence.trim_end().strip_suffix('.') {
+        Some(rest) => rest.rsplit(char::is_whitespace).next().unwrap_or_default(),
+        None => return false,
+    };
+    let word = word.trim_start_matches(|c: char| !c.is_alphanumeric());
+    ABBREVIATIONS.contains(&word)
+        || (word.chars().count() == 1 && word.chars().all(char::is_uppercase))
+}
+
+/// Grapheme ranges of the sentences of `text` by Unicode sentence segmentation, each with
+/// the whitespace after it.
+pub fn sentences(text: &[String]) -> Vec<Range<usize>> {
+    let source = text.concat();
+    let index = grapheme_index(text);
+    let mut sentences: Vec<Range<usize>> = vec![];
+    let mut abbreviation = false;
+    for (start, sentence) in source.split_sentence_bound_indices() {
+        let range = index(start)..index(start + sentence.len());
+        match sentences.last_mut() {
+            Some(last) if abbreviation => last.end = range.end,
+            _ => sentences.push(range),
+        }
+        abbreviation = abbreviated(sentence) && !sentence.ends_with('\n');
+    }
+    sentences
+}
+
+/// `sentence` of `text` without the whitespace after it.
+pub fn inner(text: &[String], mut sentence: Range<usize>) -> Range<usize> {
+    while sentence.end > sentence.start && text[sentence.end - 1].trim().is_empty() {
+        sentence.end -= 1;
+    }
+    sentence
+}
+
+/// The sentence of `text` at `cursor`, without the whitespace after it unless `around`.
+pub fn at(text: &[String], cursor: usize, around: bool) -> Option<Range<usize>> {
+    let sentence = sentences(text)
+        .into_iter()
+        .find(|sentence| sentence.contains(&cursor))?;
+    let sentence = if around {
+        sentence
+    } else {
+        inner(text, sentence)
+    };
+    (!sentence.is_empty()).then_some(sentence)
+}
+
+/// Where `(` or `)` moves the cursor from `cursor`, the start of the sentence it is in or of
+/// the previous one, or of the next one if `forward`.
+pub fn motion(text: &[String], cursor: usize, forward: bool) -> Option<usize> {
+    let starts = sentences(text).into_iter().map(|sentence| {
+        // Starting at the first character, not the indentation before it
+        let blank = text[sentence.clone()]
+            .iter()
+            .take_while(|c| c.trim().is_empty())
+            .count();
+        (sentence.start + blank).min(sentence.end.saturating_sub(1))
+    });
+    if forward {
+        starts.filter(|&start| start > cursor).min()
+    } else {
+        starts.filter(|&start| start < cursor).max()
+    }
+}
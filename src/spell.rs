@@ -0,0 +1,94 @@
+use std::{collections::HashSet, ops::Range, rc::Rc};
+
+use crate::{lint, store::Storage};
+
+/// The language of notes that set no `spelllang` in their frontmatter.
+pub const DEFAULT_LANG: &str = "en";
+
+/// The words known in a spell language, from its downloaded dictionary and the personal
+/// one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dictionary {
+    pub lang: String,
+    words: Rc<HashSet<String>>,
+    /// The personal words in lowercase
+    personal: Vec<String>,
+}
+
+impl Dictionary {
+    /// The dictionary of `lang` from `text`, a hunspell `.dic` file or a word per line.
+    pub fn parse(lang: &str, text: &str, store: &dyn Storage) -> Self {
+        let words = text
+            .lines()
+            .filter_map(|line| {
+                // Hunspell files start with the number of words and add flags after a `/`
+                let word = line.split('/').next()?.trim();
+                (!word.is_empty() && !word.chars().all(|c| c.is_ascii_digit()))
+                    .then(|| word.to_lowercase())
+            })
+            .collect();
+        let mut dictionary = Self {
+            lang: lang.to_owned(),
+            words: Rc::new(words),
+            personal: vec![],
+        };
+        dictionary.refresh(store);
+        dictionary
+    }
+
+    /// Reads the personal words again after they changed.
+    pub fn refresh(&mut self, store: &dyn Storage) {
+        self.personal = store
+            .personal_words(&self.lang)
+            .iter()
+            .map(|word| word.to_lowercase())
+            .collect();
+    }
+
+    fn knows(&self, word: &str) -> bool {
+        self.words.contains(word) || self.personal.iter().any(|personal| personal == word)
+    }
+
+    /// What tells dictionaries apart for the render cache.
+    pub fn key(&self) -> (&str, usize, &[String]) {
+        (&self.lang, self.words.len(), &self.personal)
+    }
+}
+
+/// The ranges of the words in `text` that `dictionary` does not know, words with digits
+/// and code spans are skipped.
+pub fn misspelled(text: &[String], dictionary: &Dictionary) -> Vec<Range<usize>> {
+    lint::sentences(text)
+        .into_iter()
+        .flatten()
+        .filter(|(_, word)| !word.chars().any(|c| c.is_ascii_digit()) && !dictionary.knows(word))
+        .map(|(range, _)| range)
+        .collect()
+}
+
+/// The address the dictionary of `lang` is downloaded from, `{lang}` in the `url` of
+/// `:set spellurl=` is replaced by it.
+pub fn address(url: &str, lang: &str) -> String {
+    url.replace("{lang}", lang)
+}
+
+/// Adds `word` to the personal dictionary of `lang`, `false` if it was already there.
+pub fn add_good(store: &mut dyn Storage, lang: &str, word: &str) -> bool {
+    let mut words = store.personal_words(lang);
+    if words.iter().any(|known| known == word) {
+        return false;
+    }
+    words.push(word.to_owned());
+    words.sort();
+    store.set_personal_words(lang, &words);
+    true
+}
+
+/// Removes `word` from the personal dictionary of `lang`, `false` if it was not there.
+pub fn remove_good(store: &mut dyn Storage, lang: &str, word: &str) -> bool {
+    let mut words = store.personal_words(lang);
+    let len = words.len();
+    words.retain(|known| known != word);
+    store.set_personal_words(lang, &words);
+    words.len() != len
+}
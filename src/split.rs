@@ -0,0 +1,55 @@
+use crate::buffer::Buffer;
+
+/// How much `Ctrl+W <` and `Ctrl+W >` resize a pane, in percent of the window.
+pub const STEP: i32 = 5;
+
+/// The other pane of `:vsplit`, showing a buffer next to the open one.
+///
+/// Only the open buffer is edited, `Ctrl+W h` and `Ctrl+W l` swap it with this one.
+pub struct Split {
+    pub buffer: Buffer,
+    /// Whether this pane is left of the open buffer
+    pub left: bool,
+}
+
+/// Sizes of the panes, kept in the settings as `panewidth`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Panes {
+    /// Width of the left pane in percent of the window
+    pub left_width: i32,
+    /// Whether the divider is being dragged with the mouse
+    pub dragging: bool,
+}
+
+impl Default for Panes {
+    fn default() -> Self {
+        Self {
+            left_width: 50,
+            dragging: false,
+        }
+    }
+}
+
+impl Panes {
+    /// Panes with the left width `setting`, the default if it is no number.
+    pub fn from_setting(setting: Option<String>) -> Self {
+        let mut panes = Self::default();
+        if let Some(width) = setting.and_then(|setting| setting.parse().ok()) {
+            panes.resize(width - panes.left_width);
+        }
+        panes
+    }
+
+    /// Widens the left pane by `delta` percent, keeping both panes usable.
+    pub fn resize(&mut self, delta: i32) {
+        self.left_width = (self.left_width + delta).clamp(10, 90);
+    }
+
+    /// Moves the divider to `x` of a window `width` pixels wide.
+    pub fn drag_to(&mut self, x: i32, width: f64) {
+        if width > 0. {
+            let left_width = (f64::from(x) / width * 100.).round() as i32;
+            self.resize(left_width - self.left_width);
+        }
+    }
+}
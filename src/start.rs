@@ -0,0 +1,156 @@
+use yew::{classes, html, Html};
+
+use crate::{
+    document::{Command, Commandee, Render},
+    store::Storage,
+    ApplicationState,
+};
+
+/// What the match of a pinned note counts for more than that of others.
+const PINNED_BOOST: i64 = 8;
+
+/// How well `query` matches `name` as a subsequence ignoring case, `None` if it does not.
+///
+/// Characters matched in a row and at the start of words count for more, long names for
+/// less.
+pub fn score(query: &str, name: &str) -> Option<i64> {
+    let name: Vec<_> = name.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut last: Option<usize> = None;
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let i = next + name[next..].iter().position(|&other| other == c)?;
+        score += 1;
+        if last.map_or(false, |last| last + 1 == i) {
+            score += 2;
+        }
+        if i == 0 || !name[i - 1].is_alphanumeric() {
+            score += 3;
+        }
+        last = Some(i);
+        next = i + 1;
+    }
+    Some(score * 4 - name.len() as i64 / 8)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StartEntry {
+    New,
+    Note(String),
+}
+
+/// Shown when no note is opened, lists pinned and recently modified notes.
+///
+/// `:find query` lists the notes matching `query` instead, pinned ones ranked higher.
+/// Archived notes are left out.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StartScreen {
+    pub pinned: Vec<String>,
+    pub recent: Vec<String>,
+    pub active: usize,
+    /// What the notes were found by, they are all in `recent` then
+    pub query: Option<String>,
+}
+
+impl StartScreen {
+    pub fn new(store: &dyn Storage) -> Self {
+        let (pinned, mut recent): (Vec<_>, Vec<_>) = store
+            .listed()
+            .into_iter()
+            .partition(|note| store.pinned(note));
+        recent.sort_by(|a, b| {
+            store
+                .modified(b)
+                .partial_cmp(&store.modified(a))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        Self {
+            pinned,
+            recent,
+            active: 0,
+            query: None,
+        }
+    }
+
+    /// The notes matching `query` from the best match, with the first one selected.
+    pub fn find(store: &dyn Storage, query: &str) -> Self {
+        let mut matches: Vec<_> = store
+            .listed()
+            .into_iter()
+            .filter_map(|note| {
+                let boost = if store.pinned(&note) { PINNED_BOOST } else { 0 };
+                Some((score(query, &note)? + boost, note))
+            })
+            .collect();
+        matches.sort_by(|(a, a_note), (b, b_note)| b.cmp(a).then_with(|| a_note.cmp(b_note)));
+        let recent: Vec<_> = matches.into_iter().map(|(_, note)| note).collect();
+        Self {
+            active: (!recent.is_empty()) as usize,
+            pinned: vec![],
+            recent,
+            query: Some(query.to_owned()),
+        }
+    }
+
+    fn entries(&self) -> impl Iterator<Item = StartEntry> + '_ {
+        std::iter::once(StartEntry::New).chain(
+            self.pinned
+                .iter()
+                .chain(self.recent.iter())
+                .cloned()
+                .map(StartEntry::Note),
+        )
+    }
+
+    pub fn selected(&self) -> Option<StartEntry> {
+        self.entries().nth(self.active)
+    }
+
+    fn render_section(&self, title: &str, notes: &[String], offset: usize) -> Html {
+        html! {
+            if !notes.is_empty() {
+                <>
+                    <p class={classes!("font-bold", "mt-4")}>{title}</p>
+                    {for notes.iter().enumerate().map(|(i, note)| self.render_entry(note, offset + i))}
+                </>
+            }
+        }
+    }
+
+    fn render_entry(&self, name: &str, index: usize) -> Html {
+        html! {
+            <p class={classes!((index == self.active).then_some(classes!("bg-gray-700", "rounded")))}>{name}</p>
+        }
+    }
+}
+
+impl Commandee for StartScreen {
+    fn command(&mut self, command: &Self::Command) -> Self::Response {
+        let len = 1 + self.pinned.len() + self.recent.len();
+        match command {
+            Command::Up if self.active > 0 => self.active -= 1,
+            Command::Down if self.active + 1 < len => self.active += 1,
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl Render for StartScreen {
+    fn render(&self, _state: &ApplicationState) -> Html {
+        html! {
+            <div>
+                <p class={classes!("text-2xl", "mb-4")}>{"Notething"}</p>
+                {self.render_entry("+ New note", 0)}
+                {self.render_section("Pinned", &self.pinned, 1)}
+                {match &self.query {
+                    Some(query) if self.recent.is_empty() => html! {
+                        <p class={classes!("text-gray-500", "mt-4")}>{format!("(no notes match {})", query)}</p>
+                    },
+                    Some(query) => self.render_section(&format!("Matching {}", query), &self.recent, 1),
+                    None => self.render_section("Recent", &self.recent, 1 + self.pinned.len()),
+                }}
+            </div>
+        }
+    }
+}
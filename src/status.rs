@@ -0,0 +1,49 @@
+use yew::{classes, html, Html};
+
+use crate::{document::Render, ApplicationState};
+
+/// A message shown at the bottom of the editor until the next command.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Status {
+    pub message: String,
+    pub error: bool,
+}
+
+impl Status {
+    pub fn info(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            error: false,
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            error: true,
+        }
+    }
+}
+
+impl Render for Status {
+    fn render(&self, _state: &ApplicationState) -> Html {
+        html! {
+            <div class={classes!("fixed", "bottom-0", "left-0", "w-screen", "px-2", "bg-gray-800", self.error.then_some("text-red-300"))}>
+                {&self.message}
+            </div>
+        }
+    }
+}
+
+/// The keys of an unfinished sequence like vim's `showcmd`, e.g. `2<Space>f` for `2 Space f`.
+pub fn showcmd(keys: &str) -> String {
+    keys.split(' ')
+        .map(|key| {
+            if key.chars().count() == 1 {
+                key.to_owned()
+            } else {
+                format!("<{}>", key)
+            }
+        })
+        .collect()
+}
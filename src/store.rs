@@ -0,0 +1,390 @@
+use wasm_bindgen::JsValue;
+use web_sys::window;
+
+use crate::{journal::Journal, offline, promise::field};
+
+/// Persistent storage of notes, addressed by their name.
+pub trait Storage {
+    fn load(&self, note: &str) -> Option<String>;
+    fn save(&mut self, note: &str, content: &str);
+    fn notes(&self) -> Vec<String>;
+    /// The content of `note` before it was last saved.
+    fn snapshot(&self, note: &str) -> Option<String>;
+    /// Moves `note` to the trash, an older copy of it there is kept as `note (2)`.
+    fn delete(&mut self, note: &str);
+    fn trash(&self) -> Vec<String>;
+    /// Moves `note` out of the trash, unless there is a note of that name.
+    fn restore(&mut self, note: &str);
+    /// Removes `note` from the trash for good.
+    fn purge(&mut self, note: &str);
+    /// Time of the last save in milliseconds since the epoch.
+    fn modified(&self, note: &str) -> Option<f64>;
+    /// Time of the first save in milliseconds since the epoch.
+    fn created(&self, note: &str) -> Option<f64>;
+    fn pinned(&self, note: &str) -> bool;
+    /// Pins `note` to the top of the start screen, or unpins it.
+    fn set_pinned(&mut self, note: &str, pinned: bool);
+    /// Whether `note` was put away with `:archive`.
+    fn archived(&self, note: &str) -> bool;
+    fn set_archived(&mut self, note: &str, archived: bool);
+    /// Anchors of the headings folded in `note` when it was last open.
+    fn folds(&self, note: &str) -> Vec<String>;
+    fn set_folds(&mut self, note: &str, anchors: &[String]);
+    /// Crash recovery copies of buffers by note, `""` for the unnamed buffer.
+    fn swaps(&self) -> Vec<(String, String)>;
+    /// Writes the swap copy of `note` for the buffer of the tab `tab`.
+    fn write_swap(&mut self, tab: &str, note: &str, content: &str);
+    /// The tab whose buffer the swap copy of `note` is of.
+    fn swap_tab(&self, note: &str) -> Option<String>;
+    /// Removes the swap copy of `note` together with its journal.
+    fn remove_swap(&mut self, note: &str);
+    /// The word level edits made to `note` since it was last saved.
+    fn journal(&self, note: &str) -> Option<Journal>;
+    fn write_journal(&mut self, note: &str, journal: &Journal);
+    /// The tabs the app runs in with when each was last seen, in milliseconds since the
+    /// epoch. Tabs that crashed are left behind.
+    fn tabs(&self) -> Vec<(String, f64)>;
+    /// Records `tab` as running at `seen`, or as left normally with `None`.
+    fn set_tab(&mut self, tab: &str, seen: Option<f64>);
+    /// Words added to the dictionary of the spell language `lang`.
+    fn personal_words(&self, lang: &str) -> Vec<String>;
+    fn set_personal_words(&mut self, lang: &str, words: &[String]);
+    fn setting(&self, key: &str) -> Option<String>;
+    fn set_setting(&mut self, key: &str, value: &str);
+    /// Why a write failed since this was last called, like a full storage.
+    fn take_error(&mut self) -> Option<String>;
+    /// Counts the changes to the notes, what is read from all of them is only read again
+    /// once it changed.
+    fn version(&self) -> u64;
+    /// The notes changed after [`Self::version`] was `version`, so only those are read
+    /// again.
+    fn changed_since(&self, version: u64) -> Vec<String>;
+
+    /// The notes that are not archived, those listed and searched by default.
+    fn listed(&self) -> Vec<String> {
+        self.notes()
+            .into_iter()
+            .filter(|note| !self.archived(note))
+            .collect()
+    }
+
+    /// The stored content of `note` if it was changed elsewhere since it was `base`.
+    fn conflict(&self, note: &str, base: &str) -> Option<String> {
+        self.load(note).filter(|stored| stored != base)
+    }
+
+    /// Adds the edits from the version of `note` last recorded to `content` to its journal,
+    /// starting it over if it was not recorded from `base`.
+    fn record(&mut self, note: &str, base: &str, content: &str) {
+        let journal = self
+            .journal(note)
+            .filter(|journal| journal.starts_from(base));
+        let last = journal.as_ref().and_then(|journal| journal.content(base));
+        let (mut journal, last) = match (journal, last) {
+            (Some(journal), Some(last)) => (journal, last),
+            _ => (Journal::new(base), base.to_owned()),
+        };
+        journal.record(&last, content, js_sys::Date::now());
+        self.write_journal(note, &journal);
+    }
+
+    /// `content`, edited from `base`, merged word by word with the stored version of `note`
+    /// changed elsewhere, `None` if both changed the same words.
+    fn merge(&self, note: &str, base: &str, content: &str) -> Option<String> {
+        let remote = self.load(note)?;
+        let journal = self
+            .journal(note)
+            .filter(|journal| {
+                journal.starts_from(base) && journal.content(base).as_deref() == Some(content)
+            })
+            .unwrap_or_else(|| {
+                let mut journal = Journal::new(base);
+                journal.record(base, content, js_sys::Date::now());
+                journal
+            });
+        journal.merge(base, &remote)
+    }
+}
+
+const NOTE: &str = "note:";
+const SNAPSHOT: &str = "snapshot:";
+const TRASH: &str = "trash:";
+const MODIFIED: &str = "modified:";
+const CREATED: &str = "created:";
+const PINNED: &str = "pinned:";
+const ARCHIVED: &str = "archived:";
+const FOLDS: &str = "folds:";
+const SWAP: &str = "swap:";
+const JOURNAL: &str = "journal:";
+const SWAP_TAB: &str = "swaptab:";
+const TAB: &str = "tab:";
+const SPELL: &str = "spell:";
+const SETTING: &str = "setting:";
+
+/// [`Storage`] backed by the browser's `localStorage`, with notes copied to IndexedDB.
+#[derive(Default)]
+pub struct LocalStorage {
+    /// The first write that failed since it was last taken
+    error: Option<String>,
+    version: u64,
+    /// The note each change of the version was to, the first one at version `1`
+    changes: Vec<String>,
+}
+
+impl LocalStorage {
+    /// Counts a change to `note` in the version.
+    fn changed(&mut self, note: &str) {
+        self.version += 1;
+        self.changes.push(note.to_owned());
+    }
+
+    fn storage() -> web_sys::Storage {
+        window()
+            .unwrap()
+            .local_storage()
+            .unwrap()
+            .expect("localStorage should be available")
+    }
+
+    fn get(key: &str) -> Option<String> {
+        Self::storage().get_item(key).ok().flatten()
+    }
+
+    /// Records why `result` failed, the first error is kept until it is taken.
+    fn check(&mut self, result: Result<(), JsValue>) {
+        if let Err(error) = result {
+            let name = field(&error, &["name"]).as_string();
+            let error = match name.as_deref() {
+                Some("QuotaExceededError") => {
+                    "The browser storage is full, :purge notes from the trash to make room"
+                }
+                _ => "The browser storage could not be written",
+            };
+            self.error.get_or_insert_with(|| error.to_owned());
+        }
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        let result = Self::storage().set_item(key, value);
+        self.check(result);
+    }
+
+    fn remove(&mut self, key: &str) {
+        let result = Self::storage().remove_item(key);
+        self.check(result);
+    }
+
+    fn keys(prefix: &str) -> Vec<String> {
+        let storage = Self::storage();
+        (0..storage.length().unwrap_or_default())
+            .filter_map(|i| storage.key(i).ok().flatten())
+            .filter_map(|key| key.strip_prefix(prefix).map(String::from))
+            .collect()
+    }
+
+    fn rename(&mut self, from: &str, to: &str) {
+        if let Some(value) = Self::get(from) {
+            self.set(to, &value);
+            self.remove(from);
+        }
+    }
+}
+
+impl Storage for LocalStorage {
+    fn load(&self, note: &str) -> Option<String> {
+        Self::get(&format!("{}{}", NOTE, note))
+    }
+
+    fn save(&mut self, note: &str, content: &str) {
+        let now = js_sys::Date::now().to_string();
+        match self.load(note) {
+            Some(previous) => self.set(&format!("{}{}", SNAPSHOT, note), &previous),
+            None => self.set(&format!("{}{}", CREATED, note), &now),
+        }
+        self.set(&format!("{}{}", NOTE, note), content);
+        self.set(&format!("{}{}", MODIFIED, note), &now);
+        offline::mirror(note, Some(content));
+        self.changed(note);
+    }
+
+    fn notes(&self) -> Vec<String> {
+        Self::keys(NOTE)
+    }
+
+    fn snapshot(&self, note: &str) -> Option<String> {
+        Self::get(&format!("{}{}", SNAPSHOT, note))
+    }
+
+    fn delete(&mut self, note: &str) {
+        let trashed = format!("{}{}", TRASH, note);
+        if Self::get(&trashed).is_some() {
+            let older = (2..)
+                .map(|n| format!("{} ({})", note, n))
+                .find(|name| {
+                    Self::get(&format!("{}{}", TRASH, name)).is_none() && self.load(name).is_none()
+                })
+                .unwrap();
+            self.rename(&trashed, &format!("{}{}", TRASH, older));
+        }
+        self.rename(&format!("{}{}", NOTE, note), &format!("{}{}", TRASH, note));
+        self.remove(&format!("{}{}", SNAPSHOT, note));
+        offline::mirror(note, None);
+        self.changed(note);
+    }
+
+    fn trash(&self) -> Vec<String> {
+        Self::keys(TRASH)
+    }
+
+    fn restore(&mut self, note: &str) {
+        if self.load(note).is_some() {
+            return;
+        }
+        self.rename(&format!("{}{}", TRASH, note), &format!("{}{}", NOTE, note));
+        offline::mirror(note, self.load(note).as_deref());
+        self.changed(note);
+    }
+
+    fn purge(&mut self, note: &str) {
+        self.remove(&format!("{}{}", TRASH, note));
+        self.remove(&format!("{}{}", MODIFIED, note));
+        self.remove(&format!("{}{}", CREATED, note));
+        self.remove(&format!("{}{}", PINNED, note));
+        self.remove(&format!("{}{}", ARCHIVED, note));
+        self.remove(&format!("{}{}", FOLDS, note));
+    }
+
+    fn modified(&self, note: &str) -> Option<f64> {
+        Self::get(&format!("{}{}", MODIFIED, note)).and_then(|time| time.parse().ok())
+    }
+
+    fn created(&self, note: &str) -> Option<f64> {
+        Self::get(&format!("{}{}", CREATED, note)).and_then(|time| time.parse().ok())
+    }
+
+    fn pinned(&self, note: &str) -> bool {
+        Self::get(&format!("{}{}", PINNED, note)).is_some()
+    }
+
+    fn set_pinned(&mut self, note: &str, pinned: bool) {
+        let key = format!("{}{}", PINNED, note);
+        if pinned {
+            self.set(&key, "true");
+        } else {
+            self.remove(&key);
+        }
+    }
+
+    fn archived(&self, note: &str) -> bool {
+        Self::get(&format!("{}{}", ARCHIVED, note)).is_some()
+    }
+
+    fn set_archived(&mut self, note: &str, archived: bool) {
+        let key = format!("{}{}", ARCHIVED, note);
+        if archived {
+            self.set(&key, "true");
+        } else {
+            self.remove(&key);
+        }
+        self.changed(note);
+    }
+
+    fn folds(&self, note: &str) -> Vec<String> {
+        Self::get(&format!("{}{}", FOLDS, note))
+            .map(|anchors| anchors.lines().map(String::from).collect())
+            .unwrap_or_default()
+    }
+
+    fn set_folds(&mut self, note: &str, anchors: &[String]) {
+        let key = format!("{}{}", FOLDS, note);
+        if anchors.is_empty() {
+            self.remove(&key);
+        } else {
+            self.set(&key, &anchors.join("\n"));
+        }
+    }
+
+    fn swaps(&self) -> Vec<(String, String)> {
+        Self::keys(SWAP)
+            .into_iter()
+            .filter_map(|note| {
+                let content = Self::get(&format!("{}{}", SWAP, note))?;
+                Some((note, content))
+            })
+            .collect()
+    }
+
+    fn write_swap(&mut self, tab: &str, note: &str, content: &str) {
+        self.set(&format!("{}{}", SWAP, note), content);
+        self.set(&format!("{}{}", SWAP_TAB, note), tab);
+    }
+
+    fn swap_tab(&self, note: &str) -> Option<String> {
+        Self::get(&format!("{}{}", SWAP_TAB, note))
+    }
+
+    fn remove_swap(&mut self, note: &str) {
+        self.remove(&format!("{}{}", SWAP, note));
+        self.remove(&format!("{}{}", SWAP_TAB, note));
+        self.remove(&format!("{}{}", JOURNAL, note));
+    }
+
+    fn journal(&self, note: &str) -> Option<Journal> {
+        Self::get(&format!("{}{}", JOURNAL, note)).and_then(|journal| Journal::decode(&journal))
+    }
+
+    fn write_journal(&mut self, note: &str, journal: &Journal) {
+        self.set(&format!("{}{}", JOURNAL, note), &journal.encode());
+    }
+
+    fn tabs(&self) -> Vec<(String, f64)> {
+        Self::keys(TAB)
+            .into_iter()
+            .filter_map(|tab| {
+                let seen = Self::get(&format!("{}{}", TAB, tab))?.parse().ok()?;
+                Some((tab, seen))
+            })
+            .collect()
+    }
+
+    fn set_tab(&mut self, tab: &str, seen: Option<f64>) {
+        let key = format!("{}{}", TAB, tab);
+        match seen {
+            Some(seen) => self.set(&key, &seen.to_string()),
+            None => self.remove(&key),
+        }
+    }
+
+    fn personal_words(&self, lang: &str) -> Vec<String> {
+        Self::get(&format!("{}{}", SPELL, lang))
+            .map(|words| words.lines().map(String::from).collect())
+            .unwrap_or_default()
+    }
+
+    fn set_personal_words(&mut self, lang: &str, words: &[String]) {
+        self.set(&format!("{}{}", SPELL, lang), &words.join("\n"));
+    }
+
+    fn setting(&self, key: &str) -> Option<String> {
+        Self::get(&format!("{}{}", SETTING, key))
+    }
+
+    fn set_setting(&mut self, key: &str, value: &str) {
+        self.set(&format!("{}{}", SETTING, key), value);
+    }
+
+    fn take_error(&mut self) -> Option<String> {
+        self.error.take()
+    }
+
+    fn version(&self) -> u64 {
+        self.version
+    }
+
+    fn changed_since(&self, version: u64) -> Vec<String> {
+        let mut changed = self.changes[(version as usize).min(self.changes.len())..].to_vec();
+        changed.sort();
+        changed.dedup();
+        changed
+    }
+}
@@ -0,0 +1,61 @@
+use std::{cell::Cell, rc::Rc};
+
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{window, BeforeUnloadEvent, PageTransitionEvent};
+use yew::Callback;
+
+/// How many edits of a buffer happen between writing its swap copy.
+pub const EDITS: usize = 20;
+/// Milliseconds between a running tab recording that it is still there.
+pub const HEARTBEAT: u32 = 30_000;
+/// Milliseconds after which a tab that was not seen is taken to have crashed.
+pub const STALE: f64 = 3. * HEARTBEAT as f64;
+
+/// Calls `callback` when the page is left normally, unlike when the tab crashes.
+pub fn on_page_hide(callback: Callback<()>) {
+    let listener = Closure::wrap(Box::new(move || callback.emit(())) as Box<dyn FnMut()>);
+    window()
+        .unwrap()
+        .add_event_listener_with_callback("pagehide", listener.as_ref().unchecked_ref())
+        .unwrap();
+    listener.forget();
+}
+
+/// Calls `callback` when the page is shown again from the back-forward cache after it was
+/// left.
+pub fn on_page_show(callback: Callback<()>) {
+    let listener = Closure::wrap(Box::new(move |event: PageTransitionEvent| {
+        if event.persisted() {
+            callback.emit(())
+        }
+    }) as Box<dyn FnMut(PageTransitionEvent)>);
+    window()
+        .unwrap()
+        .add_event_listener_with_callback("pageshow", listener.as_ref().unchecked_ref())
+        .unwrap();
+    listener.forget();
+}
+
+/// Asks for confirmation before the page is left while `unsaved` is set.
+pub fn on_before_unload(unsaved: Rc<Cell<bool>>) {
+    let listener = Closure::wrap(Box::new(move |event: BeforeUnloadEvent| {
+        if unsaved.get() {
+            event.prevent_default();
+            event.set_return_value("");
+        }
+    }) as Box<dyn FnMut(BeforeUnloadEvent)>);
+    window()
+        .unwrap()
+        .add_event_listener_with_callback("beforeunload", listener.as_ref().unchecked_ref())
+        .unwrap();
+    listener.forget();
+}
+
+/// How a swap copy is listed in the recovery prompt.
+pub fn name(note: &str) -> &str {
+    if note.is_empty() {
+        "[No Name]"
+    } else {
+        note
+    }
+}
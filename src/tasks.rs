@@ -0,0 +1,233 @@
+use std::ops::Range;
+
+use yew::{classes, html, Html};
+
+use crate::{
+    document::{grapheme_index, Command, Commandee, Render},
+    store::Storage,
+    ApplicationState,
+};
+
+/// A task list item of a stored note, `- [ ] text`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Task {
+    pub note: String,
+    pub line: usize,
+    /// The whole line as it was stored
+    pub source: String,
+    pub done: bool,
+    pub text: String,
+    /// The `YYYY-MM-DD` of its `@due(...)`
+    pub due: Option<String>,
+}
+
+impl Task {
+    /// Whether it is not done and was due before `today`, a `YYYY-MM-DD` date.
+    pub fn overdue(&self, today: &str) -> bool {
+        !self.done && self.due.as_deref().map_or(false, |due| due < today)
+    }
+}
+
+fn is_date(date: &str) -> bool {
+    date.len() == 10
+        && date.char_indices().all(|(i, c)| match i {
+            4 | 7 => c == '-',
+            _ => c.is_ascii_digit(),
+        })
+}
+
+/// The byte range of the `@due(2024-06-01)` annotation in `text` and its date.
+pub fn due(text: &str) -> Option<(Range<usize>, &str)> {
+    let start = text.find("@due(")?;
+    let date = &text[start + 5..];
+    let end = date.find(')')?;
+    let date = &date[..end];
+    is_date(date).then(|| (start..start + 5 + end + 1, date))
+}
+
+/// Grapheme ranges of the due dates in `text` of open tasks that are past `today`.
+pub fn overdue(text: &[String], today: &str) -> Vec<Range<usize>> {
+    let source = text.concat();
+    if !source.contains("@due(") {
+        return vec![];
+    }
+    let index = grapheme_index(text);
+    let mut ranges = vec![];
+    let mut start = 0;
+    for line in source.split('\n') {
+        if let (Some((_, false)), Some((range, date))) = (checkbox(line), due(line)) {
+            if date < today {
+                ranges.push(index(start + range.start)..index(start + range.end));
+            }
+        }
+        start += line.len() + 1;
+    }
+    ranges
+}
+
+/// Byte offset of the mark in the box of a task list item `line` and whether it is ticked.
+fn checkbox(line: &str) -> Option<(usize, bool)> {
+    let item = line.trim_start();
+    let digits = item.chars().take_while(char::is_ascii_digit).count();
+    let marker = if digits > 0 {
+        item[digits..]
+            .starts_with(|c: char| c == '.' || c == ')')
+            .then_some(digits + 1)?
+    } else {
+        item.starts_with(|c: char| c == '-' || c == '*' || c == '+')
+            .then_some(1)?
+    };
+    let rest = item[marker..].strip_prefix(' ')?;
+    let done = match rest.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+    if !matches!(rest[3..].chars().next(), None | Some(' ')) {
+        return None;
+    }
+    Some((line.len() - rest.len() + 1, done))
+}
+
+/// The task list items of `note` with content `md`, outside of code blocks.
+pub fn parse(note: &str, md: &str) -> Vec<Task> {
+    let mut code = false;
+    let mut tasks = vec![];
+    for (i, line) in md.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            code = !code;
+        } else if let (false, Some((mark, done))) = (code, checkbox(line)) {
+            let mut text = line[mark + 2..].to_owned();
+            let due = due(&text).map(|(range, date)| (range, date.to_owned()));
+            if let Some((range, _)) = &due {
+                text.replace_range(range.clone(), "");
+            }
+            tasks.push(Task {
+                note: note.to_owned(),
+                line: i,
+                source: line.to_owned(),
+                done,
+                text: text.split_whitespace().collect::<Vec<_>>().join(" "),
+                due: due.map(|(_, date)| date),
+            });
+        }
+    }
+    tasks
+}
+
+/// `md` with `task` ticked or unticked, `None` if its line is gone.
+///
+/// The line is looked for by its content further away when lines were added or removed
+/// above it.
+pub fn toggle(md: &str, task: &Task) -> Option<String> {
+    let mut lines: Vec<_> = md.split('\n').map(String::from).collect();
+    let line = if lines.get(task.line) == Some(&task.source) {
+        task.line
+    } else {
+        lines.iter().position(|line| *line == task.source)?
+    };
+    let (mark, done) = checkbox(&lines[line])?;
+    lines[line].replace_range(mark..mark + 1, if done { " " } else { "x" });
+    Some(lines.join("\n"))
+}
+
+/// The tasks of all stored notes listed by `:tasks`, `Enter` ticks or unticks one.
+///
+/// Tasks are grouped by note, open ones before those that are done, and sorted by their
+/// due date.
+#[derive(Clone, Debug)]
+pub struct Tasks {
+    pub tasks: Vec<Task>,
+    pub active: usize,
+    /// The date overdue tasks are highlighted against
+    today: String,
+}
+
+impl Tasks {
+    pub fn new(store: &dyn Storage, today: &str) -> Self {
+        let mut notes = store.notes();
+        notes.sort();
+        let mut tasks: Vec<_> = notes
+            .iter()
+            .filter_map(|note| Some(parse(note, &store.load(note)?)))
+            .flatten()
+            .collect();
+        // Tasks without a due date come last
+        let order = |task: &Task| {
+            (
+                task.note.clone(),
+                task.done,
+                task.due.is_none(),
+                task.due.clone(),
+            )
+        };
+        tasks.sort_by_key(order);
+        Self {
+            tasks,
+            active: 0,
+            today: today.to_owned(),
+        }
+    }
+
+    pub fn selected(&self) -> Option<&Task> {
+        self.tasks.get(self.active)
+    }
+
+    /// Reads the tasks again, keeping the selection at the same place.
+    pub fn refresh(&mut self, store: &dyn Storage) {
+        let active = self.active;
+        *self = Self::new(store, &self.today);
+        self.active = active.min(self.tasks.len().saturating_sub(1));
+    }
+}
+
+impl Commandee for Tasks {
+    fn command(&mut self, command: &Self::Command) -> Self::Response {
+        match command {
+            Command::Up if self.active > 0 => self.active -= 1,
+            Command::Down if self.active + 1 < self.tasks.len() => self.active += 1,
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl Render for Tasks {
+    fn render(&self, _state: &ApplicationState) -> Html {
+        let open = self.tasks.iter().filter(|task| !task.done).count();
+        let tasks = self.tasks.iter().enumerate().map(|(i, task)| {
+            let heading = (i == 0 || self.tasks[i - 1].note != task.note).then(|| {
+                html! {<p class={classes!("font-bold", "mt-2")}>{&task.note}</p>}
+            });
+            let status = (heading.is_some() || self.tasks[i - 1].done != task.done).then(|| {
+                html! {
+                    <p class={classes!("text-sm", "text-gray-500")}>{if task.done { "Done" } else { "Open" }}</p>
+                }
+            });
+            html! {
+                <>
+                    {for heading}
+                    {for status}
+                    <p class={classes!("flex", (i == self.active).then_some(classes!("bg-gray-700", "rounded")), task.done.then_some(classes!("line-through", "text-gray-500")))}>
+                        <span class={classes!("flex-1")}>{if task.done { "[x] " } else { "[ ] " }}{&task.text}</span>
+                        {for task.due.as_ref().map(|due| html! {
+                            <span class={classes!("ml-2", task.overdue(&self.today).then_some(classes!("text-red-500", "font-bold")))}>{due}</span>
+                        })}
+                    </p>
+                </>
+            }
+        });
+        html! {
+            <div>
+                <p class={classes!("font-bold")}>
+                    {format!("{} open and {} done tasks, Enter ticks or unticks one", open, self.tasks.len() - open)}
+                </p>
+                if self.tasks.is_empty() {
+                    <p class={classes!("text-gray-500")}>{"(no tasks)"}</p>
+                }
+                {for tasks}
+            </div>
+        }
+    }
+}
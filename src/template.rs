@@ -0,0 +1,102 @@
+use crate::store::Storage;
+
+/// Notes named with this prefix are offered by `:template` next to the built-in ones.
+pub const PREFIX: &str = "templates/";
+
+/// A note to start from, `{{date}}` and `{{title}}` are filled in when it is used.
+pub struct Template {
+    pub name: &'static str,
+    pub md: &'static str,
+}
+
+pub const TEMPLATES: &[Template] = &[
+    Template {
+        name: "meeting",
+        md: "# Meeting {{date}}
+
+## Attendees
+
+| Name | Role | Present |
+| ---- | ---- | ------- |
+|      |      |         |
+
+## Agenda
+
+1. Topic
+
+## Notes
+
+## Action items
+
+- [ ] Task for someone",
+    },
+    Template {
+        name: "weekly",
+        md: "# Weekly review {{date}}
+
+## Done
+
+- [x] Finished task
+
+## Time spent
+
+| Project | Hours |
+| ------- | ----- |
+|         |       |
+
+## Next week
+
+- [ ] Planned task
+
+## Reflection",
+    },
+    Template {
+        name: "reading",
+        md: "# {{title}}
+
+| Author | Year | Started | Finished |
+| ------ | ---- | ------- | -------- |
+|        |      |         |          |
+
+## Summary
+
+## Quotes
+
+> Quote
+
+## Follow up
+
+- [ ] Look up the references",
+    },
+];
+
+/// The built-in templates followed by the ones stored as notes.
+pub fn names(store: &dyn Storage) -> Vec<String> {
+    let mut stored: Vec<_> = store
+        .notes()
+        .into_iter()
+        .filter_map(|note| note.strip_prefix(PREFIX).map(String::from))
+        .filter(|name| TEMPLATES.iter().all(|template| template.name != name))
+        .collect();
+    stored.sort();
+    TEMPLATES
+        .iter()
+        .map(|template| template.name.to_owned())
+        .chain(stored)
+        .collect()
+}
+
+/// The markdown of the template `name`, a stored one replaces the built-in one.
+pub fn get(name: &str, store: &dyn Storage) -> Option<String> {
+    store.load(&format!("{}{}", PREFIX, name)).or_else(|| {
+        TEMPLATES
+            .iter()
+            .find(|template| template.name == name)
+            .map(|template| template.md.to_owned())
+    })
+}
+
+/// `md` with its placeholders filled in.
+pub fn expand(md: &str, date: &str, title: &str) -> String {
+    md.replace("{{date}}", date).replace("{{title}}", title)
+}
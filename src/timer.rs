@@ -0,0 +1,130 @@
+use gloo_timers::callback::Interval;
+
+use crate::document::{Document, Element, Paragraph};
+
+/// How often the countdown in the status line is redrawn, in milliseconds.
+pub const TICK: u32 = 1000;
+/// Text of the tasks logging sessions that were not started on a task.
+const SESSION: &str = "Focus session of";
+
+/// A `:timer` countdown shown in the status line.
+pub struct Timer {
+    /// When it ends, in milliseconds since the epoch
+    pub end: f64,
+    /// Its length as given, like `25m`
+    pub length: String,
+    /// The note it was started in, where the session is logged
+    pub note: Option<String>,
+    /// The line of the task under the cursor when it was started
+    pub task: Option<String>,
+    /// Redraws the countdown, stopping when the timer is dropped
+    pub tick: Interval,
+}
+
+impl Timer {
+    /// The time left at `now` as `mm:ss`, or `h:mm:ss` for an hour or more.
+    pub fn remaining(&self, now: f64) -> String {
+        let seconds = ((self.end - now) / 1000.).ceil().max(0.) as u64;
+        let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+        if hours > 0 {
+            format!("{}:{:02}:{:02}", hours, minutes, seconds)
+        } else {
+            format!("{:02}:{:02}", minutes, seconds)
+        }
+    }
+}
+
+/// The seconds of a length like `25m`, `90s`, `1h30m` or `25`, which counts minutes.
+pub fn parse(length: &str) -> Option<u64> {
+    if let Ok(minutes) = length.parse::<u64>() {
+        return minutes.checked_mul(60).filter(|&seconds| seconds > 0);
+    }
+    let mut seconds = 0;
+    let mut number = String::new();
+    for c in length.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'h' | 'm' | 's' => {
+                let unit = match c {
+                    'h' => 3600,
+                    'm' => 60,
+                    _ => 1,
+                };
+                let part = number.parse::<u64>().ok()?.checked_mul(unit)?;
+                seconds = part.checked_add(seconds)?;
+                number.clear();
+            }
+            _ => return None,
+        }
+    }
+    (number.is_empty() && seconds > 0).then_some(seconds)
+}
+
+/// `md` with a finished session of `length` logged.
+///
+/// The `task` it was started on counts its sessions in a `@pomodoro(2)` annotation,
+/// without one a done task with the time `now` is added at the end.
+pub fn log(md: &str, task: Option<&str>, length: &str, now: &str) -> String {
+    let mut lines: Vec<_> = md.split('\n').map(String::from).collect();
+    if let Some(line) = task.and_then(|task| lines.iter_mut().find(|line| *line == task)) {
+        let count = line.find("@pomodoro(").and_then(|start| {
+            let end = start + line[start..].find(')')?;
+            let count: u32 = line[start + 10..end].parse().ok()?;
+            Some((start..end + 1, count))
+        });
+        match count {
+            Some((range, count)) => line.replace_range(range, &format!("@pomodoro({})", count + 1)),
+            None => line.push_str(" @pomodoro(1)"),
+        }
+        return lines.join("\n");
+    }
+    let entry = format!("- [x] {} {} @done({})", SESSION, length, now);
+    let md = md.trim_end();
+    if md.is_empty() {
+        entry
+    } else if md
+        .lines()
+        .last()
+        .map_or(false, |last| last.contains(SESSION))
+    {
+        // Sessions in a row stay in one list
+        format!("{}\n{}", md, entry)
+    } else {
+        format!("{}\n\n{}", md, entry)
+    }
+}
+
+/// Logs a finished session like [`log`] in the paragraph it goes in, so the cursor and
+/// folds of the open `document` stay.
+pub fn log_document(document: &mut Document, task: Option<&str>, length: &str, now: &str) {
+    let log = |md: &str| log(md, task, length, now);
+    let replace = |paragraph: &mut Paragraph| {
+        let cursor = paragraph.cursor;
+        *paragraph = Paragraph::from(log(&paragraph.text.concat()).as_str());
+        paragraph.cursor = cursor.map(|cursor| cursor.min(paragraph.text.len()));
+    };
+    if let Some(task) = task {
+        if let Some(paragraph) = document
+            .paragraphs_mut()
+            .into_iter()
+            .find(|paragraph| paragraph.text.concat().split('\n').any(|line| line == task))
+        {
+            return replace(paragraph);
+        }
+    }
+    match document.elements.last_mut() {
+        Some(Element::Paragraph(paragraph))
+            if paragraph
+                .text
+                .concat()
+                .lines()
+                .last()
+                .map_or(false, |last| last.contains(SESSION)) =>
+        {
+            replace(paragraph)
+        }
+        _ => document
+            .elements
+            .push(Element::Paragraph(Paragraph::from(log("").as_str()))),
+    }
+}
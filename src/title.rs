@@ -0,0 +1,35 @@
+use web_sys::window;
+
+/// The favicon while there are no unsaved changes.
+const ICON: &str = "icon.png";
+/// The favicon with unsaved changes, a dot like the `*` of the title.
+const UNSAVED_ICON: &str = "data:image/svg+xml,<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 16 16'><circle cx='8' cy='8' r='6' fill='%23f59e0b'/></svg>";
+
+/// Names the tab after the open `note`, `*` marks it as `modified`.
+///
+/// With `unsaved_icon` set, the favicon also shows whether there are unsaved changes.
+pub fn update(note: Option<&str>, modified: bool, unsaved_icon: bool) {
+    let document = match window().and_then(|window| window.document()) {
+        Some(document) => document,
+        None => return,
+    };
+    let name = note.unwrap_or("[No Name]");
+    let title = format!(
+        "{}{} — md-notething",
+        name,
+        if modified { " *" } else { "" }
+    );
+    if document.title() != title {
+        document.set_title(&title);
+    }
+    if let Some(favicon) = document.get_element_by_id("favicon") {
+        let icon = if unsaved_icon && modified {
+            UNSAVED_ICON
+        } else {
+            ICON
+        };
+        if favicon.get_attribute("href").as_deref() != Some(icon) {
+            let _ = favicon.set_attribute("href", icon);
+        }
+    }
+}
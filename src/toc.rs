@@ -0,0 +1,99 @@
+use crate::document::{Command, Commandee, Document, Element, Paragraph};
+
+/// Comments around the table of contents of `:toc insert`, which is locked like a
+/// `<!-- lock -->` region.
+pub const START: &str = "<!-- toc -->";
+pub const END: &str = "<!-- /toc -->";
+
+/// A list linking to the headings of `document`, nested by their level.
+pub fn list(document: &Document) -> String {
+    let top = document
+        .elements
+        .iter()
+        .filter_map(|element| match element {
+            Element::Heading(heading) => Some(heading.level),
+            _ => None,
+        })
+        .min()
+        .unwrap_or(1);
+    document
+        .anchors()
+        .into_iter()
+        .filter_map(|(i, anchor)| match &document.elements[i] {
+            Element::Heading(heading) => Some(format!(
+                "{}- [{}](#{})",
+                "  ".repeat((heading.level - top) as usize),
+                heading.content.to_md().trim(),
+                anchor
+            )),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Whether `marker` is a line of `element` that is not code.
+fn contains(element: &Element, marker: &str) -> bool {
+    let mut fenced = false;
+    for line in element.to_md().lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fenced = !fenced;
+        } else if !fenced && line.len() - line.trim_start().len() < 4 && trimmed == marker {
+            return true;
+        }
+    }
+    false
+}
+
+/// Generates the table of contents of `document` again from its headings.
+///
+/// Returns `false` if it has none, or its end is missing so that it can not be told from
+/// the rest of the note.
+pub fn update(document: &mut Document) -> bool {
+    let start = match document
+        .elements
+        .iter()
+        .position(|element| contains(element, START))
+    {
+        Some(start) => start,
+        None => return false,
+    };
+    let end = match document.elements[start + 1..]
+        .iter()
+        .position(|element| contains(element, END))
+    {
+        Some(end) => start + 1 + end,
+        None => return false,
+    };
+    let list = list(document);
+    let old: Vec<_> = document.elements[start + 1..end]
+        .iter()
+        .map(Element::to_md)
+        .collect();
+    if old == [list.clone()] {
+        return true;
+    }
+    let generated = (!list.is_empty()).then(|| Element::Paragraph(Paragraph::from(list.as_str())));
+    let len = generated.is_some() as usize;
+    document.elements.splice(start + 1..end, generated);
+    let active = document.active_element;
+    if (start + 1..end).contains(&active) {
+        document.active_element = start + 1;
+        document.command(&Command::CursorEnterH(false));
+    } else if active >= end {
+        document.active_element = active + len - old.len();
+    }
+    true
+}
+
+/// Adds a table of contents after the active element.
+pub fn insert(document: &mut Document) {
+    let at = document.active_element + 1;
+    let list = list(document);
+    let elements = [START, list.as_str(), END]
+        .into_iter()
+        .filter(|md| !md.is_empty())
+        .map(|md| Element::Paragraph(Paragraph::from(md)));
+    document.elements.splice(at..at, elements);
+}
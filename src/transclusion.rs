@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use yew::{classes, html, Html};
+
+use crate::{
+    document::{Command, Commandee, Document, Render},
+    store::Storage,
+    wiki, ApplicationState,
+};
+
+/// The note a paragraph of only `![[Other Note]]` transcludes.
+pub fn target(text: &str) -> Option<&str> {
+    let name = text.trim().strip_prefix("![[")?.strip_suffix("]]")?.trim();
+    (!name.is_empty() && !name.contains(|c| matches!(c, '[' | ']' | '\n'))).then_some(name)
+}
+
+/// Reads the content of the notes transcluded in `document` that are not `loaded` yet by
+/// name, `None` if there is no such note.
+///
+/// Names that are no note are looked up among the aliases in `index`, if they are not
+/// ambiguous.
+pub fn load(
+    document: &Document,
+    store: &dyn Storage,
+    index: &mut Option<wiki::Index>,
+    loaded: &mut HashMap<String, Option<String>>,
+) {
+    for (_, paragraph) in document.paragraphs() {
+        let name = match target(&paragraph.text.concat()) {
+            Some(name) if !loaded.contains_key(name) => name.to_owned(),
+            _ => continue,
+        };
+        let content = store.load(&name).or_else(|| {
+            match wiki::Index::current(index, store).resolve(&name).as_slice() {
+                [note] => store.load(note),
+                _ => None,
+            }
+        });
+        loaded.insert(name, content);
+    }
+}
+
+/// The content of `note` nested read-only in the note transcluding it.
+///
+/// Transclusions in it are shown as they are written, so notes transcluding each other
+/// end.
+pub fn render(note: &str, state: &ApplicationState) -> Html {
+    let content = match state.transclusions.get(note) {
+        Some(Some(content)) => content,
+        _ => {
+            return html! {
+                <p class={classes!("text-gray-500")}>{format!("![[{}]] (no such note)", note)}</p>
+            }
+        }
+    };
+    let mut document = Document::from_md(content);
+    document.command(&Command::CursorLeave);
+    html! {
+        <div class={classes!("border-l-4", "border-gray-600", "bg-gray-800", "pl-2", "my-1")} title={format!("{}, Enter opens it", note)}>
+            <p class={classes!("text-sm", "text-gray-500")}>{note}</p>
+            {for document.elements.iter().map(|element| element.render(state))}
+        </div>
+    }
+}
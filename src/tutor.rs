@@ -0,0 +1,54 @@
+/// A step of the `:tutor`, completed once `done` holds for the markdown of the note.
+pub struct Lesson {
+    pub md: &'static str,
+    pub done: fn(&str) -> bool,
+}
+
+pub const LESSONS: &[Lesson] = &[
+    Lesson {
+        md: "# Lesson 1: Moving and inserting
+
+Move between blocks with `j` and `k` and inside a line with `h` and `l`. Press `i` to \
+enter insert mode, type, and leave it again with Escape.
+
+Go to the next line and insert the missing word so it reads \"The quick brown fox\".
+
+The brown fox",
+        done: |md| md.contains("The quick brown fox"),
+    },
+    Lesson {
+        md: "# Lesson 2: Deleting
+
+In insert mode Backspace deletes the character left of the cursor.
+
+Delete the word \"extra\" from the next line.
+
+This extra line is wrong",
+        done: |md| md.contains("This line is wrong"),
+    },
+    Lesson {
+        md: "# Lesson 3: Markdown
+
+Text surrounded by `**` is bold. Make the word \"important\" in the next line bold.
+
+This is important",
+        done: |md| md.contains("This is **important**"),
+    },
+    Lesson {
+        md: "# Lesson 4: Tables
+
+`h`, `j`, `k` and `l` move between the cells of a table. Fill the empty cell with 42.
+
+| Question | Answer |
+| --- | --- |
+| Everything | |",
+        done: |md| md.contains("| Everything | 42 |"),
+    },
+    Lesson {
+        md: "# Done
+
+You finished the tutorial. Save your notes with `:w <name>`, open them with `:e <name>` \
+and press `?` to see all keybindings.",
+        done: |_| false,
+    },
+];
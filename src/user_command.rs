@@ -0,0 +1,114 @@
+use crate::store::Storage;
+
+const SETTING: &str = "commands";
+
+/// The commands of `replacement` separated by the `|` that are not escaped or quoted.
+fn split(replacement: &str) -> Vec<String> {
+    let mut commands = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = replacement.chars().peekable();
+    while let Some(c) = chars.next() {
+        let command = commands.last_mut().unwrap();
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                command.push('|');
+                chars.next();
+            }
+            '|' if !quoted => commands.push(String::new()),
+            c => {
+                quoted ^= c == '"';
+                command.push(c);
+            }
+        }
+    }
+    commands
+        .into_iter()
+        .map(|command| command.trim().to_owned())
+        .collect()
+}
+
+/// Commands defined with `:command Name replacement`, stored in the settings.
+///
+/// The replacement may run several built-in commands separated by `|`, a `\|` or one in
+/// quotes stays in the command. `<args>` in it is replaced by the arguments, which are
+/// appended to the first command if it has none, so `:command W w` is an alias. Like in vim,
+/// names start with an uppercase letter to not shadow built-ins.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UserCommands {
+    commands: Vec<(String, String)>,
+}
+
+impl UserCommands {
+    pub fn load(store: &dyn Storage) -> Self {
+        let commands = store
+            .setting(SETTING)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| line.split_once(' '))
+            .map(|(name, replacement)| (name.to_owned(), replacement.to_owned()))
+            .collect();
+        Self { commands }
+    }
+
+    fn save(&self, store: &mut dyn Storage) {
+        let lines: Vec<_> = self
+            .commands
+            .iter()
+            .map(|(name, replacement)| format!("{} {}", name, replacement))
+            .collect();
+        store.set_setting(SETTING, &lines.join("\n"));
+    }
+
+    pub fn define(
+        &mut self,
+        store: &mut dyn Storage,
+        name: &str,
+        replacement: &str,
+    ) -> Result<(), &'static str> {
+        if !name.starts_with(|c: char| c.is_ascii_uppercase())
+            || !name.chars().all(|c| c.is_ascii_alphanumeric())
+        {
+            return Err(
+                "User commands start with an uppercase letter followed by letters or digits",
+            );
+        }
+        self.commands.retain(|(defined, _)| defined != name);
+        self.commands
+            .push((name.to_owned(), replacement.to_owned()));
+        self.save(store);
+        Ok(())
+    }
+
+    /// Removes the command `name`, `false` if there is none.
+    pub fn remove(&mut self, store: &mut dyn Storage, name: &str) -> bool {
+        let len = self.commands.len();
+        self.commands.retain(|(defined, _)| defined != name);
+        self.save(store);
+        self.commands.len() != len
+    }
+
+    /// The built-in commands `command` runs if it is a user command.
+    pub fn expand(&self, command: &str) -> Option<Vec<String>> {
+        let (name, arguments) = command.split_once(' ').unwrap_or((command, ""));
+        let (_, replacement) = self.commands.iter().find(|(defined, _)| defined == name)?;
+        let arguments = arguments.trim();
+        // Split before the arguments are put in, which may contain a `|` themselves
+        let mut commands = split(replacement);
+        if replacement.contains("<args>") {
+            for command in &mut commands {
+                *command = command.replace("<args>", arguments);
+            }
+        } else if !arguments.is_empty() {
+            commands[0] = format!("{} {}", commands[0], arguments);
+        }
+        Some(commands)
+    }
+
+    /// Lines of `:command` without arguments.
+    pub fn list(&self) -> Vec<String> {
+        self.commands
+            .iter()
+            .map(|(name, replacement)| format!("{:<12} {}", name, replacement))
+            .collect()
+    }
+}
@@ -0,0 +1,193 @@
+use crate::{attachment, store::Storage};
+
+/// What `:import vault` does with a note whose name is taken.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Collision {
+    Overwrite,
+    /// Imports it under a free name like `Note (2)`
+    KeepBoth,
+    Skip,
+}
+
+/// The entries of the prompt for a note whose name is taken, with whether they apply to
+/// all remaining ones.
+pub const CHOICES: [(&str, Collision, bool); 6] = [
+    ("Overwrite", Collision::Overwrite, false),
+    ("Keep both", Collision::KeepBoth, false),
+    ("Skip", Collision::Skip, false),
+    ("Overwrite all", Collision::Overwrite, true),
+    ("Keep both for all", Collision::KeepBoth, true),
+    ("Skip all", Collision::Skip, true),
+];
+
+/// Whether `path` is hidden, like the settings of other apps in `.obsidian/`.
+fn hidden(path: &str) -> bool {
+    path.split('/')
+        .any(|component| component.starts_with('.') || component == "__MACOSX")
+}
+
+/// The notes and attachments among the `files` of a vault by their paths.
+///
+/// Markdown files are notes named by their path without the extension, other files are
+/// attachments named by their file name. A folder everything is in, like the vault itself
+/// when a folder is picked, and the `notes/` of `:export vault` are left out of the names.
+pub fn split(files: Vec<(String, Vec<u8>)>) -> (Vec<(String, String)>, Vec<(String, Vec<u8>)>) {
+    let files: Vec<_> = files
+        .into_iter()
+        .filter(|(path, _)| !hidden(path))
+        .collect();
+    let root = files
+        .first()
+        .and_then(|(path, _)| Some(&path[..path.find('/')? + 1]))
+        .filter(|root| files.iter().all(|(path, _)| path.starts_with(root)))
+        .map_or(0, str::len);
+    let (mut notes, mut attachments) = (vec![], vec![]);
+    for (path, content) in &files {
+        let path = &path[root..];
+        let note = path
+            .strip_suffix(".md")
+            .or_else(|| path.strip_suffix(".markdown"));
+        match note {
+            Some(note) => {
+                let note = note.strip_prefix("notes/").unwrap_or(note);
+                notes.push((
+                    note.to_owned(),
+                    String::from_utf8_lossy(content).into_owned(),
+                ));
+            }
+            None => {
+                let name = path.rsplit('/').next().unwrap_or(path);
+                // Spaces would end the link destination, like for pasted files
+                let name = name.split_whitespace().collect::<Vec<_>>().join("-");
+                attachments.push((name, content.clone()));
+            }
+        }
+    }
+    (notes, attachments)
+}
+
+/// The first of `note (2)`, `note (3)`, … that is no stored note.
+pub fn free_name(note: &str, store: &dyn Storage) -> String {
+    (2..)
+        .map(|i| format!("{} ({})", note, i))
+        .find(|name| store.load(name).is_none())
+        .unwrap()
+}
+
+/// A vault being imported by `:import vault`, asking about each note whose name is taken.
+#[derive(Debug, Default)]
+pub struct Import {
+    /// The notes left to import, the last one first
+    pub notes: Vec<(String, String)>,
+    /// How taken names are handled without asking, once one of the choices for all was
+    /// picked
+    pub all: Option<Collision>,
+    pub imported: usize,
+    pub skipped: usize,
+    pub attachments: usize,
+    /// The attachments still being stored, the notes are imported once all are
+    pub pending: usize,
+    /// The attachments that were stored under another name, as the name was taken by a
+    /// different file
+    pub renamed: Vec<(String, String)>,
+}
+
+impl Import {
+    pub fn new(mut notes: Vec<(String, String)>, attachments: usize) -> Self {
+        notes.sort();
+        notes.reverse();
+        Self {
+            notes,
+            attachments,
+            pending: attachments,
+            ..Self::default()
+        }
+    }
+
+    /// Notes that the attachment `name` was kept as `stored`, `None` if it could not be
+    /// stored, and returns whether the notes can be imported now.
+    ///
+    /// Links in the notes to attachments that were renamed are changed once all are stored.
+    pub fn stored(&mut self, name: &str, stored: Option<String>) -> bool {
+        self.pending = self.pending.saturating_sub(1);
+        match stored {
+            Some(stored) if stored != name => self.renamed.push((name.to_owned(), stored)),
+            Some(_) => {}
+            None => self.attachments = self.attachments.saturating_sub(1),
+        }
+        if self.pending > 0 {
+            return false;
+        }
+        for (_, content) in &mut self.notes {
+            for (old, new) in &self.renamed {
+                *content = attachment::rename(content, old, new);
+            }
+        }
+        true
+    }
+
+    /// Saves `content` as `note`, whose name is taken, the way `collision` says.
+    pub fn resolve(
+        &mut self,
+        note: &str,
+        content: &str,
+        collision: Collision,
+        store: &mut dyn Storage,
+    ) {
+        match collision {
+            Collision::Overwrite => store.save(note, content),
+            Collision::KeepBoth => store.save(&free_name(note, store), content),
+            Collision::Skip => {
+                self.skipped += 1;
+                return;
+            }
+        }
+        self.imported += 1;
+    }
+
+    /// Saves the notes up to the first whose name is taken, which is returned to ask
+    /// about, `None` once all are imported.
+    pub fn advance(&mut self, store: &mut dyn Storage) -> Option<String> {
+        while let Some((note, content)) = self.notes.pop() {
+            if store.load(&note).is_none() {
+                store.save(&note, &content);
+                self.imported += 1;
+            } else if let Some(collision) = self.all {
+                self.resolve(&note, &content, collision, store);
+            } else {
+                self.notes.push((note.clone(), content));
+                return Some(note);
+            }
+        }
+        None
+    }
+
+    /// Resolves the note [`Self::advance`] asked about with `collision`, for all remaining ones
+    /// with `all`.
+    pub fn choose(&mut self, collision: Collision, all: bool, store: &mut dyn Storage) {
+        if let Some((note, content)) = self.notes.pop() {
+            self.resolve(&note, &content, collision, store);
+        }
+        if all {
+            self.all = Some(collision);
+        }
+    }
+
+    /// What was imported, for the notification at the end.
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "Imported {} notes and {} attachments",
+            self.imported, self.attachments
+        );
+        if self.skipped > 0 {
+            summary.push_str(&format!(", skipped {}", self.skipped));
+        }
+        if !self.renamed.is_empty() {
+            summary.push_str(&format!(
+                ", renamed {} attachments whose name was taken",
+                self.renamed.len()
+            ));
+        }
+        summary
+    }
+}
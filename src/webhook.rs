@@ -0,0 +1,96 @@
+use js_sys::{Date, JSON};
+use wasm_bindgen::JsValue;
+use web_sys::window;
+use yew::Callback;
+
+use crate::{
+    frontmatter,
+    promise::{call, field, object},
+    store::Storage,
+};
+
+/// The setting keeping the webhooks of notes the user allowed notes to be sent to.
+const ALLOWED: &str = "webhooksallowed";
+
+/// Where a saved note is sent.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Target {
+    /// The `webhook` in its frontmatter or else the one of `:set webhook=`
+    Send(String),
+    /// The `webhook` in its frontmatter, that was not allowed with `:webhook allow` yet
+    Unconfirmed(String),
+}
+
+/// Where `content` of a note is sent to.
+///
+/// Notes can come from elsewhere with `:pull`, `:import` or `:feed`, so the webhook of a
+/// note is only used once the user allowed it.
+pub fn target(content: &str, vault: Option<&str>, store: &dyn Storage) -> Option<Target> {
+    let is_url = |url: &&str| url.starts_with("https://") || url.starts_with("http://");
+    let note = frontmatter::split(content)
+        .0
+        .and_then(|frontmatter| frontmatter::get(frontmatter, "webhook"))
+        .filter(is_url);
+    match note {
+        Some(url) if allowed(store).iter().any(|allowed| allowed == url) => {
+            Some(Target::Send(url.to_owned()))
+        }
+        Some(url) => Some(Target::Unconfirmed(url.to_owned())),
+        None => vault.filter(is_url).map(|url| Target::Send(url.to_owned())),
+    }
+}
+
+/// The webhooks of notes allowed with `:webhook allow`.
+fn allowed(store: &dyn Storage) -> Vec<String> {
+    store
+        .setting(ALLOWED)
+        .unwrap_or_default()
+        .lines()
+        .map(str::to_owned)
+        .collect()
+}
+
+pub fn allow(store: &mut dyn Storage, url: &str) {
+    let mut allowed = allowed(store);
+    if !allowed.iter().any(|allowed| allowed == url) {
+        allowed.push(url.to_owned());
+        store.set_setting(ALLOWED, &allowed.join("\n"));
+    }
+}
+
+/// Posts `note` with its `content` and the time it was saved as JSON to `url`, `done`
+/// receives why that failed.
+///
+/// The server has to allow requests from the page with CORS, as the browser sends them.
+pub fn send(url: &str, note: &str, content: &str, done: Callback<String>) {
+    let payload = object(&[
+        ("note", note.into()),
+        ("content", content.into()),
+        ("saved", Date::new_0().to_iso_string().into()),
+    ]);
+    let body = JSON::stringify(&payload).unwrap_or_default();
+    let headers = object(&[("Content-Type", "application/json".into())]);
+    let init = object(&[
+        ("method", "POST".into()),
+        ("headers", headers.into()),
+        ("body", body.into()),
+    ]);
+    let window: JsValue = window().unwrap().into();
+    let url = url.to_owned();
+    call(
+        &window,
+        "fetch",
+        &[url.as_str().into(), init.into()],
+        move |response| match response {
+            Ok(response) if field(&response, &["ok"]).as_bool() == Some(true) => {}
+            Ok(response) => {
+                let status = field(&response, &["status"]).as_f64().unwrap_or_default();
+                done.emit(format!(
+                    "The webhook {} answered with status {}",
+                    url, status
+                ))
+            }
+            Err(_) => done.emit(format!("The webhook {} could not be reached", url)),
+        },
+    );
+}
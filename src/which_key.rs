@@ -0,0 +1,48 @@
+use yew::{classes, html, Html};
+
+use crate::{
+    document::Render,
+    keymap::{split_count, Keymap},
+    ApplicationState, Mode,
+};
+
+/// How long a key sequence waits for its next key by default, in milliseconds.
+pub const TIMEOUT: u32 = 1000;
+
+/// The keys that can continue a sequence typed so far, shown until it completes or times out.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hints {
+    pub keys: String,
+    pub continuations: Vec<(String, Option<&'static str>)>,
+}
+
+impl Hints {
+    pub fn new(keymap: &Keymap, mode: Mode, keys: &str) -> Self {
+        let (_, keys) = split_count(keys);
+        Self {
+            keys: keys.to_owned(),
+            continuations: keymap.continuations(mode, keys),
+        }
+    }
+}
+
+impl Render for Hints {
+    fn render(&self, _state: &ApplicationState) -> Html {
+        if self.continuations.is_empty() {
+            return html! {};
+        }
+        html! {
+            <div class={classes!("fixed", "bottom-8", "right-2", "p-2", "rounded", "bg-gray-700", "ring-2", "ring-gray-400")}>
+                <p class={classes!("text-gray-400")}>{&self.keys}</p>
+                <table>
+                    {for self.continuations.iter().map(|(key, description)| html!{
+                        <tr>
+                            <td class={classes!("pr-4", "text-yellow-300")}>{key}</td>
+                            <td>{description.unwrap_or("+more")}</td>
+                        </tr>
+                    })}
+                </table>
+            </div>
+        }
+    }
+}
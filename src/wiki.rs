@@ -0,0 +1,237 @@
+use std::ops::Range;
+
+use crate::{frontmatter, store::Storage};
+
+/// A `[[note]]` or `[[note|text]]` link, or a `![[note]]` transclusion.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WikiLink {
+    /// Byte range of the whole link in the source
+    pub range: Range<usize>,
+    /// Byte range of the note name
+    pub name: Range<usize>,
+    pub target: String,
+}
+
+/// The wiki links in `text`.
+pub fn links(text: &str) -> Vec<WikiLink> {
+    let mut links = vec![];
+    let mut offset = 0;
+    while let Some(start) = text[offset..].find("[[").map(|start| offset + start) {
+        let inner = start + 2;
+        let end = match text[inner..].find("]]") {
+            Some(end) => inner + end,
+            None => break,
+        };
+        let content = &text[inner..end];
+        if content.contains(|c| matches!(c, '[' | '\n')) {
+            offset = inner;
+            continue;
+        }
+        let name = inner..inner + content.find('|').unwrap_or(content.len());
+        let target = text[name.clone()].trim();
+        if !target.is_empty() {
+            let range_start = if text[..start].ends_with('!') {
+                start - 1
+            } else {
+                start
+            };
+            links.push(WikiLink {
+                range: range_start..end + 2,
+                target: target.to_owned(),
+                name,
+            });
+        }
+        offset = end + 2;
+    }
+    links
+}
+
+/// Byte ranges of the code blocks and code spans in `text`, what looks like a link in them
+/// is only code.
+fn code(text: &str) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    let mut fence: Option<(usize, &str)> = None;
+    let mut offset = 0;
+    while offset < text.len() {
+        let rest = &text[offset..];
+        let line_start = offset == 0 || text[..offset].ends_with('\n');
+        let line_end = rest.find('\n').map_or(rest.len(), |end| end + 1);
+        if line_start {
+            let trimmed = rest.trim_start_matches(' ');
+            let marker = ["```", "~~~"]
+                .into_iter()
+                .find(|marker| trimmed.starts_with(marker));
+            match (fence, marker) {
+                (None, Some(marker)) => fence = Some((offset, marker)),
+                (Some((start, open)), Some(marker)) if marker == open => {
+                    ranges.push(start..offset + line_end);
+                    fence = None;
+                }
+                _ => {}
+            }
+            if marker.is_some() {
+                offset += line_end;
+                continue;
+            }
+        }
+        if fence.is_some() {
+            offset += line_end;
+            continue;
+        }
+        if rest.starts_with('`') {
+            // A code span ends at the next run of as many backticks
+            let ticks = rest.len() - rest.trim_start_matches('`').len();
+            let delimiter = &rest[..ticks];
+            offset += match rest[ticks..].find(delimiter) {
+                Some(end) => {
+                    ranges.push(offset..offset + ticks + end + ticks);
+                    ticks + end + ticks
+                }
+                None => ticks,
+            };
+            continue;
+        }
+        offset += rest.chars().next().map_or(1, char::len_utf8);
+    }
+    // A fence that is not closed runs to the end
+    if let Some((start, _)) = fence {
+        ranges.push(start..text.len());
+    }
+    ranges
+}
+
+/// The name typed after an unclosed `[[` at the end of `before_cursor`.
+pub fn typed(before_cursor: &str) -> Option<&str> {
+    let (_, typed) = before_cursor.rsplit_once("[[")?;
+    (!typed.contains(|c| matches!(c, ']' | '|' | '\n'))).then_some(typed)
+}
+
+/// The `aliases` in the frontmatter of `md`, as `aliases: [one, two]` or `aliases: one, two`.
+pub fn aliases(md: &str) -> Vec<String> {
+    frontmatter::split(md)
+        .0
+        .map(|frontmatter| frontmatter::list(frontmatter, "aliases"))
+        .unwrap_or_default()
+}
+
+/// The names and aliases of the stored notes that wiki links resolve against.
+pub struct Index {
+    notes: Vec<String>,
+    /// Each alias with the note it belongs to
+    aliases: Vec<(String, String)>,
+    /// The [`Storage::version`] it was read at
+    version: u64,
+}
+
+/// The aliases of each of the `notes`, with the note they belong to.
+fn note_aliases(notes: &[String], store: &dyn Storage) -> Vec<(String, String)> {
+    notes
+        .iter()
+        .flat_map(|note| {
+            let md = store.load(note).unwrap_or_default();
+            aliases(&md)
+                .into_iter()
+                .map(move |alias| (alias, note.clone()))
+        })
+        .collect()
+}
+
+impl Index {
+    pub fn new(store: &dyn Storage) -> Self {
+        let mut notes = store.notes();
+        notes.sort();
+        let aliases = note_aliases(&notes, store);
+        Self {
+            notes,
+            aliases,
+            version: store.version(),
+        }
+    }
+
+    /// `index` read again for the notes that changed since it was, built first if there is
+    /// none yet.
+    pub fn current<'a>(index: &'a mut Option<Self>, store: &dyn Storage) -> &'a Self {
+        let index = index.get_or_insert_with(|| Self::new(store));
+        let version = store.version();
+        if index.version != version {
+            let changed = store.changed_since(index.version);
+            index.notes = store.notes();
+            index.notes.sort();
+            index.aliases.retain(|(_, note)| !changed.contains(note));
+            let stored: Vec<_> = changed
+                .into_iter()
+                .filter(|note| index.notes.contains(note))
+                .collect();
+            index.aliases.extend(note_aliases(&stored, store));
+            index.version = version;
+        }
+        index
+    }
+
+    /// The notes `target` resolves to.
+    ///
+    /// A note of that name wins, otherwise it is looked up ignoring case among the names and
+    /// the aliases. More than one note means the link is ambiguous.
+    pub fn resolve(&self, target: &str) -> Vec<String> {
+        if self.notes.iter().any(|note| note == target) {
+            return vec![target.to_owned()];
+        }
+        let target = target.to_lowercase();
+        let mut notes: Vec<_> = self
+            .notes
+            .iter()
+            .filter(|note| note.to_lowercase() == target)
+            .chain(
+                self.aliases
+                    .iter()
+                    .filter(|(alias, _)| alias.to_lowercase() == target)
+                    .map(|(_, note)| note),
+            )
+            .cloned()
+            .collect();
+        notes.sort();
+        notes.dedup();
+        notes
+    }
+
+    /// Whether `name` is one of the aliases of `note`.
+    pub fn is_alias(&self, note: &str, name: &str) -> bool {
+        self.aliases
+            .iter()
+            .any(|(alias, of)| of == note && alias.eq_ignore_ascii_case(name))
+    }
+
+    /// The note names and aliases, for completion.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<_> = self
+            .notes
+            .iter()
+            .cloned()
+            .chain(self.aliases.iter().map(|(alias, _)| alias.clone()))
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+/// `md` with the wiki links that resolve to `from` by its name pointing to `to`, `None` if
+/// there are none.
+///
+/// Links by an alias keep working after the rename and are left as they are.
+pub fn rename(md: &str, from: &str, to: &str, index: &Index) -> Option<String> {
+    let code = code(md);
+    let mut renamed = md.to_owned();
+    let mut changed = false;
+    for link in links(md).into_iter().rev() {
+        let in_code = code
+            .iter()
+            .any(|code| code.start < link.range.end && link.range.start < code.end);
+        if !in_code && index.resolve(&link.target) == [from] && !index.is_alias(from, &link.target)
+        {
+            renamed.replace_range(link.name, to);
+            changed = true;
+        }
+    }
+    changed.then_some(renamed)
+}
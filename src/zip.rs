@@ -0,0 +1,164 @@
+use crate::inflate::inflate;
+
+/// The date of all entries, 1980-01-01 in MS-DOS format, the earliest a zip can hold.
+const DATE: u16 = 1 << 5 | 1;
+/// The names are UTF-8.
+const FLAGS: u16 = 1 << 11;
+/// Version 2.0 of the format, the first with folders.
+const VERSION: u16 = 20;
+
+/// The CRC-32 of `data` zip archives check entries with.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                crc >> 1 ^ 0xedb88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// A zip archive of uncompressed files, written without a compression library.
+#[derive(Debug, Default)]
+pub struct Zip {
+    data: Vec<u8>,
+    /// The central directory listing the files at the end
+    directory: Vec<u8>,
+    files: u16,
+}
+
+impl Zip {
+    /// Adds the file `name`, which may contain `/` for folders.
+    pub fn add(&mut self, name: &str, content: &[u8]) {
+        let crc = crc32(content);
+        let size = content.len() as u32;
+        let offset = self.data.len() as u32;
+        let name = name.as_bytes();
+        let data = &mut self.data;
+        data.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        for field in [VERSION, FLAGS, 0, 0, DATE] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [crc, size, size] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+        data.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes());
+        data.extend_from_slice(name);
+        data.extend_from_slice(content);
+
+        let directory = &mut self.directory;
+        directory.extend_from_slice(&0x02014b50u32.to_le_bytes());
+        for field in [VERSION, VERSION, FLAGS, 0, 0, DATE] {
+            directory.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [crc, size, size] {
+            directory.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [name.len() as u16, 0, 0, 0, 0] {
+            directory.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [0, offset] {
+            directory.extend_from_slice(&field.to_le_bytes());
+        }
+        directory.extend_from_slice(name);
+        self.files += 1;
+    }
+
+    /// The bytes of the archive.
+    pub fn finish(self) -> Vec<u8> {
+        let Self {
+            mut data,
+            directory,
+            files,
+        } = self;
+        let offset = data.len() as u32;
+        data.extend_from_slice(&directory);
+        data.extend_from_slice(&0x06054b50u32.to_le_bytes());
+        for field in [0, 0, files, files] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [directory.len() as u32, offset] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+        data.extend_from_slice(&0u16.to_le_bytes());
+        data
+    }
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+/// The files in the zip `archive` with their paths, leaving out folders.
+///
+/// Only files stored as they are or compressed with deflate can be read, which is what
+/// nearly all zip tools write.
+pub fn read(archive: &[u8]) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let corrupt = || "The zip archive is corrupt".to_owned();
+    // The end of central directory record is last, followed only by a comment
+    let end = (0..archive.len().saturating_sub(21))
+        .rev()
+        .find(|&offset| u32_at(archive, offset) == Some(0x06054b50))
+        .ok_or_else(|| "The file is no zip archive".to_owned())?;
+    let files = u16_at(archive, end + 10).ok_or_else(corrupt)?;
+    let mut entry = u32_at(archive, end + 16).ok_or_else(corrupt)? as usize;
+    let mut read = vec![];
+    for _ in 0..files {
+        let field = |offset| u16_at(archive, entry + offset).map(usize::from);
+        if u32_at(archive, entry) != Some(0x02014b50) {
+            return Err(corrupt());
+        }
+        let (flags, method) = (field(8), field(10));
+        let size = u32_at(archive, entry + 20).ok_or_else(corrupt)? as usize;
+        let (name_length, extra, comment) = match (field(28), field(30), field(32)) {
+            (Some(name), Some(extra), Some(comment)) => (name, extra, comment),
+            _ => return Err(corrupt()),
+        };
+        let header = u32_at(archive, entry + 42).ok_or_else(corrupt)? as usize;
+        let name = archive
+            .get(entry + 46..entry + 46 + name_length)
+            .ok_or_else(corrupt)?;
+        let name = String::from_utf8_lossy(name).replace('\\', "/");
+        entry += 46 + name_length + extra + comment;
+        if name.ends_with('/') {
+            continue;
+        }
+        if flags.map_or(true, |flags| flags & 1 == 1) {
+            return Err(format!("{} is encrypted", name));
+        }
+        let start = match (
+            u16_at(archive, header + 26).map(usize::from),
+            u16_at(archive, header + 28).map(usize::from),
+        ) {
+            (Some(name), Some(extra)) => header + 30 + name + extra,
+            _ => return Err(corrupt()),
+        };
+        let data = archive.get(start..start + size).ok_or_else(corrupt)?;
+        let content = match method {
+            Some(0) => data.to_vec(),
+            Some(8) => inflate(data).ok_or_else(corrupt)?,
+            _ => {
+                return Err(format!(
+                    "{} is compressed in a way that is not supported",
+                    name
+                ))
+            }
+        };
+        read.push((name, content));
+    }
+    Ok(read)
+}
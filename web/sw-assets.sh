@@ -0,0 +1,7 @@
+#!/bin/sh
+# Lists the built files in the service worker to cache them for offline use, run by trunk
+# as a post_build hook.
+set -e
+cd "$TRUNK_STAGING_DIR"
+assets=$(find . -type f ! -name sw.js | sed 's|^\./||; s|.*|"&"|' | paste -sd, -)
+sed -i "s|^const ASSETS = \[\];|const ASSETS = [$assets];|; s|__VERSION__|$(date +%s)|" sw.js
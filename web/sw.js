@@ -0,0 +1,64 @@
+// Filled in with the built files by web/sw-assets.sh after each build
+const ASSETS = [];
+const CACHE = "notething-__VERSION__";
+
+self.addEventListener("install", (event) => {
+  event.waitUntil(
+    caches.open(CACHE).then((cache) => cache.addAll(["./", ...ASSETS]))
+  );
+  self.skipWaiting();
+});
+
+// A new build gets a new cache, so the old ones are removed
+self.addEventListener("activate", (event) => {
+  event.waitUntil(
+    caches
+      .keys()
+      .then((keys) =>
+        Promise.all(
+          keys.filter((key) => key !== CACHE).map((key) => caches.delete(key))
+        )
+      )
+  );
+  self.clients.claim();
+});
+
+// The web fonts linked from index.html
+const FONTS = ["https://fonts.googleapis.com", "https://fonts.gstatic.com"];
+
+// Only the built files and fonts are cached, api, feed and proxy requests always go to the
+// network so they see changes and their responses are not kept
+function cacheable(url) {
+  if (url.origin !== self.location.origin) {
+    return FONTS.includes(url.origin);
+  }
+  const base = new URL("./", self.location).pathname;
+  if (!url.pathname.startsWith(base)) {
+    return false;
+  }
+  const path = url.pathname.slice(base.length);
+  return path === "" || ASSETS.includes(path);
+}
+
+// Cache first for what is cacheable, fonts fetched are cached for the next offline start
+self.addEventListener("fetch", (event) => {
+  const url = new URL(event.request.url);
+  if (event.request.method !== "GET" || !cacheable(url)) {
+    return;
+  }
+  // The page is cached as "./", whatever note is in the query
+  const options = { ignoreSearch: url.pathname === new URL("./", self.location).pathname };
+  event.respondWith(
+    caches.match(event.request, options).then(
+      (cached) =>
+        cached ||
+        fetch(event.request).then((response) => {
+          if (response.ok || response.type === "opaque") {
+            const copy = response.clone();
+            caches.open(CACHE).then((cache) => cache.put(event.request, copy));
+          }
+          return response;
+        })
+    )
+  );
+});